@@ -0,0 +1,102 @@
+//! Benchmarks of the routing algorithms over the bundled topologies.
+//!
+//! The crate is a binary, so the routing engine is included directly: it only depends on the
+//! graph, not on the rest of the simulation.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use graph::AdjacencyVecGraph;
+use std::fs;
+use std::hint::black_box;
+use std::path::Path;
+
+#[allow(dead_code)]
+#[path = "../src/application/routing/engine.rs"]
+mod engine;
+
+use engine::{find_routes, NodeId, RoutingNode};
+
+struct BenchNode {
+    pdr: f32,
+    is_drone: bool,
+}
+
+impl RoutingNode for BenchNode {
+    fn hop_cost(&self, _previous: Option<NodeId>) -> f32 {
+        1.0 / (1.0 - self.pdr.min(0.9999))
+    }
+    fn is_relay(&self) -> bool {
+        self.is_drone
+    }
+}
+
+/// The graph of a topology file, with the ids of its clients and servers.
+fn load_topology(
+    path: &Path,
+) -> (
+    AdjacencyVecGraph<NodeId, BenchNode>,
+    Vec<NodeId>,
+    Vec<NodeId>,
+) {
+    let table: toml::Table = fs::read_to_string(path)
+        .expect("unable to read the topology")
+        .parse()
+        .expect("invalid topology");
+    let mut graph = AdjacencyVecGraph::new();
+    let mut hosts = [Vec::new(), Vec::new()];
+    let mut edges = Vec::new();
+    for (kind, is_drone) in [("drone", true), ("client", false), ("server", false)] {
+        let nodes = table.get(kind).and_then(|nodes| nodes.as_array());
+        for node in nodes.into_iter().flatten() {
+            let id = node["id"].as_integer().unwrap() as NodeId;
+            let pdr = node
+                .get("pdr")
+                .and_then(|pdr| pdr.as_float())
+                .unwrap_or(0.0) as f32;
+            graph.add_node(id, BenchNode { pdr, is_drone });
+            match kind {
+                "client" => hosts[0].push(id),
+                "server" => hosts[1].push(id),
+                _ => {}
+            }
+            let neighbors = node
+                .get("connected_node_ids")
+                .and_then(|ids| ids.as_array());
+            for neighbor in neighbors.into_iter().flatten() {
+                edges.push((id, neighbor.as_integer().unwrap() as NodeId));
+            }
+        }
+    }
+    for (from, to) in edges {
+        graph.add_undirected_edge(from, to);
+    }
+    let [clients, servers] = hosts;
+    (graph, clients, servers)
+}
+
+fn routing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("find_routes");
+    let mut topologies: Vec<_> = fs::read_dir("topologies/examples")
+        .expect("unable to list the topologies")
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.join("topology.toml").exists())
+        .collect();
+    topologies.sort();
+    for topology in topologies {
+        let name = topology.file_name().unwrap().to_string_lossy().to_string();
+        let (graph, clients, servers) = load_topology(&topology.join("topology.toml"));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &graph, |b, graph| {
+            b.iter(|| {
+                for client in clients.iter() {
+                    for server in servers.iter() {
+                        black_box(find_routes(graph, *client, *server));
+                    }
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, routing);
+criterion_main!(benches);
@@ -1,15 +1,169 @@
-use crate::message::base_message::{Message, MessageContent};
+use crate::application::authentication::HostKeys;
+use crate::application::encryption::{Keyring, SEALED_TAG};
+use crate::message::base_message::{
+    Message, MessageContent, MessageHeader, Priority, PROTOCOL_VERSION,
+};
+use crate::message::codec::{CodecError, MessageCodec};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
-use serde_json::Error;
 use std::collections::{BTreeMap, HashMap};
+use std::fmt::Display;
 use std::marker::PhantomData;
-use std::str::from_utf8;
+use std::time::{Duration, Instant};
 use wg_2024::network::NodeId;
 use wg_2024::packet::{Fragment, FRAGMENT_DSIZE};
 
+/// Time without new fragments after which an incomplete message is abandoned.
+pub const REASSEMBLY_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Unacked fragments a host keeps in flight for each message it sends.
+pub const SEND_WINDOW: usize = 16;
+
+/// Bytes of the checksum trailing every serialized message.
+const CHECKSUM_SIZE: usize = 4;
+
+/// CRC-32 (IEEE) of the given bytes.
+fn checksum(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// Why the fragments of a session could not be composed back into a message.
+#[derive(Debug)]
+pub enum AssemblyError {
+    /// The fragments hold no bytes at all, as the probes timing a route do.
+    Empty,
+    /// The fragments are too short to even hold the checksum.
+    Truncated,
+    /// The checksum of the fragments does not match the one sent with them, so some fragment
+    /// was corrupted or belongs to another message.
+    Corrupted { expected: u32, found: u32 },
+    /// The fragments are intact but do not hold a message of the expected kind, they hold
+    /// these bytes.
+    Malformed(CodecError, Vec<u8>),
+    /// The sender speaks another version of the protocol, so the message is not read further
+    /// than its header.
+    UnsupportedVersion(u16),
+    /// The message was sealed with a key the receiver did not agree with the sender, it holds
+    /// these bytes until a [`Keyring`] opens it.
+    Sealed(Vec<u8>),
+    /// The sealed message does not open with the key agreed with the sender, so it was not
+    /// sealed by the sender or was tampered with.
+    Forged,
+    /// The message is not signed by the host it comes from, so a drone or a host that was
+    /// issued no key forged its source.
+    Spoofed(NodeId),
+}
+
+impl AssemblyError {
+    /// The message the intact fragments hold, read as one of another kind than expected.
+    pub fn read_as<T: MessageContent + DeserializeOwned>(&self) -> Option<Message<T>> {
+        match self {
+            AssemblyError::Malformed(_err, bytes) => MessageCodec::decode(bytes).ok(),
+            _ => None,
+        }
+    }
+}
+
+impl Display for AssemblyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AssemblyError::Empty => write!(f, "empty message"),
+            AssemblyError::Truncated => write!(f, "message too short to hold its checksum"),
+            AssemblyError::Corrupted { expected, found } => write!(
+                f,
+                "integrity check failed: expected checksum {expected:08x}, found {found:08x}"
+            ),
+            AssemblyError::Malformed(err, _bytes) => write!(f, "malformed message: {err}"),
+            AssemblyError::UnsupportedVersion(version) => write!(
+                f,
+                "protocol version {version} unsupported, expected {PROTOCOL_VERSION}"
+            ),
+            AssemblyError::Sealed(_bytes) => write!(f, "message sealed with no key agreed"),
+            AssemblyError::Forged => write!(f, "sealed message does not open with the key agreed"),
+            AssemblyError::Spoofed(source) => {
+                write!(f, "message not signed by its source {source}")
+            }
+        }
+    }
+}
+
+/// How much a host buffers for the messages it is sending or receiving, so that a peer
+/// opening many sessions cannot exhaust its memory.
+#[derive(Debug, Clone, Copy)]
+pub struct AssemblyLimits {
+    pub max_sessions: usize,
+    /// Bytes of fragment data kept across all the sessions.
+    pub max_buffered_bytes: usize,
+}
+
+impl Default for AssemblyLimits {
+    fn default() -> Self {
+        AssemblyLimits {
+            max_sessions: 256,
+            max_buffered_bytes: 64 * 1024 * 1024,
+        }
+    }
+}
+
+/// Bytes buffered for the given sessions.
+fn buffered_bytes<K>(fragments: &HashMap<K, BTreeMap<u64, Fragment>>) -> usize {
+    fragments.values().map(BTreeMap::len).sum::<usize>() * FRAGMENT_DSIZE
+}
+
+/// Counters of the fragments a host received and sent, to compare how the drones behave.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FragmentStats {
+    pub received: u64,
+    /// Fragments received again after they already were.
+    pub duplicates: u64,
+    /// Fragments received after a fragment with a greater index of the same message.
+    pub out_of_order: u64,
+    pub sent: u64,
+    pub retransmitted: u64,
+}
+
+impl FragmentStats {
+    /// Joins the incoming counters of an [`Assembler`] with the outgoing ones of a
+    /// [`Disassembler`].
+    pub fn combine(self, other: FragmentStats) -> FragmentStats {
+        FragmentStats {
+            received: self.received + other.received,
+            duplicates: self.duplicates + other.duplicates,
+            out_of_order: self.out_of_order + other.out_of_order,
+            sent: self.sent + other.sent,
+            retransmitted: self.retransmitted + other.retransmitted,
+        }
+    }
+}
+
+/// Told the session, the received fragments and the total fragments every time a fragment is
+/// added to an incoming message.
+pub type ProgressObserver = Box<dyn FnMut(u64, u64, u64) + Send>;
+
+/// A session of an incoming message: the host that sent it and its id. Two hosts may use the
+/// same session ids, so the id alone does not tell their messages apart.
+pub type SessionKey = (NodeId, u64);
+
 pub struct Assembler<M: MessageContent> {
-    fragments: HashMap<u64, BTreeMap<u64, Fragment>>,
+    fragments: HashMap<SessionKey, BTreeMap<u64, Fragment>>,
+    /// When each session last received a fragment.
+    last_update: HashMap<SessionKey, Instant>,
+    progress_observer: Option<ProgressObserver>,
+    stats: FragmentStats,
+    limits: AssemblyLimits,
+    /// The keys the messages are checked to be signed with by their source, if they are.
+    host_keys: Option<HostKeys>,
     phantom_data: PhantomData<M>,
 }
 
@@ -17,34 +171,160 @@ impl<M: MessageContent + DeserializeOwned> Assembler<M> {
     pub fn new() -> Self {
         Assembler {
             fragments: HashMap::new(),
+            last_update: HashMap::new(),
+            progress_observer: None,
+            stats: FragmentStats::default(),
+            limits: AssemblyLimits::default(),
+            host_keys: None,
             phantom_data: PhantomData,
         }
     }
-    pub fn compose_message(fragments: Vec<Fragment>) -> Result<Message<M>, Error> {
-        let mut serialized = String::with_capacity(fragments.len() * FRAGMENT_DSIZE);
+
+    pub fn with_limits(mut self, limits: AssemblyLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Checks that every message is signed by the host it comes from.
+    pub fn with_host_keys(mut self, host_keys: HostKeys) -> Self {
+        self.host_keys = Some(host_keys);
+        self
+    }
+
+    pub fn with_progress_observer(mut self, observer: ProgressObserver) -> Self {
+        self.progress_observer = Some(observer);
+        self
+    }
+
+    /// Joins the fragments and checks them against the trailing checksum, and that `source`
+    /// signed them if the assembler has the keys of the hosts, before reading them. A sealed
+    /// message is left to be opened with [`Keyring::open`].
+    pub fn compose_message(
+        &self,
+        source: NodeId,
+        fragments: Vec<Fragment>,
+    ) -> Result<Message<M>, AssemblyError> {
+        let mut bytes = Vec::with_capacity(fragments.len() * FRAGMENT_DSIZE);
         for frag in fragments.iter() {
-            serialized.push_str(from_utf8(&frag.data[..frag.length as usize]).unwrap());
+            bytes.extend_from_slice(&frag.data[..frag.length as usize]);
+        }
+        if bytes.is_empty() {
+            return Err(AssemblyError::Empty);
+        }
+        if bytes.len() < CHECKSUM_SIZE {
+            return Err(AssemblyError::Truncated);
+        }
+        let trailer = bytes.split_off(bytes.len() - CHECKSUM_SIZE);
+        let expected = u32::from_be_bytes(trailer.try_into().unwrap());
+        let found = checksum(&bytes);
+        if expected != found {
+            return Err(AssemblyError::Corrupted { expected, found });
+        }
+        if let Some(host_keys) = self.host_keys.as_ref() {
+            if !host_keys.verify(source, &mut bytes) {
+                return Err(AssemblyError::Spoofed(source));
+            }
+        }
+        if bytes.first() == Some(&SEALED_TAG) {
+            return Err(AssemblyError::Sealed(bytes));
         }
-        Message::<M>::deserialize(serialized)
+        Self::read(bytes)
     }
 
+    /// Deserializes an encoded message after checking the version of the protocol it was sent
+    /// with.
+    pub fn read(bytes: Vec<u8>) -> Result<Message<M>, AssemblyError> {
+        if let Ok(header) = MessageCodec::decode::<MessageHeader>(&bytes) {
+            if header.version != PROTOCOL_VERSION {
+                return Err(AssemblyError::UnsupportedVersion(header.version));
+            }
+        }
+        MessageCodec::decode(&bytes).map_err(|err| AssemblyError::Malformed(err, bytes))
+    }
+
+    /// Adds a fragment sent by `source`, returning its message once every fragment arrived.
     pub fn insert_fragment(
         &mut self,
+        source: NodeId,
         session_id: u64,
         fragment: Fragment,
-    ) -> Option<Result<Message<M>, Error>> {
+    ) -> Option<Result<Message<M>, AssemblyError>> {
+        let key = (source, session_id);
         let frag_count = fragment.total_n_fragments as usize;
-        let frag_vec = self.fragments.entry(session_id).or_default();
-        frag_vec.insert(fragment.fragment_index, fragment);
-        if frag_vec.len() == frag_count {
-            Some(Self::compose_message(frag_vec.values().cloned().collect()))
+        self.last_update.insert(key, Instant::now());
+        let frag_vec = self.fragments.entry(key).or_default();
+        self.stats.received += 1;
+        if frag_vec
+            .last_key_value()
+            .is_some_and(|(last, _)| *last > fragment.fragment_index)
+        {
+            self.stats.out_of_order += 1;
+        }
+        if frag_vec.insert(fragment.fragment_index, fragment).is_some() {
+            self.stats.duplicates += 1;
+        }
+        let received = frag_vec.len();
+        if let Some(observer) = self.progress_observer.as_mut() {
+            observer(session_id, received as u64, frag_count as u64);
+        }
+        if received == frag_count {
+            let fragments = self.fragments.remove(&key).unwrap();
+            self.last_update.remove(&key);
+            Some(self.compose_message(source, fragments.into_values().collect()))
         } else {
             None
         }
     }
 
-    pub fn forget(&mut self, session_id: u64) {
-        self.fragments.remove(&session_id);
+    pub fn forget(&mut self, key: SessionKey) {
+        self.fragments.remove(&key);
+        self.last_update.remove(&key);
+    }
+
+    /// Forgets the incomplete messages that received no fragment for longer than `expiry`,
+    /// returning their sessions.
+    pub fn gc(&mut self, expiry: Duration) -> Vec<SessionKey> {
+        let abandoned: Vec<_> = self
+            .last_update
+            .iter()
+            .filter(|(_key, updated)| updated.elapsed() > expiry)
+            .map(|(key, _updated)| *key)
+            .collect();
+        for key in abandoned.iter() {
+            self.forget(*key);
+        }
+        abandoned
+    }
+
+    /// Forgets the least recently updated messages until the limits are respected, returning
+    /// their sessions.
+    pub fn enforce_limits(&mut self) -> Vec<SessionKey> {
+        let mut evicted = Vec::new();
+        while self.fragments.len() > self.limits.max_sessions
+            || buffered_bytes(&self.fragments) > self.limits.max_buffered_bytes
+        {
+            let Some(oldest) = self
+                .last_update
+                .iter()
+                .min_by_key(|(_key, updated)| **updated)
+                .map(|(key, _updated)| *key)
+            else {
+                break;
+            };
+            self.forget(oldest);
+            evicted.push(oldest);
+        }
+        evicted
+    }
+
+    pub fn received_fragments(&self, source: NodeId, session_id: u64) -> usize {
+        self.fragments
+            .get(&(source, session_id))
+            .map_or(0, BTreeMap::len)
+    }
+
+    pub fn stats(&self) -> FragmentStats {
+        self.stats
     }
 }
 
@@ -52,8 +332,20 @@ impl<M: MessageContent + DeserializeOwned> Assembler<M> {
 pub struct Disassembler<M: MessageContent> {
     fragments: HashMap<u64, BTreeMap<u64, Fragment>>,
     destinations: HashMap<u64, NodeId>,
+    /// The priority of each session, for its retransmissions.
+    priorities: HashMap<u64, Priority>,
+    /// First fragment of each session that was not released yet.
+    next_fragment: HashMap<u64, u64>,
+    /// When each session was disassembled.
+    started: HashMap<u64, Instant>,
     phantom_data: PhantomData<M>,
     last_session_id: u64,
+    codec: MessageCodec,
+    window: usize,
+    stats: FragmentStats,
+    limits: AssemblyLimits,
+    /// The host signing the messages and the keys it signs them with, if it does.
+    signer: Option<(NodeId, HostKeys)>,
 }
 
 impl<M: MessageContent + Serialize> Disassembler<M> {
@@ -61,14 +353,44 @@ impl<M: MessageContent + Serialize> Disassembler<M> {
         Self {
             fragments: HashMap::new(),
             destinations: HashMap::new(),
+            priorities: HashMap::new(),
+            next_fragment: HashMap::new(),
+            started: HashMap::new(),
             phantom_data: PhantomData,
             last_session_id: 0,
+            codec: MessageCodec::default(),
+            window: usize::MAX,
+            stats: FragmentStats::default(),
+            limits: AssemblyLimits::default(),
+            signer: None,
         }
     }
 
-    fn decompose_message(message: Message<M>) -> BTreeMap<u64, Fragment> {
-        let serialized = message.serialize();
-        let bytes = serialized.into_bytes();
+    pub fn with_limits(mut self, limits: AssemblyLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Keeps at most `window` unacked fragments of each session in flight, the others are
+    /// released by [`Disassembler::release_fragments`] as the ones in flight get acked.
+    pub fn with_window(mut self, window: usize) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Signs every message with the key issued to `host`.
+    pub fn with_host_keys(mut self, host: NodeId, host_keys: HostKeys) -> Self {
+        self.signer = Some((host, host_keys));
+        self
+    }
+
+    pub fn with_codec(mut self, codec: MessageCodec) -> Self {
+        self.codec = codec;
+        self
+    }
+
+    fn decompose_message(mut bytes: Vec<u8>) -> BTreeMap<u64, Fragment> {
+        bytes.extend_from_slice(&checksum(&bytes).to_be_bytes());
         let total_n_fragments = {
             let count = bytes.len() / FRAGMENT_DSIZE;
             if bytes.len() % FRAGMENT_DSIZE > 0 {
@@ -99,19 +421,90 @@ impl<M: MessageContent + Serialize> Disassembler<M> {
         fragments
     }
 
-    pub fn get_fragment(&self, session_id: u64, fragment_index: u64) -> Option<Fragment> {
-        self.fragments
+    /// The unacked fragment to send again, if there is one.
+    pub fn retransmit_fragment(
+        &mut self,
+        session_id: u64,
+        fragment_index: u64,
+    ) -> Option<Fragment> {
+        let fragment = self
+            .fragments
             .get(&session_id)?
             .get(&fragment_index)
-            .cloned()
+            .cloned()?;
+        self.stats.retransmitted += 1;
+        Some(fragment)
     }
 
+    /// Splits the message into fragments, returning the ones that fit in the window.
     pub fn disassembly(&mut self, message: Message<M>) -> Vec<Fragment> {
+        self.disassembly_with(message, self.codec)
+    }
+
+    /// Like [`Disassembler::disassembly`], for a message of any kind encoded with `codec`, its
+    /// fragments being acked and retransmitted along with those of the host.
+    pub fn disassembly_with<T: MessageContent + Serialize>(
+        &mut self,
+        message: Message<T>,
+        codec: MessageCodec,
+    ) -> Vec<Fragment> {
         let session_id = message.session_id;
-        self.destinations.insert(session_id, message.destination_id);
-        let fragments = Self::decompose_message(message);
-        self.fragments.insert(session_id, fragments.clone());
-        fragments.into_values().collect()
+        let destination = message.destination_id;
+        self.priorities.insert(session_id, message.priority);
+        self.send_bytes(session_id, destination, codec.encode(&message))
+    }
+
+    /// Like [`Disassembler::disassembly_with`], sealing the message if `keyring` agreed a key
+    /// with its destination.
+    pub fn disassembly_sealed<T: MessageContent + Serialize>(
+        &mut self,
+        message: Message<T>,
+        codec: MessageCodec,
+        keyring: &Keyring,
+    ) -> Vec<Fragment> {
+        let session_id = message.session_id;
+        let destination = message.destination_id;
+        self.priorities.insert(session_id, message.priority);
+        let bytes = keyring.seal(destination, codec.encode(&message));
+        self.send_bytes(session_id, destination, bytes)
+    }
+
+    fn send_bytes(
+        &mut self,
+        session_id: u64,
+        destination: NodeId,
+        mut bytes: Vec<u8>,
+    ) -> Vec<Fragment> {
+        if let Some((host, host_keys)) = self.signer.as_ref() {
+            host_keys.sign(*host, &mut bytes);
+        }
+        self.destinations.insert(session_id, destination);
+        let fragments = Self::decompose_message(bytes);
+        self.fragments.insert(session_id, fragments);
+        self.next_fragment.insert(session_id, 0);
+        self.started.insert(session_id, Instant::now());
+        self.release_fragments(session_id)
+    }
+
+    /// Returns the fragments of the session that now fit in the window, in order.
+    pub fn release_fragments(&mut self, session_id: u64) -> Vec<Fragment> {
+        let (Some(fragments), Some(next)) = (
+            self.fragments.get(&session_id),
+            self.next_fragment.get_mut(&session_id),
+        ) else {
+            return Vec::new();
+        };
+        let in_flight = fragments.range(..*next).count();
+        let released: Vec<Fragment> = fragments
+            .range(*next..)
+            .take(self.window.saturating_sub(in_flight))
+            .map(|(_index, fragment)| fragment.clone())
+            .collect();
+        if let Some(last) = released.last() {
+            *next = last.fragment_index + 1;
+        }
+        self.stats.sent += released.len() as u64;
+        released
     }
 
     pub fn forget_fragment(&mut self, session_id: u64, fragment_index: u64) -> Option<Fragment> {
@@ -120,6 +513,9 @@ impl<M: MessageContent + Serialize> Disassembler<M> {
             if fragments.is_empty() {
                 self.fragments.remove(&session_id);
                 self.destinations.remove(&session_id);
+                self.priorities.remove(&session_id);
+                self.next_fragment.remove(&session_id);
+                self.started.remove(&session_id);
             }
             removed
         } else {
@@ -127,11 +523,54 @@ impl<M: MessageContent + Serialize> Disassembler<M> {
         }
     }
 
+    /// Gives up sending the session.
+    pub fn forget(&mut self, session_id: u64) {
+        self.fragments.remove(&session_id);
+        self.destinations.remove(&session_id);
+        self.priorities.remove(&session_id);
+        self.next_fragment.remove(&session_id);
+        self.started.remove(&session_id);
+    }
+
+    /// Gives up the oldest sessions until the limits are respected, returning them.
+    pub fn enforce_limits(&mut self) -> Vec<u64> {
+        let mut evicted = Vec::new();
+        while self.fragments.len() > self.limits.max_sessions
+            || buffered_bytes(&self.fragments) > self.limits.max_buffered_bytes
+        {
+            let Some(oldest) = self
+                .started
+                .iter()
+                .min_by_key(|(_session_id, started)| **started)
+                .map(|(session_id, _started)| *session_id)
+            else {
+                break;
+            };
+            self.forget(oldest);
+            evicted.push(oldest);
+        }
+        evicted
+    }
+
+    /// Number of sessions with fragments not acked yet.
+    pub fn pending_sessions(&self) -> usize {
+        self.fragments.len()
+    }
+
+    /// Number of fragments of the session that have not been acked yet.
+    pub fn remaining_fragments(&self, session_id: u64) -> usize {
+        self.fragments.get(&session_id).map_or(0, BTreeMap::len)
+    }
+
     #[cfg(test)]
     pub fn has_fragments(&self, session_id: u64) -> bool {
         self.fragments.contains_key(&session_id)
     }
 
+    pub fn stats(&self) -> FragmentStats {
+        self.stats
+    }
+
     pub fn new_session_id(&mut self) -> u64 {
         let new_session_id = self.last_session_id;
         self.last_session_id += 1;
@@ -142,6 +581,13 @@ impl<M: MessageContent + Serialize> Disassembler<M> {
         self.destinations.get(&session_id).cloned()
     }
 
+    pub fn get_priority(&self, session_id: u64) -> Priority {
+        self.priorities
+            .get(&session_id)
+            .copied()
+            .unwrap_or_default()
+    }
+
     pub fn transform_session_id(session_id: u64, node_id: NodeId) -> u64 {
         (node_id as u64) << 56 | session_id
     }
@@ -0,0 +1,83 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::{Arc, RwLock};
+use wg_2024::network::NodeId;
+
+/// Bytes of the HMAC-SHA256 tag trailing every signed message, before its checksum.
+const TAG_SIZE: usize = 32;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// The keys the hosts sign the messages they send with, issued by the initializer to every
+/// host it creates and shared by the hosts to check who sent a message. The drones are given
+/// none, so they cannot pass a message off as one of a host, while the hosts trust each other
+/// since any of them could sign as another.
+#[derive(Clone, Default)]
+pub struct HostKeys(Arc<RwLock<HashMap<NodeId, [u8; 32]>>>);
+
+impl HostKeys {
+    /// Issues a new key to the host, the messages signed with the old one no longer verifying.
+    pub fn issue(&self, host: NodeId) {
+        self.0.write().unwrap().insert(host, rand::random());
+    }
+
+    fn mac(&self, host: NodeId) -> Option<HmacSha256> {
+        let keys = self.0.read().unwrap();
+        let key = keys.get(&host)?;
+        Some(HmacSha256::new_from_slice(key).expect("HMAC takes keys of any size"))
+    }
+
+    /// Appends the tag of the bytes signed by the host, leaving them unsigned if the host was
+    /// issued no key.
+    pub fn sign(&self, host: NodeId, bytes: &mut Vec<u8>) {
+        let Some(mut mac) = self.mac(host) else {
+            return;
+        };
+        mac.update(bytes);
+        bytes.extend_from_slice(&mac.finalize().into_bytes());
+    }
+
+    /// Strips the trailing tag from the bytes, returning whether the host signed them.
+    pub fn verify(&self, host: NodeId, bytes: &mut Vec<u8>) -> bool {
+        let (Some(mut mac), Some(signed)) = (self.mac(host), bytes.len().checked_sub(TAG_SIZE))
+        else {
+            return false;
+        };
+        let tag = bytes.split_off(signed);
+        mac.update(bytes);
+        mac.verify_slice(&tag).is_ok()
+    }
+}
+
+impl Debug for HostKeys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let keys = self.0.read().unwrap();
+        let hosts: Vec<_> = keys.keys().collect();
+        f.debug_struct("HostKeys").field("hosts", &hosts).finish()
+    }
+}
+
+#[test]
+fn messages_signed_by_another_host_are_spoofed() {
+    use crate::application::assembler::{Assembler, AssemblyError, Disassembler};
+    use crate::message::base_message::Message;
+    use crate::message::chat_message::ChatRequest;
+
+    let keys = HostKeys::default();
+    keys.issue(1);
+    keys.issue(2);
+    let message = Message::new(1, 3, 0, ChatRequest::Register("ada".to_string()));
+    let fragments = Disassembler::new()
+        .with_host_keys(1, keys.clone())
+        .disassembly(message);
+    let assembler = Assembler::<ChatRequest>::new().with_host_keys(keys.clone());
+    assert!(assembler.compose_message(1, fragments.clone()).is_ok());
+    let spoofed = assembler.compose_message(2, fragments.clone());
+    assert!(matches!(spoofed, Err(AssemblyError::Spoofed(2))));
+    // a new key makes the messages signed with the old one spoofed
+    keys.issue(1);
+    let reissued = assembler.compose_message(1, fragments);
+    assert!(matches!(reissued, Err(AssemblyError::Spoofed(1))));
+}
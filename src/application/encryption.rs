@@ -0,0 +1,152 @@
+use crate::application::assembler::{Assembler, AssemblyError};
+use crate::message::base_message::{Message, MessageContent};
+use crate::message::handshake::PublicKeyBytes;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use hkdf::Hkdf;
+use serde::de::DeserializeOwned;
+use sha2::Sha256;
+use std::collections::HashMap;
+use wg_2024::network::NodeId;
+use x25519_dalek::{PublicKey, StaticSecret};
+
+/// Written before a sealed message instead of the tag of a codec.
+pub const SEALED_TAG: u8 = b's';
+
+/// Bytes of the random nonce following the tag of a sealed message.
+const NONCE_SIZE: usize = 12;
+
+/// Tells the keys derived for sealing apart from any other use of the agreement.
+const KEY_CONTEXT: &[u8] = b"droning sealed messages";
+
+/// The key pair of a host and the keys it agreed with the hosts it exchanged a
+/// [`Handshake`](crate::message::handshake::Handshake) with. The handshakes are signed like
+/// every other message, so a drone can neither read the sealed messages nor take part in a
+/// handshake in place of a host, as long as the hosts were issued their keys.
+pub struct Keyring {
+    secret: StaticSecret,
+    public: PublicKey,
+    /// The cipher of the key agreed with every host.
+    peers: HashMap<NodeId, ChaCha20Poly1305>,
+}
+
+impl Default for Keyring {
+    fn default() -> Self {
+        let secret = StaticSecret::random_from_rng(rand::thread_rng());
+        Keyring {
+            public: PublicKey::from(&secret),
+            secret,
+            peers: HashMap::new(),
+        }
+    }
+}
+
+impl Keyring {
+    pub fn public_key(&self) -> PublicKeyBytes {
+        self.public.to_bytes()
+    }
+
+    /// Agrees a key with the host from its public key, replacing the one agreed before. A key
+    /// that would make the agreed one predictable is refused, returning false.
+    pub fn agree(&mut self, peer: NodeId, public_key: PublicKeyBytes) -> bool {
+        let shared = self.secret.diffie_hellman(&PublicKey::from(public_key));
+        if !shared.was_contributory() {
+            return false;
+        }
+        // both public keys go into the key, in the same order for the two hosts
+        let mut public_keys = [self.public.to_bytes(), public_key];
+        public_keys.sort();
+        let mut info = KEY_CONTEXT.to_vec();
+        info.extend(public_keys.concat());
+        let mut key = [0; 32];
+        Hkdf::<Sha256>::new(None, shared.as_bytes())
+            .expand(&info, &mut key)
+            .expect("32 bytes are far below what HKDF-SHA256 can expand to");
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(&key));
+        self.peers.insert(peer, cipher);
+        true
+    }
+
+    pub fn has_key(&self, peer: NodeId) -> bool {
+        self.peers.contains_key(&peer)
+    }
+
+    /// Forgets the key agreed with the host, which may have lost it restarting.
+    pub fn forget(&mut self, peer: NodeId) {
+        self.peers.remove(&peer);
+    }
+
+    /// Seals the encoded message for the host if a key was agreed with it, leaving it as it is
+    /// otherwise.
+    pub fn seal(&self, peer: NodeId, bytes: Vec<u8>) -> Vec<u8> {
+        let Some(cipher) = self.peers.get(&peer) else {
+            return bytes;
+        };
+        let nonce: [u8; NONCE_SIZE] = rand::random();
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce), bytes.as_slice())
+            .expect("messages are far below the size ChaCha20-Poly1305 can seal");
+        let mut sealed = Vec::with_capacity(1 + NONCE_SIZE + ciphertext.len());
+        sealed.push(SEALED_TAG);
+        sealed.extend_from_slice(&nonce);
+        sealed.extend(ciphertext);
+        sealed
+    }
+
+    /// Opens the message the host sealed, if the assembler found it sealed, passing on any
+    /// other result.
+    pub fn open<M: MessageContent + DeserializeOwned>(
+        &self,
+        peer: NodeId,
+        result: Result<Message<M>, AssemblyError>,
+    ) -> Result<Message<M>, AssemblyError> {
+        let Err(AssemblyError::Sealed(sealed)) = result else {
+            return result;
+        };
+        let Some(cipher) = self.peers.get(&peer) else {
+            return Err(AssemblyError::Sealed(sealed));
+        };
+        if sealed.len() < 1 + NONCE_SIZE {
+            return Err(AssemblyError::Truncated);
+        }
+        let (nonce, ciphertext) = sealed[1..].split_at(NONCE_SIZE);
+        let bytes = cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_err| AssemblyError::Forged)?;
+        Assembler::read(bytes)
+    }
+}
+
+#[test]
+fn sealed_messages_are_opened_with_the_key_agreed() {
+    use crate::application::assembler::Disassembler;
+    use crate::message::chat_message::ChatRequest;
+    use crate::message::codec::MessageCodec;
+    use crate::message::page::PageRequest;
+
+    let (mut client, mut server) = (Keyring::default(), Keyring::default());
+    assert!(client.agree(2, server.public_key()));
+    assert!(server.agree(1, client.public_key()));
+    let seal = |session_id, request| {
+        let message = Message::new(1, 2, session_id, request);
+        let fragments = Disassembler::<ChatRequest>::new().disassembly_sealed(
+            message,
+            MessageCodec::Json,
+            &client,
+        );
+        Assembler::<ChatRequest>::new().compose_message(1, fragments)
+    };
+    let sealed = seal(7, ChatRequest::Register("ada".to_string()));
+    assert!(matches!(sealed, Err(AssemblyError::Sealed(_))));
+    let opened = server.open(1, sealed).unwrap();
+    assert!(matches!(opened.content, ChatRequest::Register(name) if name == "ada"));
+    // a host that agreed no key cannot read it, nor one that agreed another key
+    let sealed = seal(8, ChatRequest::ClientList(PageRequest::default()));
+    assert!(matches!(
+        Keyring::default().open(1, sealed),
+        Err(AssemblyError::Sealed(_))
+    ));
+    server.agree(1, Keyring::default().public_key());
+    let sealed = seal(9, ChatRequest::ClientList(PageRequest::default()));
+    assert!(matches!(server.open(1, sealed), Err(AssemblyError::Forged)));
+}
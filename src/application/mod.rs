@@ -1,4 +1,8 @@
 pub mod assembler;
+pub mod authentication;
+pub mod encryption;
+pub mod retransmission;
+pub mod route_policy;
 pub mod routing;
 pub mod simulation_controller_messages;
 pub mod topology;
@@ -0,0 +1,91 @@
+use crate::message::base_message::Priority;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use wg_2024::network::NodeId;
+
+/// Retransmissions of a single fragment after which its message is given up.
+pub const MAX_RETRANSMISSIONS: u32 = 16;
+/// Delay before the first retransmission of a fragment, doubled at every following one.
+const BASE_BACKOFF: Duration = Duration::from_millis(10);
+const MAX_BACKOFF: Duration = Duration::from_secs(1);
+/// How often hosts look for the retransmissions that are due.
+pub const RETRANSMISSION_TICK: Duration = Duration::from_millis(10);
+
+struct PendingRetransmission {
+    due: Instant,
+    avoided: Option<NodeId>,
+    priority: Priority,
+}
+
+/// Delays the retransmissions of the nacked fragments with exponential backoff, so that a
+/// broken route is not hammered, and tells when a fragment ran out of retries. The fragments
+/// of the messages with a higher priority are retransmitted first.
+#[derive(Default)]
+pub struct RetransmissionScheduler {
+    attempts: HashMap<(u64, u64), u32>,
+    pending: HashMap<(u64, u64), PendingRetransmission>,
+}
+
+impl RetransmissionScheduler {
+    /// Schedules the retransmission of a fragment, returning `false` if it has no retries left.
+    pub fn schedule(
+        &mut self,
+        session_id: u64,
+        fragment_index: u64,
+        avoided: Option<NodeId>,
+        priority: Priority,
+    ) -> bool {
+        let attempts = self
+            .attempts
+            .entry((session_id, fragment_index))
+            .or_insert(0);
+        if *attempts >= MAX_RETRANSMISSIONS {
+            return false;
+        }
+        let backoff = BASE_BACKOFF
+            .saturating_mul(2u32.saturating_pow(*attempts))
+            .min(MAX_BACKOFF);
+        *attempts += 1;
+        self.pending.insert(
+            (session_id, fragment_index),
+            PendingRetransmission {
+                due: Instant::now() + backoff,
+                avoided,
+                priority,
+            },
+        );
+        true
+    }
+
+    /// Takes the retransmissions whose backoff elapsed, with the drone each one should avoid,
+    /// the most urgent first and the longest due first among those as urgent.
+    pub fn due(&mut self) -> Vec<(u64, u64, Option<NodeId>)> {
+        let now = Instant::now();
+        let mut due: Vec<_> = self
+            .pending
+            .iter()
+            .filter(|(_key, pending)| pending.due <= now)
+            .map(|(key, pending)| (*key, pending.avoided, pending.priority, pending.due))
+            .collect();
+        due.sort_by_key(|(_key, _avoided, priority, due)| (Reverse(*priority), *due));
+        due.into_iter()
+            .map(|((session_id, fragment_index), avoided, _priority, _due)| {
+                self.pending.remove(&(session_id, fragment_index));
+                (session_id, fragment_index, avoided)
+            })
+            .collect()
+    }
+
+    pub fn fragment_acknowledged(&mut self, session_id: u64, fragment_index: u64) {
+        self.attempts.remove(&(session_id, fragment_index));
+        self.pending.remove(&(session_id, fragment_index));
+    }
+
+    pub fn forget_session(&mut self, session_id: u64) {
+        self.attempts
+            .retain(|(session, _), _| *session != session_id);
+        self.pending
+            .retain(|(session, _), _| *session != session_id);
+    }
+}
@@ -0,0 +1,91 @@
+use super::routing::Route;
+use std::collections::HashMap;
+use wg_2024::network::NodeId;
+
+/// Share of the cheapest cost by which a route may be more expensive and still be used by
+/// [`LoadBalancing`] and kept by [`Sticky`].
+const COST_SLACK: f32 = 0.2;
+
+/// How a [`SourceRouter`](super::routing::SourceRouter) picks one of the routes it knows to a
+/// destination.
+pub trait RoutePolicy: Send + Sync {
+    /// Picks one of `routes`, which are never empty and sorted cheapest first, returning its
+    /// index.
+    fn choose(&mut self, destination: NodeId, routes: &[(f32, &Route)]) -> usize;
+}
+
+/// The cheapest route, taking turns among the ones with the same cost.
+#[derive(Default)]
+pub struct LowestCost {
+    request_count: usize,
+}
+
+impl RoutePolicy for LowestCost {
+    fn choose(&mut self, _destination: NodeId, routes: &[(f32, &Route)]) -> usize {
+        let min_cost = routes[0].0;
+        #[allow(clippy::float_equality_without_abs)]
+        let ties = routes
+            .iter()
+            .take_while(|(cost, _route)| cost - min_cost < f32::EPSILON)
+            .count();
+        let chosen = self.request_count % ties;
+        self.request_count = self.request_count.overflowing_add(1).0;
+        chosen
+    }
+}
+
+/// The route crossing the fewest drones, the cheapest among those.
+pub struct FewestHops;
+
+impl RoutePolicy for FewestHops {
+    fn choose(&mut self, _destination: NodeId, routes: &[(f32, &Route)]) -> usize {
+        routes
+            .iter()
+            .enumerate()
+            .min_by_key(|(_index, (_cost, route))| route.hops().len())
+            .map_or(0, |(index, _route)| index)
+    }
+}
+
+/// Takes turns among the routes not much more expensive than the cheapest, to spread the
+/// fragments of big messages over the network.
+#[derive(Default)]
+pub struct LoadBalancing {
+    request_count: usize,
+}
+
+impl RoutePolicy for LoadBalancing {
+    fn choose(&mut self, _destination: NodeId, routes: &[(f32, &Route)]) -> usize {
+        let max_cost = routes[0].0 * (1.0 + COST_SLACK);
+        let usable = routes
+            .iter()
+            .take_while(|(cost, _route)| *cost <= max_cost)
+            .count()
+            .max(1);
+        let chosen = self.request_count % usable;
+        self.request_count = self.request_count.overflowing_add(1).0;
+        chosen
+    }
+}
+
+/// Keeps using the same route to a destination while it is not much more expensive than the
+/// cheapest, so the fragments of a message arrive in order.
+#[derive(Default)]
+pub struct Sticky {
+    chosen: HashMap<NodeId, Vec<NodeId>>,
+}
+
+impl RoutePolicy for Sticky {
+    fn choose(&mut self, destination: NodeId, routes: &[(f32, &Route)]) -> usize {
+        let max_cost = routes[0].0 * (1.0 + COST_SLACK);
+        let previous = self.chosen.get(&destination).and_then(|hops| {
+            routes
+                .iter()
+                .position(|(cost, route)| route.hops() == hops.as_slice() && *cost <= max_cost)
+        });
+        let chosen = previous.unwrap_or(0);
+        self.chosen
+            .insert(destination, routes[chosen].1.hops().to_vec());
+        chosen
+    }
+}
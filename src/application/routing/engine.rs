@@ -0,0 +1,249 @@
+//! The routing algorithms, working on any graph of [`RoutingNode`]s and independent from the
+//! packets the topology is learned from.
+
+use graph::{AdjacencyVecGraph, ReferenceGraph};
+#[cfg(feature = "path-enumeration")]
+use std::collections::HashMap;
+#[cfg(not(feature = "path-enumeration"))]
+use std::collections::{HashMap, HashSet};
+
+/// The id of a node, the same as the one used by the network.
+pub type NodeId = u8;
+
+/// Number of distinct routes kept per destination, to have alternatives when one fails.
+#[cfg(not(feature = "path-enumeration"))]
+const ROUTES_PER_DESTINATION: usize = 4;
+
+/// A node of the graph the routes are searched on.
+pub trait RoutingNode {
+    /// The cost of crossing the node, coming from `previous` if it is not the first hop.
+    fn hop_cost(&self, previous: Option<NodeId>) -> f32;
+    /// Whether routes can cross the node instead of only starting or ending there.
+    fn is_relay(&self) -> bool;
+}
+
+#[derive(Clone, Default)]
+pub struct Route {
+    hops: Vec<NodeId>,
+}
+
+impl Route {
+    pub fn new(hops: Vec<NodeId>) -> Self {
+        Route { hops }
+    }
+    pub fn cost<N: RoutingNode>(&self, graph: &AdjacencyVecGraph<NodeId, N>) -> f32 {
+        let first = self.hops.first().map_or(0.0, |id| graph[id].hop_cost(None));
+        let links: f32 = self
+            .hops
+            .windows(2)
+            .map(|hop| graph[&hop[1]].hop_cost(Some(hop[0])))
+            .sum();
+        first + links
+    }
+    pub fn source(&self) -> Option<NodeId> {
+        self.hops.first().cloned()
+    }
+    pub fn destination(&self) -> Option<NodeId> {
+        self.hops.last().cloned()
+    }
+    pub fn hops(&self) -> &[NodeId] {
+        &self.hops
+    }
+    pub fn get_incremented(&self, last_hop: NodeId) -> Route {
+        let mut hops = self.hops.clone();
+        hops.push(last_hop);
+        Route { hops }
+    }
+
+    pub fn contains(&self, adj: &NodeId) -> bool {
+        self.hops.contains(adj)
+    }
+
+    pub fn contains_edge(&self, from: NodeId, to: NodeId) -> bool {
+        self.hops.windows(2).any(|window| window == [from, to])
+    }
+}
+
+/// The [`ROUTES_PER_DESTINATION`] cheapest routes from `source_id` to `destination`.
+#[cfg(not(feature = "path-enumeration"))]
+pub fn find_routes<N: RoutingNode>(
+    graph: &AdjacencyVecGraph<NodeId, N>,
+    source_id: NodeId,
+    destination: NodeId,
+) -> Vec<Route> {
+    k_shortest_routes(graph, source_id, destination, ROUTES_PER_DESTINATION)
+}
+
+/// Every route from `source_id` to `destination` that crosses only relays.
+#[cfg(feature = "path-enumeration")]
+pub fn find_routes<N: RoutingNode>(
+    graph: &AdjacencyVecGraph<NodeId, N>,
+    source_id: NodeId,
+    destination: NodeId,
+) -> Vec<Route> {
+    calculate_routes(graph, source_id)
+        .into_iter()
+        .filter(|route| {
+            let host_count = route.hops.iter().filter(|id| !graph[id].is_relay()).count();
+            route.destination() == Some(destination) && host_count == 2
+        })
+        .collect()
+}
+
+/// Yen's algorithm: the `k` cheapest loopless routes from `source_id` to `destination`.
+///
+/// Each new route deviates from the previous one at some node, so the routes found are
+/// distinct even when they cost the same.
+#[cfg(not(feature = "path-enumeration"))]
+fn k_shortest_routes<N: RoutingNode>(
+    graph: &AdjacencyVecGraph<NodeId, N>,
+    source_id: NodeId,
+    destination: NodeId,
+    k: usize,
+) -> Vec<Route> {
+    let Some(first) = cheapest_route(
+        graph,
+        source_id,
+        destination,
+        &HashSet::new(),
+        &HashSet::new(),
+    ) else {
+        return Vec::new();
+    };
+    let mut routes = vec![first];
+    let mut candidates: Vec<Route> = Vec::new();
+
+    while routes.len() < k {
+        let previous = routes.last().unwrap().hops.clone();
+        for spur_index in 0..previous.len() - 1 {
+            let spur_node = previous[spur_index];
+            let root = &previous[..=spur_index];
+            let removed_edges = routes
+                .iter()
+                .filter(|route| route.hops.starts_with(root))
+                .filter_map(|route| route.hops.get(spur_index + 1))
+                .map(|next| (spur_node, *next))
+                .collect::<HashSet<_>>();
+            let removed_nodes = root[..spur_index].iter().copied().collect::<HashSet<_>>();
+            let Some(spur) = cheapest_route(
+                graph,
+                spur_node,
+                destination,
+                &removed_nodes,
+                &removed_edges,
+            ) else {
+                continue;
+            };
+            let mut hops = root[..spur_index].to_vec();
+            hops.extend(spur.hops);
+            if !candidates
+                .iter()
+                .chain(routes.iter())
+                .any(|route| route.hops == hops)
+            {
+                candidates.push(Route::new(hops));
+            }
+        }
+
+        let Some(best) = candidates
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| a.cost(graph).total_cmp(&b.cost(graph)))
+            .map(|(index, _route)| index)
+        else {
+            break;
+        };
+        routes.push(candidates.swap_remove(best));
+    }
+    routes
+}
+
+/// Dijkstra's algorithm: the cheapest route from `source_id` to `destination`, skipping the
+/// `removed_nodes` and `removed_edges`.
+///
+/// Only relays are crossed, the other nodes can just be the end of a route.
+#[cfg(not(feature = "path-enumeration"))]
+fn cheapest_route<N: RoutingNode>(
+    graph: &AdjacencyVecGraph<NodeId, N>,
+    source_id: NodeId,
+    destination: NodeId,
+    removed_nodes: &HashSet<NodeId>,
+    removed_edges: &HashSet<(NodeId, NodeId)>,
+) -> Option<Route> {
+    let mut costs = HashMap::from([(source_id, graph[&source_id].hop_cost(None))]);
+    let mut predecessors = HashMap::new();
+    let mut visited = HashSet::new();
+
+    while let Some((current, cost)) = costs
+        .iter()
+        .filter(|(id, _cost)| !visited.contains(*id))
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(id, cost)| (*id, *cost))
+    {
+        if current == destination {
+            let mut hops = vec![destination];
+            while let Some(predecessor) = predecessors.get(hops.last().unwrap()) {
+                hops.push(*predecessor);
+            }
+            hops.reverse();
+            return Some(Route::new(hops));
+        }
+        visited.insert(current);
+        if current != source_id && !graph[&current].is_relay() {
+            continue;
+        }
+        for adj in graph.adjacents(&current) {
+            if visited.contains(adj)
+                || removed_nodes.contains(adj)
+                || removed_edges.contains(&(current, *adj))
+            {
+                continue;
+            }
+            let candidate = cost + graph[adj].hop_cost(Some(current));
+            if !costs.get(adj).is_some_and(|known| *known <= candidate) {
+                costs.insert(*adj, candidate);
+                predecessors.insert(*adj, current);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(feature = "path-enumeration")]
+fn extend_route<G: ReferenceGraph<NodeKey = NodeId>>(graph: &G, route: Route) -> Vec<Route> {
+    let last_node_id = route.destination().unwrap();
+    graph
+        .adjacents(&last_node_id)
+        .copied()
+        .filter(|adj| !route.contains(adj))
+        .map(|adj| route.get_incremented(adj))
+        .collect()
+}
+
+/// Enumerates every simple path from `source_id`, only kept to compare against
+/// `k_shortest_routes` since it grows exponentially on dense topologies.
+#[cfg(feature = "path-enumeration")]
+fn calculate_routes<G: ReferenceGraph<NodeKey = NodeId>>(
+    graph: &G,
+    source_id: NodeId,
+) -> Vec<Route> {
+    let mut routes = HashMap::with_capacity(15);
+    routes.insert(1, vec![Route::new(vec![source_id])]);
+
+    for i in 1u8.. {
+        let old_routes = routes.get(&i).unwrap();
+        let mut new_routes = Vec::new();
+
+        for route in old_routes.iter() {
+            new_routes.extend(extend_route(graph, route.clone()));
+        }
+
+        if new_routes.is_empty() {
+            break;
+        }
+
+        routes.insert(i + 1, new_routes);
+    }
+
+    routes.into_values().flatten().collect()
+}
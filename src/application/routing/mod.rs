@@ -0,0 +1,721 @@
+mod engine;
+
+pub use engine::Route;
+
+use super::route_policy::{LowestCost, RoutePolicy};
+use super::topology::information::{Information, InformationPack};
+use super::topology::node::{self, FragmentDelivery, Node, NodeType, PdrEstimator};
+use engine::{find_routes, RoutingNode};
+use graph::{AdjacencyVecGraph, ReferenceGraph};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+use wg_2024::packet::{Packet, PacketType};
+
+/// How often hosts send their routing table to the simulation controller.
+pub const ROUTING_TABLE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Time after which the nodes and edges not seen in any packet are forgotten.
+const TOPOLOGY_LIFETIME: Duration = Duration::from_secs(120);
+
+/// Most nodes kept in the graph by default.
+const MAX_KNOWN_NODES: usize = 128;
+
+/// Drops in a row after which a route is demoted.
+pub const MAX_CONSECUTIVE_DROPS: u32 = 3;
+/// Extra cost of a route that was just demoted.
+const DEMOTION_PENALTY: f32 = 5.0;
+/// Time after which the penalty of a demoted route halves.
+const DEMOTION_HALF_LIFE: Duration = Duration::from_secs(5);
+
+/// The recent failures of a route.
+#[derive(Default)]
+struct RouteHealth {
+    consecutive_drops: u32,
+    demoted_at: Option<Instant>,
+}
+
+impl RouteHealth {
+    fn penalty(&self) -> f32 {
+        self.demoted_at.map_or(0.0, |demoted_at| {
+            let half_lives = demoted_at.elapsed().as_secs_f32() / DEMOTION_HALF_LIFE.as_secs_f32();
+            DEMOTION_PENALTY * 0.5f32.powf(half_lives)
+        })
+    }
+}
+
+/// A route kept by a [`SourceRouter`], as shown to the simulation controller.
+#[derive(Debug, Clone, Serialize)]
+pub struct RouteDescription {
+    pub destination: NodeId,
+    pub hops: Vec<NodeId>,
+    /// The cost of the route, with the penalty it has if it was demoted.
+    pub cost: f32,
+    /// Time since a fragment was last sent along the route, if any was.
+    pub last_used: Option<Duration>,
+}
+
+/// The tunables of a [`SourceRouter`].
+#[derive(Debug, Clone, Copy)]
+pub struct RouterSettings {
+    /// How the PDR of the drones is estimated.
+    pub pdr_estimator: PdrEstimator,
+    /// Time after which the nodes and edges not seen in any packet are forgotten.
+    pub topology_lifetime: Duration,
+    /// Most nodes kept in the graph, the source included: the least recently seen are evicted
+    /// first.
+    pub max_nodes: usize,
+}
+
+impl Default for RouterSettings {
+    fn default() -> Self {
+        Self {
+            pdr_estimator: PdrEstimator::default(),
+            topology_lifetime: TOPOLOGY_LIFETIME,
+            max_nodes: MAX_KNOWN_NODES,
+        }
+    }
+}
+
+impl RoutingNode for Node {
+    fn hop_cost(&self, previous: Option<NodeId>) -> f32 {
+        self.node_type.cost_from(previous)
+    }
+    fn is_relay(&self) -> bool {
+        matches!(self.node_type, NodeType::Drone(_))
+    }
+}
+
+impl Route {
+    pub fn to_source_routing_header(&self) -> SourceRoutingHeader {
+        SourceRoutingHeader::initialize(self.hops().to_vec())
+    }
+}
+
+impl From<Route> for SourceRoutingHeader {
+    fn from(val: Route) -> Self {
+        val.to_source_routing_header()
+    }
+}
+
+impl From<&Route> for SourceRoutingHeader {
+    fn from(val: &Route) -> Self {
+        val.clone().into()
+    }
+}
+
+pub struct SourceRouter {
+    #[cfg(test)]
+    pub graph: AdjacencyVecGraph<NodeId, Node>,
+    #[cfg(not(test))]
+    graph: AdjacencyVecGraph<NodeId, Node>,
+    source_id: NodeId,
+    /// The routes to each destination with the cost they had when they were found, cheapest first.
+    routes: HashMap<NodeId, Vec<(f32, Route)>>,
+    /// Destinations whose routes must be searched again before being used.
+    dirty: HashSet<NodeId>,
+    /// When each unacknowledged fragment was sent and along which hops, by session and index.
+    in_flight: HashMap<(u64, u64), (Instant, Vec<NodeId>)>,
+    /// The routes that recently dropped fragments, by hops.
+    health: HashMap<Vec<NodeId>, RouteHealth>,
+    /// The route each dropped fragment was on, by session and index, until it is retransmitted.
+    failed_routes: HashMap<(u64, u64), Vec<NodeId>>,
+    /// When a fragment was last sent along each route, by hops.
+    last_used: HashMap<Vec<NodeId>, Instant>,
+    settings: RouterSettings,
+    /// When each node was last seen in a packet.
+    nodes_seen: HashMap<NodeId, Instant>,
+    /// When each edge was last seen in a packet, by its lower id first.
+    edges_seen: HashMap<(NodeId, NodeId), Instant>,
+    /// Picks which of the routes to a destination is used.
+    policy: Box<dyn RoutePolicy>,
+}
+
+impl SourceRouter {
+    pub fn new(source: Node) -> Self {
+        let mut graph = AdjacencyVecGraph::new();
+        let source_id = source.id;
+        graph.add_node(source.id, source);
+        Self {
+            graph,
+            source_id,
+            routes: HashMap::new(),
+            dirty: HashSet::new(),
+            in_flight: HashMap::new(),
+            health: HashMap::new(),
+            failed_routes: HashMap::new(),
+            last_used: HashMap::new(),
+            settings: RouterSettings::default(),
+            nodes_seen: HashMap::new(),
+            edges_seen: HashMap::new(),
+            policy: Box::new(LowestCost::default()),
+        }
+    }
+    pub fn with_settings(mut self, settings: RouterSettings) -> Self {
+        self.settings = settings;
+        let ids: Vec<_> = self.graph.iter().map(|(id, _node)| *id).collect();
+        for id in ids {
+            if let Some(Node {
+                node_type: NodeType::Drone(drone),
+                ..
+            }) = self.graph.get_mut(&id)
+            {
+                drone.set_estimator(settings.pdr_estimator);
+            }
+        }
+        self
+    }
+    pub fn with_route_policy(mut self, policy: Box<dyn RoutePolicy>) -> Self {
+        self.policy = policy;
+        self
+    }
+    pub fn set_route_policy(&mut self, policy: Box<dyn RoutePolicy>) {
+        self.policy = policy;
+    }
+    /// The route to `destination` picked by the route policy among the known ones.
+    pub fn get_best_route(&mut self, destination: NodeId) -> Option<SourceRoutingHeader> {
+        self.refresh(destination);
+        let routes = rank_routes(&self.routes, &self.health, destination);
+        if routes.is_empty() {
+            return None;
+        }
+
+        let chosen = self.policy.choose(destination, &routes);
+        let (_cost, route) = routes.get(chosen).unwrap_or(&routes[0]);
+
+        Some(route.to_source_routing_header())
+    }
+    /// The cheapest route to `destination` that does not cross `avoided`, falling back to
+    /// [`SourceRouter::get_best_route`] if every known route does.
+    pub fn get_route_avoiding(
+        &mut self,
+        destination: NodeId,
+        avoided: NodeId,
+    ) -> Option<SourceRoutingHeader> {
+        self.refresh(destination);
+        let avoiding = self
+            .ranked_routes(destination)
+            .into_iter()
+            .find(|(_cost, route)| !route.contains(&avoided))
+            .map(|(_cost, route)| route.to_source_routing_header());
+        avoiding.or_else(|| self.get_best_route(destination))
+    }
+    /// The route to retransmit a dropped fragment on: the cheapest among the ones sharing the
+    /// fewest drones with the route it was dropped on, crossing `avoided` only if all do.
+    pub fn get_retransmission_route(
+        &mut self,
+        destination: NodeId,
+        session_id: u64,
+        fragment_index: u64,
+        avoided: Option<NodeId>,
+    ) -> Option<SourceRoutingHeader> {
+        let Some(failed) = self.failed_routes.remove(&(session_id, fragment_index)) else {
+            return match avoided {
+                Some(avoided) => self.get_route_avoiding(destination, avoided),
+                None => self.get_best_route(destination),
+            };
+        };
+        let failed_drones: HashSet<_> = failed
+            .get(1..failed.len().saturating_sub(1))
+            .unwrap_or_default()
+            .iter()
+            .collect();
+        self.refresh(destination);
+        let disjoint = self
+            .ranked_routes(destination)
+            .into_iter()
+            .min_by_key(|(_cost, route)| {
+                let crosses_avoided = avoided.is_some_and(|avoided| route.contains(&avoided));
+                let shared = route
+                    .hops()
+                    .iter()
+                    .filter(|id| failed_drones.contains(id))
+                    .count();
+                (crosses_avoided, shared)
+            })
+            .map(|(_cost, route)| route.to_source_routing_header());
+        disjoint.or_else(|| self.get_best_route(destination))
+    }
+    pub fn update_graph(&mut self, infos: &impl InformationPack) {
+        let source = self.graph[&self.source_id].clone();
+        let now = Instant::now();
+        for info in infos.get_information(&source) {
+            match info {
+                Information::AddNode(node) => {
+                    self.nodes_seen.insert(node.id, now);
+                    self.add_node(node);
+                }
+                Information::AddEdge(from, to) => {
+                    self.edges_seen.insert((from.min(to), from.max(to)), now);
+                    self.add_edge(from, to)
+                }
+                Information::RemoveEdge(from, to) => {
+                    self.remove_edge(from, to);
+                }
+            }
+        }
+        self.evict_least_recently_seen();
+    }
+
+    pub fn add_edge(&mut self, from: u8, to: u8) {
+        if !self.graph.is_adjacent_to(&from, &to) {
+            let affected = self.destinations_affected_by(from, to);
+            self.dirty.extend(affected);
+        }
+        self.graph.add_undirected_edge(from, to)
+    }
+
+    pub fn remove_edge(&mut self, from: u8, to: u8) {
+        self.graph.remove_undirected_edge(&from, &to);
+        for (destination, routes) in self.routes.iter_mut() {
+            let count = routes.len();
+            routes.retain(|(_cost, route)| {
+                !route.contains_edge(from, to) && !route.contains_edge(to, from)
+            });
+            if routes.len() != count {
+                self.dirty.insert(*destination);
+            }
+        }
+        self.routes
+            .retain(|_destination, routes| !routes.is_empty());
+    }
+
+    pub fn add_node(&mut self, node: Node) {
+        let id = node.id;
+        if let Some(current) = self.graph.get_mut(&id) {
+            if current.is_other_useful(&node) {
+                match current.node_type {
+                    NodeType::Drone(ref mut drone) => {
+                        if let NodeType::Drone(new_drone) = node.node_type {
+                            drone.merge_drone(new_drone);
+                        }
+                    }
+                    NodeType::Server(_) | NodeType::Client(_) => {
+                        self.graph.add_node(id, node);
+                        // its application may have made it a destination
+                        self.dirty.insert(id);
+                    }
+                }
+                self.invalidate_routes_through(id);
+            }
+        } else {
+            let mut node = node;
+            if let NodeType::Drone(ref mut drone) = node.node_type {
+                drone.set_estimator(self.settings.pdr_estimator);
+            }
+            if self.is_destination(&node) {
+                self.dirty.insert(id);
+            }
+            self.graph.add_node(id, node);
+        }
+    }
+    /// Starts timing the round trip of `packet`, if it is a fragment.
+    pub fn fragment_sent(&mut self, packet: &Packet) {
+        if let PacketType::MsgFragment(fragment) = &packet.pack_type {
+            self.last_used
+                .insert(packet.routing_header.hops.clone(), Instant::now());
+            self.in_flight.insert(
+                (packet.session_id, fragment.fragment_index),
+                (Instant::now(), packet.routing_header.hops.clone()),
+            );
+        }
+    }
+
+    /// Splits the round trip time of an acknowledged fragment among the drones it crossed,
+    /// counting it as forwarded on every link it took.
+    ///
+    /// The cached routes through them are only costed again, not searched again, so that
+    /// every ack does not throw the cache away.
+    pub fn fragment_acknowledged(&mut self, session_id: u64, fragment_index: u64) {
+        let Some((sent, hops)) = self.in_flight.remove(&(session_id, fragment_index)) else {
+            return;
+        };
+        if let Some(health) = self.health.get_mut(&hops) {
+            health.consecutive_drops = 0;
+        }
+        let drones = hops
+            .get(1..hops.len().saturating_sub(1))
+            .unwrap_or_default();
+        if drones.is_empty() {
+            return;
+        }
+        let latency = sent.elapsed() / drones.len() as u32;
+        for (previous, id) in hops.iter().zip(drones) {
+            if let Some(Node {
+                node_type: NodeType::Drone(drone),
+                ..
+            }) = self.graph.get_mut(id)
+            {
+                drone.record_latency(latency);
+                drone.record_link_delivery(*previous, FragmentDelivery::Forwarded);
+            }
+        }
+        self.recost_routes_through(drones);
+    }
+
+    /// Forgets the fragments of the session still in flight or waiting for a retransmission,
+    /// once it completed, failed or was evicted: the ones lost on the way are never acked nor
+    /// nacked, and the ones given up are never retransmitted.
+    pub fn forget_session(&mut self, session_id: u64) {
+        self.in_flight
+            .retain(|&(session, _index), _sent| session != session_id);
+        self.failed_routes
+            .retain(|&(session, _index), _hops| session != session_id);
+    }
+
+    /// Counts a drop of the fragment on its route, demoting the route after
+    /// [`MAX_CONSECUTIVE_DROPS`] drops in a row.
+    pub fn fragment_dropped(&mut self, session_id: u64, fragment_index: u64) {
+        let Some((_sent, hops)) = self.in_flight.remove(&(session_id, fragment_index)) else {
+            return;
+        };
+        self.failed_routes
+            .insert((session_id, fragment_index), hops.clone());
+        self.health
+            .retain(|_hops, health| health.consecutive_drops > 0 || health.penalty() > 0.01);
+        let health = self.health.entry(hops).or_default();
+        health.consecutive_drops += 1;
+        if health.consecutive_drops >= MAX_CONSECUTIVE_DROPS {
+            health.consecutive_drops = 0;
+            health.demoted_at = Some(Instant::now());
+        }
+    }
+
+    /// Searches the routes to every destination again, after forgetting the stale topology.
+    pub fn calculate_routes(&mut self) -> usize {
+        self.expire_stale_topology();
+        let destinations = self.destinations();
+        self.routes.clear();
+        self.dirty.clear();
+        for destination in destinations {
+            self.search_routes(destination);
+        }
+        self.forget_unused_routes();
+
+        self.routes.values().map(Vec::len).sum()
+    }
+    /// Searches again only the routes to the destinations whose topology changed since the last
+    /// search, after forgetting the stale topology.
+    pub fn update_routes(&mut self) -> usize {
+        self.expire_stale_topology();
+        let dirty: Vec<_> = self.dirty.drain().collect();
+        for destination in dirty {
+            self.search_routes(destination);
+        }
+        self.forget_unused_routes();
+
+        self.routes.values().map(Vec::len).sum()
+    }
+    /// The routes kept to every destination, cheapest first.
+    pub fn describe_routes(&self) -> Vec<RouteDescription> {
+        let mut destinations: Vec<_> = self.routes.keys().copied().collect();
+        destinations.sort();
+        destinations
+            .into_iter()
+            .flat_map(|destination| {
+                self.ranked_routes(destination)
+                    .into_iter()
+                    .map(move |(cost, route)| RouteDescription {
+                        destination,
+                        hops: route.hops().to_vec(),
+                        cost,
+                        last_used: self.last_used.get(route.hops()).map(|used| used.elapsed()),
+                    })
+            })
+            .collect()
+    }
+    /// The topology known by the router in the Graphviz DOT format, with the cost of every node.
+    pub fn export_dot(&self) -> String {
+        let mut dot = format!("graph host_{} {{\n", self.source_id);
+        let mut nodes: Vec<_> = self.graph.iter().collect();
+        nodes.sort_by_key(|(id, _node)| **id);
+        for (id, node) in nodes {
+            let shape = match node.node_type {
+                NodeType::Drone(_) => "ellipse",
+                NodeType::Client(_) | NodeType::Server(_) => "box",
+            };
+            dot.push_str(&format!(
+                "    {id} [label=\"{id}\\n{}\\ncost {:.2}\" shape={shape}];\n",
+                node.node_type,
+                node.cost()
+            ));
+        }
+        let mut edges: Vec<_> = self
+            .graph
+            .edges()
+            .filter(|(from, to)| from < to)
+            .map(|(from, to)| (*from, *to))
+            .collect();
+        edges.sort();
+        for (from, to) in edges {
+            dot.push_str(&format!("    {from} -- {to};\n"));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+    pub fn unwanted_node(&mut self, node_id: &NodeId) {
+        if let Some(node) = self.graph.get_mut(node_id) {
+            if let Some(application) = node.node_type.application_mut() {
+                *application = node::ApplicationType::Unwanted;
+            }
+        }
+        self.routes.remove(node_id);
+        self.dirty.remove(node_id);
+    }
+    /// Records what a server answered it provides, so it is no longer guessed.
+    pub fn server_type_discovered(&mut self, id: NodeId, application: node::ApplicationType) {
+        if let Some(node) = self.graph.get_mut(&id) {
+            if let Some(known) = node.node_type.application_mut() {
+                *known = application;
+            }
+        }
+    }
+    pub fn forget_topology(&mut self) {
+        let source = self.graph.remove_node(&self.source_id).unwrap();
+        self.graph.clear();
+        self.graph.add_node(self.source_id, source);
+        self.routes.clear();
+        self.dirty.clear();
+        self.nodes_seen.clear();
+        self.edges_seen.clear();
+    }
+    pub fn print_reachable_servers(&self) {
+        println!(
+            "Reachable Servers are: {:?}",
+            self.routes.keys().collect::<HashSet<_>>()
+        )
+    }
+
+    /// The servers with a known route, in id order.
+    pub fn reachable_servers(&self) -> Vec<NodeId> {
+        let mut servers: Vec<_> = self.routes.keys().copied().collect();
+        servers.sort();
+        servers
+    }
+
+    /// The other servers with a known route, in id order, for a server to copy content from.
+    pub fn reachable_peer_servers(&self) -> Vec<NodeId> {
+        let mut servers: Vec<_> = self
+            .routes
+            .keys()
+            .copied()
+            .filter(|id| {
+                self.graph
+                    .get(id)
+                    .is_some_and(|node| matches!(node.node_type, NodeType::Server(_)))
+            })
+            .collect();
+        servers.sort();
+        servers
+    }
+
+    pub(crate) fn can_reach(&self, destination_id: u8) -> bool {
+        self.routes.contains_key(&destination_id)
+    }
+
+    /// Removes the nodes and edges that were not seen for longer than the topology lifetime.
+    ///
+    /// The edges of the source are kept, since they are known from its own channels.
+    fn expire_stale_topology(&mut self) {
+        let lifetime = self.settings.topology_lifetime;
+        let source_id = self.source_id;
+
+        let stale_edges: Vec<_> = self
+            .edges_seen
+            .iter()
+            .filter(|(&(from, to), seen)| {
+                from != source_id && to != source_id && seen.elapsed() > lifetime
+            })
+            .map(|(edge, _seen)| *edge)
+            .collect();
+        for (from, to) in stale_edges {
+            self.edges_seen.remove(&(from, to));
+            self.remove_edge(from, to);
+        }
+
+        let stale_nodes: Vec<_> = self
+            .nodes_seen
+            .iter()
+            .filter(|(&id, seen)| id != source_id && seen.elapsed() > lifetime)
+            .map(|(id, _seen)| *id)
+            .collect();
+        for id in stale_nodes {
+            self.forget_node(id);
+        }
+    }
+
+    /// Evicts the least recently seen nodes while there are more than the settings allow.
+    fn evict_least_recently_seen(&mut self) {
+        let excess = self
+            .graph
+            .iter()
+            .count()
+            .saturating_sub(self.settings.max_nodes);
+        if excess == 0 {
+            return;
+        }
+        // the nodes never seen in a packet come first, then the oldest
+        let mut candidates: Vec<_> = self
+            .graph
+            .iter()
+            .map(|(id, _node)| *id)
+            .filter(|id| *id != self.source_id)
+            .map(|id| (self.nodes_seen.get(&id).copied(), id))
+            .collect();
+        candidates.sort();
+        for (_seen, id) in candidates.into_iter().take(excess) {
+            self.forget_node(id);
+        }
+    }
+
+    /// Removes `id` from the graph together with the routes crossing it.
+    fn forget_node(&mut self, id: NodeId) {
+        self.graph.remove_node(&id);
+        self.nodes_seen.remove(&id);
+        self.edges_seen
+            .retain(|&(from, to), _seen| from != id && to != id);
+        self.routes.remove(&id);
+        self.dirty.remove(&id);
+        for (destination, routes) in self.routes.iter_mut() {
+            let count = routes.len();
+            routes.retain(|(_cost, route)| !route.contains(&id));
+            if routes.len() != count {
+                self.dirty.insert(*destination);
+            }
+        }
+        self.routes
+            .retain(|_destination, routes| !routes.is_empty());
+    }
+
+    /// Drops the last use of the routes that are not kept anymore.
+    fn forget_unused_routes(&mut self) {
+        let kept: HashSet<_> = self
+            .routes
+            .values()
+            .flatten()
+            .map(|(_cost, route)| route.hops())
+            .collect();
+        self.last_used
+            .retain(|hops, _used| kept.contains(hops.as_slice()));
+    }
+
+    /// Whether the source may send messages to `node`: a server it can talk to, another
+    /// server of the same application if the source is a server, or with the `peer-routes`
+    /// feature also another client.
+    fn is_destination(&self, node: &Node) -> bool {
+        let source = &self.graph[&self.source_id];
+        source.is_route_meaningful(node)
+            || source.is_replica_route_meaningful(node)
+            || (cfg!(feature = "peer-routes") && source.is_peer_route_meaningful(node))
+    }
+
+    /// The hosts the source may send messages to.
+    fn destinations(&self) -> Vec<NodeId> {
+        self.graph
+            .iter()
+            .filter(|(_id, node)| self.is_destination(node))
+            .map(|(id, _node)| *id)
+            .collect()
+    }
+
+    /// The destinations whose routes may change with a new edge between `from` and `to`.
+    ///
+    /// Routes cross only drones, so an edge reaching a host can only matter for that host.
+    fn destinations_affected_by(&self, from: NodeId, to: NodeId) -> Vec<NodeId> {
+        let hosts: Vec<_> = [from, to]
+            .into_iter()
+            .filter(|id| {
+                *id != self.source_id
+                    && self
+                        .graph
+                        .get(id)
+                        .is_some_and(|node| !matches!(node.node_type, NodeType::Drone(_)))
+            })
+            .collect();
+        if hosts.is_empty() {
+            self.destinations()
+        } else {
+            hosts
+        }
+    }
+
+    /// Marks the destinations reached through `id` as dirty, since their cost changed.
+    fn invalidate_routes_through(&mut self, id: NodeId) {
+        for (destination, routes) in self.routes.iter() {
+            if routes.iter().any(|(_cost, route)| route.contains(&id)) {
+                self.dirty.insert(*destination);
+            }
+        }
+    }
+
+    /// Updates the cost of the cached routes crossing any of `ids`, keeping them cheapest first.
+    fn recost_routes_through(&mut self, ids: &[NodeId]) {
+        for routes in self.routes.values_mut() {
+            if !routes
+                .iter()
+                .any(|(_cost, route)| ids.iter().any(|id| route.contains(id)))
+            {
+                continue;
+            }
+            for (cost, route) in routes.iter_mut() {
+                *cost = route.cost(&self.graph);
+            }
+            routes.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+        }
+    }
+
+    fn ranked_routes(&self, destination: NodeId) -> Vec<(f32, &Route)> {
+        rank_routes(&self.routes, &self.health, destination)
+    }
+
+    /// Searches the routes to `destination` again if they are dirty.
+    fn refresh(&mut self, destination: NodeId) {
+        if self.dirty.remove(&destination) {
+            self.search_routes(destination);
+        }
+    }
+
+    fn search_routes(&mut self, destination: NodeId) {
+        let mut routes: Vec<_> = match self.graph.get(&destination) {
+            Some(node) if self.is_destination(node) => {
+                find_routes(&self.graph, self.source_id, destination)
+                    .into_iter()
+                    .map(|route| (route.cost(&self.graph), route))
+                    .collect()
+            }
+            _ => Vec::new(),
+        };
+        routes.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+        if routes.is_empty() {
+            self.routes.remove(&destination);
+        } else {
+            self.routes.insert(destination, routes);
+        }
+    }
+
+    #[cfg(test)]
+    #[allow(unused)]
+    pub fn show_graph(&self) {
+        println!("{:?}", self.graph);
+    }
+}
+
+/// The routes to `destination`, cheapest first, with the penalty of the demoted ones.
+fn rank_routes<'a>(
+    routes: &'a HashMap<NodeId, Vec<(f32, Route)>>,
+    health: &HashMap<Vec<NodeId>, RouteHealth>,
+    destination: NodeId,
+) -> Vec<(f32, &'a Route)> {
+    let mut routes: Vec<_> = routes
+        .get(&destination)
+        .into_iter()
+        .flatten()
+        .map(|(cost, route)| {
+            let penalty = health.get(route.hops()).map_or(0.0, RouteHealth::penalty);
+            (cost + penalty, route)
+        })
+        .collect();
+    routes.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+    routes
+}
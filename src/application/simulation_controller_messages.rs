@@ -1,5 +1,13 @@
+use crate::application::assembler::FragmentStats;
+use crate::application::routing::RouteDescription;
+use crate::application::topology::node::ApplicationType;
 use crate::message::base_message::Message;
+use crate::message::server_stats::ServerStats;
 use crossbeam_channel::Sender;
+use serde::Deserialize;
+use std::fmt::Display;
+use std::path::PathBuf;
+use std::time::Duration;
 use wg_2024::{network::NodeId, packet::Packet};
 
 #[derive(Debug)]
@@ -7,6 +15,93 @@ pub enum HostEvent {
     MessageSent(Message<String>),
     MessageReceived(Message<String>),
     FloodInitiated(NodeId, u64),
+    /// `host` started sending or receiving a message of `total_fragments` fragments.
+    SessionStarted {
+        host: NodeId,
+        session_id: u64,
+        direction: SessionDirection,
+        total_fragments: u64,
+    },
+    /// `remaining` fragments of the session still have to be acked or received.
+    SessionProgress {
+        host: NodeId,
+        session_id: u64,
+        remaining: u64,
+    },
+    SessionEnded {
+        host: NodeId,
+        session_id: u64,
+    },
+    /// `host` gave up receiving the message of the session, since its fragments stopped coming.
+    SessionAbandoned {
+        host: NodeId,
+        session_id: u64,
+    },
+    /// `host` dropped the session to stay within its memory budget.
+    SessionEvicted {
+        host: NodeId,
+        session_id: u64,
+        direction: SessionDirection,
+    },
+    /// `host` gave up sending the message of the session, since one of its fragments ran out
+    /// of retransmissions.
+    MessageFailed {
+        host: NodeId,
+        session_id: u64,
+    },
+    /// Every fragment `host` sent in the session was acked, `latency` after the first one left
+    /// over `route`.
+    SessionStats {
+        host: NodeId,
+        session_id: u64,
+        destination: NodeId,
+        latency: Duration,
+        retransmissions: u32,
+        route: Vec<NodeId>,
+    },
+    /// The card game ended, with the points of every player, the best first.
+    GameOver {
+        leaderboard: Vec<(NodeId, u32)>,
+    },
+    /// `host` searched its routes again after one of its periodic floods, finding `routes`.
+    RoutesRecalculated {
+        host: NodeId,
+        routes: usize,
+    },
+    /// The routes `host` currently knows, sent periodically.
+    RoutingTable(NodeId, Vec<RouteDescription>),
+    /// The fragment counters of a host, sent periodically.
+    FragmentStats(NodeId, FragmentStats),
+    /// The requests a server handled, sent periodically.
+    ServerStats(NodeId, ServerStats),
+    /// Files were added to, modified in or removed from the assets `host` serves.
+    LibraryChanged {
+        host: NodeId,
+        updated: Vec<String>,
+        removed: Vec<String>,
+    },
+    /// `host` received a message of the session that `source`, where it comes from, did not
+    /// sign, so a drone or a host without a key forged it.
+    MessageSpoofed {
+        host: NodeId,
+        source: NodeId,
+        session_id: u64,
+    },
+    /// The thread running the behaviour of server `host` panicked, so the server stopped.
+    BehaviourPanicked {
+        host: NodeId,
+    },
+    /// `host` stopped, after its responses were acked or `abandoned` of them could not be.
+    ShutDown {
+        host: NodeId,
+        abandoned: usize,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionDirection {
+    Outgoing,
+    Incoming,
 }
 
 #[derive(Debug)]
@@ -14,4 +109,51 @@ pub enum HostCommand {
     Crash,
     AddConnectedDrone(NodeId, Sender<Packet>),
     RemoveConnectedDrone(NodeId),
+    SendRequest(NodeId, ClientRequest),
+    InitiateFlood,
+    /// Writes the topology known by the host to the file, in the Graphviz DOT format.
+    ExportTopology(PathBuf),
+}
+
+/// A request the controller asks a client to send to a server, independent of the kind of
+/// client: each client turns it into its own request type, refusing the ones it can't send.
+#[derive(Debug, Clone, Deserialize)]
+pub enum ClientRequest {
+    ClientList,
+    Register(String),
+    SendMessage { to: String, message: String },
+    TextList,
+    Text(String),
+    MediaList,
+    Media(String),
+}
+
+impl ClientRequest {
+    pub fn application_type(&self) -> ApplicationType {
+        match self {
+            ClientRequest::ClientList
+            | ClientRequest::Register(_)
+            | ClientRequest::SendMessage { .. } => ApplicationType::Chat,
+            ClientRequest::TextList
+            | ClientRequest::Text(_)
+            | ClientRequest::MediaList
+            | ClientRequest::Media(_) => ApplicationType::Content,
+        }
+    }
+}
+
+impl Display for ClientRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClientRequest::ClientList => write!(f, "ClientList"),
+            ClientRequest::Register(name) => write!(f, "Register({name})"),
+            ClientRequest::SendMessage { to, message } => {
+                write!(f, "SendMessage(to: {to}, message: {message})")
+            }
+            ClientRequest::TextList => write!(f, "TextList"),
+            ClientRequest::Text(name) => write!(f, "Text({name})"),
+            ClientRequest::MediaList => write!(f, "MediaList"),
+            ClientRequest::Media(name) => write!(f, "Media({name})"),
+        }
+    }
 }
@@ -48,10 +48,13 @@ impl InformationPack for (&SourceRoutingHeader, &Fragment) {
             source_node.node_type.weak_counter_part(),
         )));
 
-        for id in iter {
+        for (previous, id) in routing_header.hops.iter().zip(iter) {
             result.push(Information::AddNode(Node::new(
                 *id,
-                NodeType::Drone(Drone::with_delivery(vec![FragmentDelivery::Forwarded])),
+                NodeType::Drone(
+                    Drone::with_delivery(vec![FragmentDelivery::Forwarded])
+                        .with_link_delivery(*previous, FragmentDelivery::Forwarded),
+                ),
             )));
         }
 
@@ -81,10 +84,12 @@ impl InformationPack for (&SourceRoutingHeader, &Ack) {
             source_node.node_type.strong_counter_part(),
         )));
 
+        // every drone on the route of an ack forwarded it, but acks are never dropped: the
+        // links are credited along the route of the acked fragment instead
         for id in iter {
             result.push(Information::AddNode(Node::new(
                 *id,
-                NodeType::Drone(Drone::new()),
+                NodeType::Drone(Drone::with_delivery(vec![FragmentDelivery::Forwarded])),
             )));
         }
 
@@ -164,21 +169,29 @@ impl InformationPack for (&SourceRoutingHeader, &Nack) {
 
                 let mut result = Vec::new();
 
+                // the nack goes back along the route, so every drone got the fragment from the
+                // hop following it in the nack, and the first one dropped it
                 let first_id = *iter.next().unwrap();
+                let mut dropper = Drone::with_delivery(vec![FragmentDelivery::Dropped]);
+                if let Some(previous) = routing_header.hops.get(1) {
+                    dropper = dropper.with_link_delivery(*previous, FragmentDelivery::Dropped);
+                }
                 result.push(Information::AddNode(Node::new(
                     first_id,
-                    NodeType::Drone(Drone::with_delivery(vec![FragmentDelivery::Dropped])),
+                    NodeType::Drone(dropper),
                 )));
 
-                for id in iter {
+                // the last hop is the source of the fragment, which has no previous one
+                for (id, previous) in iter.zip(routing_header.hops.iter().skip(2)) {
                     result.push(Information::AddNode(Node::new(
                         *id,
-                        NodeType::Drone(Drone::with_delivery(vec![FragmentDelivery::Forwarded])),
+                        NodeType::Drone(
+                            Drone::with_delivery(vec![FragmentDelivery::Forwarded])
+                                .with_link_delivery(*previous, FragmentDelivery::Forwarded),
+                        ),
                     )));
                 }
 
-                result.pop();
-
                 result.extend(edges);
 
                 result
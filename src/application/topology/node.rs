@@ -1,9 +1,10 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::fmt::{Debug, Display, Formatter, Result};
+use std::time::Duration;
 use wg_2024::network::NodeId;
 use wg_2024::packet::NodeType as SimpleNodeType;
 
-const MEMORY_SIZE: usize = 250;
+const LATENCY_MEMORY_SIZE: usize = 50;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ApplicationType {
@@ -14,7 +15,7 @@ pub enum ApplicationType {
 }
 
 impl ApplicationType {
-    fn compatible(&self, other: &Self) -> bool {
+    pub fn compatible(&self, other: &Self) -> bool {
         match (self, other) {
             (ApplicationType::Unknown, _) => true,
             (_, ApplicationType::Unknown) => true,
@@ -31,9 +32,114 @@ pub enum FragmentDelivery {
     Dropped,
 }
 
+/// How the PDR of a drone or a link is estimated from the fragments that crossed it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PdrEstimator {
+    /// Weight of every new fragment in the moving average, the higher the shorter the memory.
+    pub decay: f32,
+    /// Fragments to see before the estimate is trusted.
+    pub warm_up: u32,
+}
+
+impl Default for PdrEstimator {
+    fn default() -> Self {
+        Self {
+            decay: 0.01,
+            warm_up: 25,
+        }
+    }
+}
+
+/// An exponentially weighted moving average of the fragments dropped by a drone or a link.
+#[derive(Clone, Default)]
+pub struct DeliveryHistory {
+    estimator: PdrEstimator,
+    pdr: f32,
+    samples: u32,
+}
+
+impl DeliveryHistory {
+    fn new(estimator: PdrEstimator) -> Self {
+        Self {
+            estimator,
+            pdr: 0.0,
+            samples: 0,
+        }
+    }
+
+    fn with_deliveries(deliveries: Vec<FragmentDelivery>) -> Self {
+        let mut history = Self::default();
+        for delivery in deliveries {
+            history.record(delivery);
+        }
+        history
+    }
+
+    pub fn record(&mut self, delivery: FragmentDelivery) {
+        let dropped = if delivery == FragmentDelivery::Dropped {
+            1.0
+        } else {
+            0.0
+        };
+        self.update(dropped, 1);
+    }
+
+    /// Moves the average towards `pdr` as if `samples` fragments with that rate were seen.
+    ///
+    /// Until the memory of the average is filled each fragment weights as much as the previous
+    /// ones, so the first deliveries are not biased towards a PDR of zero.
+    fn update(&mut self, pdr: f32, samples: u32) {
+        if samples == 0 {
+            return;
+        }
+        let total = self.samples.saturating_add(samples);
+        let weight = (samples as f32 / total as f32)
+            .max(1.0 - (1.0 - self.estimator.decay).powi(samples as i32));
+        self.pdr += (pdr - self.pdr) * weight;
+        self.samples = total;
+    }
+
+    fn merge(&mut self, other: DeliveryHistory) {
+        self.update(other.pdr, other.samples);
+    }
+
+    fn set_estimator(&mut self, estimator: PdrEstimator) {
+        self.estimator = estimator;
+    }
+
+    fn is_empty(&self) -> bool {
+        self.samples == 0
+    }
+
+    fn is_known(&self) -> bool {
+        self.samples >= self.estimator.warm_up
+    }
+
+    /// The share of dropped fragments, once enough of them were seen to tell.
+    pub fn estimate_pdr(&self) -> Option<f32> {
+        self.is_known().then_some(self.pdr)
+    }
+
+    /// A 95% confidence interval of the PDR, which is `(0.0, 1.0)` while nothing is known.
+    pub fn pdr_bounds(&self) -> (f32, f32) {
+        if self.is_empty() {
+            return (0.0, 1.0);
+        }
+        // the average only remembers about 2 / decay fragments
+        let memory = (2.0 - self.estimator.decay) / self.estimator.decay;
+        let samples = (self.samples as f32).min(memory);
+        let margin = 1.96 * (self.pdr * (1.0 - self.pdr) / samples).sqrt() + 1.0 / samples;
+        ((self.pdr - margin).max(0.0), (self.pdr + margin).min(1.0))
+    }
+}
+
 #[derive(Clone)]
 pub struct Drone {
-    latest_deliveries: VecDeque<FragmentDelivery>,
+    latest_deliveries: DeliveryHistory,
+    /// The deliveries of the fragments received from each neighbor.
+    links: HashMap<NodeId, DeliveryHistory>,
+    /// Time taken to forward a fragment, estimated from the round trip times of its routes.
+    latest_latencies: VecDeque<Duration>,
 }
 
 impl Debug for Drone {
@@ -51,40 +157,99 @@ impl Default for Drone {
 impl Drone {
     pub fn new() -> Self {
         Self {
-            latest_deliveries: VecDeque::with_capacity(MEMORY_SIZE),
+            latest_deliveries: DeliveryHistory::default(),
+            links: HashMap::new(),
+            latest_latencies: VecDeque::with_capacity(LATENCY_MEMORY_SIZE),
         }
     }
 
     pub fn with_delivery(deliveries: Vec<FragmentDelivery>) -> Self {
         Self {
-            latest_deliveries: deliveries.into(),
+            latest_deliveries: DeliveryHistory::with_deliveries(deliveries),
+            ..Self::new()
         }
     }
 
+    /// Also records `delivery` on the link the fragment came from.
+    pub fn with_link_delivery(mut self, previous: NodeId, delivery: FragmentDelivery) -> Self {
+        self.record_link_delivery(previous, delivery);
+        self
+    }
+
+    /// Records `delivery` on the link the fragment came from only.
+    pub fn record_link_delivery(&mut self, previous: NodeId, delivery: FragmentDelivery) {
+        self.link_mut(previous).record(delivery);
+    }
+
     pub fn merge_drone(&mut self, other: Drone) {
-        for delivery in other.latest_deliveries.into_iter() {
-            self.record_delivery(delivery);
+        self.latest_deliveries.merge(other.latest_deliveries);
+        for (previous, deliveries) in other.links.into_iter() {
+            self.link_mut(previous).merge(deliveries);
+        }
+        for latency in other.latest_latencies.into_iter() {
+            self.record_latency(latency);
         }
     }
 
-    pub fn record_delivery(&mut self, delivery: FragmentDelivery) {
-        self.latest_deliveries.push_back(delivery);
-        if self.latest_deliveries.len() > MEMORY_SIZE {
-            self.latest_deliveries.pop_front();
+    fn link_mut(&mut self, previous: NodeId) -> &mut DeliveryHistory {
+        let estimator = self.latest_deliveries.estimator;
+        self.links
+            .entry(previous)
+            .or_insert_with(|| DeliveryHistory::new(estimator))
+    }
+
+    /// Changes how the PDR of the drone and its links is estimated from now on.
+    pub fn set_estimator(&mut self, estimator: PdrEstimator) {
+        self.latest_deliveries.set_estimator(estimator);
+        for link in self.links.values_mut() {
+            link.set_estimator(estimator);
         }
     }
 
-    pub fn calculate_pdr(&self) -> f32 {
-        if self.latest_deliveries.len() < MEMORY_SIZE / 10 {
-            return 0.0;
+    pub fn record_latency(&mut self, latency: Duration) {
+        self.latest_latencies.push_back(latency);
+        if self.latest_latencies.len() > LATENCY_MEMORY_SIZE {
+            self.latest_latencies.pop_front();
         }
-        let dropped = self
-            .latest_deliveries
-            .iter()
-            .filter(|&d| *d == FragmentDelivery::Dropped)
-            .count();
-        let total = self.latest_deliveries.len();
-        dropped as f32 / total as f32
+    }
+
+    pub fn average_latency(&self) -> Option<Duration> {
+        if self.latest_latencies.is_empty() {
+            return None;
+        }
+        Some(self.latest_latencies.iter().sum::<Duration>() / self.latest_latencies.len() as u32)
+    }
+
+    pub fn record_delivery(&mut self, delivery: FragmentDelivery) {
+        self.latest_deliveries.record(delivery);
+    }
+
+    pub fn calculate_pdr(&self) -> f32 {
+        self.latest_deliveries.estimate_pdr().unwrap_or(0.0)
+    }
+
+    /// Whether enough fragments crossed the drone for its PDR to be trusted.
+    pub fn is_pdr_known(&self) -> bool {
+        self.latest_deliveries.is_known()
+    }
+
+    pub fn pdr_bounds(&self) -> (f32, f32) {
+        self.latest_deliveries.pdr_bounds()
+    }
+
+    /// The PDR of the fragments coming from `previous`, falling back to the PDR of the whole
+    /// drone while there are too few of them.
+    pub fn link_pdr(&self, previous: NodeId) -> f32 {
+        self.links
+            .get(&previous)
+            .and_then(DeliveryHistory::estimate_pdr)
+            .unwrap_or_else(|| self.calculate_pdr())
+    }
+
+    fn is_empty(&self) -> bool {
+        self.latest_deliveries.is_empty()
+            && self.links.values().all(DeliveryHistory::is_empty)
+            && self.latest_latencies.is_empty()
     }
 }
 
@@ -98,9 +263,17 @@ pub enum NodeType {
 impl Display for NodeType {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
         match self {
-            NodeType::Drone(drone) => {
-                write!(f, "Drone: PDR = {:.2}%", drone.calculate_pdr() * 100.0)
+            NodeType::Drone(drone) if drone.is_pdr_known() => {
+                let (lower, upper) = drone.pdr_bounds();
+                write!(
+                    f,
+                    "Drone: PDR = {:.2}% ({:.2}%-{:.2}%)",
+                    drone.calculate_pdr() * 100.0,
+                    lower * 100.0,
+                    upper * 100.0
+                )
             }
+            NodeType::Drone(_) => write!(f, "Drone: PDR unknown"),
             NodeType::Server(application) => write!(f, "Server: {:?}", application),
             NodeType::Client(application) => write!(f, "Client: {:?}", application),
         }
@@ -140,13 +313,28 @@ impl NodeType {
         }
     }
     pub fn cost(&self) -> f32 {
+        self.cost_from(None)
+    }
+    /// The cost of crossing this node coming from `previous`, which uses the PDR of that link
+    /// when it is known.
+    pub fn cost_from(&self, previous: Option<NodeId>) -> f32 {
         match self {
             NodeType::Drone(drone) => {
                 const ALPHA: f32 = 0.1;
                 const B: f32 = 2.0;
-                let pdr = drone.calculate_pdr().min(0.9999);
+                // cost of every millisecond a congested drone takes to forward a fragment
+                const LATENCY_WEIGHT: f32 = 0.05;
+                let pdr = previous
+                    .map_or_else(
+                        || drone.calculate_pdr(),
+                        |previous| drone.link_pdr(previous),
+                    )
+                    .min(0.9999);
                 let retransmit_exp = 1.0 / (1.0 - pdr);
-                (1.0 - ALPHA) * retransmit_exp.log(B) + ALPHA
+                let latency = drone
+                    .average_latency()
+                    .map_or(0.0, |latency| latency.as_secs_f32() * 1000.0);
+                (1.0 - ALPHA) * retransmit_exp.log(B) + ALPHA + LATENCY_WEIGHT * latency
             }
             _ => 0.0,
         }
@@ -192,7 +380,7 @@ impl Node {
             return false;
         }
         if let NodeType::Drone(drone) = &other.node_type {
-            if !drone.latest_deliveries.is_empty() {
+            if !drone.is_empty() {
                 return true;
             }
         }
@@ -225,4 +413,27 @@ impl Node {
         }
         false
     }
+
+    /// Whether `other` is another server of the same application, one this server could copy
+    /// content from.
+    pub fn is_replica_route_meaningful(&self, other: &Self) -> bool {
+        if self.id == other.id {
+            return false;
+        }
+        match (&self.node_type, &other.node_type) {
+            (NodeType::Server(app1), NodeType::Server(app2)) => app1.compatible(app2),
+            _ => false,
+        }
+    }
+
+    /// Whether `other` is another client this one could message directly.
+    pub fn is_peer_route_meaningful(&self, other: &Self) -> bool {
+        if self.id == other.id {
+            return false;
+        }
+        match (&self.node_type, &other.node_type) {
+            (NodeType::Client(app1), NodeType::Client(app2)) => app1.compatible(app2),
+            _ => false,
+        }
+    }
 }
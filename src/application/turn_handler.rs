@@ -1,12 +1,22 @@
+use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 
 use wg_2024::network::NodeId;
 
 pub type TurnHandlerArc = Arc<Mutex<TurnHandler>>;
 
+/// Points scored for a text or media that arrived.
+pub const DOWNLOAD_POINTS: u32 = 2;
+/// Points scored for a chat message the server passed on.
+pub const MESSAGE_POINTS: u32 = 1;
+
 pub struct TurnHandler {
     nodes: Vec<NodeId>,
     current_turn: usize,
+    /// Rounds completed, a round being over once every player had a turn.
+    rounds: usize,
+    /// The points of every player that ever joined the game.
+    scores: HashMap<NodeId, u32>,
 }
 
 impl TurnHandler {
@@ -14,6 +24,8 @@ impl TurnHandler {
         Self {
             nodes: Vec::new(),
             current_turn: 0,
+            rounds: 0,
+            scores: HashMap::new(),
         }
     }
 
@@ -23,14 +35,40 @@ impl TurnHandler {
 
     pub fn yield_turn(&mut self) {
         self.current_turn = (self.current_turn + 1) % self.nodes.len();
+        if self.current_turn == 0 {
+            self.rounds += 1;
+        }
+    }
+
+    pub fn rounds(&self) -> usize {
+        self.rounds
     }
 
     pub fn subscribe(&mut self, node: NodeId) {
         self.nodes.push(node);
+        self.scores.entry(node).or_insert(0);
     }
 
     pub fn unsubscribe(&mut self, node: NodeId) {
         self.nodes.retain(|&x| x != node);
+        if self.current_turn >= self.nodes.len() {
+            self.current_turn = 0;
+        }
+    }
+
+    pub fn add_points(&mut self, node: NodeId, points: u32) {
+        *self.scores.entry(node).or_insert(0) += points;
+    }
+
+    /// The players and their points, the best first.
+    pub fn leaderboard(&self) -> Vec<(NodeId, u32)> {
+        let mut leaderboard: Vec<_> = self
+            .scores
+            .iter()
+            .map(|(node, points)| (*node, *points))
+            .collect();
+        leaderboard.sort_by_key(|(node, points)| (std::cmp::Reverse(*points), *node));
+        leaderboard
     }
 }
 
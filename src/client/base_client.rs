@@ -1,24 +1,137 @@
-use crate::application::assembler::{Assembler, Disassembler};
-use crate::application::routing::SourceRouter;
-use crate::application::simulation_controller_messages::{HostCommand, HostEvent};
+use crate::application::assembler::{
+    Assembler, AssemblyError, AssemblyLimits, Disassembler, ProgressObserver, REASSEMBLY_TIMEOUT,
+    SEND_WINDOW,
+};
+use crate::application::authentication::HostKeys;
+use crate::application::encryption::Keyring;
+use crate::application::retransmission::{RetransmissionScheduler, RETRANSMISSION_TICK};
+use crate::application::route_policy::{LowestCost, RoutePolicy};
+use crate::application::routing::{RouterSettings, SourceRouter, ROUTING_TABLE_INTERVAL};
+use crate::application::simulation_controller_messages::{
+    ClientRequest, HostCommand, HostEvent, SessionDirection,
+};
 use crate::application::topology::node::{ApplicationType, Node, NodeType};
 use crate::application::turn_handler::TurnHandlerArc;
 use crate::initialization::network_initializer::Runnable;
-use crate::message::base_message::{Message, Request, Response};
-use crossbeam_channel::{bounded, select, Receiver, Sender, TryRecvError};
+use crate::message::base_message::{
+    Message, MessageContent, Request, Response, ServerType, PROTOCOL_VERSION,
+};
+use crate::message::codec::MessageCodec;
+use crate::message::discovery::{Discovery, DISCOVERY_CODEC};
+use crate::message::error_response::ErrorResponse;
+use crate::message::handshake::{Handshake, HANDSHAKE_CODEC};
+use crossbeam_channel::{after, bounded, never, select, Receiver, Sender};
 use rand::random;
+use serde::Serialize;
 use std::collections::HashMap;
-use std::fmt::Display;
+use std::fmt::{Debug, Display};
+use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
 use std::thread;
 use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use std::{fs, io};
 use wg_2024::network::{NodeId, SourceRoutingHeader};
 use wg_2024::packet::{
-    FloodRequest, Nack, NackType, NodeType as SimpleNodeType, Packet, PacketType,
+    FloodRequest, Fragment, Nack, NackType, NodeType as SimpleNodeType, Packet, PacketType,
+    FRAGMENT_DSIZE,
 };
 use PacketType::{Ack as Quack, Nack as Quacknt, *};
 
 use super::card::Card;
 use super::client_game::ClientGame;
+use super::client_repl::{repl_requested, ClientRepl};
+use super::pending_requests::{PendingRequests, ResponseCallback, ResponseResult};
+use super::utils::{self, parse_input, progress_bar};
+
+/// How long a card waits for a response before asking whether to keep waiting.
+pub const RESPONSE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Why a card did not get the response it waited for.
+#[derive(Debug)]
+pub enum ResponseError {
+    TimedOut,
+    /// The server does not provide the requested service.
+    WrongServer,
+    /// The request ran out of retransmissions.
+    Undeliverable,
+    /// The server speaks this version of the protocol rather than [`PROTOCOL_VERSION`].
+    UnsupportedVersion(u16),
+    /// The server turned the request down.
+    Rejected(ErrorResponse),
+}
+
+/// How a broadcast gathers the responses of the servers it asked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregation {
+    /// The first server to answer wins, the others being no longer waited for.
+    FirstWins,
+    /// Every server is waited for, so that what they answered can be merged.
+    Merge,
+}
+
+/// What an exchange does once a response arrives: send its next request, or end with what it
+/// got done.
+pub enum Step<Request, T> {
+    Next(Request),
+    Done(T),
+}
+
+impl Display for ResponseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResponseError::TimedOut => write!(f, "The server did not answer in time"),
+            ResponseError::WrongServer => write!(f, "This is the wrong kind of Server"),
+            ResponseError::Undeliverable => write!(f, "The request could not be delivered"),
+            ResponseError::UnsupportedVersion(version) => write!(
+                f,
+                "The server speaks version {version} of the protocol, this client {PROTOCOL_VERSION}"
+            ),
+            ResponseError::Rejected(error) if error.retryable => {
+                write!(f, "{}, try again later", error.message)
+            }
+            ResponseError::Rejected(error) => write!(f, "{}", error.message),
+        }
+    }
+}
+
+/// How long a probe waits for its ack before the route is considered lost.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// How a probe sent along a route went.
+#[derive(Debug)]
+pub enum ProbeOutcome {
+    /// The destination acked the probe after the round trip time.
+    Acked(Duration),
+    /// A node on the route nacked the probe.
+    Nacked(NackType),
+    /// Nothing came back within [`PROBE_TIMEOUT`].
+    Lost,
+}
+
+impl Display for ProbeOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProbeOutcome::Acked(rtt) => write!(f, "{} ms", rtt.as_millis()),
+            ProbeOutcome::Nacked(nack_type) => write!(f, "nacked ({nack_type:?})"),
+            ProbeOutcome::Lost => write!(f, "lost"),
+        }
+    }
+}
+
+/// How long the servers have to answer what they provide.
+const DISCOVERY_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long a server has to accept the key offered before the request is given up.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long a flood started by the client collects responses before the routes are searched.
+const FLOOD_RESPONSE_WAIT: Duration = Duration::from_millis(1500);
+/// How often clients flood the network by default, like servers do.
+pub const FLOOD_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Incoming messages with at least this many fragments get a progress bar in the terminal.
+const PROGRESS_BAR_MIN_FRAGMENTS: u64 = 32;
 
 pub trait ClientBehaviour: Send + Sized + 'static {
     type RequestType: Request + Display;
@@ -26,6 +139,60 @@ pub trait ClientBehaviour: Send + Sized + 'static {
     fn cards() -> Vec<Card<Self>>;
     fn on_response_received(&mut self, response: Message<Self::ResponseType>);
     fn application_type() -> ApplicationType;
+    /// Turns a request from the simulation controller into one this client can send.
+    fn controller_request(&mut self, request: ClientRequest) -> Option<Self::RequestType>;
+    /// Called when the player's turn at the terminal starts.
+    fn on_turn_started(&mut self) {}
+    /// Called when `server` acked a fragment of a request.
+    fn on_ack(&mut self, _server: NodeId, _session_id: u64, _fragment_index: u64) {}
+    /// Called when a fragment of a request to `server` was nacked, before it is retransmitted.
+    fn on_nack(&mut self, _server: NodeId, _session_id: u64, _nack: &Nack) {}
+    /// Called when a request to `server` was given up, having run out of retransmissions.
+    fn on_session_failed(&mut self, _server: NodeId, _session_id: u64) {}
+    /// Whether the client is driven by the card game on the terminal.
+    fn plays_cards() -> bool {
+        true
+    }
+    /// Called on every iteration of the client loop, for behaviours that act on their own.
+    fn on_tick(_client: &mut Client<Self>) {}
+    /// A command the client runs on its own as if the controller sent it, polled
+    /// continuously.
+    fn next_command(&mut self) -> Option<HostCommand> {
+        None
+    }
+    /// How the client picks one of the routes it knows to a server.
+    fn route_policy() -> Box<dyn RoutePolicy> {
+        Box::new(LowestCost::default())
+    }
+    fn router_settings() -> RouterSettings {
+        RouterSettings::default()
+    }
+    /// How often the client floods the network in the background, if at all.
+    fn flood_interval() -> Option<Duration> {
+        Some(FLOOD_INTERVAL)
+    }
+    /// How the messages sent by the host are encoded.
+    fn codec() -> MessageCodec {
+        MessageCodec::default()
+    }
+    /// How much the host buffers for the messages it sends and receives.
+    fn assembly_limits() -> AssemblyLimits {
+        AssemblyLimits::default()
+    }
+    /// Whether the requests are sealed, agreeing a key with every server before the first
+    /// request sent to it.
+    fn encrypted() -> bool {
+        false
+    }
+}
+
+/// When an outgoing session started, how many fragments it resent and the route its first
+/// fragment took, reported once every fragment is acked.
+struct SessionTiming {
+    destination: NodeId,
+    started: Instant,
+    retransmissions: u32,
+    route: Vec<NodeId>,
 }
 
 pub struct Client<B: ClientBehaviour> {
@@ -34,13 +201,28 @@ pub struct Client<B: ClientBehaviour> {
     assembler: Assembler<B::ResponseType>,
     disassembler: Disassembler<B::RequestType>,
     router: SourceRouter,
+    retransmissions: RetransmissionScheduler,
+    pending: PendingRequests<B>,
+    /// What the servers answered to the [`Discovery`] requests, by session.
+    discovered: HashMap<u64, ServerType>,
+    /// What the servers answered they provide, by server.
+    server_types: HashMap<NodeId, ServerType>,
+    /// The keys agreed with the servers, if the behaviour seals its requests.
+    keyring: Keyring,
+    session_timings: HashMap<u64, SessionTiming>,
     controller_send: Sender<HostEvent>,
     controller_recv: Receiver<HostCommand>,
     packet_recv: Receiver<Packet>,
     packet_send: HashMap<NodeId, Sender<Packet>>,
     active: bool,
+    last_routing_table: Instant,
+    /// When the client last flooded, if it ever did.
+    last_flood: Option<Instant>,
+    /// When the routes are searched again after a periodic flood.
+    routes_due: Option<Instant>,
     card_receiver: Receiver<Card<B>>,
     cards_join_handle: Option<JoinHandle<()>>,
+    turn_handler: TurnHandlerArc,
 }
 
 impl<B> Client<B>
@@ -57,19 +239,45 @@ where
         behaviour: B,
     ) -> Self {
         let (sender, receiver) = bounded(0);
+        let (card_receiver, cards_join_handle) = if !B::plays_cards() {
+            (never(), None)
+        } else if repl_requested() {
+            let handle = ClientRepl::start_thread(id, sender, turn_handler.clone());
+            (receiver, Some(handle))
+        } else {
+            let handle = ClientGame::start_thread(id, sender, turn_handler.clone());
+            (receiver, Some(handle))
+        };
         Self {
             behaviour,
             id,
-            assembler: Assembler::new(),
-            disassembler: Disassembler::new(),
-            router: SourceRouter::new(Node::new(id, NodeType::Client(B::application_type()))),
+            assembler: Assembler::new()
+                .with_progress_observer(Self::progress_observer(id, controller_send.clone()))
+                .with_limits(B::assembly_limits()),
+            disassembler: Disassembler::new()
+                .with_codec(B::codec())
+                .with_window(SEND_WINDOW)
+                .with_limits(B::assembly_limits()),
+            router: SourceRouter::new(Node::new(id, NodeType::Client(B::application_type())))
+                .with_settings(B::router_settings())
+                .with_route_policy(B::route_policy()),
+            retransmissions: RetransmissionScheduler::default(),
+            pending: PendingRequests::default(),
+            discovered: HashMap::new(),
+            server_types: HashMap::new(),
+            keyring: Keyring::default(),
+            session_timings: HashMap::new(),
             controller_send,
             controller_recv,
             packet_recv,
             packet_send,
-            card_receiver: receiver,
+            card_receiver,
             active: false,
-            cards_join_handle: Some(ClientGame::start_thread(id, sender, turn_handler)),
+            last_routing_table: Instant::now(),
+            last_flood: None,
+            routes_due: None,
+            cards_join_handle,
+            turn_handler,
         }
     }
 
@@ -95,30 +303,225 @@ where
         )
     }
 
-    pub(crate) fn send_request(&mut self, request: Message<B::RequestType>) -> bool {
-        let session_id = request.session_id;
-        let destination_id = request.destination_id;
-        if !self.router.can_reach(destination_id) {
+    /// Signs the messages sent with the key issued to the client, checking that the ones
+    /// received are signed by the host they come from.
+    pub fn with_host_keys(mut self, host_keys: HostKeys) -> Self {
+        self.assembler = self.assembler.with_host_keys(host_keys.clone());
+        self.disassembler = self.disassembler.with_host_keys(self.id, host_keys);
+        self
+    }
+
+    /// Sends a request with the priority of what it asks for.
+    pub(crate) fn send_request(&mut self, mut request: Message<B::RequestType>) -> bool {
+        request.priority = request.content.priority();
+        let server = request.destination_id;
+        if B::encrypted() && !self.keyring.has_key(server) && !self.agree_key(server) {
+            println!("Client {} agreed no key with server {server}", self.id);
             return false;
         }
+        self.send_message(request, B::codec(), true)
+    }
+
+    /// Offers a key to the server with a [`Handshake`], returning whether the server accepted
+    /// it within [`HANDSHAKE_TIMEOUT`].
+    fn agree_key(&mut self, server: NodeId) -> bool {
+        let session_id = self.new_session_id();
+        let offer = Handshake::Offer(self.keyring.public_key());
+        let offer = Message::new(self.id, server, session_id, offer);
+        self.send_message(offer, HANDSHAKE_CODEC, false)
+            && self.wait_until(HANDSHAKE_TIMEOUT, |client| client.keyring.has_key(server))
+    }
+
+    /// Sends a message of any kind, encoded with `codec` and sealed if `sealed` and a key was
+    /// agreed with its destination, returning whether it could be sent.
+    fn send_message<T: MessageContent + Serialize + Display>(
+        &mut self,
+        message: Message<T>,
+        codec: MessageCodec,
+        sealed: bool,
+    ) -> bool {
+        let session_id = message.session_id;
+        let destination_id = message.destination_id;
+        if !self.router.can_reach(destination_id) {
+            // the topology may be outdated, so explore it once more before giving up
+            self.refresh_topology();
+            self.calculate_routes();
+            if !self.router.can_reach(destination_id) {
+                return false;
+            }
+        }
         self.controller_send
-            .send(HostEvent::MessageSent(request.to_string_message()))
+            .send(HostEvent::MessageSent(message.to_string_message()))
             .unwrap();
-        let fragments = self.disassembler.disassembly(request);
-        let packets = fragments
-            .into_iter()
-            .map(|frag| Packet {
+        let fragments = if sealed {
+            self.disassembler
+                .disassembly_sealed(message, codec, &self.keyring)
+        } else {
+            self.disassembler.disassembly_with(message, codec)
+        };
+        let evicted = self.disassembler.enforce_limits();
+        let sendable = !evicted.contains(&session_id);
+        self.report_evicted(evicted, SessionDirection::Outgoing);
+        if !sendable {
+            return false;
+        }
+        self.controller_send
+            .send(HostEvent::SessionStarted {
+                host: self.id,
                 session_id,
-                routing_header: self.router.get_best_route(destination_id).unwrap(),
-                pack_type: PacketType::MsgFragment(frag),
+                direction: SessionDirection::Outgoing,
+                total_fragments: self.disassembler.remaining_fragments(session_id) as u64,
             })
-            .collect::<Vec<Packet>>();
-        packets.into_iter().for_each(|packet| self.forward(packet));
+            .unwrap();
+        self.session_timings.insert(
+            session_id,
+            SessionTiming {
+                destination: destination_id,
+                started: Instant::now(),
+                retransmissions: 0,
+                route: Vec::new(),
+            },
+        );
+        self.send_fragments(session_id, destination_id, fragments);
+
+        true
+    }
 
+    /// Sends a request in a new session, returning the session to wait for the response the
+    /// predicate accepts with [`Client::wait_for_response`].
+    pub(crate) fn request(
+        &mut self,
+        destination: NodeId,
+        content: B::RequestType,
+        predicate: impl FnMut(&Message<B::ResponseType>) -> bool + Send + 'static,
+    ) -> Option<u64> {
+        let session_id = self.new_session_id();
+        if !self.send_request(Message::new(self.id, destination, session_id, content)) {
+            return None;
+        }
+        self.pending
+            .insert(session_id, destination, Box::new(predicate), None, None);
+        Some(session_id)
+    }
+
+    /// Sends a request in a new session without waiting for it: `on_response` handles the
+    /// response the predicate accepts, or why it did not come within [`RESPONSE_TIMEOUT`].
+    pub(crate) fn request_then(
+        &mut self,
+        destination: NodeId,
+        content: B::RequestType,
+        predicate: impl FnMut(&Message<B::ResponseType>) -> bool + Send + 'static,
+        on_response: impl FnOnce(&mut Client<B>, ResponseResult<B>) + Send + 'static,
+    ) -> bool {
+        let session_id = self.new_session_id();
+        if !self.send_request(Message::new(self.id, destination, session_id, content)) {
+            return false;
+        }
+        let on_response: ResponseCallback<B> = Box::new(on_response);
+        self.pending.insert(
+            session_id,
+            destination,
+            Box::new(predicate),
+            Some(on_response),
+            Some(Instant::now() + RESPONSE_TIMEOUT),
+        );
         true
     }
 
+    /// Runs an exchange with the server in one call: sends `first`, then hands each response
+    /// to `step` and sends the request it asks for next, until it ends the exchange. Every
+    /// request carries the same exchange id, which the server gives back with its response,
+    /// so that only the responses of the exchange are taken. Returns the outcome `step` ended
+    /// with, or why a response did not come.
+    pub(crate) fn exchange<T>(
+        &mut self,
+        destination: NodeId,
+        first: B::RequestType,
+        mut step: impl FnMut(&mut Self, Message<B::ResponseType>) -> Step<B::RequestType, T>,
+    ) -> Result<T, ResponseError> {
+        let exchange_id = self.new_session_id();
+        let mut request = first;
+        loop {
+            let session_id = self.new_session_id();
+            let message = Message {
+                exchange_id: Some(exchange_id),
+                ..Message::new(self.id, destination, session_id, request)
+            };
+            if !self.send_request(message) {
+                return Err(ResponseError::Undeliverable);
+            }
+            let predicate = move |response: &Message<B::ResponseType>| {
+                response.exchange_id == Some(exchange_id)
+            };
+            self.pending
+                .insert(session_id, destination, Box::new(predicate), None, None);
+            let response = self.wait_for_response(session_id)?;
+            match step(self, response) {
+                Step::Next(next) => request = next,
+                Step::Done(outcome) => return Ok(outcome),
+            }
+        }
+    }
+
+    /// Ends a pending request, handing its outcome to its callback or to the card waiting
+    /// for it.
+    fn complete_request(&mut self, session_id: u64, result: ResponseResult<B>) {
+        let Some(request) = self.pending.take(session_id) else {
+            return;
+        };
+        match request.on_response {
+            Some(on_response) => on_response(self, result),
+            None => self.pending.store_response(session_id, result),
+        }
+    }
+
+    /// Completes the request the message answers, if any, or leaves it to the behaviour. An
+    /// error fails the request of its session whatever the predicate of the request.
+    fn dispatch_response(&mut self, message: Message<B::ResponseType>) {
+        if let Some(error) = message.content.as_error() {
+            if self.pending.awaits(message.session_id, message.source_id) {
+                let error = ResponseError::Rejected(error.clone());
+                self.complete_request(message.session_id, Err(error));
+                return;
+            }
+        }
+        match self.pending.match_response(&message) {
+            Some(session_id) => self.complete_request(session_id, Ok(message)),
+            None => self.behaviour.on_response_received(message),
+        }
+    }
+
+    /// Gives up the requests whose callback waited for too long.
+    fn expire_requests(&mut self) {
+        for session_id in self.pending.expired() {
+            self.complete_request(session_id, Err(ResponseError::TimedOut));
+        }
+    }
+
+    /// Sends the fragments on the best route to `destination`, rescheduling them if there is
+    /// none yet.
+    fn send_fragments(&mut self, session_id: u64, destination: NodeId, fragments: Vec<Fragment>) {
+        let mut packets = Vec::with_capacity(fragments.len());
+        for frag in fragments {
+            match self.router.get_best_route(destination) {
+                Some(routing_header) => {
+                    packets.push(Packet::new_fragment(routing_header, session_id, frag))
+                }
+                None => self.route_missing(session_id, frag.fragment_index, None),
+            }
+        }
+        if let (Some(timing), Some(packet)) =
+            (self.session_timings.get_mut(&session_id), packets.first())
+        {
+            if timing.route.is_empty() {
+                timing.route = packet.routing_header.hops.clone();
+            }
+        }
+        packets.into_iter().for_each(|packet| self.forward(packet));
+    }
+
     pub(crate) fn initiate_flood(&mut self) {
+        self.last_flood = Some(Instant::now());
         let flood_id = random();
         self.controller_send
             .send(HostEvent::FloodInitiated(self.id, flood_id))
@@ -133,6 +536,125 @@ where
         }
     }
 
+    /// Floods the network for a fragment that has no route to its destination, unless a flood
+    /// is already waiting for its responses, and retransmits the fragment after its backoff.
+    fn route_missing(&mut self, session_id: u64, fragment_index: u64, avoided: Option<NodeId>) {
+        if self.routes_due.is_none() {
+            self.initiate_flood();
+            self.routes_due = Some(Instant::now() + FLOOD_RESPONSE_WAIT);
+        }
+        self.schedule_retransmission(session_id, fragment_index, avoided);
+    }
+
+    /// Floods the network every [`ClientBehaviour::flood_interval`], searching the routes
+    /// again once the responses had time to come back.
+    fn flood_periodically(&mut self) {
+        if let Some(interval) = B::flood_interval() {
+            if self
+                .last_flood
+                .is_none_or(|last| last.elapsed() >= interval)
+            {
+                self.initiate_flood();
+                self.routes_due = Some(Instant::now() + FLOOD_RESPONSE_WAIT);
+            }
+        }
+        if self.routes_due.is_some_and(|due| due <= Instant::now()) {
+            self.routes_due = None;
+            let routes = self.calculate_routes();
+            self.controller_send
+                .send(HostEvent::RoutesRecalculated {
+                    host: self.id,
+                    routes,
+                })
+                .unwrap();
+        }
+    }
+
+    /// Floods the network and handles the packets received for a while, returning how many
+    /// flood responses came back.
+    pub(crate) fn refresh_topology(&mut self) -> usize {
+        self.initiate_flood();
+        let deadline = after(FLOOD_RESPONSE_WAIT);
+        let mut count = 0;
+        loop {
+            select! {
+                recv(self.packet_recv) -> packet => match packet {
+                    Ok(packet) => {
+                        if let PacketType::FloodResponse(_) = &packet.pack_type {
+                            count += 1;
+                        }
+                        self.handle_packet_normal(packet);
+                    }
+                    Err(_) => break,
+                },
+                recv(deadline) -> _ => break,
+            }
+        }
+        count
+    }
+
+    /// The known routes to `destination`, cheapest first.
+    pub(crate) fn routes_to(&self, destination: NodeId) -> Vec<Vec<NodeId>> {
+        self.router
+            .read()
+            .describe_routes()
+            .into_iter()
+            .filter(|route| route.destination == destination)
+            .map(|route| route.hops)
+            .collect()
+    }
+
+    /// The hops of the route the requests to `destination` currently take.
+    pub(crate) fn best_route(&self, destination: NodeId) -> Option<Vec<NodeId>> {
+        self.router
+            .write()
+            .get_best_route(destination)
+            .map(|header| header.hops)
+    }
+
+    /// Sends a single fragment along `hops` and times its ack, handling the other packets
+    /// received meanwhile. The probe is a whole message with no bytes, so the destination
+    /// acks it and drops it without holding a session open.
+    pub(crate) fn probe(&mut self, hops: Vec<NodeId>) -> ProbeOutcome {
+        let session_id = self.new_session_id();
+        let fragment = Fragment {
+            fragment_index: 0,
+            total_n_fragments: 1,
+            length: 0,
+            data: [0; FRAGMENT_DSIZE],
+        };
+        let packet =
+            Packet::new_fragment(SourceRoutingHeader::initialize(hops), session_id, fragment);
+        let sent = Instant::now();
+        self.forward(packet);
+        let deadline = after(PROBE_TIMEOUT);
+        let outcome = loop {
+            select! {
+                recv(self.packet_recv) -> packet => match packet {
+                    Ok(packet) if packet.session_id == session_id => match packet.pack_type {
+                        Quack(_) => {
+                            self.router.fragment_acknowledged(session_id, 0);
+                            break ProbeOutcome::Acked(sent.elapsed());
+                        }
+                        Quacknt(quacknt) => {
+                            if let NackType::Dropped = quacknt.nack_type {
+                                self.router.fragment_dropped(session_id, 0);
+                            }
+                            break ProbeOutcome::Nacked(quacknt.nack_type);
+                        }
+                        _ => {}
+                    },
+                    Ok(packet) => self.handle_packet_normal(packet),
+                    Err(_) => break ProbeOutcome::Lost,
+                },
+                recv(deadline) -> _ => break ProbeOutcome::Lost,
+            }
+        };
+        // the probe is not sent again, whatever came of it
+        self.router.forget_session(session_id);
+        outcome
+    }
+
     fn send_flood_request(&self, sender: &Sender<Packet>, flood_request: Packet) {
         sender.send(flood_request).unwrap();
     }
@@ -140,6 +662,7 @@ where
     fn forward(&mut self, mut packet: Packet) {
         if let Some(next_hop) = packet.routing_header.next_hop() {
             if let Some(sender) = self.packet_send.get(&next_hop) {
+                self.router.fragment_sent(&packet);
                 packet.routing_header.increase_hop_index();
                 sender.send(packet).unwrap();
             }
@@ -150,10 +673,238 @@ where
         self.router.forget_topology()
     }
 
+    pub(crate) fn export_topology(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.router.export_dot())
+    }
+
+    pub(crate) fn set_route_policy(&mut self, policy: Box<dyn RoutePolicy>) {
+        self.router.set_route_policy(policy);
+    }
+
+    /// Reads a line from the terminal on another thread, handling the packets received
+    /// meanwhile so that floods are answered and acks are not missed while the player types.
+    fn read_line(&mut self) -> String {
+        let (line_send, line_recv) = bounded(1);
+        thread::spawn(move || line_send.send(utils::read_line()).ok());
+        loop {
+            select! {
+                recv(line_recv) -> line => break line.unwrap_or_default(),
+                recv(self.packet_recv) -> packet => {
+                    if let Ok(packet) = packet {
+                        self.handle_packet_normal(packet);
+                    }
+                }
+                default(RETRANSMISSION_TICK) => {}
+            }
+            self.retransmit_due();
+            self.expire_requests();
+        }
+    }
+
+    /// Asks the player for a value, servicing the network while waiting for the answer.
+    pub(crate) fn input<T>(&mut self, prompt: String) -> T
+    where
+        T: FromStr<Err: Debug> + Display,
+    {
+        parse_input(prompt, |_| true, || self.read_line())
+    }
+
+    /// Like [`Client::input`], asking again until the value passes the check.
+    pub(crate) fn input_with_check<T, F>(&mut self, prompt: String, check: F) -> T
+    where
+        T: FromStr<Err: Debug> + Display,
+        F: Fn(&T) -> bool,
+    {
+        parse_input(prompt, check, || self.read_line())
+    }
+
+    /// Gives the player points in the card game, if the client plays it.
+    pub(crate) fn score(&self, points: u32) {
+        if !B::plays_cards() {
+            return;
+        }
+        self.turn_handler
+            .lock()
+            .unwrap()
+            .add_points(self.id, points);
+        println!("+{points} points");
+    }
+
+    /// Shows the final scores of the card game and reports them to the controller.
+    pub(crate) fn report_game_over(&self) {
+        let leaderboard = self.turn_handler.lock().unwrap().leaderboard();
+        println!("The game is over! Leaderboard:");
+        for (rank, (client, points)) in leaderboard.iter().enumerate() {
+            println!("{}. Client {client}: {points} points", rank + 1);
+        }
+        self.controller_send
+            .send(HostEvent::GameOver { leaderboard })
+            .ok();
+    }
+
     pub(crate) fn print_reachable_servers(&self) {
         self.router.print_reachable_servers();
     }
 
+    pub(crate) fn reachable_servers(&self) -> Vec<NodeId> {
+        self.router.reachable_servers()
+    }
+
+    /// Asks every reachable server what it provides with a [`Discovery`] request, which the
+    /// servers of any application read, recording the answers in the router.
+    pub(crate) fn discover_server_types(
+        &mut self,
+    ) -> Vec<(NodeId, Result<ServerType, ResponseError>)> {
+        let servers = self.reachable_servers();
+        let sessions = self.broadcast_sessions(
+            servers,
+            Aggregation::Merge,
+            DISCOVERY_TIMEOUT,
+            |client, server, session_id| {
+                let request =
+                    Message::new(client.id, server, session_id, Discovery::ServerTypeRequest);
+                client.send_message(request, DISCOVERY_CODEC, false)
+            },
+            |client, session_id| client.discovered.contains_key(&session_id).then_some(true),
+        );
+        sessions
+            .into_iter()
+            .map(|(server, session_id)| {
+                let Some(session_id) = session_id else {
+                    return (server, Err(ResponseError::Undeliverable));
+                };
+                let Some(server_type) = self.discovered.remove(&session_id) else {
+                    return (server, Err(ResponseError::TimedOut));
+                };
+                self.router
+                    .write()
+                    .server_type_discovered(server, server_type.application_type());
+                self.server_types.insert(server, server_type);
+                (server, Ok(server_type))
+            })
+            .collect()
+    }
+
+    /// The reachable servers of the type, asking what they provide first if some of them did
+    /// not tell yet.
+    pub(crate) fn servers_of_type(&mut self, server_type: ServerType) -> Vec<NodeId> {
+        let servers = self.reachable_servers();
+        if servers
+            .iter()
+            .any(|server| !self.server_types.contains_key(server))
+        {
+            self.discover_server_types();
+        }
+        servers
+            .into_iter()
+            .filter(|server| self.server_types.get(server) == Some(&server_type))
+            .collect()
+    }
+
+    /// Sends the same request to every reachable server of the type, each in its own session,
+    /// and waits for the responses the predicate accepts as the aggregation says. Returns what
+    /// each server answered, leaving out those no longer waited for once one won.
+    pub(crate) fn broadcast(
+        &mut self,
+        server_type: ServerType,
+        content: B::RequestType,
+        predicate: impl FnMut(&Message<B::ResponseType>) -> bool + Clone + Send + 'static,
+        aggregation: Aggregation,
+    ) -> Vec<(NodeId, ResponseResult<B>)>
+    where
+        B::RequestType: Clone,
+    {
+        let servers = self.servers_of_type(server_type);
+        let sessions = self.broadcast_sessions(
+            servers,
+            aggregation,
+            RESPONSE_TIMEOUT,
+            |client, server, session_id| {
+                let request = Message::new(client.id, server, session_id, content.clone());
+                if !client.send_request(request) {
+                    return false;
+                }
+                let predicate = Box::new(predicate.clone());
+                client
+                    .pending
+                    .insert(session_id, server, predicate, None, None);
+                true
+            },
+            |client, session_id| client.pending.response(session_id).map(Result::is_ok),
+        );
+        sessions
+            .into_iter()
+            .filter_map(|(server, session_id)| {
+                let Some(session_id) = session_id else {
+                    return Some((server, Err(ResponseError::Undeliverable)));
+                };
+                if let Some(result) = self.pending.take_response(session_id) {
+                    return Some((server, result));
+                }
+                self.pending.take(session_id);
+                match aggregation {
+                    Aggregation::FirstWins => None,
+                    Aggregation::Merge => Some((server, Err(ResponseError::TimedOut))),
+                }
+            })
+            .collect()
+    }
+
+    /// Sends a request to each server in a new session with `send`, then handles the packets
+    /// until the sessions are answered as the aggregation needs or `timeout` passed. `answered`
+    /// tells whether the server answered in the session, and whether it succeeded. Returns the
+    /// session of each server, none if the request could not be sent to it.
+    fn broadcast_sessions(
+        &mut self,
+        servers: Vec<NodeId>,
+        aggregation: Aggregation,
+        timeout: Duration,
+        mut send: impl FnMut(&mut Self, NodeId, u64) -> bool,
+        answered: impl Fn(&Self, u64) -> Option<bool>,
+    ) -> Vec<(NodeId, Option<u64>)> {
+        let sessions: Vec<_> = servers
+            .into_iter()
+            .map(|server| {
+                let session_id = self.new_session_id();
+                let sent = send(self, server, session_id);
+                (server, sent.then_some(session_id))
+            })
+            .collect();
+        self.wait_until(timeout, |client| {
+            let answers: Vec<_> = sessions
+                .iter()
+                .filter_map(|(_server, session_id)| *session_id)
+                .map(|session_id| answered(client, session_id))
+                .collect();
+            let all_answered = answers.iter().all(Option::is_some);
+            match aggregation {
+                Aggregation::FirstWins => all_answered || answers.contains(&Some(true)),
+                Aggregation::Merge => all_answered,
+            }
+        });
+        sessions
+    }
+
+    /// Handles the packets coming until `done` holds or `timeout` passed, returning whether
+    /// `done` held.
+    fn wait_until(&mut self, timeout: Duration, done: impl Fn(&Self) -> bool) -> bool {
+        let deadline = after(timeout);
+        while !done(self) {
+            self.retransmit_due();
+            self.expire_requests();
+            select! {
+                recv(self.packet_recv) -> packet => {
+                    if let Ok(packet) = packet {
+                        self.handle_packet_normal(packet);
+                    }
+                }
+                recv(deadline) -> _ => return false,
+                default(RETRANSMISSION_TICK) => {}
+            }
+        }
+        true
+    }
+
     pub fn new_session_id(&mut self) -> u64 {
         Disassembler::<B::RequestType>::transform_session_id(
             self.disassembler.new_session_id(),
@@ -178,14 +929,36 @@ where
                         self.handle_packet_normal(packet);
                     }
                 }
-                recv(self.card_receiver) -> card => {
-                    if let Ok(card) = card {
+                recv(self.card_receiver) -> card => match card {
+                    Ok(card) => {
                         card.activate(self);
-                        self.card_receiver.recv().unwrap();
+                        self.wait_for_card_game();
                     }
+                    // the game is over, the client keeps serving the network
+                    Err(_) => self.card_receiver = never(),
                 }
+                default(RETRANSMISSION_TICK) => {}
             }
 
+            if self.last_routing_table.elapsed() >= ROUTING_TABLE_INTERVAL {
+                self.last_routing_table = Instant::now();
+                self.controller_send
+                    .send(HostEvent::RoutingTable(
+                        self.id,
+                        self.router.describe_routes(),
+                    ))
+                    .ok();
+                let stats = self.assembler.stats().combine(self.disassembler.stats());
+                self.controller_send
+                    .send(HostEvent::FragmentStats(self.id, stats))
+                    .ok();
+            }
+            self.service_ticks();
+            while let Some(command) = self.behaviour.next_command() {
+                self.handle_command(command);
+            }
+            B::on_tick(self);
+
             thread::yield_now();
         }
 
@@ -196,10 +969,6 @@ where
         println!("Client {} stopped", self.id);
     }
 
-    pub(crate) fn try_recv_packet(&self) -> Result<Packet, TryRecvError> {
-        self.packet_recv.try_recv()
-    }
-
     pub fn handle_command(&mut self, command: HostCommand) {
         match command {
             HostCommand::Crash => {
@@ -213,53 +982,128 @@ where
                 self.router.remove_edge(self.id, id);
                 self.packet_send.remove(&id);
             }
+            HostCommand::InitiateFlood => {
+                self.initiate_flood();
+            }
+            HostCommand::ExportTopology(path) => {
+                if let Err(err) = self.export_topology(&path) {
+                    println!("Client {} cannot export its topology: {err}", self.id);
+                }
+            }
+            HostCommand::SendRequest(destination, request) => {
+                self.send_client_request(destination, request);
+            }
+        }
+    }
+
+    /// Sends a request that is not specific to the kind of client, if this client can.
+    fn send_client_request(&mut self, destination: NodeId, request: ClientRequest) {
+        let Some(content) = self.behaviour.controller_request(request) else {
+            println!("Client {} cannot send this kind of request", self.id);
+            return;
+        };
+        if !self.router.can_reach(destination) {
+            self.router.update_routes();
+        }
+        let session_id = self.new_session_id();
+        if !self.send_request(Message::new(self.id, destination, session_id, content)) {
+            println!("Client {} cannot reach {}", self.id, destination);
         }
     }
 
     pub fn handle_packet_normal(&mut self, packet: Packet) {
         self.router.update_graph(&packet);
+        self.react_to_packet(packet);
+    }
+
+    /// Waits for the card game to tell the card is over, handling the packets and the ticks
+    /// meanwhile as the main loop does, so that peers are acked and answered.
+    fn wait_for_card_game(&mut self) {
+        loop {
+            select! {
+                recv(self.card_receiver) -> _ => return,
+                recv(self.packet_recv) -> packet => {
+                    if let Ok(packet) = packet {
+                        self.handle_packet_normal(packet);
+                    }
+                }
+                default(RETRANSMISSION_TICK) => {}
+            }
+            self.service_ticks();
+        }
+    }
+
+    /// The periodic work of the client: floods, stale sessions, retransmissions and expired
+    /// requests.
+    fn service_ticks(&mut self) {
+        self.flood_periodically();
+        self.abandon_stale_sessions();
+        self.retransmit_due();
+        self.expire_requests();
+    }
+
+    /// Handles a packet whose information is already in the topology.
+    fn react_to_packet(&mut self, packet: Packet) {
         let session_id = packet.session_id;
         match packet.pack_type {
             MsgFragment(frag) => {
-                let quack = Packet::new_ack(
-                    self.router
-                        .get_best_route(packet.routing_header.source().unwrap())
-                        .unwrap(),
-                    session_id,
-                    frag.fragment_index,
-                );
-                self.forward(quack);
-                if let Some(message_result) = self.assembler.insert_fragment(session_id, frag) {
+                let source = packet.routing_header.source().unwrap();
+                self.acknowledge_received(source, session_id, frag.fragment_index);
+                if let Some(message_result) = self.receive_fragment(source, session_id, frag) {
                     match message_result {
                         Ok(message) => {
                             self.controller_send
                                 .send(HostEvent::MessageReceived(message.to_string_message()))
                                 .unwrap();
-                            self.assembler.forget(session_id);
-                            self.behaviour.on_response_received(message);
+                            self.dispatch_response(message);
+                        }
+                        // a probe timing the route, acked already
+                        Err(AssemblyError::Empty) => {}
+                        Err(AssemblyError::UnsupportedVersion(version)) => {
+                            let error = ResponseError::UnsupportedVersion(version);
+                            self.complete_request(session_id, Err(error));
+                        }
+                        Err(AssemblyError::Spoofed(source)) => {
+                            println!(
+                                "Client {} dropped session {session_id}, not signed by {source}",
+                                self.id
+                            );
+                            self.controller_send
+                                .send(HostEvent::MessageSpoofed {
+                                    host: self.id,
+                                    source,
+                                    session_id,
+                                })
+                                .unwrap();
                         }
-                        Err(_) => {
-                            todo!("Send UnexpectedRecipient Quacknt");
+                        Err(err) => {
+                            if let Some(message) = err.read_as::<Discovery>() {
+                                self.receive_discovery(message);
+                            } else if let Some(message) = err.read_as::<Handshake>() {
+                                self.receive_handshake(message);
+                            } else {
+                                println!(
+                                    "Client {} cannot read session {}: {err}",
+                                    self.id, session_id
+                                );
+                            }
                         }
                     }
                 }
             }
             Quack(quack) => {
-                self.disassembler
-                    .forget_fragment(session_id, quack.fragment_index);
-            }
-            Quacknt(quacknt) => match quacknt.nack_type {
-                NackType::ErrorInRouting(_) => {
-                    self.retransmit(session_id, quacknt.fragment_index);
+                if let Some(server) = self.disassembler.get_destination(session_id) {
+                    self.behaviour
+                        .on_ack(server, session_id, quack.fragment_index);
                 }
-                NackType::DestinationIsDrone => {}
-                NackType::Dropped => {
-                    self.retransmit(session_id, quacknt.fragment_index);
-                }
-                NackType::UnexpectedRecipient(id) => {
-                    self.unwanted_node(&id);
+                self.acknowledge_fragment(session_id, quack.fragment_index);
+            }
+            Quacknt(quacknt) => {
+                if let Some(server) = self.disassembler.get_destination(session_id) {
+                    self.behaviour.on_nack(server, session_id, &quacknt);
                 }
-            },
+                self.react_to_nack(session_id, quacknt, packet.routing_header.source());
+            }
             FloodRequest(mut request) => {
                 request.increment(self.id, SimpleNodeType::Client);
                 let response = request.generate_response(session_id);
@@ -269,19 +1113,270 @@ where
         }
     }
 
+    /// Keeps what a server answered to a [`Discovery`] request, for the card waiting for it.
+    fn receive_discovery(&mut self, message: Message<Discovery>) {
+        self.controller_send
+            .send(HostEvent::MessageReceived(message.to_string_message()))
+            .unwrap();
+        if let Discovery::ServerTypeResponse(server_type) = message.content {
+            self.discovered.insert(message.session_id, server_type);
+        }
+    }
+
+    /// Takes the key a server accepted, sealing the requests sent to it from now on.
+    fn receive_handshake(&mut self, message: Message<Handshake>) {
+        self.controller_send
+            .send(HostEvent::MessageReceived(message.to_string_message()))
+            .unwrap();
+        let Handshake::Accept(public_key) = message.content else {
+            return;
+        };
+        if !self.keyring.agree(message.source_id, public_key) {
+            println!(
+                "Client {} refused the key of server {}",
+                self.id, message.source_id
+            );
+        }
+    }
+
+    /// Retransmits the nacked fragment, or learns why it cannot be delivered.
+    fn react_to_nack(&mut self, session_id: u64, quacknt: Nack, nacked_by: Option<NodeId>) {
+        match quacknt.nack_type {
+            NackType::ErrorInRouting(_) => {
+                self.schedule_retransmission(session_id, quacknt.fragment_index, None);
+            }
+            NackType::DestinationIsDrone => {}
+            NackType::Dropped => {
+                self.router
+                    .write()
+                    .fragment_dropped(session_id, quacknt.fragment_index);
+                self.schedule_retransmission(session_id, quacknt.fragment_index, nacked_by);
+            }
+            NackType::UnexpectedRecipient(id) => {
+                // the server may have restarted and lost the key agreed with it
+                self.keyring.forget(id);
+                self.unwanted_node(&id);
+                self.complete_request(session_id, Err(ResponseError::WrongServer));
+            }
+        }
+    }
+
+    /// Reports the progress of the incoming messages to the controller, drawing a progress bar
+    /// for the long ones.
+    fn progress_observer(id: NodeId, controller_send: Sender<HostEvent>) -> ProgressObserver {
+        Box::new(move |session_id, received, total| {
+            controller_send
+                .send(HostEvent::SessionProgress {
+                    host: id,
+                    session_id,
+                    remaining: total - received,
+                })
+                .unwrap();
+            if total >= PROGRESS_BAR_MIN_FRAGMENTS {
+                print!("\r{}", progress_bar(received, total));
+                if received == total {
+                    println!();
+                }
+                io::stdout().flush().ok();
+            }
+        })
+    }
+
+    /// Acks an incoming fragment back to its source, if there is a route to it. Otherwise the
+    /// source retransmits the fragment and it is acked then.
+    fn acknowledge_received(&mut self, source: NodeId, session_id: u64, fragment_index: u64) {
+        if let Some(routing_header) = self.router.get_best_route(source) {
+            self.forward(Packet::new_ack(routing_header, session_id, fragment_index));
+        }
+    }
+
+    /// Reports the sessions dropped to stay within the memory budget.
+    fn report_evicted(&mut self, evicted: Vec<u64>, direction: SessionDirection) {
+        for session_id in evicted {
+            if direction == SessionDirection::Outgoing {
+                self.retransmissions.forget_session(session_id);
+                self.router.forget_session(session_id);
+                self.session_timings.remove(&session_id);
+            }
+            self.controller_send
+                .send(HostEvent::SessionEvicted {
+                    host: self.id,
+                    session_id,
+                    direction,
+                })
+                .unwrap();
+        }
+    }
+
+    /// Adds an incoming fragment to its message, reporting when the session starts and ends.
+    fn receive_fragment(
+        &mut self,
+        source: NodeId,
+        session_id: u64,
+        fragment: Fragment,
+    ) -> Option<Result<Message<B::ResponseType>, AssemblyError>> {
+        let total_fragments = fragment.total_n_fragments;
+        if self.assembler.received_fragments(source, session_id) == 0 {
+            self.controller_send
+                .send(HostEvent::SessionStarted {
+                    host: self.id,
+                    session_id,
+                    direction: SessionDirection::Incoming,
+                    total_fragments,
+                })
+                .unwrap();
+        }
+        let message = self
+            .assembler
+            .insert_fragment(source, session_id, fragment)
+            .map(|message| self.keyring.open(source, message));
+        let evicted = self.assembler.enforce_limits();
+        let evicted = evicted.into_iter().map(|(_source, session_id)| session_id);
+        self.report_evicted(evicted.collect(), SessionDirection::Incoming);
+        if message.is_some() {
+            self.controller_send
+                .send(HostEvent::SessionEnded {
+                    host: self.id,
+                    session_id,
+                })
+                .unwrap();
+        }
+        message
+    }
+
+    /// Gives up the incoming messages whose fragments stopped coming, reporting them.
+    fn abandon_stale_sessions(&mut self) {
+        for (_source, session_id) in self.assembler.gc(REASSEMBLY_TIMEOUT) {
+            self.controller_send
+                .send(HostEvent::SessionAbandoned {
+                    host: self.id,
+                    session_id,
+                })
+                .unwrap();
+        }
+    }
+
+    /// Forgets an acked fragment, reporting the progress of the session.
+    fn acknowledge_fragment(&mut self, session_id: u64, fragment_index: u64) {
+        self.router
+            .write()
+            .fragment_acknowledged(session_id, fragment_index);
+        self.retransmissions
+            .fragment_acknowledged(session_id, fragment_index);
+        if self
+            .disassembler
+            .forget_fragment(session_id, fragment_index)
+            .is_none()
+        {
+            return;
+        }
+        let event = match self.disassembler.remaining_fragments(session_id) {
+            0 => {
+                self.report_session_stats(session_id);
+                self.router.forget_session(session_id);
+                HostEvent::SessionEnded {
+                    host: self.id,
+                    session_id,
+                }
+            }
+            remaining => HostEvent::SessionProgress {
+                host: self.id,
+                session_id,
+                remaining: remaining as u64,
+            },
+        };
+        self.controller_send.send(event).unwrap();
+        // the ack made room in the window
+        let released = self.disassembler.release_fragments(session_id);
+        if let Some(destination) = self.disassembler.get_destination(session_id) {
+            self.send_fragments(session_id, destination, released);
+        }
+    }
+
+    /// Reports how long the session took to be fully acked and how many fragments it resent.
+    fn report_session_stats(&mut self, session_id: u64) {
+        let Some(timing) = self.session_timings.remove(&session_id) else {
+            return;
+        };
+        self.controller_send
+            .send(HostEvent::SessionStats {
+                host: self.id,
+                session_id,
+                destination: timing.destination,
+                latency: timing.started.elapsed(),
+                retransmissions: timing.retransmissions,
+                route: timing.route,
+            })
+            .unwrap();
+    }
+
     pub fn unwanted_node(&mut self, node_id: &NodeId) {
         self.router.unwanted_node(node_id);
     }
 
-    pub fn retransmit(&mut self, session_id: u64, fragment_index: u64) {
-        if let Some(fragment) = self.disassembler.get_fragment(session_id, fragment_index) {
-            let routing_header = self
-                .router
-                .get_best_route(self.disassembler.get_destination(session_id).unwrap())
-                .unwrap();
+    /// Retransmits the fragment after its backoff, giving up its message if it ran out of
+    /// retries.
+    fn schedule_retransmission(
+        &mut self,
+        session_id: u64,
+        fragment_index: u64,
+        avoided: Option<NodeId>,
+    ) {
+        let priority = self.disassembler.get_priority(session_id);
+        if self
+            .retransmissions
+            .schedule(session_id, fragment_index, avoided, priority)
+        {
+            return;
+        }
+        if let Some(server) = self.disassembler.get_destination(session_id) {
+            self.behaviour.on_session_failed(server, session_id);
+        }
+        self.retransmissions.forget_session(session_id);
+        self.router.forget_session(session_id);
+        self.session_timings.remove(&session_id);
+        self.disassembler.forget(session_id);
+        self.controller_send
+            .send(HostEvent::MessageFailed {
+                host: self.id,
+                session_id,
+            })
+            .unwrap();
+        self.complete_request(session_id, Err(ResponseError::Undeliverable));
+    }
+
+    /// Sends the retransmissions whose backoff elapsed.
+    fn retransmit_due(&mut self) {
+        for (session_id, fragment_index, avoided) in self.retransmissions.due() {
+            self.retransmit(session_id, fragment_index, avoided);
+        }
+    }
+
+    /// Sends a fragment again, on a route as disjoint as possible from the one it was dropped
+    /// on and avoiding the drone that dropped it if there is another route.
+    pub fn retransmit(&mut self, session_id: u64, fragment_index: u64, avoided: Option<NodeId>) {
+        if let Some(fragment) = self
+            .disassembler
+            .retransmit_fragment(session_id, fragment_index)
+        {
+            let Some(destination) = self.disassembler.get_destination(session_id) else {
+                return;
+            };
+            let Some(routing_header) = self.router.get_retransmission_route(
+                destination,
+                session_id,
+                fragment_index,
+                avoided,
+            ) else {
+                self.route_missing(session_id, fragment_index, avoided);
+                return;
+            };
 
             let packet = Packet::new_fragment(routing_header, session_id, fragment);
 
+            if let Some(timing) = self.session_timings.get_mut(&session_id) {
+                timing.retransmissions += 1;
+            }
             self.forward(packet);
         }
     }
@@ -294,39 +1389,48 @@ where
         self.router.calculate_routes()
     }
 
-    pub fn wait_for_response(
+    /// Waits for the response to the request sent in the session, asking the player whether
+    /// to keep waiting every time it does not come within [`RESPONSE_TIMEOUT`].
+    pub fn wait_for_response(&mut self, session_id: u64) -> ResponseResult<B> {
+        loop {
+            match self.wait_for_response_within(session_id, Some(RESPONSE_TIMEOUT)) {
+                Err(ResponseError::TimedOut) => {
+                    let answer = self.input_with_check(
+                        "The server did not answer yet, keep waiting? (y/n)".to_string(),
+                        |answer: &String| answer == "y" || answer == "n",
+                    );
+                    if answer == "n" {
+                        self.pending.take(session_id);
+                        break Err(ResponseError::TimedOut);
+                    }
+                }
+                result => break result,
+            }
+        }
+    }
+
+    /// Waits for the response to the request sent in the session, giving up after `timeout`
+    /// if there is one. The other requests go on meanwhile.
+    pub fn wait_for_response_within(
         &mut self,
-        mut predicate: impl FnMut(&Message<B::ResponseType>) -> bool,
-    ) -> Result<Message<B::ResponseType>, String> {
+        session_id: u64,
+        timeout: Option<Duration>,
+    ) -> ResponseResult<B> {
+        let deadline = timeout.map_or_else(never, after);
         loop {
+            if let Some(result) = self.pending.take_response(session_id) {
+                break result;
+            }
+            self.retransmit_due();
+            self.expire_requests();
             select! {
                 recv(self.packet_recv) -> packet => {
                     if let Ok(packet) = packet {
-                        match packet.pack_type {
-                            PacketType::MsgFragment(frag) => {
-                                self.router.update_graph(&(&packet.routing_header, &frag));
-                                if let Some(Ok(message)) = self.assembler.insert_fragment(packet.session_id, frag) {
-                                    self.controller_send.send(HostEvent::MessageReceived(message.to_string_message())).unwrap();
-                                    self.assembler.forget(packet.session_id);
-                                    if predicate(&message) {
-                                        break Ok(message);
-                                    }
-                                    self.behaviour.on_response_received(message);
-                                }
-                            }
-                            Quacknt(Nack {
-                                nack_type: NackType::UnexpectedRecipient(_),
-                                ..
-                            }) => {
-                                self.handle_packet_normal(packet);
-                                break Err("This is the wrong kind of Server".to_string());
-                            }
-                            _ => {
-                                self.handle_packet_normal(packet);
-                            }
-                        }
+                        self.handle_packet_normal(packet);
                     }
                 }
+                recv(deadline) -> _ => break Err(ResponseError::TimedOut),
+                default(RETRANSMISSION_TICK) => {}
             }
         }
     }
@@ -336,4 +1440,115 @@ impl<B: ClientBehaviour> Runnable for Client<B> {
     fn run(&mut self) {
         self.run();
     }
+    fn application_type(&self) -> ApplicationType {
+        B::application_type()
+    }
+}
+
+#[test]
+fn exchanges_take_only_the_responses_of_their_own_id() {
+    use crate::application::turn_handler::create_turn_handler;
+    use crate::client::stress_client::StressClient;
+    use crate::message::content_message::{ContentRequest, ContentResponse};
+    use crate::message::echo_message::{EchoRequest, EchoResponse};
+    use crossbeam_channel::unbounded;
+
+    let (events_send, _events) = unbounded();
+    let (_commands, controller_recv) = unbounded();
+    let (client_send, packet_recv) = unbounded();
+    let (drone_send, drone_recv) = unbounded();
+
+    // drone 5 and server 2 in one, answering every request with the echo of another exchange
+    // before the echo of its own
+    let server = thread::spawn(move || {
+        let back = SourceRoutingHeader {
+            hop_index: 2,
+            hops: vec![2, 5, 1],
+        };
+        let mut assembler = Assembler::<ContentRequest>::new();
+        let mut exchange_ids = Vec::new();
+        let mut session_id = 100;
+        while let Ok(packet) = drone_recv.recv_timeout(Duration::from_secs(5)) {
+            match packet.pack_type {
+                PacketType::FloodRequest(request) => {
+                    let mut response = request
+                        .get_incremented(5, SimpleNodeType::Drone)
+                        .get_incremented(2, SimpleNodeType::Server)
+                        .generate_response(packet.session_id);
+                    response.routing_header.hop_index = response.routing_header.hops.len() - 1;
+                    client_send.send(response).unwrap();
+                }
+                PacketType::MsgFragment(fragment) => {
+                    let ack =
+                        Packet::new_ack(back.clone(), packet.session_id, fragment.fragment_index);
+                    client_send.send(ack).unwrap();
+                    let Some(Ok(request)) =
+                        assembler.insert_fragment(1, packet.session_id, fragment)
+                    else {
+                        continue;
+                    };
+                    let exchange_id = request.exchange_id;
+                    exchange_ids.push(exchange_id);
+                    let ContentRequest::Echo(echo) = request.content else {
+                        continue;
+                    };
+                    let echoes = [
+                        (exchange_id.map(|id| id + 1), b"stray".to_vec()),
+                        (exchange_id, echo.payload),
+                    ];
+                    for (exchange_id, payload) in echoes {
+                        let echo = EchoResponse {
+                            payload,
+                            hops: 1,
+                            one_way_ms: 0,
+                        };
+                        let response = Message {
+                            exchange_id,
+                            ..Message::new(2, 1, session_id, ContentResponse::Echo(echo))
+                        };
+                        for fragment in Disassembler::new().disassembly(response) {
+                            let packet = Packet::new_fragment(back.clone(), session_id, fragment);
+                            client_send.send(packet).unwrap();
+                        }
+                        session_id += 1;
+                    }
+                }
+                _ => {}
+            }
+        }
+        exchange_ids
+    });
+
+    let mut client = StressClient::with_default_behaviour(
+        1,
+        events_send,
+        controller_recv,
+        packet_recv,
+        HashMap::from([(5, drone_send)]),
+        create_turn_handler(),
+    );
+    let echo = |payload: &[u8]| {
+        ContentRequest::Echo(EchoRequest {
+            payload: payload.to_vec(),
+            sent_at: 0,
+        })
+    };
+    let outcome = client.exchange(2, echo(b"first"), |_client, response| {
+        match response.content {
+            ContentResponse::Echo(EchoResponse { payload, .. }) if payload == b"first" => {
+                Step::Next(echo(b"second"))
+            }
+            content => Step::Done(content),
+        }
+    });
+    assert!(matches!(
+        outcome,
+        Ok(ContentResponse::Echo(EchoResponse { payload, .. })) if payload == b"second"
+    ));
+
+    drop(client);
+    let exchange_ids = server.join().unwrap();
+    assert_eq!(exchange_ids.len(), 2);
+    assert!(exchange_ids[0].is_some());
+    assert_eq!(exchange_ids[0], exchange_ids[1]);
 }
@@ -1,11 +1,14 @@
 use super::base_client::{Client, ClientBehaviour};
+use crate::application::route_policy::FewestHops;
+use crate::message::base_message::{Request, Response};
 use colored::{ColoredString, Colorize};
-use std::{sync::Arc, thread, time::Duration, vec};
-use wg_2024::packet::PacketType;
+use serde::Deserialize;
+use std::{path::PathBuf, sync::Arc, thread, time::Duration, vec};
+use wg_2024::network::NodeId;
 
 const CARD_WIDTH: usize = 50;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Deserialize)]
 pub enum Rarity {
     Common,
     Rare,
@@ -89,6 +92,20 @@ where
         }
     }
 
+    /// The same card, drawn with another rarity.
+    pub fn with_rarity(mut self, rarity: Rarity) -> Self {
+        self.rarity = rarity;
+        self
+    }
+
+    pub fn title(&self) -> &'static str {
+        self.title
+    }
+
+    pub fn description(&self) -> &'static str {
+        self.description
+    }
+
     pub fn is_yield_turn(&self) -> bool {
         self.title == "Yield Turn"
     }
@@ -197,6 +214,32 @@ where
     )
 }
 
+/// Played on its own at the start of every turn, to let the behaviour greet the player.
+pub fn turn_started_card<B>() -> Card<B>
+where
+    B: ClientBehaviour,
+{
+    Card::new(
+        "Turn Started",
+        "Your turn starts",
+        Rarity::Common,
+        |base_client: &mut Client<B>| base_client.behaviour.on_turn_started(),
+    )
+}
+
+/// Played on its own by the client that finished the last round of the game.
+pub fn game_over_card<B>() -> Card<B>
+where
+    B: ClientBehaviour,
+{
+    Card::new(
+        "Game Over",
+        "The game is over",
+        Rarity::Common,
+        |base_client: &mut Client<B>| base_client.report_game_over(),
+    )
+}
+
 pub fn flood_request_card<B>() -> Card<B>
 where
     B: ClientBehaviour,
@@ -206,18 +249,7 @@ where
         "Send a FloodRequest out",
         Rarity::Quacking,
         |base_client: &mut Client<B>| {
-            base_client.initiate_flood();
-
-            thread::sleep(Duration::from_millis(1500));
-
-            let mut count = 0;
-
-            while let Ok(packet) = base_client.try_recv_packet() {
-                if let PacketType::FloodResponse(_) = &packet.pack_type {
-                    count += 1;
-                }
-                base_client.handle_packet_normal(packet);
-            }
+            let count = base_client.refresh_topology();
 
             println!("{count} FloodResponses received");
         },
@@ -242,6 +274,97 @@ where
     )
 }
 
+/// Probes every known route to a destination, to check the one the requests take is sensible.
+pub fn ping_card<B>() -> Card<B>
+where
+    B: ClientBehaviour,
+{
+    Card::new(
+        "Ping",
+        "Time a probe along every known route to a destination",
+        Rarity::Common,
+        |base_client: &mut Client<B>| {
+            let destination: NodeId = base_client.input("Input the destination's ID".to_string());
+            let routes = base_client.routes_to(destination);
+            if routes.is_empty() {
+                println!("No known route to {destination}");
+                return;
+            }
+            let best = base_client.best_route(destination);
+            for hops in routes {
+                let chosen = if best.as_ref() == Some(&hops) {
+                    " (in use)"
+                } else {
+                    ""
+                };
+                let path: Vec<String> = hops.iter().map(ToString::to_string).collect();
+                let outcome = base_client.probe(hops);
+                println!("{}: {outcome}{chosen}", path.join(" -> "));
+            }
+
+            thread::sleep(Duration::from_millis(500));
+        },
+    )
+}
+
+/// Asks every reachable server what it provides, so the right destination is known.
+pub fn server_types_card<B>() -> Card<B>
+where
+    B: ClientBehaviour,
+{
+    Card::new(
+        "Server Types",
+        "Ask every reachable server whether it is a Chat, Text or Media server",
+        Rarity::Common,
+        |base_client: &mut Client<B>| {
+            let answers = base_client.discover_server_types();
+            if answers.is_empty() {
+                println!("No reachable server, play The Explorer first");
+            }
+            for (server, answer) in answers {
+                match answer {
+                    Ok(server_type) => println!("Server {server}: {server_type}"),
+                    Err(err) => println!("Server {server}: {err}"),
+                }
+            }
+
+            thread::sleep(Duration::from_millis(500));
+        },
+    )
+}
+
+pub fn server_stats_card<B>() -> Card<B>
+where
+    B: ClientBehaviour,
+{
+    Card::new(
+        "Server Stats",
+        "Ask a server how many requests it handled, and how fast",
+        Rarity::Common,
+        |base_client: &mut Client<B>| {
+            let server: NodeId = base_client.input("Input the server's ID".to_string());
+            let Some(session_id) =
+                base_client.request(server, B::RequestType::server_stats(), |response| {
+                    response.content.as_server_stats().is_some()
+                })
+            else {
+                println!("Failed to send the request");
+                return;
+            };
+            match base_client.wait_for_response(session_id) {
+                Ok(response) => {
+                    if let Some(stats) = response.content.as_server_stats() {
+                        print!("Server {server}: {stats}");
+                    }
+                }
+                Err(err) => println!("{err}"),
+            }
+
+            thread::sleep(Duration::from_millis(500));
+        },
+    )
+}
+
 pub fn generic_cards<B>() -> Vec<Card<B>>
 where
     B: ClientBehaviour,
@@ -249,6 +372,9 @@ where
     vec![
         flood_request_card(),
         the_navigator_card(),
+        ping_card(),
+        server_types_card(),
+        server_stats_card(),
         Card::new(
             "Forget Topology",
             "Forget the current topology and draw a The Explorer card",
@@ -263,6 +389,32 @@ where
                 thread::sleep(Duration::from_millis(500));
             },
         ),
+        Card::new(
+            "The Shortcut",
+            "Pick the routes crossing the fewest drones from now on",
+            Rarity::Rare,
+            |base_client: &mut Client<B>| {
+                base_client.set_route_policy(Box::new(FewestHops));
+
+                println!("You take the shortest ways!");
+
+                thread::sleep(Duration::from_millis(500));
+            },
+        ),
+        Card::new(
+            "The Cartographer",
+            "Draw the known topology to a Graphviz file",
+            Rarity::Common,
+            |base_client: &mut Client<B>| {
+                let path = PathBuf::from(format!("topology_{}.dot", base_client.id));
+                match base_client.export_topology(&path) {
+                    Ok(()) => println!("Topology drawn to {}", path.display()),
+                    Err(err) => println!("Unable to draw the topology: {err}"),
+                }
+
+                thread::sleep(Duration::from_millis(500));
+            },
+        ),
         Card::new(
             "Servers",
             "Discover the servers in the network",
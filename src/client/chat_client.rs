@@ -1,18 +1,226 @@
+use std::collections::{BTreeSet, HashMap};
+use std::time::{Duration, Instant};
+
 use wg_2024::network::NodeId;
 
 use super::card::{Card, Rarity};
-use super::utils::input;
+use super::transcript::Transcript;
+use crate::application::route_policy::{RoutePolicy, Sticky};
+use crate::application::simulation_controller_messages::ClientRequest;
 use crate::application::topology::node::ApplicationType;
-use crate::client::base_client::{Client, ClientBehaviour};
+use crate::application::turn_handler::MESSAGE_POINTS;
+use crate::client::base_client::{Client, ClientBehaviour, ResponseError, Step};
 use crate::message::base_message::Message;
 use crate::message::chat_message::{ChatRequest, ChatResponse};
+use crate::message::error_response::{ErrorCode, ErrorResponse};
+use crate::message::page::PageRequest;
 
 pub type ChatClient = Client<ChatClientBehaviour>;
 
+/// How often the registered clients tell their servers they are still there, well within the
+/// servers' registration timeout.
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(20);
+
+/// The ids of the messages a server passed on to the client, to tell which ones went missing.
+struct Inbox {
+    /// Every message up to this one arrived.
+    last: u64,
+    /// The messages that arrived after one that did not.
+    ahead: BTreeSet<u64>,
+    /// Whether the messages missing were asked for already.
+    catching_up: bool,
+}
+
+impl Inbox {
+    /// An inbox taking the messages from `first` on, the client knowing nothing of the
+    /// earlier ones.
+    fn starting_at(first: u64) -> Self {
+        Inbox {
+            last: first.saturating_sub(1),
+            ahead: BTreeSet::new(),
+            catching_up: false,
+        }
+    }
+
+    /// Takes note of the message, returning false if it arrived already.
+    fn receive(&mut self, id: u64) -> bool {
+        if id <= self.last || !self.ahead.insert(id) {
+            return false;
+        }
+        while self.ahead.remove(&(self.last + 1)) {
+            self.last += 1;
+        }
+        true
+    }
+
+    fn has_gap(&self) -> bool {
+        !self.ahead.is_empty()
+    }
+
+    /// Gives up the messages still missing, returning how many they are.
+    fn skip_missing(&mut self) -> u64 {
+        let Some(&newest) = self.ahead.last() else {
+            return 0;
+        };
+        let missing = newest - self.last - self.ahead.len() as u64;
+        self.last = newest;
+        self.ahead.clear();
+        missing
+    }
+}
+
 #[derive(Default)]
 pub struct ChatClientBehaviour {
     username: Option<String>,
     messages: Vec<(String, String)>,
+    /// The conversations of the user, once they registered.
+    transcript: Option<Transcript>,
+    /// The groups the user is in, with the server of each.
+    groups: Vec<(String, NodeId)>,
+    /// The servers the username is registered on, kept alive.
+    servers: BTreeSet<NodeId>,
+    last_keepalive: Option<Instant>,
+    /// The messages every server passed on to the client.
+    inboxes: HashMap<NodeId, Inbox>,
+}
+
+impl ChatClientBehaviour {
+    /// Takes the username, picking up the conversations saved under it.
+    fn set_username(&mut self, username: String) {
+        if self.username.as_ref() != Some(&username) {
+            self.transcript = Some(Transcript::load(username.clone()));
+        }
+        self.username = Some(username);
+    }
+
+    /// Tells every server the user registered on that they are still there, forgetting the
+    /// servers that expired the registration meanwhile.
+    fn keep_alive(base_client: &mut ChatClient) {
+        let Some(username) = base_client.behaviour.username.clone() else {
+            return;
+        };
+        let servers: Vec<NodeId> = base_client.behaviour.servers.iter().copied().collect();
+        for server in servers {
+            base_client.request_then(
+                server,
+                ChatRequest::KeepAlive(username.clone()),
+                |response| matches!(response.content, ChatResponse::Alive),
+                move |base_client, response| {
+                    if let Err(ResponseError::Rejected(ErrorResponse {
+                        code: ErrorCode::NotRegistered,
+                        ..
+                    })) = response
+                    {
+                        base_client.behaviour.servers.remove(&server);
+                        println!("Your registration on server {server} expired, register again");
+                    }
+                },
+            );
+        }
+    }
+
+    /// Creates or joins a group, as `request` asks, remembering it if the server agrees.
+    fn enter_group(base_client: &mut ChatClient, server: NodeId, request: ChatRequest) {
+        let Some(session_id) = base_client.request(server, request, |response| {
+            matches!(response.content, ChatResponse::GroupJoined { .. })
+        }) else {
+            println!("Failed to send the request");
+            return;
+        };
+        match base_client.wait_for_response(session_id) {
+            Ok(response) => {
+                if let ChatResponse::GroupJoined { group, members } = response.content {
+                    println!("You are in {group} with {members:?}");
+                    let groups = &mut base_client.behaviour.groups;
+                    groups.retain(|(joined, _server)| *joined != group);
+                    groups.push((group, server));
+                }
+            }
+            Err(err) => println!("{err}"),
+        }
+    }
+
+    /// Takes a message a server passed on, unless it arrived already, and notes whether some
+    /// message the server sent before it is missing.
+    fn receive_message(&mut self, server: NodeId, content: ChatResponse) {
+        let Some(id) = content.message_id() else {
+            return;
+        };
+        let inbox = self
+            .inboxes
+            .entry(server)
+            .or_insert_with(|| Inbox::starting_at(id));
+        if !inbox.receive(id) {
+            return;
+        }
+        let (from, message) = match content {
+            ChatResponse::MessageFrom { from, message, .. } => {
+                if let Some(username) = self.username.clone() {
+                    self.record(from.clone(), username, message.clone());
+                }
+                (from, message)
+            }
+            ChatResponse::GroupMessage {
+                group,
+                from,
+                message,
+                ..
+            } => {
+                self.record(from.clone(), group.clone(), message.clone());
+                (format!("{from} in {group}"), message)
+            }
+            _ => return,
+        };
+        self.messages.push((from.clone(), message));
+        println!("New message from {from} ({} unread)", self.messages.len());
+    }
+
+    /// Asks the servers that passed on messages after some that went missing for the ones
+    /// missing, giving up those the servers no longer keep.
+    fn catch_up(base_client: &mut ChatClient) {
+        let servers: Vec<(NodeId, u64)> = base_client
+            .behaviour
+            .inboxes
+            .iter()
+            .filter(|(_server, inbox)| inbox.has_gap() && !inbox.catching_up)
+            .map(|(server, inbox)| (*server, inbox.last))
+            .collect();
+        for (server, since) in servers {
+            let sent = base_client.request_then(
+                server,
+                ChatRequest::History { since },
+                |response| matches!(response.content, ChatResponse::History(_)),
+                move |base_client, response| {
+                    if let Ok(Message {
+                        content: ChatResponse::History(messages),
+                        ..
+                    }) = response
+                    {
+                        for message in messages {
+                            base_client.behaviour.receive_message(server, message);
+                        }
+                    }
+                    let Some(inbox) = base_client.behaviour.inboxes.get_mut(&server) else {
+                        return;
+                    };
+                    inbox.catching_up = false;
+                    let lost = inbox.skip_missing();
+                    if lost > 0 {
+                        println!("{lost} messages from server {server} were lost");
+                    }
+                },
+            );
+            if let Some(inbox) = base_client.behaviour.inboxes.get_mut(&server) {
+                inbox.catching_up = sent;
+            }
+        }
+    }
+
+    fn record(&mut self, from: String, to: String, message: String) {
+        if let Some(transcript) = self.transcript.as_mut() {
+            transcript.record(from, to, message);
+        }
+    }
 }
 
 impl ClientBehaviour for ChatClientBehaviour {
@@ -25,24 +233,70 @@ impl ClientBehaviour for ChatClientBehaviour {
                 "List all clients registered on the Chat Server",
                 Rarity::Common,
                 |base_client: &mut ChatClient| {
-                    let destination: NodeId = input("Enter the recipient's ID".to_string());
-                    let session_id = base_client.new_session_id();
-                    if !base_client.send_request(Message::new(
-                        base_client.id,
+                    let destination: NodeId =
+                        base_client.input("Enter the recipient's ID".to_string());
+                    let mut page = PageRequest::default();
+                    loop {
+                        let request = ChatRequest::ClientList(page);
+                        let Some(session_id) =
+                            base_client.request(destination, request, |response| {
+                                matches!(response.content, ChatResponse::ClientList(_))
+                            })
+                        else {
+                            println!("Failed to send the request");
+                            return;
+                        };
+                        let clients = match base_client.wait_for_response(session_id) {
+                            Ok(Message {
+                                content: ChatResponse::ClientList(clients),
+                                ..
+                            }) => clients,
+                            Ok(_) => return,
+                            Err(err) => {
+                                println!("{err}");
+                                return;
+                            }
+                        };
+                        println!("Clients: {:?}", clients.items);
+                        let Some(next) = clients.next else {
+                            return;
+                        };
+                        let answer: String = base_client.input_with_check(
+                            format!("Listed {next} of {}, show more? (y/n)", clients.total),
+                            |answer: &String| answer == "y" || answer == "n",
+                        );
+                        if answer == "n" {
+                            return;
+                        }
+                        page = page.after(next);
+                    }
+                },
+            ),
+            Card::new(
+                "Register",
+                "Register your username",
+                Rarity::Common,
+                |base_client: &mut ChatClient| {
+                    let destination: NodeId =
+                        base_client.input("Enter the Chat Server's ID".to_string());
+                    let username: String = base_client.input("Enter your username".to_string());
+                    let Some(session_id) = base_client.request(
                         destination,
-                        session_id,
-                        ChatRequest::ClientList,
-                    )) {
+                        ChatRequest::Register(username),
+                        |response| matches!(response.content, ChatResponse::Registered { .. }),
+                    ) else {
                         println!("Failed to send the request");
                         return;
-                    }
+                    };
+
+                    let response = base_client.wait_for_response(session_id);
 
-                    let response = base_client.wait_for_response(|response| {
-                        matches!(response.content, ChatResponse::ClientList(_))
-                    });
                     match response {
                         Ok(response) => {
-                            if let ChatResponse::ClientList(clients) = response.content {
+                            if let ChatResponse::Registered { username, clients } = response.content
+                            {
+                                base_client.behaviour.set_username(username);
+                                base_client.behaviour.servers.insert(destination);
                                 println!("Clients: {:?}", clients);
                             }
                         }
@@ -53,38 +307,67 @@ impl ClientBehaviour for ChatClientBehaviour {
                 },
             ),
             Card::new(
-                "Register",
-                "Register your username",
+                "Unregister",
+                "Free your username on a Chat Server",
                 Rarity::Common,
                 |base_client: &mut ChatClient| {
-                    let destination: NodeId = input("Enter the Chat Server's ID".to_string());
-                    let username: String = input("Enter your username".to_string());
-                    let session_id = base_client.new_session_id();
-                    if !base_client.send_request(Message::new(
-                        base_client.id,
+                    let Some(username) = base_client.behaviour.username.clone() else {
+                        println!("You need to register first!");
+                        return;
+                    };
+                    let destination: NodeId =
+                        base_client.input("Enter the Chat Server's ID".to_string());
+                    let Some(session_id) = base_client.request(
                         destination,
-                        session_id,
-                        ChatRequest::Register(username.clone()),
-                    )) {
+                        ChatRequest::Unregister(username.clone()),
+                        |response| matches!(response.content, ChatResponse::Unregistered(_)),
+                    ) else {
                         println!("Failed to send the request");
                         return;
+                    };
+                    match base_client.wait_for_response(session_id) {
+                        Ok(_) => {
+                            base_client.behaviour.servers.remove(&destination);
+                            println!("{username} is free on server {destination}");
+                        }
+                        Err(ResponseError::Rejected(error))
+                            if error.code == ErrorCode::NotRegistered =>
+                        {
+                            base_client.behaviour.servers.remove(&destination);
+                            println!("{username} was not registered there");
+                        }
+                        Err(err) => println!("{err}"),
                     }
-
-                    base_client.behaviour.username = Some(username);
-
-                    let response = base_client.wait_for_response(|response| {
-                        matches!(response.content, ChatResponse::ClientList(_))
-                    });
-
-                    match response {
+                },
+            ),
+            Card::new(
+                "Rename",
+                "Change your username on a Chat Server",
+                Rarity::Common,
+                |base_client: &mut ChatClient| {
+                    let Some(from) = base_client.behaviour.username.clone() else {
+                        println!("You need to register first!");
+                        return;
+                    };
+                    let destination: NodeId =
+                        base_client.input("Enter the Chat Server's ID".to_string());
+                    let to: String = base_client.input("Enter your new username".to_string());
+                    let Some(session_id) = base_client.request(
+                        destination,
+                        ChatRequest::Rename { from, to },
+                        |response| matches!(response.content, ChatResponse::Renamed { .. }),
+                    ) else {
+                        println!("Failed to send the request");
+                        return;
+                    };
+                    match base_client.wait_for_response(session_id) {
                         Ok(response) => {
-                            if let ChatResponse::ClientList(clients) = response.content {
-                                println!("Clients: {:?}", clients);
+                            if let ChatResponse::Renamed { to, .. } = response.content {
+                                println!("You are now {to}");
+                                base_client.behaviour.set_username(to);
                             }
                         }
-                        Err(err) => {
-                            println!("{err}");
-                        }
+                        Err(err) => println!("{err}"),
                     }
                 },
             ),
@@ -94,30 +377,191 @@ impl ClientBehaviour for ChatClientBehaviour {
                 Rarity::Common,
                 |base_client: &mut ChatClient| match base_client.behaviour.username.clone() {
                     Some(username) => {
-                        let server_id: NodeId = input("Enter the Chat Server's ID".to_string());
-                        let to: String = input("Enter the recipient's username".to_string());
-                        let content: String = input("Enter the message".to_string());
-                        let session_id = base_client.new_session_id();
-                        if !base_client.send_request(Message::new(
-                            base_client.id,
+                        let server_id: NodeId =
+                            base_client.input("Enter the Chat Server's ID".to_string());
+                        let to: String =
+                            base_client.input("Enter the recipient's username".to_string());
+                        let content: String = base_client.input("Enter the message".to_string());
+                        let Some(session_id) = base_client.request(
                             server_id,
-                            session_id,
                             ChatRequest::SendMessage {
-                                from: username,
+                                from: username.clone(),
                                 to,
-                                message: content,
+                                message: content.clone(),
+                            },
+                            |response| {
+                                matches!(response.content, ChatResponse::MessageForwarded { .. })
                             },
-                        )) {
+                        ) else {
                             println!("Failed to send the request");
-                        }
+                            return;
+                        };
 
-                        // TODO: Wait for response (it could receive a NotFound response)
+                        match base_client.wait_for_response(session_id) {
+                            Ok(response) => {
+                                if let ChatResponse::MessageForwarded { to } = response.content {
+                                    println!("Message delivered to {to}");
+                                    base_client.score(MESSAGE_POINTS);
+                                    base_client.behaviour.record(username, to, content);
+                                }
+                            }
+                            Err(err) => {
+                                println!("{err}");
+                            }
+                        }
                     }
                     None => {
                         println!("You need to register first!");
                     }
                 },
             ),
+            Card::new(
+                "Register and Send",
+                "Register a username and send a message with it in one go",
+                Rarity::Rare,
+                |base_client: &mut ChatClient| {
+                    let destination: NodeId =
+                        base_client.input("Enter the Chat Server's ID".to_string());
+                    let username: String = base_client.input("Enter your username".to_string());
+                    let to: String =
+                        base_client.input("Enter the recipient's username".to_string());
+                    let content: String = base_client.input("Enter the message".to_string());
+                    let send = ChatRequest::SendMessage {
+                        from: username.clone(),
+                        to,
+                        message: content.clone(),
+                    };
+                    let mut send = Some(send);
+                    let outcome = base_client.exchange(
+                        destination,
+                        ChatRequest::Register(username.clone()),
+                        |base_client, response| match response.content {
+                            ChatResponse::Registered { username, .. } => {
+                                base_client.behaviour.set_username(username);
+                                base_client.behaviour.servers.insert(destination);
+                                match send.take() {
+                                    Some(send) => Step::Next(send),
+                                    None => Step::Done(None),
+                                }
+                            }
+                            ChatResponse::MessageForwarded { to } => Step::Done(Some(to)),
+                            _ => Step::Done(None),
+                        },
+                    );
+                    match outcome {
+                        Ok(Some(to)) => {
+                            println!("Registered as {username}, message delivered to {to}");
+                            base_client.score(MESSAGE_POINTS);
+                            base_client.behaviour.record(username, to, content);
+                        }
+                        Ok(None) => println!("The server answered out of turn"),
+                        Err(err) => println!("{err}"),
+                    }
+                },
+            ),
+            Card::new(
+                "Create Group",
+                "Open a group on a Chat Server",
+                Rarity::Common,
+                |base_client: &mut ChatClient| {
+                    let Some(username) = base_client.behaviour.username.clone() else {
+                        println!("You need to register first!");
+                        return;
+                    };
+                    let server: NodeId =
+                        base_client.input("Enter the Chat Server's ID".to_string());
+                    let group: String = base_client.input("Enter the group's name".to_string());
+                    let request = ChatRequest::CreateGroup { group, username };
+                    ChatClientBehaviour::enter_group(base_client, server, request);
+                },
+            ),
+            Card::new(
+                "Join Group",
+                "Join a group opened on a Chat Server",
+                Rarity::Common,
+                |base_client: &mut ChatClient| {
+                    let Some(username) = base_client.behaviour.username.clone() else {
+                        println!("You need to register first!");
+                        return;
+                    };
+                    let server: NodeId =
+                        base_client.input("Enter the Chat Server's ID".to_string());
+                    let group: String = base_client.input("Enter the group's name".to_string());
+                    let request = ChatRequest::JoinGroup { group, username };
+                    ChatClientBehaviour::enter_group(base_client, server, request);
+                },
+            ),
+            Card::new(
+                "Send To Group",
+                "Send a message to every member of one of your groups",
+                Rarity::Common,
+                |base_client: &mut ChatClient| {
+                    let Some(username) = base_client.behaviour.username.clone() else {
+                        println!("You need to register first!");
+                        return;
+                    };
+                    let groups = base_client.behaviour.groups.clone();
+                    if groups.is_empty() {
+                        println!("You are in no group yet");
+                        return;
+                    }
+                    for (i, (group, server)) in groups.iter().enumerate() {
+                        println!("{}. {group} on server {server}", i + 1);
+                    }
+                    let choice = base_client
+                        .input_with_check("Choose the group".to_string(), |&choice: &usize| {
+                            (1..=groups.len()).contains(&choice)
+                        });
+                    let (group, server) = groups[choice - 1].clone();
+                    let message: String = base_client.input("Enter the message".to_string());
+                    let request = ChatRequest::SendToGroup {
+                        from: username.clone(),
+                        group: group.clone(),
+                        message: message.clone(),
+                    };
+                    let Some(session_id) = base_client.request(server, request, |response| {
+                        matches!(response.content, ChatResponse::GroupMessageSent { .. })
+                    }) else {
+                        println!("Failed to send the request");
+                        return;
+                    };
+                    match base_client.wait_for_response(session_id) {
+                        Ok(response) => {
+                            if let ChatResponse::GroupMessageSent { recipients, .. } =
+                                response.content
+                            {
+                                println!("Message delivered to {recipients} members of {group}");
+                                if recipients > 0 {
+                                    base_client.score(MESSAGE_POINTS);
+                                }
+                                base_client.behaviour.record(username, group, message);
+                            }
+                        }
+                        Err(err) => println!("{err}"),
+                    }
+                },
+            ),
+            Card::new(
+                "Subscribe",
+                "Be told about the users registering on a Chat Server",
+                Rarity::Common,
+                |base_client: &mut ChatClient| {
+                    let destination: NodeId =
+                        base_client.input("Enter the Chat Server's ID".to_string());
+                    let Some(session_id) =
+                        base_client.request(destination, ChatRequest::Subscribe, |response| {
+                            matches!(response.content, ChatResponse::Subscribed)
+                        })
+                    else {
+                        println!("Failed to send the request");
+                        return;
+                    };
+                    match base_client.wait_for_response(session_id) {
+                        Ok(_) => println!("You will be told about the users joining"),
+                        Err(err) => println!("{err}"),
+                    }
+                },
+            ),
             Card::new(
                 "Read Messages",
                 "Read all messages received",
@@ -128,16 +572,141 @@ impl ClientBehaviour for ChatClientBehaviour {
                     }
                 },
             ),
+            Card::new(
+                "History",
+                "Show the messages a Chat Server passed on to you, in the order it did",
+                Rarity::Common,
+                |base_client: &mut ChatClient| {
+                    let server: NodeId =
+                        base_client.input("Enter the Chat Server's ID".to_string());
+                    let Some(session_id) = base_client.request(
+                        server,
+                        ChatRequest::History { since: 0 },
+                        |response| matches!(response.content, ChatResponse::History(_)),
+                    ) else {
+                        println!("Failed to send the request");
+                        return;
+                    };
+                    match base_client.wait_for_response(session_id) {
+                        Ok(response) => {
+                            let ChatResponse::History(messages) = response.content else {
+                                return;
+                            };
+                            if messages.is_empty() {
+                                println!("Server {server} passed on no message to you");
+                            }
+                            for message in messages {
+                                if let ChatResponse::MessageFrom {
+                                    id,
+                                    timestamp,
+                                    ref from,
+                                    message: ref text,
+                                }
+                                | ChatResponse::GroupMessage {
+                                    id,
+                                    timestamp,
+                                    ref from,
+                                    message: ref text,
+                                    ..
+                                } = message
+                                {
+                                    println!("#{id} [{timestamp}] {from}: {text}");
+                                }
+                                base_client.behaviour.receive_message(server, message);
+                            }
+                        }
+                        Err(err) => println!("{err}"),
+                    }
+                },
+            ),
+            Card::new(
+                "Transcript",
+                "Show your conversations and export them to a text file",
+                Rarity::Common,
+                |base_client: &mut ChatClient| {
+                    let Some(transcript) = base_client.behaviour.transcript.as_ref() else {
+                        println!("You need to register first!");
+                        return;
+                    };
+                    for entry in transcript.entries() {
+                        println!("{entry}");
+                    }
+                    match transcript.export() {
+                        Ok(path) => println!("Transcript exported to {}", path.display()),
+                        Err(err) => println!("Unable to export the transcript: {err}"),
+                    }
+                },
+            ),
         ]
     }
 
     fn on_response_received(&mut self, response: Message<ChatResponse>) {
-        if let ChatResponse::MessageFrom { from, message } = response.content {
-            self.messages.push((from, message));
+        match response.content {
+            content @ (ChatResponse::MessageFrom { .. } | ChatResponse::GroupMessage { .. }) => {
+                self.receive_message(response.source_id, content);
+            }
+            ChatResponse::Registered { username, .. } => {
+                self.servers.insert(response.source_id);
+                println!("Registered as {username} on server {}", response.source_id);
+            }
+            ChatResponse::Error(error) => {
+                println!("Server {}: {}", response.source_id, error.message);
+            }
+            ChatResponse::UserJoined(username) => {
+                println!("{username} joined server {}", response.source_id);
+            }
+            _ => {}
+        }
+    }
+
+    fn on_tick(client: &mut ChatClient) {
+        let due = match client.behaviour.last_keepalive {
+            Some(last) => last.elapsed() >= KEEPALIVE_INTERVAL,
+            None => true,
+        };
+        if due && !client.behaviour.servers.is_empty() {
+            client.behaviour.last_keepalive = Some(Instant::now());
+            ChatClientBehaviour::keep_alive(client);
+        }
+        ChatClientBehaviour::catch_up(client);
+    }
+
+    fn on_turn_started(&mut self) {
+        if !self.messages.is_empty() {
+            println!(
+                "You have {} unread messages, play Read Messages to read them",
+                self.messages.len()
+            );
         }
     }
 
     fn application_type() -> ApplicationType {
         ApplicationType::Chat
     }
+
+    fn route_policy() -> Box<dyn RoutePolicy> {
+        Box::new(Sticky::default())
+    }
+
+    /// The chats are private, so the drones relaying them only see ciphertext.
+    fn encrypted() -> bool {
+        true
+    }
+
+    fn controller_request(&mut self, request: ClientRequest) -> Option<ChatRequest> {
+        match request {
+            ClientRequest::ClientList => Some(ChatRequest::ClientList(PageRequest::default())),
+            ClientRequest::Register(username) => {
+                self.set_username(username.clone());
+                Some(ChatRequest::Register(username))
+            }
+            ClientRequest::SendMessage { to, message } => {
+                let from = self.username.clone()?;
+                // the confirmation is not awaited, so the message is recorded as it leaves
+                self.record(from.clone(), to.clone(), message.clone());
+                Some(ChatRequest::SendMessage { from, to, message })
+            }
+            _ => None,
+        }
+    }
 }
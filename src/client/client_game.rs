@@ -1,5 +1,5 @@
 use std::{
-    iter,
+    env, iter,
     thread::{self, JoinHandle},
     time::Duration,
 };
@@ -16,6 +16,7 @@ use crate::{
 use super::{
     base_client::ClientBehaviour,
     card::{self, Card},
+    deck::Deck,
 };
 
 pub struct ClientGame<B>
@@ -23,22 +24,30 @@ where
     B: ClientBehaviour,
 {
     id: NodeId,
+    deck: Deck<B>,
     hand: Vec<Card<B>>,
     cards_played: usize,
     card_sender: Sender<Card<B>>,
     turn_handler: TurnHandlerArc,
+    /// The rounds played before the game is over.
+    rounds: usize,
 }
 
 const STARTING_HAND_SIZE: usize = 5;
 
+/// Rounds the card game lasts, 10 if unset.
+pub const ROUNDS_VARIABLE: &str = "BAGEL_ROUNDS";
+
+const DEFAULT_ROUNDS: usize = 10;
+
 impl<B> ClientGame<B>
 where
     B: ClientBehaviour,
 {
-    fn draw_card() -> Card<B> {
+    fn draw_card(deck: &Deck<B>) -> Card<B> {
         let mut rng = rand::thread_rng();
 
-        let mut all_cards = card::generic_cards().into_iter().chain(B::cards());
+        let mut all_cards = deck.cards().iter();
 
         let max_prob = all_cards.clone().map(|card| card.prob_value()).sum();
         let mut pick = rng.gen_range(0..max_prob);
@@ -46,27 +55,34 @@ where
         loop {
             let next_card = all_cards.next().unwrap();
             if pick < next_card.prob_value() {
-                break next_card;
+                break next_card.clone();
             }
             pick -= next_card.prob_value();
         }
     }
 
-    fn starting_hand() -> Vec<Card<B>> {
-        iter::once(card::flood_request_card())
-            .chain(iter::once(card::the_navigator_card()))
-            .chain(iter::repeat_with(|| Self::draw_card()))
-            .take(STARTING_HAND_SIZE)
+    fn starting_hand(deck: &Deck<B>) -> Vec<Card<B>> {
+        deck.starting()
+            .iter()
+            .cloned()
+            .chain(iter::repeat_with(|| Self::draw_card(deck)))
+            .take(STARTING_HAND_SIZE.max(deck.starting().len()))
             .collect()
     }
 
     pub fn new(id: NodeId, card_sender: Sender<Card<B>>, turn_handler: TurnHandlerArc) -> Self {
+        let deck = Deck::load();
         Self {
             id,
-            hand: Self::starting_hand(),
+            hand: Self::starting_hand(&deck),
+            deck,
             cards_played: 0,
             card_sender,
             turn_handler,
+            rounds: env::var(ROUNDS_VARIABLE)
+                .ok()
+                .and_then(|rounds| rounds.parse().ok())
+                .unwrap_or(DEFAULT_ROUNDS),
         }
     }
 
@@ -94,6 +110,10 @@ where
         self.turn_handler.lock().unwrap().yield_turn();
     }
 
+    fn is_game_over(&self) -> bool {
+        self.turn_handler.lock().unwrap().rounds() >= self.rounds
+    }
+
     fn unsubscribe_from_turn_handler(&self) {
         self.turn_handler.lock().unwrap().unsubscribe(self.id);
     }
@@ -106,13 +126,23 @@ where
     }
 
     pub(crate) fn draw_new_card(&mut self) {
-        let new_card = Self::draw_card();
+        let new_card = Self::draw_card(&self.deck);
         self.hand.push(new_card);
     }
 
+    /// Has the client play a card, returning `false` if it stopped.
+    fn play_card(&self, card: Card<B>) -> bool {
+        // the second send tells the client the card is over
+        self.card_sender.send(card.clone()).is_ok() && self.card_sender.send(card).is_ok()
+    }
+
     fn handle_turn(&mut self) -> bool {
         println!("It's Client {}'s turn", self.id);
 
+        if !self.play_card(card::turn_started_card()) {
+            return true;
+        }
+
         self.draw_new_card();
 
         self.hand.insert(0, card::yield_turn_card());
@@ -141,10 +171,7 @@ where
             let is_yield = card.is_yield_turn();
             let is_forget_topology = card.is_forget_topology();
 
-            self.card_sender
-                .send(card.clone())
-                .expect("unable to send card");
-            if self.card_sender.send(card).is_err() {
+            if !self.play_card(card) {
                 return true;
             }
 
@@ -165,6 +192,11 @@ where
 
         self.yield_turn();
 
+        if self.is_game_over() {
+            self.play_card(card::game_over_card());
+            return true;
+        }
+
         false
     }
 
@@ -174,6 +206,9 @@ where
         thread::sleep(Duration::from_millis(500));
 
         loop {
+            if self.is_game_over() {
+                break;
+            }
             if self.is_my_turn() {
                 if self.handle_turn() {
                     break;
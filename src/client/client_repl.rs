@@ -0,0 +1,123 @@
+use std::{
+    env,
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crossbeam_channel::Sender;
+use wg_2024::network::NodeId;
+
+use crate::{application::turn_handler::TurnHandlerArc, client::utils::input_with_check};
+
+use super::{
+    base_client::ClientBehaviour,
+    card::{self, Card},
+};
+
+/// Set to anything but `0` to replace the card game with [`ClientRepl`].
+pub const REPL_VARIABLE: &str = "BAGEL_REPL";
+
+/// Whether the clients should offer a plain menu instead of the card game.
+pub fn repl_requested() -> bool {
+    env::var(REPL_VARIABLE).is_ok_and(|value| value != "0")
+}
+
+/// A numbered menu of every action of the card game, always available, for testing by hand.
+/// The clients still take turns at the terminal, one at a time.
+pub struct ClientRepl<B>
+where
+    B: ClientBehaviour,
+{
+    id: NodeId,
+    actions: Vec<Card<B>>,
+    card_sender: Sender<Card<B>>,
+    turn_handler: TurnHandlerArc,
+}
+
+impl<B> ClientRepl<B>
+where
+    B: ClientBehaviour,
+{
+    pub fn new(id: NodeId, card_sender: Sender<Card<B>>, turn_handler: TurnHandlerArc) -> Self {
+        Self {
+            id,
+            actions: card::generic_cards()
+                .into_iter()
+                .chain(B::cards())
+                .collect(),
+            card_sender,
+            turn_handler,
+        }
+    }
+
+    pub fn start_thread(
+        id: NodeId,
+        card_sender: Sender<Card<B>>,
+        turn_handler: TurnHandlerArc,
+    ) -> JoinHandle<()>
+    where
+        B: 'static,
+    {
+        thread::spawn(move || Self::new(id, card_sender, turn_handler).run())
+    }
+
+    fn is_my_turn(&self) -> bool {
+        let current_turn = self.turn_handler.lock().unwrap().current_turn();
+        current_turn == self.id
+    }
+
+    fn print_menu(&self) {
+        println!("Client {}:", self.id);
+        println!("  0. Next client");
+        for (i, action) in self.actions.iter().enumerate() {
+            println!("  {}. {}: {}", i + 1, action.title(), action.description());
+        }
+    }
+
+    /// Has the client run an action, returning `false` if it stopped.
+    fn play_action(&self, action: Card<B>) -> bool {
+        // the second send tells the client the action is over, as the card game does
+        self.card_sender.send(action.clone()).is_ok() && self.card_sender.send(action).is_ok()
+    }
+
+    /// Runs actions until the player moves on to the next client, returning `true` once the
+    /// client stopped.
+    fn handle_turn(&mut self) -> bool {
+        if !self.play_action(card::turn_started_card()) {
+            return true;
+        }
+        loop {
+            self.print_menu();
+
+            let choice: usize = input_with_check("Choose an action: ".to_string(), |&choice| {
+                choice <= self.actions.len()
+            });
+            if choice == 0 {
+                return false;
+            }
+
+            if !self.play_action(self.actions[choice - 1].clone()) {
+                return true;
+            }
+        }
+    }
+
+    pub fn run(&mut self) {
+        self.turn_handler.lock().unwrap().subscribe(self.id);
+
+        thread::sleep(Duration::from_millis(500));
+
+        loop {
+            if self.is_my_turn() {
+                if self.handle_turn() {
+                    break;
+                }
+                self.turn_handler.lock().unwrap().yield_turn();
+            } else {
+                thread::sleep(Duration::from_millis(500));
+            }
+        }
+
+        self.turn_handler.lock().unwrap().unsubscribe(self.id);
+    }
+}
@@ -0,0 +1,116 @@
+use std::{env, fs};
+
+use serde::Deserialize;
+
+use super::{
+    base_client::ClientBehaviour,
+    card::{self, Card, Rarity},
+};
+
+/// The TOML file the decks of the card game are read from, every card is in the deck if unset.
+pub const DECK_VARIABLE: &str = "BAGEL_DECK";
+
+/// A card of the deck, found by its title, with the rarity it is drawn with if changed.
+#[derive(Debug, Clone, Deserialize)]
+struct DeckCard {
+    title: String,
+    rarity: Option<Rarity>,
+}
+
+/// Which cards the players draw and which ones they start with, for instance:
+///
+/// ```toml
+/// starting = ["The Explorer", "Ping"]
+///
+/// [[card]]
+/// title = "Ping"
+/// rarity = "Quacking"
+///
+/// [[card]]
+/// title = "Request Media"
+/// ```
+///
+/// A single file is shared by every kind of client, so each one skips the cards it lacks.
+#[derive(Debug, Clone, Deserialize)]
+struct DeckFile {
+    starting: Option<Vec<String>>,
+    #[serde(default, rename = "card")]
+    cards: Vec<DeckCard>,
+}
+
+impl DeckFile {
+    fn load(path: &str) -> Result<Self, String> {
+        let data = fs::read_to_string(path).map_err(|err| format!("cannot read {path}: {err}"))?;
+        toml::from_str(&data).map_err(|err| format!("cannot parse {path}: {err}"))
+    }
+}
+
+/// The cards a client can draw, and the ones it starts with.
+pub struct Deck<B: ClientBehaviour> {
+    cards: Vec<Card<B>>,
+    starting: Vec<Card<B>>,
+}
+
+impl<B: ClientBehaviour> Default for Deck<B> {
+    fn default() -> Self {
+        Self {
+            cards: card::generic_cards()
+                .into_iter()
+                .chain(B::cards())
+                .collect(),
+            starting: vec![card::flood_request_card(), card::the_navigator_card()],
+        }
+    }
+}
+
+impl<B: ClientBehaviour> Deck<B> {
+    /// The deck of the file named by [`DECK_VARIABLE`], or every card if there is none or it
+    /// leaves this client without cards.
+    pub fn load() -> Self {
+        let Ok(path) = env::var(DECK_VARIABLE) else {
+            return Self::default();
+        };
+        match DeckFile::load(&path) {
+            Ok(file) => Self::from_file(file).unwrap_or_else(|| {
+                println!("The deck in {path} has none of this client's cards, using every card");
+                Self::default()
+            }),
+            Err(err) => {
+                println!("Using every card, {err}");
+                Self::default()
+            }
+        }
+    }
+
+    fn from_file(file: DeckFile) -> Option<Self> {
+        let all = Self::default();
+        let find = |title: &str| all.cards.iter().find(|card| card.title() == title).cloned();
+        let cards: Vec<Card<B>> = file
+            .cards
+            .iter()
+            .filter_map(|deck_card| {
+                let card = find(&deck_card.title)?;
+                Some(match deck_card.rarity {
+                    Some(rarity) => card.with_rarity(rarity),
+                    None => card,
+                })
+            })
+            .collect();
+        if cards.is_empty() {
+            return None;
+        }
+        let starting = match file.starting {
+            Some(titles) => titles.iter().filter_map(|title| find(title)).collect(),
+            None => all.starting,
+        };
+        Some(Self { cards, starting })
+    }
+
+    pub fn cards(&self) -> &[Card<B>] {
+        &self.cards
+    }
+
+    pub fn starting(&self) -> &[Card<B>] {
+        &self.starting
+    }
+}
@@ -0,0 +1,93 @@
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+/// The directory the web browser saves media to, `downloads` if unset.
+pub const DOWNLOADS_VARIABLE: &str = "BAGEL_DOWNLOADS";
+/// Set to `0` to keep the web browser from opening the media it downloads.
+pub const OPEN_MEDIA_VARIABLE: &str = "BAGEL_OPEN_MEDIA";
+
+const DEFAULT_DIRECTORY: &str = "downloads";
+
+/// Where the downloaded media are saved and whether they are opened afterwards.
+#[derive(Debug, Clone)]
+pub struct Downloads {
+    directory: PathBuf,
+    open: bool,
+}
+
+impl Default for Downloads {
+    fn default() -> Self {
+        Self {
+            directory: env::var_os(DOWNLOADS_VARIABLE)
+                .map_or_else(|| PathBuf::from(DEFAULT_DIRECTORY), PathBuf::from),
+            open: env::var(OPEN_MEDIA_VARIABLE).map_or(true, |value| value != "0"),
+        }
+    }
+}
+
+/// The extension matching the format of the media, recognized by its first bytes.
+fn extension_of(media: &[u8]) -> &'static str {
+    match media {
+        [0x89, b'P', b'N', b'G', ..] => "png",
+        [0xff, 0xd8, 0xff, ..] => "jpg",
+        [b'G', b'I', b'F', b'8', ..] => "gif",
+        _ => "bin",
+    }
+}
+
+impl Downloads {
+    /// The file a media is saved to: its name without the characters unfit for a path, with
+    /// the extension of its format if it has none, and numbered if the name is taken.
+    fn path_for(&self, name: &str, media: &[u8]) -> PathBuf {
+        let mut stem: String = name
+            .chars()
+            .filter(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'))
+            .collect::<String>()
+            .trim_matches('.')
+            .to_string();
+        if stem.is_empty() {
+            stem = "media".to_string();
+        }
+        let extension = match Path::new(&stem).extension() {
+            Some(_) => None,
+            None => Some(extension_of(media)),
+        };
+        let file_name = |copy: usize| {
+            let numbered = match copy {
+                0 => stem.clone(),
+                copy => match stem.rsplit_once('.') {
+                    Some((base, ext)) if extension.is_none() => format!("{base}-{copy}.{ext}"),
+                    _ => format!("{stem}-{copy}"),
+                },
+            };
+            match extension {
+                Some(extension) => format!("{numbered}.{extension}"),
+                None => numbered,
+            }
+        };
+        (0..)
+            .map(|copy| self.directory.join(file_name(copy)))
+            .find(|path| !path.exists())
+            .unwrap()
+    }
+
+    /// Saves a media without overwriting an earlier download, returning where it went.
+    pub fn save(&self, name: &str, media: &[u8]) -> io::Result<PathBuf> {
+        fs::create_dir_all(&self.directory)?;
+        let path = self.path_for(name, media);
+        fs::write(&path, media)?;
+        Ok(path)
+    }
+
+    /// Opens a saved media with the default application, if opening is enabled.
+    pub fn open(&self, path: &Path) {
+        if !self.open {
+            return;
+        }
+        if let Err(err) = open::that(path) {
+            println!("Unable to open {}: {err}", path.display());
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use std::{fmt::Display, fs, io, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+use wg_2024::network::NodeId;
+
+use super::utils::unix_timestamp;
+
+/// Where the history of every web browser is kept, one file per client.
+const HISTORY_DIRECTORY: &str = "history";
+/// Most requests remembered, the oldest are forgotten first. Bookmarks are always kept.
+const MAX_HISTORY_ENTRIES: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ItemKind {
+    Text,
+    Media,
+}
+
+/// An item requested to a server.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub server: NodeId,
+    pub kind: ItemKind,
+    pub name: String,
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+}
+
+impl Display for HistoryEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:?} {} from server {} (at {})",
+            self.kind, self.name, self.server, self.timestamp
+        )
+    }
+}
+
+/// The items a web browser requested and the ones it bookmarked, saved to a JSON file keyed by
+/// the client id so that they survive restarts.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct History {
+    entries: Vec<HistoryEntry>,
+    bookmarks: Vec<HistoryEntry>,
+}
+
+impl History {
+    fn path(client: NodeId) -> PathBuf {
+        PathBuf::from(HISTORY_DIRECTORY).join(format!("browser_{client}.json"))
+    }
+
+    /// The history of the client, empty if it has none or it cannot be read.
+    pub fn load(client: NodeId) -> Self {
+        fs::read_to_string(Self::path(client))
+            .ok()
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, client: NodeId) -> io::Result<()> {
+        fs::create_dir_all(HISTORY_DIRECTORY)?;
+        fs::write(Self::path(client), serde_json::to_string_pretty(self)?)
+    }
+
+    /// Records a request, saving the history right away.
+    pub fn record(&mut self, client: NodeId, server: NodeId, kind: ItemKind, name: String) {
+        self.entries.push(HistoryEntry {
+            server,
+            kind,
+            name,
+            timestamp: unix_timestamp(),
+        });
+        let overflow = self.entries.len().saturating_sub(MAX_HISTORY_ENTRIES);
+        self.entries.drain(..overflow);
+        if let Err(err) = self.save(client) {
+            println!("Unable to save the history: {err}");
+        }
+    }
+
+    /// Bookmarks an entry of the history, unless the same item already is.
+    pub fn bookmark(&mut self, client: NodeId, entry: HistoryEntry) {
+        let known = self.bookmarks.iter().any(|bookmark| {
+            bookmark.server == entry.server
+                && bookmark.kind == entry.kind
+                && bookmark.name == entry.name
+        });
+        if !known {
+            self.bookmarks.push(entry);
+        }
+        if let Err(err) = self.save(client) {
+            println!("Unable to save the bookmarks: {err}");
+        }
+    }
+
+    /// The requests, newest first.
+    pub fn entries(&self) -> impl Iterator<Item = &HistoryEntry> {
+        self.entries.iter().rev()
+    }
+
+    pub fn bookmarks(&self) -> &[HistoryEntry] {
+        &self.bookmarks
+    }
+}
@@ -2,7 +2,16 @@ mod base_client;
 pub(crate) mod card;
 pub mod chat_client;
 mod client_game;
+mod client_repl;
+mod deck;
+mod downloads;
+mod history;
+mod pending_requests;
+pub mod scripted_client;
+pub mod stress_client;
+mod transcript;
 pub(crate) mod utils;
 pub mod web_browser;
+mod web_page;
 
 pub use chat_client::ChatClient;
@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+use std::time::Instant;
+
+use wg_2024::network::NodeId;
+
+use super::base_client::{Client, ClientBehaviour, ResponseError};
+use crate::message::base_message::Message;
+
+pub type ResponseResult<B> = Result<Message<<B as ClientBehaviour>::ResponseType>, ResponseError>;
+/// Tells whether a message from the server is the response to a request.
+pub type ResponsePredicate<B> =
+    Box<dyn FnMut(&Message<<B as ClientBehaviour>::ResponseType>) -> bool + Send>;
+/// Handles the response to a request, or why it did not come, once the request is over.
+pub type ResponseCallback<B> = Box<dyn FnOnce(&mut Client<B>, ResponseResult<B>) + Send>;
+
+pub(crate) struct PendingRequest<B: ClientBehaviour> {
+    destination: NodeId,
+    predicate: ResponsePredicate<B>,
+    /// `None` if a card waits for the response instead.
+    pub(crate) on_response: Option<ResponseCallback<B>>,
+    deadline: Option<Instant>,
+}
+
+/// The requests still waiting for their response, by session id, so that several of them can
+/// be outstanding at once.
+pub(crate) struct PendingRequests<B: ClientBehaviour> {
+    requests: HashMap<u64, PendingRequest<B>>,
+    /// The outcomes of the requests a card waits for, until it takes them.
+    responses: HashMap<u64, ResponseResult<B>>,
+}
+
+impl<B: ClientBehaviour> Default for PendingRequests<B> {
+    fn default() -> Self {
+        Self {
+            requests: HashMap::new(),
+            responses: HashMap::new(),
+        }
+    }
+}
+
+impl<B: ClientBehaviour> PendingRequests<B> {
+    pub fn insert(
+        &mut self,
+        session_id: u64,
+        destination: NodeId,
+        predicate: ResponsePredicate<B>,
+        on_response: Option<ResponseCallback<B>>,
+        deadline: Option<Instant>,
+    ) {
+        self.requests.insert(
+            session_id,
+            PendingRequest {
+                destination,
+                predicate,
+                on_response,
+                deadline,
+            },
+        );
+    }
+
+    /// Finds the request the message answers: the one of its session, as the servers answer in
+    /// the session of the request, or else the oldest request to its sender that accepts it.
+    pub fn match_response(&mut self, message: &Message<B::ResponseType>) -> Option<u64> {
+        if self.awaits(message.session_id, message.source_id) {
+            return Some(message.session_id);
+        }
+        let mut candidates: Vec<_> = self
+            .requests
+            .iter_mut()
+            .filter(|(_session_id, request)| request.destination == message.source_id)
+            .collect();
+        candidates.sort_by_key(|(session_id, _request)| **session_id);
+        candidates
+            .into_iter()
+            .find_map(|(session_id, request)| (request.predicate)(message).then_some(*session_id))
+    }
+
+    /// Whether the session is a request to the node still waiting for its response.
+    pub fn awaits(&self, session_id: u64, source: NodeId) -> bool {
+        self.requests
+            .get(&session_id)
+            .is_some_and(|request| request.destination == source)
+    }
+
+    pub fn take(&mut self, session_id: u64) -> Option<PendingRequest<B>> {
+        self.requests.remove(&session_id)
+    }
+
+    /// The requests whose deadline passed.
+    pub fn expired(&self) -> Vec<u64> {
+        let now = Instant::now();
+        self.requests
+            .iter()
+            .filter(|(_session_id, request)| request.deadline.is_some_and(|due| due <= now))
+            .map(|(session_id, _request)| *session_id)
+            .collect()
+    }
+
+    pub fn store_response(&mut self, session_id: u64, result: ResponseResult<B>) {
+        self.responses.insert(session_id, result);
+    }
+
+    /// The outcome of the request a card waits for, if it came, leaving it for the card.
+    pub fn response(&self, session_id: u64) -> Option<&ResponseResult<B>> {
+        self.responses.get(&session_id)
+    }
+
+    pub fn take_response(&mut self, session_id: u64) -> Option<ResponseResult<B>> {
+        self.responses.remove(&session_id)
+    }
+}
+
+#[test]
+fn responses_go_to_the_request_of_their_session() {
+    use crate::client::stress_client::StressClientBehaviour;
+    use crate::message::content_message::ContentResponse;
+    use crate::message::echo_message::EchoResponse;
+
+    let mut pending = PendingRequests::<StressClientBehaviour>::default();
+    for session_id in [1, 2] {
+        pending.insert(session_id, 9, Box::new(|_response| true), None, None);
+    }
+    let echo = |session_id| {
+        let echo = EchoResponse {
+            payload: Vec::new(),
+            hops: 2,
+            one_way_ms: 0,
+        };
+        Message::new(9, 1, session_id, ContentResponse::Echo(echo))
+    };
+
+    assert_eq!(pending.match_response(&echo(2)), Some(2));
+    assert_eq!(pending.match_response(&echo(1)), Some(1));
+    // a message in a session of its own is taken by the oldest request accepting it
+    assert_eq!(pending.match_response(&echo(7)), Some(1));
+}
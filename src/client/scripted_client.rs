@@ -0,0 +1,212 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+use wg_2024::network::NodeId;
+use wg_2024::packet::Nack;
+
+use super::base_client::{Client, ClientBehaviour};
+use super::card::Card;
+use crate::application::assembler::AssemblyLimits;
+use crate::application::route_policy::RoutePolicy;
+use crate::application::routing::RouterSettings;
+use crate::application::simulation_controller_messages::{ClientRequest, HostCommand};
+use crate::application::topology::node::ApplicationType;
+use crate::message::base_message::Message;
+use crate::message::codec::MessageCodec;
+
+pub type ScriptedClient<B> = Client<ScriptedBehaviour<B>>;
+
+/// A step of a [`Scenario`], run `delay_ms` milliseconds after the previous one: either a
+/// request or a flood.
+///
+/// ```toml
+/// [[step]]
+/// delay_ms = 500
+/// destination = 7
+/// request = { Media = "#whale" }
+/// expect = "Media("
+///
+/// [[step]]
+/// flood = true
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScenarioStep {
+    #[serde(default)]
+    pub delay_ms: u64,
+    pub destination: Option<NodeId>,
+    pub request: Option<ClientRequest>,
+    /// Floods the network instead of sending a request.
+    #[serde(default)]
+    pub flood: bool,
+    /// Text the response must contain, if any is checked.
+    pub expect: Option<String>,
+}
+
+impl ScenarioStep {
+    /// The command the client runs for the step, if the step is complete.
+    fn command(&self) -> Option<HostCommand> {
+        match (self.flood, self.destination, &self.request) {
+            (true, _, _) => Some(HostCommand::InitiateFlood),
+            (false, Some(destination), Some(request)) => {
+                Some(HostCommand::SendRequest(destination, request.clone()))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The requests a [`ScriptedClient`] sends, read from a TOML file.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Scenario {
+    /// Starts over once the last step was sent, for soak tests.
+    #[serde(default)]
+    pub repeat: bool,
+    #[serde(default, rename = "step")]
+    pub steps: Vec<ScenarioStep>,
+}
+
+impl Scenario {
+    pub fn load(path: &str) -> Result<Scenario, String> {
+        let data = fs::read_to_string(path).map_err(|err| format!("cannot read {path}: {err}"))?;
+        let scenario: Scenario =
+            toml::from_str(&data).map_err(|err| format!("cannot parse {path}: {err}"))?;
+        match scenario
+            .steps
+            .iter()
+            .position(|step| step.command().is_none())
+        {
+            Some(index) => Err(format!(
+                "step {} of {path} needs a destination and a request, or flood = true",
+                index + 1
+            )),
+            None => Ok(scenario),
+        }
+    }
+}
+
+/// Plays a [`Scenario`] instead of the card game, leaving everything else to the behaviour
+/// `B` it wraps, so that headless runs generate traffic on their own.
+pub struct ScriptedBehaviour<B> {
+    inner: B,
+    scenario: Scenario,
+    next_step: usize,
+    next_at: Instant,
+    /// What the response to the step being sent must contain.
+    step_expect: Option<String>,
+    /// What the responses to the requests sent so far must contain, oldest first.
+    expected: VecDeque<Option<String>>,
+}
+
+impl<B: ClientBehaviour + Default> ScriptedBehaviour<B> {
+    pub fn new(scenario: Scenario) -> Self {
+        let first_delay = scenario.steps.first().map_or(0, |step| step.delay_ms);
+        ScriptedBehaviour {
+            inner: B::default(),
+            scenario,
+            next_step: 0,
+            next_at: Instant::now() + Duration::from_millis(first_delay),
+            step_expect: None,
+            expected: VecDeque::new(),
+        }
+    }
+}
+
+impl<B: ClientBehaviour> ClientBehaviour for ScriptedBehaviour<B> {
+    type RequestType = B::RequestType;
+    type ResponseType = B::ResponseType;
+
+    fn cards() -> Vec<Card<Self>> {
+        Vec::new()
+    }
+
+    fn plays_cards() -> bool {
+        false
+    }
+
+    fn on_response_received(&mut self, response: Message<Self::ResponseType>) {
+        if let Some(Some(expected)) = self.expected.pop_front() {
+            let content = response.content.to_string();
+            if !content.contains(&expected) {
+                println!(
+                    "Client {} expected a response with {expected:?}, got {content}",
+                    response.destination_id
+                );
+            }
+        }
+        self.inner.on_response_received(response);
+    }
+
+    fn on_ack(&mut self, server: NodeId, session_id: u64, fragment_index: u64) {
+        self.inner.on_ack(server, session_id, fragment_index);
+    }
+
+    fn on_nack(&mut self, server: NodeId, session_id: u64, nack: &Nack) {
+        self.inner.on_nack(server, session_id, nack);
+    }
+
+    fn on_session_failed(&mut self, server: NodeId, session_id: u64) {
+        self.inner.on_session_failed(server, session_id);
+    }
+
+    fn application_type() -> ApplicationType {
+        B::application_type()
+    }
+
+    fn controller_request(&mut self, request: ClientRequest) -> Option<Self::RequestType> {
+        let request = self.inner.controller_request(request);
+        let expect = self.step_expect.take();
+        if request.is_some() {
+            self.expected.push_back(expect);
+        }
+        request
+    }
+
+    fn next_command(&mut self) -> Option<HostCommand> {
+        if self.next_at > Instant::now() {
+            return None;
+        }
+        if self.next_step == self.scenario.steps.len() {
+            if !self.scenario.repeat || self.scenario.steps.is_empty() {
+                return None;
+            }
+            self.next_step = 0;
+        }
+        let step = self.scenario.steps[self.next_step].clone();
+        self.next_step += 1;
+        let next_delay = self
+            .scenario
+            .steps
+            .get(self.next_step % self.scenario.steps.len())
+            .map_or(0, |step| step.delay_ms);
+        self.next_at = Instant::now() + Duration::from_millis(next_delay);
+        // a flood gets no response to check
+        self.step_expect = step.expect.clone().filter(|_| !step.flood);
+        step.command()
+    }
+
+    fn route_policy() -> Box<dyn RoutePolicy> {
+        B::route_policy()
+    }
+
+    fn router_settings() -> RouterSettings {
+        B::router_settings()
+    }
+
+    fn flood_interval() -> Option<Duration> {
+        B::flood_interval()
+    }
+
+    fn codec() -> MessageCodec {
+        B::codec()
+    }
+
+    fn assembly_limits() -> AssemblyLimits {
+        B::assembly_limits()
+    }
+
+    fn encrypted() -> bool {
+        B::encrypted()
+    }
+}
@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+use std::env;
+use std::time::{Duration, Instant};
+
+use rand::Rng;
+use wg_2024::network::NodeId;
+
+use super::base_client::{Client, ClientBehaviour};
+use super::card::Card;
+use crate::application::simulation_controller_messages::ClientRequest;
+use crate::application::topology::node::ApplicationType;
+use crate::message::base_message::Message;
+use crate::message::content_message::{ContentRequest, ContentResponse};
+use crate::message::media_message::{MediaRequest, MediaResponse};
+use crate::message::page::PageRequest;
+use crate::message::text_message::{TextRequest, TextResponse};
+
+/// Requests the stress clients send every second, 5 if unset.
+pub const STRESS_RATE_VARIABLE: &str = "BAGEL_STRESS_RATE";
+
+const DEFAULT_RATE: f64 = 5.0;
+
+pub type StressClient = Client<StressClientBehaviour>;
+
+/// Requests random texts and media from every content server it knows at a steady rate,
+/// without playing the card game, to keep the drones under load.
+pub struct StressClientBehaviour {
+    interval: Duration,
+    next_at: Instant,
+    /// The items every server listed, empty until its lists arrive.
+    items: HashMap<NodeId, Vec<ContentRequest>>,
+}
+
+impl Default for StressClientBehaviour {
+    fn default() -> Self {
+        let rate = env::var(STRESS_RATE_VARIABLE)
+            .ok()
+            .and_then(|rate| rate.parse::<f64>().ok())
+            .filter(|rate| *rate > 0.0)
+            .unwrap_or(DEFAULT_RATE);
+        Self {
+            interval: Duration::from_secs_f64(1.0 / rate),
+            next_at: Instant::now(),
+            items: HashMap::new(),
+        }
+    }
+}
+
+impl StressClientBehaviour {
+    /// Asks a server for its texts and media, remembering them as they arrive.
+    fn request_lists(client: &mut StressClient, server: NodeId) {
+        client.behaviour.items.insert(server, Vec::new());
+        let lists: [fn(PageRequest) -> ContentRequest; 2] = [
+            |page| ContentRequest::TextRequest(TextRequest::TextList(page)),
+            |page| ContentRequest::MediaRequest(MediaRequest::MediaList(page)),
+        ];
+        for list in lists {
+            Self::request_page(client, server, list, PageRequest::default());
+        }
+    }
+
+    /// Asks a server for a page of one of its lists, then for the next one once it arrives.
+    fn request_page(
+        client: &mut StressClient,
+        server: NodeId,
+        list: fn(PageRequest) -> ContentRequest,
+        page: PageRequest,
+    ) {
+        client.request_then(
+            server,
+            list(page),
+            |response| is_list(&response.content),
+            move |client, response| {
+                let Ok(response) = response else {
+                    return;
+                };
+                let (items, next) = match response.content {
+                    ContentResponse::TextResponse(TextResponse::TextList(names)) => (
+                        names
+                            .items
+                            .into_iter()
+                            .map(|name| ContentRequest::TextRequest(TextRequest::Text(name)))
+                            .collect(),
+                        names.next,
+                    ),
+                    ContentResponse::MediaResponse(MediaResponse::MediaList(listing)) => (
+                        listing
+                            .items
+                            .into_iter()
+                            .map(|media| MediaRequest::Media(media.name))
+                            .map(ContentRequest::MediaRequest)
+                            .collect(),
+                        listing.next,
+                    ),
+                    _ => (Vec::new(), None),
+                };
+                client
+                    .behaviour
+                    .items
+                    .entry(server)
+                    .or_default()
+                    .extend(items);
+                if let Some(next) = next {
+                    Self::request_page(client, server, list, page.after(next));
+                }
+            },
+        );
+    }
+}
+
+/// Whether the response answers a list request, so it is not taken for an item.
+fn is_list(response: &ContentResponse) -> bool {
+    matches!(
+        response,
+        ContentResponse::TextResponse(TextResponse::TextList(_))
+            | ContentResponse::MediaResponse(MediaResponse::MediaList(_))
+    )
+}
+
+impl ClientBehaviour for StressClientBehaviour {
+    type RequestType = ContentRequest;
+    type ResponseType = ContentResponse;
+
+    fn cards() -> Vec<Card<Self>> {
+        Vec::new()
+    }
+
+    fn plays_cards() -> bool {
+        false
+    }
+
+    fn on_response_received(&mut self, _response: Message<ContentResponse>) {}
+
+    fn on_tick(client: &mut Client<Self>) {
+        let now = Instant::now();
+        if client.behaviour.next_at > now {
+            return;
+        }
+        client.behaviour.next_at = now + client.behaviour.interval;
+        let servers = client.reachable_servers();
+        if servers.is_empty() {
+            return;
+        }
+        let mut rng = rand::thread_rng();
+        let server = servers[rng.gen_range(0..servers.len())];
+        let Some(items) = client.behaviour.items.get(&server) else {
+            Self::request_lists(client, server);
+            return;
+        };
+        if items.is_empty() {
+            return;
+        }
+        let item = items[rng.gen_range(0..items.len())].clone();
+        client.request_then(
+            server,
+            item,
+            |response| !is_list(&response.content),
+            |_client, _response| {},
+        );
+    }
+
+    fn application_type() -> ApplicationType {
+        ApplicationType::Content
+    }
+
+    fn controller_request(&mut self, request: ClientRequest) -> Option<ContentRequest> {
+        match request {
+            ClientRequest::TextList => Some(ContentRequest::TextRequest(TextRequest::TextList(
+                PageRequest::default(),
+            ))),
+            ClientRequest::Text(name) => Some(ContentRequest::TextRequest(TextRequest::Text(name))),
+            ClientRequest::MediaList => Some(ContentRequest::MediaRequest(
+                MediaRequest::MediaList(PageRequest::default()),
+            )),
+            ClientRequest::Media(name) => {
+                Some(ContentRequest::MediaRequest(MediaRequest::Media(name)))
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use std::{fmt::Display, fs, io, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::utils::unix_timestamp;
+
+/// Where the transcripts of the chat clients are kept, one file per username.
+const TRANSCRIPT_DIRECTORY: &str = "transcripts";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptEntry {
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+    pub from: String,
+    pub to: String,
+    pub message: String,
+}
+
+impl Display for TranscriptEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "[{}] {} -> {}: {}",
+            self.timestamp, self.from, self.to, self.message
+        )
+    }
+}
+
+/// The messages a user sent and received, saved to a file named after them so that
+/// conversations survive restarts.
+#[derive(Debug, Default)]
+pub struct Transcript {
+    username: String,
+    entries: Vec<TranscriptEntry>,
+}
+
+impl Transcript {
+    /// The file of the user, named without the characters unfit for a path.
+    fn path(username: &str, extension: &str) -> PathBuf {
+        let name: String = username
+            .chars()
+            .filter(|c| c.is_alphanumeric() || matches!(c, '-' | '_'))
+            .collect();
+        PathBuf::from(TRANSCRIPT_DIRECTORY).join(format!("{name}.{extension}"))
+    }
+
+    /// The transcript of the user, empty if they have none or it cannot be read.
+    pub fn load(username: String) -> Self {
+        let entries = fs::read_to_string(Self::path(&username, "json"))
+            .ok()
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default();
+        Self { username, entries }
+    }
+
+    fn save(&self) -> io::Result<()> {
+        fs::create_dir_all(TRANSCRIPT_DIRECTORY)?;
+        fs::write(
+            Self::path(&self.username, "json"),
+            serde_json::to_string_pretty(&self.entries)?,
+        )
+    }
+
+    /// Records a message, saving the transcript right away.
+    pub fn record(&mut self, from: String, to: String, message: String) {
+        self.entries.push(TranscriptEntry {
+            timestamp: unix_timestamp(),
+            from,
+            to,
+            message,
+        });
+        if let Err(err) = self.save() {
+            println!("Unable to save the transcript: {err}");
+        }
+    }
+
+    pub fn entries(&self) -> &[TranscriptEntry] {
+        &self.entries
+    }
+
+    /// Writes the transcript as plain text, one message per line, returning where it went.
+    pub fn export(&self) -> io::Result<PathBuf> {
+        fs::create_dir_all(TRANSCRIPT_DIRECTORY)?;
+        let path = Self::path(&self.username, "txt");
+        let lines: Vec<String> = self.entries.iter().map(ToString::to_string).collect();
+        fs::write(&path, lines.join("\n") + "\n")?;
+        Ok(path)
+    }
+}
@@ -2,50 +2,57 @@ use std::{
     fmt::{Debug, Display},
     io::stdin,
     str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
 };
 
-fn read_line() -> String {
+pub fn read_line() -> String {
     let mut string = String::new();
     stdin().read_line(&mut string).ok();
     string
 }
 
-pub fn input_with_check<T, F>(prompt: String, check: F) -> T
+/// Asks for a value until a line read by `read_line` parses and passes the check.
+pub fn parse_input<T, F>(prompt: String, check: F, mut read_line: impl FnMut() -> String) -> T
 where
     T: FromStr<Err: Debug> + Display,
     F: Fn(&T) -> bool,
 {
-    println!("{prompt}");
-
-    let res = T::from_str(read_line().trim());
-
-    match res {
-        Ok(index) if check(&index) => index,
-        _ => {
-            println!("Invalid input, try again");
-            input_with_check(prompt, check)
+    loop {
+        println!("{prompt}");
+        match T::from_str(read_line().trim()) {
+            Ok(value) if check(&value) => break value,
+            _ => println!("Invalid input, try again"),
         }
     }
 }
 
-pub fn input<T>(prompt: String) -> T
+pub fn input_with_check<T, F>(prompt: String, check: F) -> T
 where
     T: FromStr<Err: Debug> + Display,
+    F: Fn(&T) -> bool,
 {
-    println!("{}", prompt);
-
-    let res = T::from_str(read_line().trim());
-
-    match res {
-        Ok(index) => index,
-        _ => {
-            println!("Invalid input, try again");
-            input(prompt)
-        }
-    }
+    parse_input(prompt, check, read_line)
 }
 
 pub fn wait_for_input() {
     println!("Press enter to continue...");
     read_line();
 }
+
+/// Seconds since the Unix epoch, to timestamp what the clients save.
+pub fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+/// A bar like `[#####-----] 5/10 fragments`.
+pub fn progress_bar(done: u64, total: u64) -> String {
+    const WIDTH: u64 = 30;
+    let filled = (done * WIDTH).checked_div(total).unwrap_or(WIDTH) as usize;
+    format!(
+        "[{}{}] {done}/{total} fragments",
+        "#".repeat(filled),
+        "-".repeat(WIDTH as usize - filled)
+    )
+}
@@ -1,27 +1,673 @@
+use std::collections::HashMap;
+use std::fmt::Display;
 use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
 
 use colored::Colorize;
 use wg_2024::network::NodeId;
+use wg_2024::packet::{Nack, NackType};
 
 use super::{
-    base_client::{Client, ClientBehaviour},
+    base_client::{Aggregation, Client, ClientBehaviour, ResponseError, Step},
     card::Card,
 };
 use crate::{
-    application::topology::node::ApplicationType,
-    client::{card::Rarity, utils::input},
+    application::{
+        route_policy::{LoadBalancing, RoutePolicy},
+        simulation_controller_messages::ClientRequest,
+        topology::node::ApplicationType,
+        turn_handler::DOWNLOAD_POINTS,
+    },
+    client::{card::Rarity, downloads::Downloads, web_page::Page},
     message::{
-        base_message::Message,
+        base_message::{Message, ServerType},
         content_message::{ContentRequest, ContentResponse},
-        media_message::{MediaRequest, MediaResponse},
+        content_metadata::ContentMetadata,
+        echo_message::{unix_millis, EchoRequest},
+        file_message::{FileRequest, FileResponse},
+        media_message::{MediaRequest, MediaResponse, CHUNK_SIZE},
+        page::{self, PageRequest},
         text_message::{TextRequest, TextResponse},
     },
 };
 
 pub type WebBrowser = Client<WebBrowserBehaviour>;
 
+/// Most entries of the history listed at once.
+const SHOWN_HISTORY_ENTRIES: usize = 20;
+/// Times a chunk of a media is requested before the download is paused.
+const CHUNK_ATTEMPTS: usize = 3;
+
 #[derive(Default)]
-pub struct WebBrowserBehaviour;
+pub struct WebBrowserBehaviour {
+    reliability: HashMap<NodeId, ServerReliability>,
+    /// The last server that sent media, where the media linked by texts are looked for.
+    media_server: Option<NodeId>,
+    /// The page whose media are loading, if any.
+    page: Option<(u64, Page)>,
+    page_id: u64,
+    downloads: Downloads,
+    history: Option<History>,
+    /// The media whose download was paused, by server and name.
+    partial: HashMap<(NodeId, String), PartialMedia>,
+}
+
+/// The bytes of a media downloaded so far, from its start.
+struct PartialMedia {
+    metadata: ContentMetadata,
+    data: Vec<u8>,
+}
+
+/// How the fragments of the requests to a server fared.
+#[derive(Debug, Default, Clone, Copy)]
+struct ServerReliability {
+    acked: u64,
+    nacked: u64,
+    failed_requests: u64,
+}
+
+impl ServerReliability {
+    /// The share of the fragments that reached the server at the first attempt or later.
+    fn delivery_rate(&self) -> f64 {
+        self.acked as f64 / (self.acked + self.nacked).max(1) as f64
+    }
+}
+
+impl WebBrowserBehaviour {
+    /// The history of the client, read from its file the first time it is needed.
+    fn history(&mut self, client: NodeId) -> &mut History {
+        self.history.get_or_insert_with(|| History::load(client))
+    }
+
+    /// Records a media of the page being loaded, showing the page once all of them are there.
+    fn media_loaded(&mut self, page_id: u64, link: &str, outcome: Result<String, String>) {
+        let Some((loading, page)) = self.page.as_mut() else {
+            return;
+        };
+        if *loading != page_id {
+            // a newer page replaced it
+            return;
+        }
+        page.media_loaded(link, outcome);
+        if page.is_loaded() {
+            page.render();
+            self.page = None;
+        }
+    }
+}
+
+impl WebBrowser {
+    fn record_history(&mut self, server: NodeId, kind: ItemKind, name: String) {
+        let client = self.id;
+        self.behaviour
+            .history(client)
+            .record(client, server, kind, name);
+    }
+}
+
+/// Loads the media linked by a page all at once, then shows it, without waiting for them.
+fn load_page(base_client: &mut WebBrowser, page: Page) {
+    let links = page.links();
+    if links.is_empty() {
+        page.render();
+        return;
+    }
+    let media_server = match base_client.behaviour.media_server {
+        Some(media_server) => media_server,
+        None => base_client.input(format!(
+            "Input the media server's ID to load the {} linked media",
+            links.len()
+        )),
+    };
+    println!(
+        "Loading the {} media of {}{}",
+        links.len(),
+        page.title(),
+        "…".dimmed()
+    );
+    base_client.behaviour.page_id += 1;
+    let page_id = base_client.behaviour.page_id;
+    base_client.behaviour.page = Some((page_id, page));
+
+    for link in links {
+        let callback_link = link.clone();
+        let requested = base_client.request_then(
+            media_server,
+            ContentRequest::MediaRequest(MediaRequest::Media(link.clone())),
+            |response| {
+                matches!(
+                    response.content,
+                    ContentResponse::MediaResponse(MediaResponse::Media(_))
+                )
+            },
+            move |base_client, response| {
+                let link = callback_link;
+                let outcome = match response {
+                    Ok(response) => match response.content {
+                        ContentResponse::MediaResponse(MediaResponse::Media(media)) => {
+                            base_client.behaviour.media_server = Some(response.source_id);
+                            base_client
+                                .behaviour
+                                .downloads
+                                .save(&link, &media)
+                                .map(|path| path.display().to_string())
+                                .map_err(|err| err.to_string())
+                        }
+                        _ => unreachable!(),
+                    },
+                    Err(err) => Err(err.to_string()),
+                };
+                base_client.behaviour.media_loaded(page_id, &link, outcome);
+            },
+        );
+        if !requested {
+            base_client.behaviour.media_loaded(
+                page_id,
+                &link,
+                Err("the media server is unreachable".to_string()),
+            );
+        }
+    }
+}
+
+/// Requests the metadata of a text and then the text, showing it as a page once it arrives
+/// as described.
+fn download_text(base_client: &mut WebBrowser, destination: NodeId, name: String) {
+    let Some(session_id) = base_client.request(
+        destination,
+        ContentRequest::TextRequest(TextRequest::Metadata(name.clone())),
+        |response| {
+            matches!(
+                response.content,
+                ContentResponse::TextResponse(TextResponse::Metadata(_))
+            )
+        },
+    ) else {
+        println!("Failed to send the request");
+        return;
+    };
+    let metadata = match base_client.wait_for_response(session_id) {
+        Ok(response) => match response.content {
+            ContentResponse::TextResponse(TextResponse::Metadata(metadata)) => metadata,
+            _ => unreachable!(),
+        },
+        Err(err) => {
+            println!("{err}");
+            return;
+        }
+    };
+    println!("Downloading {metadata}");
+    let Some(session_id) = base_client.request(
+        destination,
+        ContentRequest::TextRequest(TextRequest::Text(name.clone())),
+        |response| {
+            matches!(
+                response.content,
+                ContentResponse::TextResponse(TextResponse::Text(_))
+            )
+        },
+    ) else {
+        println!("Failed to send the request");
+        return;
+    };
+    base_client.record_history(destination, ItemKind::Text, name.clone());
+    let response = base_client.wait_for_response(session_id);
+
+    match response {
+        Ok(response) => match response.content {
+            ContentResponse::TextResponse(TextResponse::Text(text))
+                if !metadata.matches(text.as_bytes()) =>
+            {
+                println!("The text arrived altered, it does not match its metadata");
+            }
+            ContentResponse::TextResponse(TextResponse::Text(text)) => {
+                base_client.score(DOWNLOAD_POINTS);
+                println!("The server sent the following text:");
+                let page = Page::new(name, text);
+                page.render();
+                load_page(base_client, page);
+            }
+            _ => unreachable!(),
+        },
+        Err(err) => {
+            println!("{err}");
+        }
+    }
+}
+
+/// Lists the first page of the texts of a server, then downloads the one the player chooses,
+/// in a single exchange.
+fn browse_texts(base_client: &mut WebBrowser, destination: NodeId) {
+    let list = ContentRequest::TextRequest(TextRequest::TextList(PageRequest::default()));
+    let outcome = base_client.exchange(destination, list, |base_client, response| {
+        match response.content {
+            ContentResponse::TextResponse(TextResponse::TextList(page))
+                if !page.items.is_empty() =>
+            {
+                for (i, name) in page.items.iter().enumerate() {
+                    println!("{}. {name}", i + 1);
+                }
+                let choice: usize = base_client
+                    .input_with_check("Choose the text".to_string(), |choice| {
+                        (1..=page.items.len()).contains(choice)
+                    });
+                let name = page.items[choice - 1].clone();
+                base_client.record_history(destination, ItemKind::Text, name.clone());
+                Step::Next(ContentRequest::TextRequest(TextRequest::Text(name)))
+            }
+            ContentResponse::TextResponse(TextResponse::Text(text)) => Step::Done(Some(text)),
+            _ => Step::Done(None),
+        }
+    });
+    match outcome {
+        Ok(Some(text)) => {
+            base_client.score(DOWNLOAD_POINTS);
+            println!("The server sent the following text:");
+            let page = Page::new(format!("a text of server {destination}"), text);
+            page.render();
+            load_page(base_client, page);
+        }
+        Ok(None) => println!("The server has no text to read"),
+        Err(err) => println!("{err}"),
+    }
+}
+
+/// Asks every reachable server of the type for the metadata of an item, returning the first
+/// one to describe it.
+fn find_server(
+    base_client: &mut WebBrowser,
+    server_type: ServerType,
+    request: ContentRequest,
+) -> Option<NodeId> {
+    let answers = base_client.broadcast(
+        server_type,
+        request,
+        |response| {
+            matches!(
+                response.content,
+                ContentResponse::TextResponse(TextResponse::Metadata(_))
+                    | ContentResponse::MediaResponse(MediaResponse::Metadata(_))
+            )
+        },
+        Aggregation::FirstWins,
+    );
+    if answers.is_empty() {
+        println!("No reachable {server_type} server, play The Explorer first");
+        return None;
+    }
+    let found = answers
+        .iter()
+        .find_map(|(server, answer)| answer.is_ok().then_some(*server));
+    match found {
+        Some(server) => println!("Server {server} has it"),
+        None => {
+            for (server, answer) in answers {
+                if let Err(err) = answer {
+                    println!("Server {server}: {err}");
+                }
+            }
+        }
+    }
+    found
+}
+
+/// Sends a media request, returning the answer the predicate accepts, or `None` if it could
+/// not be sent.
+fn media_request(
+    base_client: &mut WebBrowser,
+    destination: NodeId,
+    request: MediaRequest,
+    mut predicate: impl FnMut(&MediaResponse) -> bool + Send + 'static,
+) -> Option<Result<MediaResponse, ResponseError>> {
+    let session_id =
+        base_client.request(
+            destination,
+            request.into(),
+            move |response| match &response.content {
+                ContentResponse::MediaResponse(response) => predicate(response),
+                _ => false,
+            },
+        )?;
+    let response = base_client.wait_for_response(session_id);
+    Some(response.map(|response| match response.content {
+        ContentResponse::MediaResponse(response) => response,
+        _ => unreachable!(),
+    }))
+}
+
+/// Requests a media one chunk at a time, saving it to the downloads once it is complete and
+/// matches its metadata. A chunk is retried on its own when it fails, and a download given up
+/// is resumed the next time the same media is requested, unless the media changed meanwhile.
+fn download_media(base_client: &mut WebBrowser, destination: NodeId, name: String) {
+    let metadata = media_request(
+        base_client,
+        destination,
+        MediaRequest::Metadata(name.clone()),
+        |response| matches!(response, MediaResponse::Metadata(_)),
+    );
+    let metadata = match metadata {
+        Some(Ok(MediaResponse::Metadata(metadata))) => metadata,
+        Some(Ok(_)) => unreachable!(),
+        Some(Err(err)) => {
+            println!("{err}");
+            return;
+        }
+        None => {
+            println!("Failed to send the request");
+            return;
+        }
+    };
+    base_client.record_history(destination, ItemKind::Media, name.clone());
+    println!("Downloading {metadata}");
+    let size = metadata.size;
+    let key = (destination, name.clone());
+    let mut partial = match base_client.behaviour.partial.remove(&key) {
+        Some(partial) if partial.metadata == metadata => {
+            println!("Resuming {name} from byte {}", partial.data.len());
+            partial
+        }
+        _ => PartialMedia {
+            metadata,
+            data: Vec::with_capacity(size as usize),
+        },
+    };
+    while (partial.data.len() as u64) < size {
+        let offset = partial.data.len() as u64;
+        let mut chunk = None;
+        for attempt in 1..=CHUNK_ATTEMPTS {
+            let request = MediaRequest::Chunk {
+                name: name.clone(),
+                offset,
+                len: CHUNK_SIZE,
+            };
+            let response = media_request(
+                base_client,
+                destination,
+                request,
+                move |response| matches!(response, MediaResponse::Chunk { offset: at, .. } if *at == offset),
+            );
+            match response {
+                Some(Ok(MediaResponse::Chunk { data, .. })) if !data.is_empty() => {
+                    chunk = Some(data);
+                    break;
+                }
+                Some(Ok(_)) => {
+                    println!("{name} was cut short on the server meanwhile");
+                    return;
+                }
+                // asking again will not help, the media was removed meanwhile most likely
+                Some(Err(ResponseError::Rejected(error))) if !error.retryable => {
+                    println!("{error}");
+                    return;
+                }
+                Some(Err(err)) => println!(
+                    "The chunk at byte {offset} failed ({err}), attempt {attempt} of {CHUNK_ATTEMPTS}"
+                ),
+                None => println!(
+                    "The chunk at byte {offset} could not be sent, attempt {attempt} of {CHUNK_ATTEMPTS}"
+                ),
+            }
+        }
+        let Some(data) = chunk else {
+            println!("Download paused at {offset} of {size} bytes, request {name} again to resume");
+            base_client.behaviour.partial.insert(key, partial);
+            return;
+        };
+        partial.data.extend(data);
+        let received = partial.data.len() as u64;
+        println!(
+            "{name}: {received} of {size} bytes ({}%)",
+            received * 100 / size.max(1)
+        );
+    }
+    if !partial.metadata.matches(&partial.data) {
+        println!("{name} arrived altered, it does not match its metadata");
+        return;
+    }
+    base_client.behaviour.media_server = Some(destination);
+    let downloads = &base_client.behaviour.downloads;
+    match downloads.save(&name, &partial.data) {
+        Ok(path) => {
+            println!("Media saved to {}", path.display());
+            downloads.open(&path);
+            base_client.score(DOWNLOAD_POINTS);
+        }
+        Err(err) => println!("Unable to save the media: {err}"),
+    }
+}
+
+/// Sends a local file to a server to store: texts (`.txt`) to a text server, anything else as
+/// a media named `#` and the file name without its extension, to a media server.
+fn upload(base_client: &mut WebBrowser, destination: NodeId, path: &Path) {
+    let (Some(stem), Some(file_name)) = (
+        path.file_stem().and_then(|stem| stem.to_str()),
+        path.file_name().and_then(|name| name.to_str()),
+    ) else {
+        println!("{} is not a file", path.display());
+        return;
+    };
+    let is_text = path.extension().is_some_and(|extension| extension == "txt");
+    let request: ContentRequest = if is_text {
+        match fs::read_to_string(path) {
+            Ok(text) => TextRequest::Upload(file_name.to_string(), text).into(),
+            Err(err) => {
+                println!("Unable to read {}: {err}", path.display());
+                return;
+            }
+        }
+    } else {
+        match fs::read(path) {
+            Ok(media) => MediaRequest::Upload(format!("#{stem}"), media).into(),
+            Err(err) => {
+                println!("Unable to read {}: {err}", path.display());
+                return;
+            }
+        }
+    };
+    let Some(session_id) = base_client.request(destination, request, |response| {
+        matches!(
+            response.content,
+            ContentResponse::TextResponse(TextResponse::Uploaded(_))
+                | ContentResponse::MediaResponse(MediaResponse::Uploaded(_))
+        )
+    }) else {
+        println!("Failed to send the request");
+        return;
+    };
+
+    match base_client.wait_for_response(session_id) {
+        Ok(response) => match response.content {
+            ContentResponse::TextResponse(TextResponse::Uploaded(name))
+            | ContentResponse::MediaResponse(MediaResponse::Uploaded(name)) => {
+                println!("The server stored {name}");
+            }
+            _ => unreachable!(),
+        },
+        Err(err) => {
+            println!("{err}");
+        }
+    }
+}
+
+/// Sends a request to a file storage server, returning its answer if it stores files.
+fn file_request(
+    base_client: &mut WebBrowser,
+    destination: NodeId,
+    request: FileRequest,
+) -> Option<FileResponse> {
+    let Some(session_id) = base_client.request(destination, request.into(), |response| {
+        matches!(response.content, ContentResponse::FileResponse(_))
+    }) else {
+        println!("Failed to send the request");
+        return None;
+    };
+    match base_client.wait_for_response(session_id) {
+        Ok(response) => match response.content {
+            ContentResponse::FileResponse(response) => Some(response),
+            _ => unreachable!(),
+        },
+        Err(err) => {
+            println!("{err}");
+            None
+        }
+    }
+}
+
+/// Sends echoes one after the other to an echo server, reporting how many came back, how far
+/// they travelled and how long they took.
+fn echo(base_client: &mut WebBrowser, destination: NodeId, count: usize, size: usize) {
+    let mut returned = 0;
+    let mut round_trips = Duration::ZERO;
+    for _ in 0..count {
+        let started = Instant::now();
+        let request = ContentRequest::Echo(EchoRequest {
+            payload: vec![b'q'; size],
+            sent_at: unix_millis(),
+        });
+        let Some(session_id) = base_client.request(destination, request, |response| {
+            matches!(response.content, ContentResponse::Echo(_))
+        }) else {
+            println!("Failed to send the request");
+            return;
+        };
+        match base_client.wait_for_response(session_id) {
+            Ok(response) => match response.content {
+                ContentResponse::Echo(echo) => {
+                    returned += 1;
+                    round_trips += started.elapsed();
+                    println!(
+                        "{} bytes back over {} drones, {} ms to get there",
+                        echo.payload.len(),
+                        echo.hops,
+                        echo.one_way_ms
+                    );
+                }
+                _ => unreachable!(),
+            },
+            Err(err) => println!("{err}"),
+        }
+    }
+    if returned > 0 {
+        println!(
+            "{returned} of {count} echoes came back, in {} ms on average",
+            (round_trips / returned).as_millis()
+        );
+    } else {
+        println!("No echo came back");
+    }
+}
+
+/// Lists the texts of the server matching the query, downloading the one the player picks.
+fn search_texts(base_client: &mut WebBrowser, destination: NodeId, query: String) {
+    let Some(session_id) = base_client.request(
+        destination,
+        ContentRequest::TextRequest(TextRequest::Search(query)),
+        |response| {
+            matches!(
+                response.content,
+                ContentResponse::TextResponse(TextResponse::SearchResults(_))
+            )
+        },
+    ) else {
+        println!("Failed to send the request");
+        return;
+    };
+    let hits = match base_client.wait_for_response(session_id) {
+        Ok(response) => match response.content {
+            ContentResponse::TextResponse(TextResponse::SearchResults(hits)) => hits,
+            _ => unreachable!(),
+        },
+        Err(err) => {
+            println!("{err}");
+            return;
+        }
+    };
+    if hits.is_empty() {
+        println!("No text matches");
+        return;
+    }
+    for (i, hit) in hits.iter().enumerate() {
+        println!("{}. {}: {}", i + 1, hit.name.bold(), hit.snippet);
+    }
+    let choice: usize = base_client.input_with_check(
+        "Choose the text to read, 0 for none".to_string(),
+        |choice| *choice <= hits.len(),
+    );
+    if choice > 0 {
+        download_text(base_client, destination, hits[choice - 1].name.clone());
+    }
+}
+
+/// Has the player pick one of the files the server stores, if there is any.
+fn choose_file(base_client: &mut WebBrowser, destination: NodeId) -> Option<String> {
+    let Some(FileResponse::FileList(files)) =
+        file_request(base_client, destination, FileRequest::FileList)
+    else {
+        return None;
+    };
+    if files.is_empty() {
+        println!("The server stores no file");
+        return None;
+    }
+    for (i, (name, size)) in files.iter().enumerate() {
+        println!("{}. {name} ({size} bytes)", i + 1);
+    }
+    let choice: usize = base_client.input_with_check("Choose the file".to_string(), |choice| {
+        (1..=files.len()).contains(choice)
+    });
+    Some(files[choice - 1].0.clone())
+}
+
+/// Lists the items of a server page after page, asking the player before fetching each next
+/// one. `page_of` takes the page out of the responses answering `list`. Returns whether the
+/// server listed any.
+fn list_pages<T: Display + 'static>(
+    base_client: &mut WebBrowser,
+    destination: NodeId,
+    what: &str,
+    list: fn(PageRequest) -> ContentRequest,
+    page_of: fn(&ContentResponse) -> Option<&page::Page<T>>,
+) -> bool {
+    let mut request = PageRequest::default();
+    let mut listed = false;
+    loop {
+        let Some(session_id) = base_client.request(destination, list(request), move |response| {
+            page_of(&response.content).is_some()
+        }) else {
+            println!("Failed to send the request");
+            return listed;
+        };
+        let response = match base_client.wait_for_response(session_id) {
+            Ok(response) => response,
+            Err(err) => {
+                println!("{err}");
+                return listed;
+            }
+        };
+        let Some(page) = page_of(&response.content) else {
+            return listed;
+        };
+        if !listed {
+            println!("The server contains {} {what}:", page.total);
+            listed = true;
+        }
+        for (i, item) in page.items.iter().enumerate() {
+            println!("{}. {}", page.offset() + i, item);
+        }
+        let Some(next) = page.next else {
+            return listed;
+        };
+        let answer: String = base_client.input_with_check(
+            format!("Listed {next} of {}, show the next page? (y/n)", page.total),
+            |answer: &String| answer == "y" || answer == "n",
+        );
+        if answer == "n" {
+            return listed;
+        }
+        request = request.after(next);
+    }
+}
 
 impl ClientBehaviour for WebBrowserBehaviour {
     type RequestType = ContentRequest;
@@ -35,43 +681,20 @@ impl ClientBehaviour for WebBrowserBehaviour {
                 "List of text items",
                 Rarity::Common,
                 |base_client: &mut WebBrowser| {
-                    let destination: NodeId = input("Input the recipient's ID".to_string());
-                    let session_id = base_client.new_session_id();
-                    if !base_client.send_request(Message::new(
-                        base_client.id,
+                    let destination: NodeId =
+                        base_client.input("Input the recipient's ID".to_string());
+                    list_pages(
+                        base_client,
                         destination,
-                        session_id,
-                        ContentRequest::TextRequest(TextRequest::TextList),
-                    )) {
-                        println!("Failed to send the request");
-                        return;
-                    }
-
-                    let response = base_client.wait_for_response(|response| {
-                        matches!(
-                            response.content,
-                            ContentResponse::TextResponse(TextResponse::TextList(_))
-                                | ContentResponse::ServiceNotProvided
-                        )
-                    });
-
-                    match response {
-                        Ok(response) => {
-                            if let ContentResponse::TextResponse(TextResponse::TextList(list)) =
-                                response.content
-                            {
-                                println!("The server contains the following texts:");
-                                for (i, text) in list.iter().enumerate() {
-                                    println!("{}. {}", i, text);
-                                }
-                            } else {
-                                println!("The server does not provide text content");
+                        "texts",
+                        |page| ContentRequest::TextRequest(TextRequest::TextList(page)),
+                        |response| match response {
+                            ContentResponse::TextResponse(TextResponse::TextList(page)) => {
+                                Some(page)
                             }
-                        }
-                        Err(err) => {
-                            println!("{err}");
-                        }
-                    }
+                            _ => None,
+                        },
+                    );
                 },
             ),
             Card::new(
@@ -79,173 +702,324 @@ impl ClientBehaviour for WebBrowserBehaviour {
                 "Download a text item",
                 Rarity::Rare,
                 |base_client: &mut WebBrowser| {
-                    let destination: NodeId = input("Input the recipient's ID".to_string());
-                    let file_name: String = input("Input the file name".to_string());
-                    let session_id = base_client.new_session_id();
-                    if !base_client.send_request(Message::new(
-                        base_client.id,
-                        destination,
-                        session_id,
-                        ContentRequest::TextRequest(TextRequest::Text(file_name)),
-                    )) {
-                        println!("Failed to send the request");
-                        return;
-                    }
-
-                    let response = base_client.wait_for_response(|response| {
-                        matches!(
-                            response.content,
-                            ContentResponse::TextResponse(TextResponse::Text(_))
-                                | ContentResponse::TextResponse(TextResponse::NotFound)
-                                | ContentResponse::ServiceNotProvided
-                        )
-                    });
-
-                    match response {
-                        Ok(response) => match response.content {
-                            ContentResponse::TextResponse(TextResponse::Text(text)) => {
-                                println!("The server sent the following text:");
-
-                                for line in text.lines() {
-                                    for word in line.split(" ") {
-                                        let colored_word = if word.starts_with("#") {
-                                            word.cyan().underline()
-                                        } else {
-                                            word.normal()
-                                        };
-                                        print!("{} ", colored_word);
-                                    }
-                                }
-                            }
-                            ContentResponse::TextResponse(TextResponse::NotFound) => {
-                                println!("The text item was not found");
-                            }
-                            ContentResponse::ServiceNotProvided => {
-                                println!("The server does not provide text content");
-                            }
-                            _ => unreachable!(),
-                        },
-                        Err(err) => {
-                            println!("{err}");
-                        }
+                    let destination: NodeId =
+                        base_client.input("Input the recipient's ID".to_string());
+                    let file_name: String = base_client.input("Input the file name".to_string());
+                    download_text(base_client, destination, file_name);
+                },
+            ),
+            Card::new(
+                "Browse Texts",
+                "List the texts of a server and read one of them",
+                Rarity::Rare,
+                |base_client: &mut WebBrowser| {
+                    let destination: NodeId =
+                        base_client.input("Input the recipient's ID".to_string());
+                    browse_texts(base_client, destination);
+                },
+            ),
+            Card::new(
+                "Find Text",
+                "Download a text from whichever text server has it",
+                Rarity::Rare,
+                |base_client: &mut WebBrowser| {
+                    let file_name: String = base_client.input("Input the file name".to_string());
+                    let request = TextRequest::Metadata(file_name.clone()).into();
+                    if let Some(server) = find_server(base_client, ServerType::Text, request) {
+                        download_text(base_client, server, file_name);
                     }
                 },
             ),
+            Card::new(
+                "Search Texts",
+                "Find the texts of a server holding some words",
+                Rarity::Common,
+                |base_client: &mut WebBrowser| {
+                    let destination: NodeId =
+                        base_client.input("Input the recipient's ID".to_string());
+                    let query: String =
+                        base_client.input("Input the words to look for".to_string());
+                    search_texts(base_client, destination, query);
+                },
+            ),
             Card::new(
                 "Media List",
                 "List of media items",
                 Rarity::Common,
                 |base_client: &mut WebBrowser| {
-                    let destination: NodeId = input("Input the recipient's ID".to_string());
-                    let session_id = base_client.new_session_id();
-                    if !base_client.send_request(Message::new(
-                        base_client.id,
+                    let destination: NodeId =
+                        base_client.input("Input the recipient's ID".to_string());
+                    let listed = list_pages(
+                        base_client,
                         destination,
-                        session_id,
-                        ContentRequest::MediaRequest(MediaRequest::MediaList),
-                    )) {
-                        println!("Failed to send the request");
-                        return;
+                        "medias",
+                        |page| ContentRequest::MediaRequest(MediaRequest::MediaList(page)),
+                        |response| match response {
+                            ContentResponse::MediaResponse(MediaResponse::MediaList(page)) => {
+                                Some(page)
+                            }
+                            _ => None,
+                        },
+                    );
+                    if listed {
+                        base_client.behaviour.media_server = Some(destination);
                     }
-
-                    let response = base_client.wait_for_response(|response| {
-                        matches!(
-                            response.content,
-                            ContentResponse::MediaResponse(MediaResponse::MediaList(_))
-                                | ContentResponse::ServiceNotProvided
-                        )
+                },
+            ),
+            Card::new(
+                "Download Media",
+                "Download a media item",
+                Rarity::Rare,
+                |base_client: &mut WebBrowser| {
+                    let destination: NodeId =
+                        base_client.input("Input the recipient's ID".to_string());
+                    let file_name: String =
+                        base_client.input("Input the file name (with # as prefix)".to_string());
+                    download_media(base_client, destination, file_name);
+                },
+            ),
+            Card::new(
+                "Find Media",
+                "Download a media from whichever media server has it",
+                Rarity::Rare,
+                |base_client: &mut WebBrowser| {
+                    let file_name: String =
+                        base_client.input("Input the file name (with # as prefix)".to_string());
+                    let request = MediaRequest::Metadata(file_name.clone()).into();
+                    if let Some(server) = find_server(base_client, ServerType::Media, request) {
+                        download_media(base_client, server, file_name);
+                    }
+                },
+            ),
+            Card::new(
+                "Media Thumbnail",
+                "Preview a media scaled down, in a fraction of its fragments",
+                Rarity::Common,
+                |base_client: &mut WebBrowser| {
+                    let destination: NodeId =
+                        base_client.input("Input the recipient's ID".to_string());
+                    let name: String =
+                        base_client.input("Input the file name (with # as prefix)".to_string());
+                    let max_px: u32 =
+                        base_client.input("Input the largest side in pixels".to_string());
+                    let request = MediaRequest::Thumbnail(name.clone(), max_px);
+                    let response = media_request(base_client, destination, request, |response| {
+                        matches!(response, MediaResponse::Thumbnail(_))
                     });
-
                     match response {
-                        Ok(response) => {
-                            if let ContentResponse::MediaResponse(MediaResponse::MediaList(list)) =
-                                response.content
-                            {
-                                println!("The server contains the following medias:");
-                                for (i, media) in list.iter().enumerate() {
-                                    println!("{}. {}", i, media);
+                        Some(Ok(MediaResponse::Thumbnail(thumbnail))) => {
+                            let downloads = &base_client.behaviour.downloads;
+                            match downloads.save(&format!("{name}_thumbnail"), &thumbnail) {
+                                Ok(path) => {
+                                    println!("Thumbnail saved to {}", path.display());
+                                    downloads.open(&path);
                                 }
-                            } else {
-                                println!("The server does not provide text content");
+                                Err(err) => println!("Unable to save the thumbnail: {err}"),
                             }
                         }
+                        Some(Ok(_)) => unreachable!(),
+                        Some(Err(err)) => println!("{err}"),
+                        None => println!("Failed to send the request"),
+                    }
+                },
+            ),
+            Card::new(
+                "Upload",
+                "Send a local text or media to a server to store",
+                Rarity::Rare,
+                |base_client: &mut WebBrowser| {
+                    let destination: NodeId =
+                        base_client.input("Input the recipient's ID".to_string());
+                    let path: String = base_client.input("Input the path of the file".to_string());
+                    upload(base_client, destination, Path::new(&path));
+                },
+            ),
+            Card::new(
+                "Store File",
+                "Keep any local file on a file storage server",
+                Rarity::Rare,
+                |base_client: &mut WebBrowser| {
+                    let destination: NodeId =
+                        base_client.input("Input the server's ID".to_string());
+                    let path: String = base_client.input("Input the path of the file".to_string());
+                    let path = Path::new(&path);
+                    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+                        println!("{} is not a file", path.display());
+                        return;
+                    };
+                    let data = match fs::read(path) {
+                        Ok(data) => data,
                         Err(err) => {
-                            println!("{err}");
+                            println!("Unable to read {}: {err}", path.display());
+                            return;
                         }
+                    };
+                    let request = FileRequest::Upload(name.to_string(), data);
+                    if let Some(FileResponse::Uploaded(name)) =
+                        file_request(base_client, destination, request)
+                    {
+                        println!("The server stored {name}");
                     }
                 },
             ),
             Card::new(
-                "Download Media",
-                "Download a media item",
+                "Fetch File",
+                "Download one of the files on a file storage server",
+                Rarity::Common,
+                |base_client: &mut WebBrowser| {
+                    let destination: NodeId =
+                        base_client.input("Input the server's ID".to_string());
+                    let Some(name) = choose_file(base_client, destination) else {
+                        return;
+                    };
+                    let request = FileRequest::File(name.clone());
+                    let Some(FileResponse::File(data)) =
+                        file_request(base_client, destination, request)
+                    else {
+                        return;
+                    };
+                    match base_client.behaviour.downloads.save(&name, &data) {
+                        Ok(path) => {
+                            println!("File saved to {}", path.display());
+                            base_client.score(DOWNLOAD_POINTS);
+                        }
+                        Err(err) => println!("Unable to save the file: {err}"),
+                    }
+                },
+            ),
+            Card::new(
+                "Delete File",
+                "Remove a file you stored on a file storage server",
+                Rarity::Common,
+                |base_client: &mut WebBrowser| {
+                    let destination: NodeId =
+                        base_client.input("Input the server's ID".to_string());
+                    let Some(name) = choose_file(base_client, destination) else {
+                        return;
+                    };
+                    if let Some(FileResponse::Deleted(name)) =
+                        file_request(base_client, destination, FileRequest::Delete(name))
+                    {
+                        println!("{name} was deleted");
+                    }
+                },
+            ),
+            Card::new(
+                "Echo",
+                "Time echoes sent to an echo server",
+                Rarity::Common,
+                |base_client: &mut WebBrowser| {
+                    let destination: NodeId =
+                        base_client.input("Input the echo server's ID".to_string());
+                    let count: usize = base_client
+                        .input_with_check("How many echoes?".to_string(), |count| *count > 0);
+                    let size: usize = base_client.input("How many bytes each?".to_string());
+                    echo(base_client, destination, count, size);
+                },
+            ),
+            Card::new(
+                "History",
+                "List the items requested so far and bookmark one",
+                Rarity::Common,
+                |base_client: &mut WebBrowser| {
+                    let client = base_client.id;
+                    let entries: Vec<_> = base_client
+                        .behaviour
+                        .history(client)
+                        .entries()
+                        .take(SHOWN_HISTORY_ENTRIES)
+                        .cloned()
+                        .collect();
+                    if entries.is_empty() {
+                        println!("Nothing was requested yet");
+                        return;
+                    }
+                    for (i, entry) in entries.iter().enumerate() {
+                        println!("{}. {}", i + 1, entry);
+                    }
+                    let choice: usize = base_client.input_with_check(
+                        "Input the number of an item to bookmark (0 for none)".to_string(),
+                        |&choice| choice <= entries.len(),
+                    );
+                    if choice > 0 {
+                        let history = base_client.behaviour.history(client);
+                        history.bookmark(client, entries[choice - 1].clone());
+                        println!("Bookmarked!");
+                    }
+                },
+            ),
+            Card::new(
+                "Bookmarks",
+                "Request a bookmarked item again",
                 Rarity::Rare,
                 |base_client: &mut WebBrowser| {
-                    let destination: NodeId = input("Input the recipient's ID".to_string());
-                    let file_name: String =
-                        input("Input the file name (with # as prefix)".to_string());
-                    let session_id = base_client.new_session_id();
-                    if !base_client.send_request(Message::new(
-                        base_client.id,
-                        destination,
-                        session_id,
-                        ContentRequest::MediaRequest(MediaRequest::Media(file_name)),
-                    )) {
-                        println!("Failed to send the request");
+                    let client = base_client.id;
+                    let bookmarks = base_client.behaviour.history(client).bookmarks().to_vec();
+                    if bookmarks.is_empty() {
+                        println!("There are no bookmarks yet");
                         return;
                     }
-
-                    let response = base_client.wait_for_response(|response| {
-                        matches!(
-                            response.content,
-                            ContentResponse::MediaResponse(MediaResponse::Media(_))
-                                | ContentResponse::MediaResponse(MediaResponse::NotFound)
-                                | ContentResponse::ServiceNotProvided
-                        )
-                    });
-
-                    match response {
-                        Ok(response) => match response.content {
-                            ContentResponse::MediaResponse(MediaResponse::Media(media)) => {
-                                fs::write("assets/temp.png", media).expect("Unable to write file");
-                                open::that("assets/temp.png").expect("Unable to open file");
-                            }
-                            ContentResponse::MediaResponse(MediaResponse::NotFound) => {
-                                println!("The media item was not found");
-                            }
-                            _ => {
-                                println!("The server does not provide text content");
-                            }
-                        },
-                        Err(err) => {
-                            println!("{err}");
+                    for (i, bookmark) in bookmarks.iter().enumerate() {
+                        println!("{}. {}", i + 1, bookmark);
+                    }
+                    let choice: usize = base_client.input_with_check(
+                        "Input the number of the item to request (0 for none)".to_string(),
+                        |&choice| choice <= bookmarks.len(),
+                    );
+                    let Some(bookmark) = choice.checked_sub(1).map(|i| bookmarks[i].clone()) else {
+                        return;
+                    };
+                    match bookmark.kind {
+                        ItemKind::Text => {
+                            download_text(base_client, bookmark.server, bookmark.name)
+                        }
+                        ItemKind::Media => {
+                            download_media(base_client, bookmark.server, bookmark.name)
                         }
                     }
                 },
             ),
+            Card::new(
+                "Reliability",
+                "Show how the requests to each server fared",
+                Rarity::Common,
+                |base_client: &mut WebBrowser| {
+                    let mut servers: Vec<_> = base_client.behaviour.reliability.iter().collect();
+                    servers.sort_by_key(|(server, _reliability)| **server);
+                    if servers.is_empty() {
+                        println!("No request was sent yet");
+                    }
+                    for (server, reliability) in servers {
+                        println!(
+                            "Server {server}: {:.0}% of {} fragments delivered, {} requests failed",
+                            reliability.delivery_rate() * 100.0,
+                            reliability.acked + reliability.nacked,
+                            reliability.failed_requests
+                        );
+                    }
+                },
+            ),
             Card::new(
                 "Upgrade",
                 "Upgrade the catalog of media files",
                 Rarity::Quacking,
                 |base_client: &mut WebBrowser| {
-                    let destination: NodeId = input("Input the recipient's ID".to_string());
-                    let session_id = base_client.new_session_id();
-                    if !base_client.send_request(Message::new(
-                        base_client.id,
+                    let destination: NodeId =
+                        base_client.input("Input the recipient's ID".to_string());
+                    let Some(session_id) = base_client.request(
                         destination,
-                        session_id,
                         ContentRequest::MediaRequest(MediaRequest::ExpandList),
-                    )) {
+                        |response| {
+                            matches!(
+                                response.content,
+                                ContentResponse::MediaResponse(MediaResponse::MediaList(_))
+                            )
+                        },
+                    ) else {
                         println!("Failed to send the request");
                         return;
-                    }
-
-                    let response = base_client.wait_for_response(|response| {
-                        matches!(
-                            response.content,
-                            ContentResponse::MediaResponse(MediaResponse::MediaList(_))
-                                | ContentResponse::ServiceNotProvided
-                        )
-                    });
+                    };
+                    let response = base_client.wait_for_response(session_id);
 
                     match response {
                         Ok(response) => {
@@ -254,11 +1028,13 @@ impl ClientBehaviour for WebBrowserBehaviour {
                             {
                                 println!("The server's been upgraded!");
                                 println!("The server now contains the following medias:");
-                                for (i, media) in list.iter().enumerate() {
+                                for (i, media) in list.items.iter().enumerate() {
                                     println!("{}. {}", i, media);
                                 }
-                            } else {
-                                println!("The server does not provide text content");
+                                if list.next.is_some() {
+                                    let more = list.total - list.items.len();
+                                    println!("...and {more} more, listed by the Media List card");
+                                }
                             }
                         }
                         Err(err) => {
@@ -267,12 +1043,81 @@ impl ClientBehaviour for WebBrowserBehaviour {
                     }
                 },
             ),
+            Card::new(
+                "Follow Server",
+                "Be told about the texts and media a server gets from now on",
+                Rarity::Common,
+                |base_client: &mut WebBrowser| {
+                    let destination: NodeId =
+                        base_client.input("Input the server's ID".to_string());
+                    let Some(session_id) =
+                        base_client.request(destination, ContentRequest::NotifyOnNew, |response| {
+                            matches!(response.content, ContentResponse::Subscribed)
+                        })
+                    else {
+                        println!("Failed to send the request");
+                        return;
+                    };
+                    match base_client.wait_for_response(session_id) {
+                        Ok(_) => {
+                            println!("You will be told about the content server {destination} gets")
+                        }
+                        Err(err) => println!("{err}"),
+                    }
+                },
+            ),
         ]
     }
 
-    fn on_response_received(&mut self, _response: Message<Self::ResponseType>) {}
+    fn on_response_received(&mut self, response: Message<Self::ResponseType>) {
+        if let ContentResponse::NewContent(name) = response.content {
+            println!("Server {} has something new: {name}", response.source_id);
+        }
+    }
+
+    fn on_ack(&mut self, server: NodeId, _session_id: u64, _fragment_index: u64) {
+        self.reliability.entry(server).or_default().acked += 1;
+    }
+
+    fn on_nack(&mut self, server: NodeId, _session_id: u64, nack: &Nack) {
+        self.reliability.entry(server).or_default().nacked += 1;
+        if matches!(
+            nack.nack_type,
+            NackType::Dropped | NackType::ErrorInRouting(_)
+        ) {
+            println!(
+                "A fragment for server {server} was lost, retrying{}",
+                "…".dimmed()
+            );
+        }
+    }
+
+    fn on_session_failed(&mut self, server: NodeId, _session_id: u64) {
+        self.reliability.entry(server).or_default().failed_requests += 1;
+        println!("{}", format!("A request to server {server} failed").red());
+    }
 
     fn application_type() -> ApplicationType {
         ApplicationType::Content
     }
+
+    fn route_policy() -> Box<dyn RoutePolicy> {
+        Box::new(LoadBalancing::default())
+    }
+
+    fn controller_request(&mut self, request: ClientRequest) -> Option<ContentRequest> {
+        match request {
+            ClientRequest::TextList => Some(ContentRequest::TextRequest(TextRequest::TextList(
+                PageRequest::default(),
+            ))),
+            ClientRequest::Text(name) => Some(ContentRequest::TextRequest(TextRequest::Text(name))),
+            ClientRequest::MediaList => Some(ContentRequest::MediaRequest(
+                MediaRequest::MediaList(PageRequest::default()),
+            )),
+            ClientRequest::Media(name) => {
+                Some(ContentRequest::MediaRequest(MediaRequest::Media(name)))
+            }
+            _ => None,
+        }
+    }
 }
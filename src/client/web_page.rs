@@ -0,0 +1,71 @@
+use colored::Colorize;
+
+/// A text together with the media it links with `#name` words, loaded in the background.
+pub struct Page {
+    title: String,
+    text: String,
+    /// Every linked media, with where it was saved or why it could not be, once it is known.
+    media: Vec<(String, Option<Result<String, String>>)>,
+}
+
+/// The media a word links, without the punctuation following it.
+fn link_of(word: &str) -> Option<&str> {
+    let link = word.trim_end_matches(|c: char| !c.is_alphanumeric());
+    (link.starts_with('#') && link.len() > 1).then_some(link)
+}
+
+impl Page {
+    pub fn new(title: String, text: String) -> Self {
+        let mut media: Vec<(String, Option<Result<String, String>>)> = Vec::new();
+        for link in text.split_whitespace().filter_map(link_of) {
+            if media.iter().all(|(known, _outcome)| known != link) {
+                media.push((link.to_string(), None));
+            }
+        }
+        Self { title, text, media }
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn links(&self) -> Vec<String> {
+        self.media
+            .iter()
+            .map(|(link, _outcome)| link.clone())
+            .collect()
+    }
+
+    /// Records where a media was saved, or why it could not be.
+    pub fn media_loaded(&mut self, link: &str, outcome: Result<String, String>) {
+        if let Some((_link, loaded)) = self.media.iter_mut().find(|(known, _)| known == link) {
+            *loaded = Some(outcome);
+        }
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.media.iter().all(|(_link, outcome)| outcome.is_some())
+    }
+
+    /// Prints the text with every link replaced by where its media was saved.
+    pub fn render(&self) {
+        println!("{}", self.title.bold());
+        for line in self.text.lines() {
+            for word in line.split(' ') {
+                let outcome = link_of(word).and_then(|link| {
+                    self.media
+                        .iter()
+                        .find(|(known, _outcome)| known == link)
+                        .map(|(_link, outcome)| outcome)
+                });
+                match outcome {
+                    Some(Some(Ok(path))) => print!("{} ", format!("[{word} → {path}]").cyan()),
+                    Some(Some(Err(reason))) => print!("{} ", format!("[{word}: {reason}]").red()),
+                    Some(None) => print!("{} ", word.cyan().underline()),
+                    None => print!("{word} "),
+                }
+            }
+            println!();
+        }
+    }
+}
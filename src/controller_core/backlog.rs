@@ -0,0 +1,42 @@
+use crossbeam_channel::Sender;
+use iced::alignment::{Horizontal, Vertical};
+use iced::widget::canvas::{Frame, Path, Text};
+use iced::{Color, Pixels, Point, Vector};
+use std::collections::HashMap;
+use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
+
+/// Queue length above which a node is considered stuck or slow.
+pub const BACKLOG_THRESHOLD: usize = 20;
+
+/// Number of packets waiting in every node's inbound queue.
+///
+/// Each node owns a single channel, so the length seen from any clone of its `Sender`
+/// is the length of the node's receiving queue.
+pub fn packet_backlogs(packet_senders: &HashMap<NodeId, Sender<Packet>>) -> HashMap<NodeId, usize> {
+    packet_senders
+        .iter()
+        .map(|(id, sender)| (*id, sender.len()))
+        .collect()
+}
+
+pub fn draw_backlog_badge(frame: &mut Frame, node_position: Point, backlog: usize) {
+    if backlog <= BACKLOG_THRESHOLD {
+        return;
+    }
+    let center = node_position + Vector::new(22.0, -22.0);
+    frame.fill(&Path::circle(center, 11.0), Color::from_rgb8(220, 40, 40));
+    frame.fill_text(Text {
+        content: if backlog > 99 {
+            "99+".to_string()
+        } else {
+            backlog.to_string()
+        },
+        position: center,
+        color: Color::WHITE,
+        size: Pixels(11.0),
+        horizontal_alignment: Horizontal::Center,
+        vertical_alignment: Vertical::Center,
+        ..Text::default()
+    });
+}
@@ -0,0 +1,62 @@
+use iced::widget::{button, center, column, container, mouse_area, opaque, row, stack, text};
+use iced::{Color, Element};
+use wg_2024::network::NodeId;
+
+/// Overlays `base` with a modal asking the user to confirm an irreversible action.
+///
+/// Clicking outside the dialog counts as cancelling it.
+pub fn confirmation_modal<'a, M>(
+    base: impl Into<Element<'a, M>>,
+    title: String,
+    body: String,
+    on_confirm: M,
+    on_cancel: M,
+) -> Element<'a, M>
+where
+    M: Clone + 'a,
+{
+    let dialog = container(
+        column![
+            text(title).size(22),
+            text(body),
+            row![
+                button("Cancel").on_press(on_cancel.clone()),
+                button("Confirm").style(button::danger).on_press(on_confirm),
+            ]
+            .spacing(10),
+        ]
+        .spacing(15),
+    )
+    .width(400)
+    .padding(20)
+    .style(container::rounded_box);
+
+    stack![
+        base.into(),
+        opaque(
+            mouse_area(center(opaque(dialog)).style(|_theme| {
+                container::Style {
+                    background: Some(
+                        Color {
+                            a: 0.6,
+                            ..Color::BLACK
+                        }
+                        .into(),
+                    ),
+                    ..container::Style::default()
+                }
+            }))
+            .on_press(on_cancel)
+        )
+    ]
+    .into()
+}
+
+pub fn crash_warning<'a>(id: NodeId, neighbors: impl Iterator<Item = &'a NodeId>) -> String {
+    let mut neighbors = neighbors.copied().collect::<Vec<_>>();
+    neighbors.sort();
+    format!(
+        "Node {id} will be removed from the network. This action cannot be undone.\nAffected neighbors: {:?}",
+        neighbors
+    )
+}
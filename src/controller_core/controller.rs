@@ -0,0 +1,348 @@
+use super::event_log::EventLog;
+use super::events::{ControllerEvent, EventPump};
+use super::fragment_stats::HostFragmentStats;
+use super::operations::{
+    send_add_neighbor, send_crash, send_remove_neighbor, spawn_node, validate_edge_removal,
+    validate_new_edge, validate_new_node, validate_removal, AsNetworkNode,
+};
+use super::replay::PacketTraces;
+use super::routing_tables::RoutingTables;
+use super::server_stats::HostServerStats;
+use super::session_latency::SessionLatencies;
+use super::sessions::HostSessions;
+use super::throughput::Throughput;
+use crate::application::authentication::HostKeys;
+use crate::application::simulation_controller_messages::{ClientRequest, HostCommand};
+use crate::initialization::network_initializer::{ControllerInfo, NetworkNode};
+use crate::initialization::node_creators::{
+    ActualClientCreator, ActualDroneCreator, ActualServerCreator, ClientCreator, DroneCreator,
+    ServerCreator,
+};
+use crossbeam_channel::Sender;
+use graph::{AdjacencyVecGraph, ReferenceGraph};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use wg_2024::controller::DroneCommand;
+use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
+
+/// The state of a running simulation, shared by every controller GUI.
+///
+/// `T` is the value each GUI stores per node: it wraps the [`NetworkNode`] together with
+/// whatever the view needs, like the position on a canvas. Every change to the network goes
+/// through the methods here, which validate it and send the commands to the nodes.
+pub struct SimulationController<
+    T,
+    DC = ActualDroneCreator,
+    CC = ActualClientCreator,
+    SC = ActualServerCreator,
+> {
+    graph: AdjacencyVecGraph<NodeId, T>,
+    packet_senders: HashMap<NodeId, Sender<Packet>>,
+    drone_creator: DC,
+    client_creator: CC,
+    server_creator: SC,
+    events: EventPump,
+    throughput: Throughput,
+    packet_traces: PacketTraces,
+    host_sessions: HostSessions,
+    routing_tables: RoutingTables,
+    fragment_stats: HostFragmentStats,
+    server_stats: HostServerStats,
+    session_latencies: SessionLatencies,
+    protected_nodes: HashSet<NodeId>,
+    /// The keys issued to the hosts, a host spawned being issued one as well.
+    host_keys: HostKeys,
+}
+
+impl<T, DC, CC, SC> SimulationController<T, DC, CC, SC>
+where
+    T: AsNetworkNode + Clone,
+    DC: DroneCreator,
+    CC: ClientCreator,
+    SC: ServerCreator,
+{
+    pub fn new(info: ControllerInfo<DC, CC, SC>, wrap: impl Fn(NetworkNode) -> T) -> Self {
+        Self {
+            graph: info.network_graph.map_values(wrap),
+            packet_senders: info.packet_senders,
+            drone_creator: info.drone_creator,
+            client_creator: info.client_creator,
+            server_creator: info.server_creator,
+            events: EventPump::new(
+                info.drone_event_controller_recv,
+                info.host_event_controller_recv,
+            ),
+            throughput: Throughput::default(),
+            packet_traces: PacketTraces::default(),
+            host_sessions: HostSessions::default(),
+            routing_tables: RoutingTables::default(),
+            fragment_stats: HostFragmentStats::default(),
+            server_stats: HostServerStats::default(),
+            session_latencies: SessionLatencies::default(),
+            protected_nodes: info.protected_nodes,
+            host_keys: info.host_keys,
+        }
+    }
+
+    pub fn graph(&self) -> &AdjacencyVecGraph<NodeId, T> {
+        &self.graph
+    }
+
+    /// Gives access to the view data of a node; its [`NetworkNode`] must not be replaced.
+    pub fn node_mut(&mut self, id: NodeId) -> Option<&mut T> {
+        self.graph.get_mut(&id)
+    }
+
+    pub fn packet_senders(&self) -> &HashMap<NodeId, Sender<Packet>> {
+        &self.packet_senders
+    }
+
+    pub fn event_log(&self) -> &EventLog {
+        self.events.event_log()
+    }
+
+    pub fn throughput(&self) -> &Throughput {
+        &self.throughput
+    }
+
+    pub fn packet_traces(&self) -> &PacketTraces {
+        &self.packet_traces
+    }
+
+    pub fn host_sessions(&self) -> &HostSessions {
+        &self.host_sessions
+    }
+
+    pub fn routing_tables(&self) -> &RoutingTables {
+        &self.routing_tables
+    }
+
+    pub fn fragment_stats(&self) -> &HostFragmentStats {
+        &self.fragment_stats
+    }
+
+    pub fn server_stats(&self) -> &HostServerStats {
+        &self.server_stats
+    }
+
+    pub fn session_latencies(&self) -> &SessionLatencies {
+        &self.session_latencies
+    }
+
+    /// Whether `id` is marked as protected in the topology file, so it cannot be crashed or
+    /// unlinked from the GUI.
+    pub fn is_protected(&self, id: NodeId) -> bool {
+        self.protected_nodes.contains(&id)
+    }
+
+    /// Forwards the pending shortcut packets and returns the other events.
+    pub fn drain_events(&mut self) -> Vec<ControllerEvent> {
+        let events = self.events.drain(&self.packet_senders);
+        for event in events.iter() {
+            self.throughput.record(event);
+            self.packet_traces.record(event);
+            self.host_sessions.record(event);
+            self.routing_tables.record(event);
+            self.fragment_stats.record(event);
+            self.server_stats.record(event);
+            self.session_latencies
+                .record(event, |id| self.drone_creator.implementation_of(id));
+        }
+        events
+    }
+
+    pub fn drone_implementations(&self) -> Vec<&'static str> {
+        self.drone_creator.implementations()
+    }
+
+    /// Makes the next spawned drone use `implementation` instead of the next one in turn.
+    pub fn select_drone_implementation(&mut self, implementation: &str) -> Result<(), String> {
+        if self.drone_creator.select_implementation(implementation) {
+            Ok(())
+        } else {
+            Err(format!(
+                "There is no drone implementation called {implementation}"
+            ))
+        }
+    }
+
+    /// Spawns `node` with `id`, already linked to `neighbors`.
+    pub fn spawn_node(
+        &mut self,
+        id: NodeId,
+        mut node: T,
+        neighbors: &[NodeId],
+    ) -> Result<(), String> {
+        validate_new_node(&self.graph, id, node.network_node(), neighbors)?;
+        let neighbor_senders = neighbors
+            .iter()
+            .map(|neighbor| (*neighbor, self.packet_senders[neighbor].clone()))
+            .collect();
+        let packet_send = spawn_node(
+            id,
+            node.network_node_mut(),
+            neighbor_senders,
+            &mut self.drone_creator,
+            &mut self.client_creator,
+            &mut self.server_creator,
+            &self.host_keys,
+        );
+        for neighbor in neighbors {
+            send_add_neighbor(self.graph[neighbor].network_node(), id, packet_send.clone());
+        }
+        self.packet_senders.insert(id, packet_send);
+        self.graph.add_node(id, node);
+        for neighbor in neighbors {
+            self.graph.add_undirected_edge(id, *neighbor);
+        }
+        Ok(())
+    }
+
+    /// Crashes `id` and unlinks it from its neighbors.
+    pub fn remove_node(&mut self, id: NodeId) -> Result<(), String> {
+        if self.is_protected(id) {
+            return Err(format!("Node {id} is protected and cannot be removed"));
+        }
+        validate_removal(&self.graph, id)?;
+        send_crash(self.graph[&id].network_node());
+        for neighbor in self.graph.adjacents(&id) {
+            send_remove_neighbor(self.graph[neighbor].network_node(), id);
+        }
+        self.graph.remove_node(&id);
+        self.packet_senders.remove(&id);
+        self.host_sessions.forget(id);
+        self.routing_tables.forget(id);
+        self.fragment_stats.forget(id);
+        self.server_stats.forget(id);
+        Ok(())
+    }
+
+    pub fn add_edge(&mut self, from: NodeId, to: NodeId) -> Result<(), String> {
+        validate_new_edge(&self.graph, from, to)?;
+        send_add_neighbor(
+            self.graph[&from].network_node(),
+            to,
+            self.packet_senders[&to].clone(),
+        );
+        send_add_neighbor(
+            self.graph[&to].network_node(),
+            from,
+            self.packet_senders[&from].clone(),
+        );
+        self.graph.add_undirected_edge(from, to);
+        Ok(())
+    }
+
+    pub fn remove_edge(&mut self, from: NodeId, to: NodeId) -> Result<(), String> {
+        if let Some(id) = [from, to].into_iter().find(|id| self.is_protected(*id)) {
+            return Err(format!("Node {id} is protected and cannot be unlinked"));
+        }
+        validate_edge_removal(&self.graph, from, to)?;
+        send_remove_neighbor(self.graph[&from].network_node(), to);
+        send_remove_neighbor(self.graph[&to].network_node(), from);
+        self.graph.remove_undirected_edge(&from, &to);
+        Ok(())
+    }
+
+    /// The current PDR of the drone `id`, kept up to date by [`SimulationController::set_pdr`].
+    pub fn pdr(&self, id: NodeId) -> Option<f32> {
+        match self.graph.get(&id).map(AsNetworkNode::network_node) {
+            Some(NetworkNode::Drone { pdr, .. }) => Some(*pdr),
+            _ => None,
+        }
+    }
+
+    pub fn set_pdr(&mut self, id: NodeId, new_pdr: f32) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&new_pdr) {
+            return Err("The PDR must be a number between 0 and 1".to_string());
+        }
+        match self.graph.get_mut(&id).map(AsNetworkNode::network_node_mut) {
+            Some(NetworkNode::Drone { pdr, command_send }) => {
+                *pdr = new_pdr;
+                command_send
+                    .send(DroneCommand::SetPacketDropRate(new_pdr))
+                    .ok();
+                Ok(())
+            }
+            Some(node) => Err(format!("{node} {id} is not a drone")),
+            None => Err(format!("Node {id} does not exist")),
+        }
+    }
+
+    /// Makes the host `id` flood the network to rediscover the topology.
+    pub fn initiate_flood(&self, id: NodeId) -> Result<(), String> {
+        match self.graph.get(&id).map(AsNetworkNode::network_node) {
+            Some(NetworkNode::Client { command_send, .. })
+            | Some(NetworkNode::Server { command_send, .. }) => {
+                command_send.send(HostCommand::InitiateFlood).ok();
+                Ok(())
+            }
+            Some(node) => Err(format!("{node} {id} cannot initiate a flood")),
+            None => Err(format!("Node {id} does not exist")),
+        }
+    }
+
+    /// Makes the host `id` write the topology it knows to `path`, in the Graphviz DOT format.
+    pub fn export_topology(&self, id: NodeId, path: PathBuf) -> Result<(), String> {
+        match self.graph.get(&id).map(AsNetworkNode::network_node) {
+            Some(NetworkNode::Client { command_send, .. })
+            | Some(NetworkNode::Server { command_send, .. }) => {
+                command_send.send(HostCommand::ExportTopology(path)).ok();
+                Ok(())
+            }
+            Some(node) => Err(format!("{node} {id} has no topology to export")),
+            None => Err(format!("Node {id} does not exist")),
+        }
+    }
+
+    /// Asks where to save the topology known by the host `id` and makes it export it there;
+    /// does nothing if the dialog is cancelled.
+    pub fn export_topology_with_dialog(&self, id: NodeId) -> Result<(), String> {
+        let path = rfd::FileDialog::new()
+            .set_file_name(format!("topology_{id}.dot"))
+            .add_filter("Graphviz", &["dot", "gv"])
+            .save_file();
+
+        match path {
+            Some(path) => self.export_topology(id, path),
+            None => Ok(()),
+        }
+    }
+
+    /// Asks `client` to send `request` to `server`, if both can handle that kind of request.
+    pub fn send_request(
+        &self,
+        client: NodeId,
+        server: NodeId,
+        request: ClientRequest,
+    ) -> Result<(), String> {
+        let request_type = request.application_type();
+        let Some(NetworkNode::Client {
+            command_send,
+            application_type,
+        }) = self.graph.get(&client).map(AsNetworkNode::network_node)
+        else {
+            return Err(format!("Client {client} does not exist"));
+        };
+        if !application_type.compatible(&request_type) {
+            return Err(format!(
+                "{request} cannot be sent by a {application_type:?} client"
+            ));
+        }
+        match self.graph.get(&server).map(AsNetworkNode::network_node) {
+            Some(NetworkNode::Server {
+                application_type, ..
+            }) if !application_type.compatible(&request_type) => Err(format!(
+                "{request} cannot be handled by a {application_type:?} server"
+            )),
+            Some(NetworkNode::Server { .. }) => {
+                command_send
+                    .send(HostCommand::SendRequest(server, request))
+                    .ok();
+                Ok(())
+            }
+            _ => Err(format!("Server {server} does not exist")),
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use graph::{AdjacencyVecGraph, ReferenceGraph};
+use iced::keyboard::key::Named;
+use iced::keyboard::Key;
+use iced::widget::canvas::{Frame, Path, Stroke};
+use iced::{Color, Point};
+use wg_2024::network::NodeId;
+
+/// Distance from an edge within which a click selects it.
+const EDGE_HIT_TOLERANCE: f32 = 6.0;
+const SELECTED_EDGE_COLOR: Color = Color::from_rgb(1.0, 0.55, 0.0);
+
+/// Distance between `point` and the segment going from `from` to `to`.
+fn distance_to_segment(point: Point, from: Point, to: Point) -> f32 {
+    let segment = to - from;
+    let length_squared = segment.x * segment.x + segment.y * segment.y;
+    if length_squared == 0.0 {
+        return point.distance(from);
+    }
+    let projection =
+        ((point.x - from.x) * segment.x + (point.y - from.y) * segment.y) / length_squared;
+    let t = projection.clamp(0.0, 1.0);
+    point.distance(Point::new(from.x + t * segment.x, from.y + t * segment.y))
+}
+
+/// The edge closest to `point`, if it passes within [`EDGE_HIT_TOLERANCE`] of it.
+pub fn edge_at<T>(
+    graph: &AdjacencyVecGraph<NodeId, T>,
+    position: impl Fn(&T) -> Point,
+    point: Point,
+) -> Option<(NodeId, NodeId)> {
+    graph
+        .edges()
+        .map(|(from, to)| {
+            let distance = distance_to_segment(point, position(&graph[from]), position(&graph[to]));
+            ((*from, *to), distance)
+        })
+        .filter(|(_edge, distance)| *distance <= EDGE_HIT_TOLERANCE)
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(edge, _distance)| edge)
+}
+
+/// Whether `key` asks to remove the selected edge.
+pub fn is_delete_key(key: &Key) -> bool {
+    matches!(key, Key::Named(Named::Delete))
+}
+
+pub fn draw_selected_edge(frame: &mut Frame, from: Point, to: Point) {
+    frame.stroke(
+        &Path::line(from, to),
+        Stroke::default()
+            .with_width(5.0)
+            .with_color(SELECTED_EDGE_COLOR),
+    );
+}
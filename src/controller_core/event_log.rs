@@ -0,0 +1,108 @@
+use crate::application::simulation_controller_messages::HostEvent;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use wg_2024::controller::DroneEvent;
+
+const MAX_LOGGED_EVENTS: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub enum EventSource {
+    Drone,
+    Host,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LoggedEvent {
+    pub timestamp_ms: u128,
+    pub source: EventSource,
+    pub description: String,
+}
+
+impl LoggedEvent {
+    fn new(source: EventSource, description: String) -> Self {
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_millis())
+            .unwrap_or_default();
+        Self {
+            timestamp_ms,
+            source,
+            description,
+        }
+    }
+
+    fn to_csv_row(&self) -> String {
+        format!(
+            "{},{:?},\"{}\"",
+            self.timestamp_ms,
+            self.source,
+            self.description.replace('"', "\"\"")
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct EventLog {
+    events: VecDeque<LoggedEvent>,
+}
+
+impl EventLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_drone_event(&mut self, event: &DroneEvent) {
+        self.push(LoggedEvent::new(EventSource::Drone, format!("{:?}", event)));
+    }
+
+    pub fn record_host_event(&mut self, event: &HostEvent) {
+        self.push(LoggedEvent::new(EventSource::Host, format!("{:?}", event)));
+    }
+
+    fn push(&mut self, event: LoggedEvent) {
+        self.events.push_back(event);
+        if self.events.len() > MAX_LOGGED_EVENTS {
+            self.events.pop_front();
+        }
+    }
+
+    /// Writes the log as JSON lines if `path` ends in `.json`/`.jsonl`, as CSV otherwise.
+    pub fn export(&self, path: &Path) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        let is_json = path
+            .extension()
+            .is_some_and(|extension| extension == "json" || extension == "jsonl");
+
+        if is_json {
+            for event in self.events.iter() {
+                serde_json::to_writer(&mut writer, event)?;
+                writeln!(writer)?;
+            }
+        } else {
+            writeln!(writer, "timestamp_ms,source,description")?;
+            for event in self.events.iter() {
+                writeln!(writer, "{}", event.to_csv_row())?;
+            }
+        }
+
+        writer.flush()
+    }
+
+    /// Asks the user where to save the log; returns `Ok(false)` if the dialog was cancelled.
+    pub fn export_with_dialog(&self) -> io::Result<bool> {
+        let path = rfd::FileDialog::new()
+            .set_file_name("events.csv")
+            .add_filter("CSV", &["csv"])
+            .add_filter("JSON Lines", &["jsonl", "json"])
+            .save_file();
+
+        match path {
+            Some(path) => self.export(&path).map(|_| true),
+            None => Ok(false),
+        }
+    }
+}
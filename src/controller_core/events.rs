@@ -0,0 +1,78 @@
+use super::event_log::EventLog;
+use crate::application::simulation_controller_messages::HostEvent;
+use crossbeam_channel::{Receiver, Sender};
+use std::collections::HashMap;
+use std::time::Duration;
+use wg_2024::controller::DroneEvent;
+use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
+
+/// How often the controllers should call [`EventPump::drain`].
+pub const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug)]
+pub enum ControllerEvent {
+    Drone(DroneEvent),
+    Host(HostEvent),
+}
+
+/// Owns the event channels of a simulation, handling the events the controller must react
+/// to by itself and recording everything into an [`EventLog`].
+pub struct EventPump {
+    drone_event_recv: Receiver<DroneEvent>,
+    host_event_recv: Receiver<HostEvent>,
+    event_log: EventLog,
+}
+
+impl EventPump {
+    pub fn new(
+        drone_event_recv: Receiver<DroneEvent>,
+        host_event_recv: Receiver<HostEvent>,
+    ) -> Self {
+        Self {
+            drone_event_recv,
+            host_event_recv,
+            event_log: EventLog::new(),
+        }
+    }
+
+    pub fn event_log(&self) -> &EventLog {
+        &self.event_log
+    }
+
+    /// Takes every pending event, delivering `ControllerShortcut` packets straight to their
+    /// destination, and returns the others for the UI to show.
+    pub fn drain(
+        &mut self,
+        packet_senders: &HashMap<NodeId, Sender<Packet>>,
+    ) -> Vec<ControllerEvent> {
+        let mut events = Vec::new();
+        for event in self.drone_event_recv.try_iter() {
+            self.event_log.record_drone_event(&event);
+            if let DroneEvent::ControllerShortcut(packet) = event {
+                if let Some(destination_sender) = packet
+                    .routing_header
+                    .destination()
+                    .and_then(|destination| packet_senders.get(&destination))
+                {
+                    destination_sender.send(packet).ok();
+                }
+            } else {
+                events.push(ControllerEvent::Drone(event));
+            }
+        }
+        for event in self.host_event_recv.try_iter() {
+            // routing tables and statistics are snapshots sent periodically, not worth logging
+            if !matches!(
+                event,
+                HostEvent::RoutingTable(..)
+                    | HostEvent::FragmentStats(..)
+                    | HostEvent::ServerStats(..)
+            ) {
+                self.event_log.record_host_event(&event);
+            }
+            events.push(ControllerEvent::Host(event));
+        }
+        events
+    }
+}
@@ -0,0 +1,50 @@
+use super::events::ControllerEvent;
+use crate::application::assembler::FragmentStats;
+use crate::application::simulation_controller_messages::HostEvent;
+use iced::widget::{column, text};
+use iced::Element;
+use std::collections::HashMap;
+use wg_2024::network::NodeId;
+
+/// The latest fragment counters sent by every host.
+#[derive(Default)]
+pub struct HostFragmentStats {
+    stats: HashMap<NodeId, FragmentStats>,
+}
+
+impl HostFragmentStats {
+    pub fn record(&mut self, event: &ControllerEvent) {
+        if let ControllerEvent::Host(HostEvent::FragmentStats(host, stats)) = event {
+            self.stats.insert(*host, *stats);
+        }
+    }
+
+    /// Drops the counters of a host that left the network.
+    pub fn forget(&mut self, host: NodeId) {
+        self.stats.remove(&host);
+    }
+
+    pub fn of(&self, host: NodeId) -> Option<&FragmentStats> {
+        self.stats.get(&host)
+    }
+
+    /// Shows how many fragments `host` got twice or out of order, and how many it resent.
+    pub fn view<'a, M: 'a>(&self, host: NodeId) -> Element<'a, M> {
+        let Some(stats) = self.of(host) else {
+            return text("No fragment statistics yet").into();
+        };
+        column![
+            text("Fragment statistics:"),
+            text(format!(
+                "received {}, {} duplicates, {} out of order",
+                stats.received, stats.duplicates, stats.out_of_order
+            )),
+            text(format!(
+                "sent {}, {} retransmitted",
+                stats.sent, stats.retransmitted
+            )),
+        ]
+        .spacing(5)
+        .into()
+    }
+}
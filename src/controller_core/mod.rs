@@ -0,0 +1,17 @@
+pub mod backlog;
+pub mod confirmation;
+pub mod controller;
+pub mod edges;
+pub mod event_log;
+pub mod events;
+pub mod fragment_stats;
+pub mod operations;
+pub mod replay;
+pub mod routing_tables;
+pub mod send_form;
+pub mod server_stats;
+pub mod session_latency;
+pub mod sessions;
+pub mod settings;
+pub mod throughput;
+pub mod toast;
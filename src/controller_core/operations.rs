@@ -0,0 +1,228 @@
+use crate::application::authentication::HostKeys;
+use crate::application::simulation_controller_messages::HostCommand;
+use crate::initialization::network_initializer::NetworkNode;
+use crate::initialization::node_creators::{ClientCreator, DroneCreator, ServerCreator};
+use crossbeam_channel::{unbounded, Sender};
+use graph::{AdjacencyVecGraph, ReferenceGraph};
+use std::collections::HashMap;
+use std::thread;
+use wg_2024::controller::DroneCommand;
+use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
+
+/// Graph values the controllers store per node, whatever extra GUI data they carry.
+pub trait AsNetworkNode {
+    fn network_node(&self) -> &NetworkNode;
+    fn network_node_mut(&mut self) -> &mut NetworkNode;
+}
+
+impl AsNetworkNode for NetworkNode {
+    fn network_node(&self) -> &NetworkNode {
+        self
+    }
+    fn network_node_mut(&mut self) -> &mut NetworkNode {
+        self
+    }
+}
+
+pub fn send_add_neighbor(node: &NetworkNode, neighbor: NodeId, neighbor_sender: Sender<Packet>) {
+    match node {
+        NetworkNode::Drone { command_send, .. } => {
+            command_send
+                .send(DroneCommand::AddSender(neighbor, neighbor_sender))
+                .ok();
+        }
+        NetworkNode::Client { command_send, .. } | NetworkNode::Server { command_send, .. } => {
+            command_send
+                .send(HostCommand::AddConnectedDrone(neighbor, neighbor_sender))
+                .ok();
+        }
+    }
+}
+
+pub fn send_remove_neighbor(node: &NetworkNode, neighbor: NodeId) {
+    match node {
+        NetworkNode::Drone { command_send, .. } => {
+            command_send.send(DroneCommand::RemoveSender(neighbor)).ok();
+        }
+        NetworkNode::Client { command_send, .. } | NetworkNode::Server { command_send, .. } => {
+            command_send
+                .send(HostCommand::RemoveConnectedDrone(neighbor))
+                .ok();
+        }
+    }
+}
+
+pub fn send_crash(node: &NetworkNode) {
+    match node {
+        NetworkNode::Drone { command_send, .. } => {
+            command_send.send(DroneCommand::Crash).ok();
+        }
+        NetworkNode::Client { command_send, .. } | NetworkNode::Server { command_send, .. } => {
+            command_send.send(HostCommand::Crash).ok();
+        }
+    }
+}
+
+/// Checks that a node of kind `node` can be spawned already linked to `neighbors`.
+pub fn validate_new_node<T: AsNetworkNode>(
+    graph: &AdjacencyVecGraph<NodeId, T>,
+    id: NodeId,
+    node: &NetworkNode,
+    neighbors: &[NodeId],
+) -> Result<(), String> {
+    if graph.contains_node(&id) {
+        return Err(format!("Node {id} already exists"));
+    }
+    let is_host = !matches!(node, NetworkNode::Drone { .. });
+    for neighbor in neighbors {
+        let Some(neighbor_node) = graph.get(neighbor) else {
+            return Err(format!("Node {neighbor} does not exist"));
+        };
+        match neighbor_node.network_node() {
+            NetworkNode::Drone { .. } => {}
+            _ if is_host => {
+                return Err(format!(
+                    "{node} {id} can only be connected to drones, {neighbor} is not one"
+                ));
+            }
+            NetworkNode::Client { .. } if graph.adjacents(neighbor).count() >= 2 => {
+                return Err(format!(
+                    "Client {neighbor} is already connected to two drones"
+                ));
+            }
+            _ => {}
+        }
+    }
+    match node {
+        NetworkNode::Client { .. } if neighbors.len() > 2 => {
+            Err("A client can be connected to at most two drones".to_string())
+        }
+        NetworkNode::Server { .. } if neighbors.len() == 1 => {
+            Err("A server must be connected to at least two drones".to_string())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Starts the thread of a new node already linked to `neighbor_senders`, storing its
+/// command channel in `node` and returning the sender of its packet channel.
+///
+/// The neighbors still have to be told about the new node with [`send_add_neighbor`].
+pub fn spawn_node(
+    id: NodeId,
+    node: &mut NetworkNode,
+    neighbor_senders: HashMap<NodeId, Sender<Packet>>,
+    drone_creator: &mut impl DroneCreator,
+    client_creator: &mut impl ClientCreator,
+    server_creator: &mut impl ServerCreator,
+    host_keys: &HostKeys,
+) -> Sender<Packet> {
+    let (packet_send, packet_recv) = unbounded();
+    let mut runnable = match node {
+        NetworkNode::Drone { pdr, command_send } => {
+            let (cmd_send, cmd_recv) = unbounded();
+            *command_send = cmd_send;
+            drone_creator.create_drone(id, cmd_recv, packet_recv, neighbor_senders, *pdr)
+        }
+        NetworkNode::Client { command_send, .. } => {
+            let (cmd_send, cmd_recv) = unbounded();
+            *command_send = cmd_send;
+            host_keys.issue(id);
+            client_creator.create_client(id, cmd_recv, packet_recv, neighbor_senders, host_keys)
+        }
+        NetworkNode::Server { command_send, .. } => {
+            let (cmd_send, cmd_recv) = unbounded();
+            *command_send = cmd_send;
+            host_keys.issue(id);
+            server_creator.create_server(id, cmd_recv, packet_recv, neighbor_senders, host_keys)
+        }
+    };
+    node.set_application_type(runnable.application_type());
+    thread::spawn(move || runnable.run());
+    packet_send
+}
+
+/// Checks that `id` can leave the network without disconnecting it or leaving a host with
+/// too few drones.
+pub fn validate_removal<T: AsNetworkNode + Clone>(
+    graph: &AdjacencyVecGraph<NodeId, T>,
+    id: NodeId,
+) -> Result<(), String> {
+    let neighbors: Vec<NodeId> = graph.adjacents(&id).copied().collect();
+    let mut new_graph = graph.clone();
+    new_graph.remove_node(&id);
+    if !new_graph.is_connected_undirected() {
+        return Err(format!("Removing {id} would disconnect the network"));
+    }
+    validate_host_degrees(&new_graph, &neighbors)
+}
+
+/// Checks that `from` and `to` can be linked without breaking the host constraints.
+pub fn validate_new_edge<T: AsNetworkNode>(
+    graph: &AdjacencyVecGraph<NodeId, T>,
+    from: NodeId,
+    to: NodeId,
+) -> Result<(), String> {
+    if graph.is_adjacent_to(&from, &to) {
+        return Err(format!("{from} and {to} are already connected"));
+    }
+    for (id, other) in [(from, to), (to, from)] {
+        match graph[&id].network_node() {
+            NetworkNode::Drone { .. } => {}
+            node if !matches!(graph[&other].network_node(), NetworkNode::Drone { .. }) => {
+                return Err(format!("{node} {id} can only be connected to drones"));
+            }
+            NetworkNode::Client { .. } if graph.adjacents(&id).count() >= 2 => {
+                return Err(format!("Client {id} is already connected to two drones"));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the link between `from` and `to` can be removed without disconnecting the
+/// network or leaving a host with too few drones.
+pub fn validate_edge_removal<T: AsNetworkNode + Clone>(
+    graph: &AdjacencyVecGraph<NodeId, T>,
+    from: NodeId,
+    to: NodeId,
+) -> Result<(), String> {
+    let mut new_graph = graph.clone();
+    new_graph.remove_undirected_edge(&from, &to);
+    if !new_graph.is_connected_undirected() {
+        return Err(format!(
+            "Removing the link {from}-{to} would disconnect the network"
+        ));
+    }
+    validate_host_degrees(&new_graph, &[from, to])
+}
+
+/// Checks the degree of the `touched` hosts only, so that a host already out of bounds does
+/// not block the edits elsewhere.
+fn validate_host_degrees<T: AsNetworkNode>(
+    graph: &AdjacencyVecGraph<NodeId, T>,
+    touched: &[NodeId],
+) -> Result<(), String> {
+    for id in touched {
+        let Some(node) = graph.get(id) else {
+            continue;
+        };
+        let degree = graph.adjacents(id).count();
+        match node.network_node() {
+            NetworkNode::Client { .. } if !(1..=2).contains(&degree) => {
+                return Err(format!(
+                    "Client {id} must be connected to one or two drones"
+                ));
+            }
+            NetworkNode::Server { .. } if degree < 2 => {
+                return Err(format!(
+                    "Server {id} must be connected to at least two drones"
+                ));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,177 @@
+use super::events::ControllerEvent;
+use iced::alignment::{Horizontal, Vertical};
+use iced::widget::canvas::{Frame, Path, Stroke, Text};
+use iced::{Color, Pixels, Point};
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{Display, Formatter};
+use std::time::{Duration, Instant};
+use wg_2024::controller::DroneEvent;
+use wg_2024::network::NodeId;
+use wg_2024::packet::PacketType;
+
+/// Number of sessions whose hops are kept for replaying.
+const MAX_TRACED_SESSIONS: usize = 200;
+/// Time each hop stays highlighted during a replay.
+const STEP_INTERVAL: Duration = Duration::from_millis(600);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HopKind {
+    Fragment,
+    Ack,
+    Nack,
+    Flood,
+}
+
+impl HopKind {
+    fn color(self) -> Color {
+        match self {
+            HopKind::Fragment => Color::from_rgb8(40, 120, 230),
+            HopKind::Ack => Color::from_rgb8(40, 180, 80),
+            HopKind::Nack => Color::from_rgb8(230, 60, 40),
+            HopKind::Flood => Color::from_rgb8(230, 160, 30),
+        }
+    }
+}
+
+impl Display for HopKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HopKind::Fragment => write!(f, "Fragment"),
+            HopKind::Ack => write!(f, "Ack"),
+            HopKind::Nack => write!(f, "Nack"),
+            HopKind::Flood => write!(f, "Flood"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Hop {
+    pub from: NodeId,
+    pub to: NodeId,
+    pub kind: HopKind,
+}
+
+/// The hops of the latest sessions, rebuilt from the `PacketSent` events of the drones.
+#[derive(Default)]
+pub struct PacketTraces {
+    sessions: HashMap<u64, Vec<Hop>>,
+    order: VecDeque<u64>,
+}
+
+impl PacketTraces {
+    pub fn record(&mut self, event: &ControllerEvent) {
+        let ControllerEvent::Drone(DroneEvent::PacketSent(packet)) = event else {
+            return;
+        };
+        let (Some(from), Some(to)) = (
+            packet.routing_header.previous_hop(),
+            packet.routing_header.current_hop(),
+        ) else {
+            return;
+        };
+        let kind = match packet.pack_type {
+            PacketType::MsgFragment(_) => HopKind::Fragment,
+            PacketType::Ack(_) => HopKind::Ack,
+            PacketType::Nack(_) => HopKind::Nack,
+            PacketType::FloodRequest(_) | PacketType::FloodResponse(_) => HopKind::Flood,
+        };
+        let session_id = packet.session_id;
+        if !self.sessions.contains_key(&session_id) {
+            self.order.push_back(session_id);
+            if self.order.len() > MAX_TRACED_SESSIONS {
+                if let Some(oldest) = self.order.pop_front() {
+                    self.sessions.remove(&oldest);
+                }
+            }
+        }
+        self.sessions
+            .entry(session_id)
+            .or_default()
+            .push(Hop { from, to, kind });
+    }
+
+    /// The traced session ids, most recent first.
+    pub fn sessions(&self) -> Vec<u64> {
+        self.order.iter().rev().copied().collect()
+    }
+
+    pub fn hops(&self, session_id: u64) -> &[Hop] {
+        self.sessions
+            .get(&session_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+}
+
+/// Walks through the hops of a session one at a time.
+#[derive(Debug)]
+pub struct Replay {
+    session_id: u64,
+    step: usize,
+    stepped: Instant,
+}
+
+impl Replay {
+    pub fn new(session_id: u64) -> Self {
+        Self {
+            session_id,
+            step: 0,
+            stepped: Instant::now(),
+        }
+    }
+
+    /// Moves to the next hop once [`STEP_INTERVAL`] has passed, returning whether there are
+    /// hops left to show.
+    pub fn advance(&mut self, traces: &PacketTraces) -> bool {
+        if self.stepped.elapsed() >= STEP_INTERVAL {
+            self.step += 1;
+            self.stepped = Instant::now();
+        }
+        self.step < traces.hops(self.session_id).len()
+    }
+
+    /// Draws the hops already replayed faded out and the current one highlighted.
+    pub fn draw(
+        &self,
+        frame: &mut Frame,
+        traces: &PacketTraces,
+        position: impl Fn(NodeId) -> Option<Point>,
+    ) {
+        let hops = traces.hops(self.session_id);
+        for (index, hop) in hops.iter().enumerate().take(self.step + 1) {
+            let (Some(from), Some(to)) = (position(hop.from), position(hop.to)) else {
+                continue;
+            };
+            let current = index == self.step;
+            let color = Color {
+                a: if current { 1.0 } else { 0.25 },
+                ..hop.kind.color()
+            };
+            frame.stroke(
+                &Path::line(from, to),
+                Stroke::default()
+                    .with_width(if current { 6.0 } else { 3.0 })
+                    .with_color(color),
+            );
+            if current {
+                frame.fill(&Path::circle(to, 8.0), color);
+                frame.fill_text(Text {
+                    content: format!(
+                        "{} {}/{}: {} -> {}",
+                        hop.kind,
+                        index + 1,
+                        hops.len(),
+                        hop.from,
+                        hop.to
+                    ),
+                    position: Point::new((from.x + to.x) / 2.0, (from.y + to.y) / 2.0 - 12.0),
+                    color,
+                    size: Pixels(14.0),
+                    horizontal_alignment: Horizontal::Center,
+                    vertical_alignment: Vertical::Bottom,
+                    ..Text::default()
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use super::events::ControllerEvent;
+use crate::application::routing::RouteDescription;
+use crate::application::simulation_controller_messages::HostEvent;
+use iced::widget::{column, text};
+use iced::Element;
+use std::collections::HashMap;
+use wg_2024::network::NodeId;
+
+/// The latest routing table sent by every host.
+#[derive(Default)]
+pub struct RoutingTables {
+    tables: HashMap<NodeId, Vec<RouteDescription>>,
+}
+
+impl RoutingTables {
+    pub fn record(&mut self, event: &ControllerEvent) {
+        if let ControllerEvent::Host(HostEvent::RoutingTable(host, routes)) = event {
+            self.tables.insert(*host, routes.clone());
+        }
+    }
+
+    /// Drops the table of a host that left the network.
+    pub fn forget(&mut self, host: NodeId) {
+        self.tables.remove(&host);
+    }
+
+    pub fn of(&self, host: NodeId) -> &[RouteDescription] {
+        self.tables.get(&host).map_or(&[], Vec::as_slice)
+    }
+
+    /// Lists the routes `host` knows, so it is clear why it picks a path.
+    pub fn view<'a, M: 'a>(&self, host: NodeId) -> Element<'a, M> {
+        let mut lines = column![text("Known routes:")].spacing(5);
+        for route in self.of(host) {
+            let last_used = route.last_used.map_or("never used".to_string(), |used| {
+                format!("used {:.1}s ago", used.as_secs_f32())
+            });
+            lines = lines.push(text(format!(
+                "to {}: {:?} cost {:.2}, {last_used}",
+                route.destination, route.hops, route.cost
+            )));
+        }
+        lines.into()
+    }
+}
@@ -0,0 +1,188 @@
+use super::operations::AsNetworkNode;
+use crate::application::simulation_controller_messages::ClientRequest;
+use crate::application::topology::node::ApplicationType;
+use crate::initialization::network_initializer::NetworkNode;
+use graph::{AdjacencyVecGraph, ReferenceGraph};
+use iced::widget::{button, column, pick_list, text_input};
+use iced::Element;
+use std::fmt::{Display, Formatter};
+use wg_2024::network::NodeId;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestKind {
+    ClientList,
+    Register,
+    SendMessage,
+    TextList,
+    Text,
+    MediaList,
+    Media,
+}
+
+impl RequestKind {
+    const ALL: [RequestKind; 7] = [
+        RequestKind::ClientList,
+        RequestKind::Register,
+        RequestKind::SendMessage,
+        RequestKind::TextList,
+        RequestKind::Text,
+        RequestKind::MediaList,
+        RequestKind::Media,
+    ];
+
+    fn application_type(self) -> ApplicationType {
+        match self {
+            RequestKind::ClientList | RequestKind::Register | RequestKind::SendMessage => {
+                ApplicationType::Chat
+            }
+            _ => ApplicationType::Content,
+        }
+    }
+
+    /// The placeholder of the text argument of the request, if it takes one.
+    fn argument(self) -> Option<&'static str> {
+        match self {
+            RequestKind::Register => Some("Username"),
+            RequestKind::SendMessage => Some("Recipient's username"),
+            RequestKind::Text | RequestKind::Media => Some("File name"),
+            _ => None,
+        }
+    }
+}
+
+impl Display for RequestKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            RequestKind::ClientList => "Client list",
+            RequestKind::Register => "Register",
+            RequestKind::SendMessage => "Send message",
+            RequestKind::TextList => "Text list",
+            RequestKind::Text => "Text",
+            RequestKind::MediaList => "Media list",
+            RequestKind::Media => "Media",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum SendFormMessage {
+    ClientSelected(NodeId),
+    ServerSelected(NodeId),
+    KindSelected(RequestKind),
+    ArgumentChanged(String),
+    MessageChanged(String),
+    Submitted,
+}
+
+/// A form picking a client, a server and a request for the client to send to the server.
+///
+/// Only the servers and requests matching the application of the selected client are offered.
+#[derive(Default)]
+pub struct SendForm {
+    client: Option<NodeId>,
+    server: Option<NodeId>,
+    kind: Option<RequestKind>,
+    argument: String,
+    message: String,
+}
+
+impl SendForm {
+    /// Applies every message but [`SendFormMessage::Submitted`], which the GUI handles by
+    /// sending [`SendForm::request`] through the controller.
+    pub fn update(&mut self, message: SendFormMessage) {
+        match message {
+            SendFormMessage::ClientSelected(id) => {
+                self.client = Some(id);
+                self.server = None;
+                self.kind = None;
+            }
+            SendFormMessage::ServerSelected(id) => self.server = Some(id),
+            SendFormMessage::KindSelected(kind) => self.kind = Some(kind),
+            SendFormMessage::ArgumentChanged(argument) => self.argument = argument,
+            SendFormMessage::MessageChanged(message) => self.message = message,
+            SendFormMessage::Submitted => {}
+        }
+    }
+
+    /// The client, the server and the request filled in so far.
+    pub fn request(&self) -> Result<(NodeId, NodeId, ClientRequest), String> {
+        let (Some(client), Some(server), Some(kind)) = (self.client, self.server, self.kind) else {
+            return Err("Pick a client, a server and a request first".to_string());
+        };
+        if let Some(argument) = kind.argument().filter(|_| self.argument.is_empty()) {
+            return Err(format!("{argument} is missing"));
+        }
+        let argument = self.argument.clone();
+        let request = match kind {
+            RequestKind::ClientList => ClientRequest::ClientList,
+            RequestKind::Register => ClientRequest::Register(argument),
+            RequestKind::SendMessage => ClientRequest::SendMessage {
+                to: argument,
+                message: self.message.clone(),
+            },
+            RequestKind::TextList => ClientRequest::TextList,
+            RequestKind::Text => ClientRequest::Text(argument),
+            RequestKind::MediaList => ClientRequest::MediaList,
+            RequestKind::Media => ClientRequest::Media(argument),
+        };
+        Ok((client, server, request))
+    }
+
+    pub fn view<T: AsNetworkNode>(
+        &self,
+        graph: &AdjacencyVecGraph<NodeId, T>,
+    ) -> Element<SendFormMessage> {
+        let client_type =
+            self.client
+                .and_then(|id| graph.get(&id))
+                .and_then(|node| match node.network_node() {
+                    NetworkNode::Client {
+                        application_type, ..
+                    } => Some(*application_type),
+                    _ => None,
+                });
+        let accepts = |other: &ApplicationType| client_type.is_some_and(|t| t.compatible(other));
+
+        let mut clients = graph
+            .iter()
+            .filter(|(_id, node)| matches!(node.network_node(), NetworkNode::Client { .. }))
+            .map(|(id, _node)| *id)
+            .collect::<Vec<_>>();
+        clients.sort();
+        let mut servers = graph
+            .iter()
+            .filter(|(_id, node)| match node.network_node() {
+                NetworkNode::Server {
+                    application_type, ..
+                } => accepts(application_type),
+                _ => false,
+            })
+            .map(|(id, _node)| *id)
+            .collect::<Vec<_>>();
+        servers.sort();
+        let kinds = RequestKind::ALL
+            .into_iter()
+            .filter(|kind| accepts(&kind.application_type()))
+            .collect::<Vec<_>>();
+
+        let mut form = column![
+            pick_list(clients, self.client, SendFormMessage::ClientSelected).placeholder("Client"),
+            pick_list(servers, self.server, SendFormMessage::ServerSelected).placeholder("Server"),
+            pick_list(kinds, self.kind, SendFormMessage::KindSelected).placeholder("Request"),
+        ]
+        .spacing(10);
+        if let Some(argument) = self.kind.and_then(RequestKind::argument) {
+            form = form.push(
+                text_input(argument, &self.argument).on_input(SendFormMessage::ArgumentChanged),
+            );
+        }
+        if self.kind == Some(RequestKind::SendMessage) {
+            form = form.push(
+                text_input("Message", &self.message).on_input(SendFormMessage::MessageChanged),
+            );
+        }
+        form.push(button("Send").on_press(SendFormMessage::Submitted))
+            .into()
+    }
+}
@@ -0,0 +1,71 @@
+use super::events::ControllerEvent;
+use crate::application::simulation_controller_messages::HostEvent;
+use crate::message::server_stats::ServerStats;
+use iced::widget::{column, text};
+use iced::Element;
+use std::collections::HashMap;
+use wg_2024::network::NodeId;
+
+/// The latest request statistics sent by every server.
+#[derive(Default)]
+pub struct HostServerStats {
+    stats: HashMap<NodeId, ServerStats>,
+}
+
+impl HostServerStats {
+    pub fn record(&mut self, event: &ControllerEvent) {
+        if let ControllerEvent::Host(HostEvent::ServerStats(host, stats)) = event {
+            self.stats.insert(*host, stats.clone());
+        }
+    }
+
+    /// Drops the statistics of a server that left the network.
+    pub fn forget(&mut self, host: NodeId) {
+        self.stats.remove(&host);
+    }
+
+    pub fn of(&self, host: NodeId) -> Option<&ServerStats> {
+        self.stats.get(&host)
+    }
+
+    /// Where `host` ranks among the servers by the requests of the last minute, the busiest
+    /// first, and how many servers reported.
+    fn rank(&self, host: NodeId) -> Option<(usize, usize)> {
+        let recent = self.of(host)?.recent_requests;
+        let busier = self
+            .stats
+            .values()
+            .filter(|stats| stats.recent_requests > recent)
+            .count();
+        Some((busier + 1, self.stats.len()))
+    }
+
+    /// Shows how many requests `host` handled, of which kinds, and how it ranks among the
+    /// servers by the requests of the last minute.
+    pub fn view<'a, M: 'a>(&self, host: NodeId) -> Element<'a, M> {
+        let (Some(stats), Some((rank, servers))) = (self.of(host), self.rank(host)) else {
+            return text("No request statistics yet").into();
+        };
+        let kinds = stats
+            .by_kind
+            .iter()
+            .map(|(kind, count)| format!("{kind} {count}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        column![
+            text("Request statistics:"),
+            text(format!(
+                "{} requests, {} in the last minute, ranking {rank} of {servers} servers",
+                stats.requests, stats.recent_requests
+            )),
+            text(format!(
+                "answered in {} µs on average, {} µs at most",
+                stats.mean_processing.as_micros(),
+                stats.slowest_processing.as_micros()
+            )),
+            text(kinds),
+        ]
+        .spacing(5)
+        .into()
+    }
+}
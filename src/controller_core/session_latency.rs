@@ -0,0 +1,96 @@
+use super::events::ControllerEvent;
+use crate::application::simulation_controller_messages::HostEvent;
+use iced::widget::{column, progress_bar, row, text, Column};
+use iced::Element;
+use std::collections::{BTreeMap, HashSet};
+use std::time::Duration;
+use wg_2024::network::NodeId;
+
+/// The sessions delivered over routes crossing drones of one implementation.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LatencySummary {
+    pub sessions: u32,
+    pub total_latency: Duration,
+    pub retransmissions: u32,
+}
+
+impl LatencySummary {
+    pub fn mean_latency(&self) -> Duration {
+        self.total_latency / self.sessions.max(1)
+    }
+}
+
+/// End-to-end latency of the sessions sent by the clients, grouped by the implementations of
+/// the drones they crossed.
+#[derive(Default)]
+pub struct SessionLatencies {
+    by_implementation: BTreeMap<&'static str, LatencySummary>,
+}
+
+impl SessionLatencies {
+    /// Counts the session once for every implementation found on its route, telling the
+    /// drones apart from the hosts with `implementation_of`.
+    pub fn record(
+        &mut self,
+        event: &ControllerEvent,
+        implementation_of: impl Fn(NodeId) -> Option<&'static str>,
+    ) {
+        let ControllerEvent::Host(HostEvent::SessionStats {
+            latency,
+            retransmissions,
+            route,
+            ..
+        }) = event
+        else {
+            return;
+        };
+        let implementations: HashSet<&'static str> = route
+            .iter()
+            .filter_map(|&node| implementation_of(node))
+            .collect();
+        for implementation in implementations {
+            let summary = self.by_implementation.entry(implementation).or_default();
+            summary.sessions += 1;
+            summary.total_latency += *latency;
+            summary.retransmissions += retransmissions;
+        }
+    }
+
+    pub fn summaries(&self) -> impl Iterator<Item = (&'static str, &LatencySummary)> {
+        self.by_implementation
+            .iter()
+            .map(|(implementation, summary)| (*implementation, summary))
+    }
+
+    /// Charts the mean latency of every implementation, relative to the slowest one.
+    pub fn view<'a, M: 'a>(&self) -> Element<'a, M> {
+        let slowest = self
+            .summaries()
+            .map(|(_implementation, summary)| summary.mean_latency().as_secs_f32())
+            .fold(0.0, f32::max);
+        if slowest == 0.0 {
+            return text("No delivered sessions yet").into();
+        }
+        let rows = self.summaries().map(|(implementation, summary)| {
+            let mean = summary.mean_latency();
+            row![
+                text(implementation).width(150),
+                progress_bar(0.0..=slowest, mean.as_secs_f32()).width(200),
+                text(format!(
+                    "{} ms over {} sessions, {} retransmissions",
+                    mean.as_millis(),
+                    summary.sessions,
+                    summary.retransmissions
+                )),
+            ]
+            .spacing(10)
+            .into()
+        });
+        column![
+            text("Latency by drone implementation:"),
+            Column::with_children(rows).spacing(5)
+        ]
+        .spacing(5)
+        .into()
+    }
+}
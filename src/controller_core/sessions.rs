@@ -0,0 +1,117 @@
+use super::events::ControllerEvent;
+use crate::application::simulation_controller_messages::{HostEvent, SessionDirection};
+use iced::widget::{column, text};
+use iced::{Color, Element};
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+use wg_2024::network::NodeId;
+
+/// Time without progress after which a session is shown as stalled.
+const STALL_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy)]
+pub struct SessionStatus {
+    pub direction: SessionDirection,
+    pub total_fragments: u64,
+    pub remaining: u64,
+    updated: Instant,
+}
+
+impl SessionStatus {
+    pub fn is_stalled(&self) -> bool {
+        self.updated.elapsed() >= STALL_TIMEOUT
+    }
+}
+
+/// The sessions every host is sending or receiving, kept up to date from its events.
+#[derive(Default)]
+pub struct HostSessions {
+    sessions: HashMap<NodeId, BTreeMap<u64, SessionStatus>>,
+}
+
+impl HostSessions {
+    pub fn record(&mut self, event: &ControllerEvent) {
+        let ControllerEvent::Host(event) = event else {
+            return;
+        };
+        match *event {
+            HostEvent::SessionStarted {
+                host,
+                session_id,
+                direction,
+                total_fragments,
+            } => {
+                self.sessions.entry(host).or_default().insert(
+                    session_id,
+                    SessionStatus {
+                        direction,
+                        total_fragments,
+                        remaining: total_fragments,
+                        updated: Instant::now(),
+                    },
+                );
+            }
+            HostEvent::SessionProgress {
+                host,
+                session_id,
+                remaining,
+            } => {
+                if let Some(status) = self
+                    .sessions
+                    .get_mut(&host)
+                    .and_then(|sessions| sessions.get_mut(&session_id))
+                {
+                    status.remaining = remaining;
+                    status.updated = Instant::now();
+                }
+            }
+            HostEvent::SessionEnded { host, session_id }
+            | HostEvent::SessionAbandoned { host, session_id }
+            | HostEvent::MessageFailed { host, session_id }
+            | HostEvent::SessionEvicted {
+                host, session_id, ..
+            } => {
+                if let Some(sessions) = self.sessions.get_mut(&host) {
+                    sessions.remove(&session_id);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Drops the sessions of a host that left the network.
+    pub fn forget(&mut self, host: NodeId) {
+        self.sessions.remove(&host);
+    }
+
+    pub fn of(&self, host: NodeId) -> impl Iterator<Item = (&u64, &SessionStatus)> {
+        self.sessions.get(&host).into_iter().flatten()
+    }
+
+    /// Lists the in-flight sessions of `host`, highlighting the stalled ones.
+    pub fn view<'a, M: 'a>(&self, host: NodeId) -> Element<'a, M> {
+        let mut lines = column![text("Active sessions:")].spacing(5);
+        for (session_id, status) in self.of(host) {
+            let direction = match status.direction {
+                SessionDirection::Outgoing => "sending",
+                SessionDirection::Incoming => "receiving",
+            };
+            let line = text(format!(
+                "{session_id}: {direction} {}/{} fragments{}",
+                status.total_fragments - status.remaining,
+                status.total_fragments,
+                if status.is_stalled() {
+                    " (stalled)"
+                } else {
+                    ""
+                },
+            ));
+            lines = lines.push(if status.is_stalled() {
+                line.color(Color::from_rgb8(220, 40, 40))
+            } else {
+                line
+            });
+        }
+        lines.into()
+    }
+}
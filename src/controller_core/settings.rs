@@ -0,0 +1,57 @@
+use iced::Theme;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io;
+
+/// File the controller preferences are kept in between runs.
+const SETTINGS_PATH: &str = "controller.toml";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemeChoice {
+    #[default]
+    Light,
+    Dark,
+}
+
+impl ThemeChoice {
+    pub const ALL: [ThemeChoice; 2] = [ThemeChoice::Light, ThemeChoice::Dark];
+
+    pub fn theme(self) -> Theme {
+        match self {
+            ThemeChoice::Light => Theme::Light,
+            ThemeChoice::Dark => Theme::Dark,
+        }
+    }
+}
+
+impl Display for ThemeChoice {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ThemeChoice::Light => write!(f, "Light"),
+            ThemeChoice::Dark => write!(f, "Dark"),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub theme: ThemeChoice,
+}
+
+impl Settings {
+    /// Reads the saved settings, falling back to the defaults if there are none.
+    pub fn load() -> Self {
+        fs::read_to_string(SETTINGS_PATH)
+            .ok()
+            .and_then(|content| toml::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let content = toml::to_string(self).map_err(io::Error::other)?;
+        fs::write(SETTINGS_PATH, content)
+    }
+}
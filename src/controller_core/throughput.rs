@@ -0,0 +1,168 @@
+use super::events::ControllerEvent;
+use iced::alignment::{Horizontal, Vertical};
+use iced::mouse::Cursor;
+use iced::widget::canvas::{Frame, Geometry, Path, Program, Stroke, Text};
+use iced::{Color, Pixels, Point, Rectangle, Renderer, Theme};
+use std::collections::VecDeque;
+use std::time::Instant;
+use wg_2024::controller::DroneEvent;
+
+/// Seconds of history shown by the chart.
+const WINDOW_SECONDS: u64 = 60;
+const SENT_COLOR: Color = Color::from_rgb(0.2, 0.7, 0.3);
+const DROPPED_COLOR: Color = Color::from_rgb(0.85, 0.2, 0.2);
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Sample {
+    second: u64,
+    sent: u32,
+    dropped: u32,
+}
+
+/// Packets sent and dropped by the drones in each of the last [`WINDOW_SECONDS`] seconds.
+pub struct Throughput {
+    started: Instant,
+    samples: VecDeque<Sample>,
+}
+
+impl Default for Throughput {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            samples: VecDeque::new(),
+        }
+    }
+}
+
+impl Throughput {
+    pub fn record(&mut self, event: &ControllerEvent) {
+        let sample = match event {
+            ControllerEvent::Drone(DroneEvent::PacketSent(_)) => Sample {
+                sent: 1,
+                ..Sample::default()
+            },
+            ControllerEvent::Drone(DroneEvent::PacketDropped(_)) => Sample {
+                dropped: 1,
+                ..Sample::default()
+            },
+            _ => return,
+        };
+        let second = self.current_second();
+        match self.samples.back_mut() {
+            Some(last) if last.second == second => {
+                last.sent += sample.sent;
+                last.dropped += sample.dropped;
+            }
+            _ => self.samples.push_back(Sample { second, ..sample }),
+        }
+        while self
+            .samples
+            .front()
+            .is_some_and(|first| first.second + WINDOW_SECONDS < second)
+        {
+            self.samples.pop_front();
+        }
+    }
+
+    fn current_second(&self) -> u64 {
+        self.started.elapsed().as_secs()
+    }
+
+    /// Sent and dropped packets of every complete second in the window, oldest first.
+    fn rates(&self) -> Vec<(u32, u32)> {
+        let end = self.current_second();
+        let start = end.saturating_sub(WINDOW_SECONDS);
+        (start..end)
+            .map(|second| {
+                self.samples
+                    .iter()
+                    .find(|sample| sample.second == second)
+                    .map_or((0, 0), |sample| (sample.sent, sample.dropped))
+            })
+            .collect()
+    }
+
+    /// Plots packets/s and drops/s over the whole `frame`.
+    pub fn draw_chart(&self, frame: &mut Frame) {
+        let rates = self.rates();
+        let max = rates
+            .iter()
+            .map(|(sent, dropped)| *sent.max(dropped))
+            .max()
+            .unwrap_or(0)
+            .max(1) as f32;
+        let size = frame.size();
+        let step = size.width / WINDOW_SECONDS as f32;
+        let point = |index: usize, value: u32| {
+            Point::new(
+                size.width - (rates.len() - 1 - index) as f32 * step,
+                size.height - value as f32 / max * (size.height - 30.0),
+            )
+        };
+
+        let line = |values: Vec<u32>| {
+            Path::new(|builder| {
+                for (index, value) in values.into_iter().enumerate() {
+                    if index == 0 {
+                        builder.move_to(point(index, value));
+                    } else {
+                        builder.line_to(point(index, value));
+                    }
+                }
+            })
+        };
+        frame.stroke(
+            &line(rates.iter().map(|(sent, _)| *sent).collect()),
+            Stroke::default().with_width(2.0).with_color(SENT_COLOR),
+        );
+        frame.stroke(
+            &line(rates.iter().map(|(_, dropped)| *dropped).collect()),
+            Stroke::default().with_width(2.0).with_color(DROPPED_COLOR),
+        );
+
+        let (sent, dropped) = rates.last().copied().unwrap_or_default();
+        for (index, (label, color)) in [
+            (format!("{sent} packets/s"), SENT_COLOR),
+            (format!("{dropped} drops/s"), DROPPED_COLOR),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            frame.fill_text(Text {
+                content: label,
+                position: Point::new(5.0 + index as f32 * 120.0, 5.0),
+                color,
+                size: Pixels(14.0),
+                horizontal_alignment: Horizontal::Left,
+                vertical_alignment: Vertical::Top,
+                ..Text::default()
+            });
+        }
+        frame.fill_text(Text {
+            content: format!("max {max}/s, last {WINDOW_SECONDS}s"),
+            position: Point::new(size.width - 5.0, 5.0),
+            color: Color::from_rgb(0.5, 0.5, 0.5),
+            size: Pixels(12.0),
+            horizontal_alignment: Horizontal::Right,
+            vertical_alignment: Vertical::Top,
+            ..Text::default()
+        });
+    }
+}
+
+impl<Message> Program<Message> for Throughput {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: Cursor,
+    ) -> Vec<Geometry<Renderer>> {
+        let mut frame = Frame::new(renderer, bounds.size());
+        self.draw_chart(&mut frame);
+        vec![frame.into_geometry()]
+    }
+}
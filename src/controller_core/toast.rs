@@ -0,0 +1,56 @@
+use iced::alignment::{Horizontal, Vertical};
+use iced::widget::{column, container, stack, text};
+use iced::{Element, Length};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+const TOAST_DURATION: Duration = Duration::from_secs(4);
+
+/// Transient notifications explaining why a requested operation did nothing.
+#[derive(Default)]
+pub struct Toasts {
+    toasts: VecDeque<(Instant, String)>,
+}
+
+impl Toasts {
+    pub fn push(&mut self, message: impl Into<String>) {
+        self.toasts.push_back((Instant::now(), message.into()));
+    }
+
+    /// Drops the notifications shown for longer than [`TOAST_DURATION`].
+    pub fn expire(&mut self) {
+        while self
+            .toasts
+            .front()
+            .is_some_and(|(shown, _)| shown.elapsed() >= TOAST_DURATION)
+        {
+            self.toasts.pop_front();
+        }
+    }
+
+    /// Stacks the pending notifications over the bottom right corner of `base`.
+    pub fn view<'a, M: 'a>(&self, base: impl Into<Element<'a, M>>) -> Element<'a, M> {
+        if self.toasts.is_empty() {
+            return base.into();
+        }
+
+        let toasts = column(self.toasts.iter().map(|(_, message)| {
+            container(text(message.clone()))
+                .padding(10)
+                .style(container::rounded_box)
+                .into()
+        }))
+        .spacing(5);
+
+        stack![
+            base.into(),
+            container(toasts)
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .align_x(Horizontal::Right)
+                .align_y(Vertical::Bottom)
+                .padding(20),
+        ]
+        .into()
+    }
+}
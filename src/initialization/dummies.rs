@@ -13,7 +13,10 @@ use wg_2024::{
 };
 
 use crate::{
-    application::simulation_controller_messages::{HostCommand, HostEvent},
+    application::{
+        authentication::HostKeys,
+        simulation_controller_messages::{HostCommand, HostEvent},
+    },
     message::base_message::Message,
 };
 
@@ -167,6 +170,7 @@ impl ClientCreator for DummyHostCreator {
         controller_recv: Receiver<crate::application::simulation_controller_messages::HostCommand>,
         packet_recv: Receiver<Packet>,
         _packet_send: HashMap<NodeId, Sender<Packet>>,
+        _host_keys: &HostKeys,
     ) -> Box<dyn Runnable> {
         Box::new(DummyHost::new(
             id,
@@ -188,6 +192,7 @@ impl ServerCreator for DummyHostCreator {
         controller_recv: Receiver<crate::application::simulation_controller_messages::HostCommand>,
         packet_recv: Receiver<Packet>,
         _packet_send: HashMap<NodeId, Sender<Packet>>,
+        _host_keys: &HostKeys,
     ) -> Box<dyn Runnable> {
         Box::new(DummyHost::new(
             id,
@@ -3,11 +3,14 @@ macro_rules! drone_factories {
     ($($type_name:ty),* $(,)?) => {{
         vec![
             $(
-                Box::new(
-                    |id, csend, crecv, precv, psend, pdr| -> Box<dyn Runnable> {
-                        Box::new(<$type_name>::new(id, csend, crecv, precv, psend, pdr))
-                    }
-                ) as Box<dyn DroneCreatorFunction>
+                (
+                    stringify!($type_name),
+                    Box::new(
+                        |id, csend, crecv, precv, psend, pdr| -> Box<dyn Runnable> {
+                            Box::new(<$type_name>::new(id, csend, crecv, precv, psend, pdr))
+                        }
+                    ) as Box<dyn DroneCreatorFunction>
+                )
             ),*
         ]
     }};
@@ -19,8 +22,11 @@ macro_rules! client_factories {
         vec![
             $(
                 Box::new(
-                    |id, csend, crecv, precv, psend, turn_handler| -> Box<dyn Runnable> {
-                        Box::new(<$type_name>::with_default_behaviour(id, csend, crecv, precv, psend, turn_handler))
+                    |id, csend, crecv, precv, psend, turn_handler, host_keys| -> Box<dyn Runnable> {
+                        Box::new(
+                            <$type_name>::with_default_behaviour(id, csend, crecv, precv, psend, turn_handler)
+                                .with_host_keys(host_keys)
+                        )
                     }
                 ) as Box<dyn ClientCreatorFunction>
             ),*
@@ -34,8 +40,11 @@ macro_rules! server_factories {
         vec![
             $(
                 Box::new(
-                    |id, csend, crecv, precv, psend| -> Box<dyn Runnable> {
-                        Box::new(<$type_name>::with_default_behaviour(id, csend, crecv, precv, psend))
+                    |id, csend, crecv, precv, psend, host_keys| -> Box<dyn Runnable> {
+                        Box::new(
+                            <$type_name>::with_default_behaviour(id, csend, crecv, precv, psend)
+                                .with_host_keys(host_keys)
+                        )
                     }
                 ) as Box<dyn ServerCreatorFunction>
             ),*
@@ -1,9 +1,13 @@
+use crate::application::authentication::HostKeys;
 use crate::application::simulation_controller_messages::{HostCommand, HostEvent};
+use crate::application::topology::node::ApplicationType;
+use crate::client::scripted_client::Scenario;
 use crossbeam_channel::{unbounded, Receiver, Sender};
 use graph::{AdjacencyVecGraph, ReferenceGraph};
+use serde::Deserialize;
 use std::fmt::Display;
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fs,
     thread::{self, JoinHandle},
 };
@@ -31,23 +35,44 @@ pub enum NetworkNode {
     },
     Client {
         command_send: Sender<HostCommand>,
+        application_type: ApplicationType,
     },
     Server {
         command_send: Sender<HostCommand>,
+        application_type: ApplicationType,
     },
 }
 
+impl NetworkNode {
+    pub fn set_application_type(&mut self, new_type: ApplicationType) {
+        if let NetworkNode::Client {
+            application_type, ..
+        }
+        | NetworkNode::Server {
+            application_type, ..
+        } = self
+        {
+            *application_type = new_type;
+        }
+    }
+}
+
 impl Display for NetworkNode {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                NetworkNode::Drone { .. } => "Drone",
-                NetworkNode::Client { .. } => "Client",
-                NetworkNode::Server { .. } => "Server",
-            }
-        )
+        let application = |application_type: &ApplicationType| match application_type {
+            ApplicationType::Chat => "Chat",
+            ApplicationType::Content => "Content",
+            ApplicationType::Unknown | ApplicationType::Unwanted => "",
+        };
+        match self {
+            NetworkNode::Drone { .. } => write!(f, "Drone"),
+            NetworkNode::Client {
+                application_type, ..
+            } => write!(f, "{}Client", application(application_type)),
+            NetworkNode::Server {
+                application_type, ..
+            } => write!(f, "{}Server", application(application_type)),
+        }
     }
 }
 
@@ -80,6 +105,10 @@ where
     pub drone_event_controller_recv: Receiver<DroneEvent>,
     pub packet_senders: HashMap<NodeId, Sender<Packet>>,
     pub handles: ThreadHandles,
+    /// Nodes the controllers must not crash or unlink, listed in the topology file.
+    pub protected_nodes: HashSet<NodeId>,
+    /// The keys the hosts sign their messages with.
+    pub host_keys: HostKeys,
 }
 
 struct ControllerChannels {
@@ -91,6 +120,10 @@ struct ControllerChannels {
 
 pub trait Runnable: Send {
     fn run(&mut self);
+
+    fn application_type(&self) -> ApplicationType {
+        ApplicationType::Unknown
+    }
 }
 
 impl<T: Drone> Runnable for T {
@@ -105,6 +138,16 @@ pub fn start_actual_simulation(
     start_generic_simulation(topology_path)
 }
 
+/// Starts the simulation with clients that play the scenario instead of the card game.
+pub fn start_scripted_simulation(
+    topology_path: &str,
+    scenario: Scenario,
+) -> ControllerInfo<ActualDroneCreator, ActualClientCreator, ActualServerCreator> {
+    start_simulation_with(topology_path, |controller_send| {
+        ActualClientCreator::scripted(controller_send, scenario)
+    })
+}
+
 #[allow(unused)]
 pub fn start_dummy_simulation(
     topology_path: &str,
@@ -113,6 +156,18 @@ pub fn start_dummy_simulation(
 }
 
 fn start_generic_simulation<DC, CC, SC>(topology_path: &str) -> ControllerInfo<DC, CC, SC>
+where
+    DC: DroneCreator,
+    CC: ClientCreator,
+    SC: ServerCreator,
+{
+    start_simulation_with(topology_path, CC::new)
+}
+
+fn start_simulation_with<DC, CC, SC>(
+    topology_path: &str,
+    client_creator: impl FnOnce(Sender<HostEvent>) -> CC,
+) -> ControllerInfo<DC, CC, SC>
 where
     DC: DroneCreator,
     CC: ClientCreator,
@@ -124,7 +179,7 @@ where
     let (host_event_to_controller, host_event_controller_recv) = unbounded();
 
     let drone_creator = DC::new(drone_event_to_controller.clone());
-    let client_creator = CC::new(host_event_to_controller.clone());
+    let client_creator = client_creator(host_event_to_controller.clone());
     let server_creator = SC::new(host_event_to_controller.clone());
 
     let (mut controller_info, runnables) = create_simulation(
@@ -139,6 +194,7 @@ where
     let handles = spawn_threads(runnables);
 
     controller_info.handles = handles;
+    controller_info.protected_nodes = parse_protected_nodes(topology_path);
 
     controller_info
 }
@@ -151,7 +207,7 @@ pub fn create_simulation<DC: DroneCreator, CC: ClientCreator, SC: ServerCreator>
     drone_event_controller_recv: Receiver<DroneEvent>,
     host_event_controller_recv: Receiver<HostEvent>,
 ) -> (ControllerInfo<DC, CC, SC>, Runnables) {
-    let (network_graph, mut controller_channels) = create_topology_graph(
+    let (mut network_graph, mut controller_channels) = create_topology_graph(
         config,
         drone_event_controller_recv,
         host_event_controller_recv,
@@ -159,14 +215,16 @@ pub fn create_simulation<DC: DroneCreator, CC: ClientCreator, SC: ServerCreator>
 
     let (packet_senders, packet_receivers) = create_packet_channels(&network_graph);
 
+    let host_keys = HostKeys::default();
     let runnables = create_runnables(
-        &network_graph,
+        &mut network_graph,
         &packet_senders,
         packet_receivers,
         &mut controller_channels,
         &mut drone_creator,
         &mut client_creator,
         &mut server_creator,
+        &host_keys,
     );
 
     (
@@ -179,6 +237,8 @@ pub fn create_simulation<DC: DroneCreator, CC: ClientCreator, SC: ServerCreator>
             host_event_controller_recv: controller_channels.host_event_controller_recv,
             packet_senders,
             handles: HashMap::new(),
+            protected_nodes: HashSet::new(),
+            host_keys,
         },
         runnables,
     )
@@ -190,6 +250,32 @@ pub fn parse_topology_file(path: &str) -> Config {
     config
 }
 
+/// The optional `[controller]` table of a topology file, ignored by the network itself:
+///
+/// ```toml
+/// [controller]
+/// protected = [1, 2]
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ControllerSection {
+    protected: Vec<NodeId>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct TopologyControllerSettings {
+    controller: ControllerSection,
+}
+
+pub fn parse_protected_nodes(path: &str) -> HashSet<NodeId> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|config_data| toml::from_str::<TopologyControllerSettings>(&config_data).ok())
+        .map(|settings| settings.controller.protected.into_iter().collect())
+        .unwrap_or_default()
+}
+
 fn create_topology_graph(
     config: &Config,
     drone_event_controller_recv: Receiver<DroneEvent>,
@@ -241,6 +327,7 @@ fn add_nodes(
             client.id,
             NetworkNode::Client {
                 command_send: client_command_send,
+                application_type: ApplicationType::Unknown,
             },
         );
     }
@@ -252,6 +339,7 @@ fn add_nodes(
             server.id,
             NetworkNode::Server {
                 command_send: server_command_send,
+                application_type: ApplicationType::Unknown,
             },
         );
     }
@@ -295,16 +383,25 @@ fn create_packet_channels<T>(
 }
 
 fn create_runnables(
-    graph: &NetworkGraph,
+    graph: &mut NetworkGraph,
     packet_senders: &HashMap<NodeId, Sender<Packet>>,
     mut packet_receivers: HashMap<NodeId, Receiver<Packet>>,
     controller_channels: &mut ControllerChannels,
     drone_creator: &mut impl DroneCreator,
     client_creator: &mut impl ClientCreator,
     server_creator: &mut impl ServerCreator,
+    host_keys: &HostKeys,
 ) -> Runnables {
     let mut runnables = HashMap::new();
 
+    // every host is issued its key before any starts, so that none misses the keys of the
+    // others
+    for (node_id, node_value) in graph.iter() {
+        if !matches!(node_value, NetworkNode::Drone { .. }) {
+            host_keys.issue(*node_id);
+        }
+    }
+
     for (node_id, node_value) in graph.iter() {
         let packet_recv = packet_receivers.remove(node_id).unwrap();
         let packet_send = find_packet_send(graph.adjacents(node_id), packet_senders);
@@ -328,6 +425,7 @@ fn create_runnables(
                     .unwrap(),
                 packet_recv,
                 packet_send,
+                host_keys,
             ),
             NetworkNode::Server { .. } => server_creator.create_server(
                 *node_id,
@@ -337,12 +435,19 @@ fn create_runnables(
                     .unwrap(),
                 packet_recv,
                 packet_send,
+                host_keys,
             ),
         };
 
         runnables.insert(*node_id, runnable);
     }
 
+    for (node_id, runnable) in runnables.iter() {
+        if let Some(node) = graph.get_mut(node_id) {
+            node.set_application_type(runnable.application_type());
+        }
+    }
+
     runnables
 }
 
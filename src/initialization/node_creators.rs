@@ -1,12 +1,22 @@
 use super::network_initializer::Runnable;
 use crate::{
     application::{
+        authentication::HostKeys,
         simulation_controller_messages::{HostCommand, HostEvent},
         turn_handler::{self, TurnHandlerArc},
     },
-    client::{web_browser::WebBrowser, ChatClient},
+    client::{
+        chat_client::ChatClientBehaviour,
+        scripted_client::{Scenario, ScriptedBehaviour, ScriptedClient},
+        stress_client::StressClient,
+        web_browser::{WebBrowser, WebBrowserBehaviour},
+        ChatClient,
+    },
     client_factories, drone_factories,
-    server::{chat_server::ChatServer, media_server::MediaServer, text_server::TextServer},
+    server::{
+        chat_server::ChatServer, echo_server::EchoServer, file_storage_server::FileStorageServer,
+        media_server::MediaServer, text_server::TextServer,
+    },
     server_factories,
 };
 use crossbeam_channel::{Receiver, Sender};
@@ -101,6 +111,7 @@ pub trait ClientCreatorFunction {
         packet_recv: Receiver<Packet>,
         packet_send: HashMap<NodeId, Sender<Packet>>,
         turn_handler: TurnHandlerArc,
+        host_keys: HostKeys,
     ) -> Box<dyn Runnable>;
 }
 
@@ -113,6 +124,7 @@ where
         Receiver<Packet>,
         HashMap<NodeId, Sender<Packet>>,
         TurnHandlerArc,
+        HostKeys,
     ) -> Box<dyn Runnable>,
 {
     fn create_client(
@@ -123,6 +135,7 @@ where
         packet_recv: Receiver<Packet>,
         packet_send: HashMap<NodeId, Sender<Packet>>,
         turn_handler: TurnHandlerArc,
+        host_keys: HostKeys,
     ) -> Box<dyn Runnable> {
         self(
             id,
@@ -131,6 +144,7 @@ where
             packet_recv,
             packet_send,
             turn_handler,
+            host_keys,
         )
     }
 }
@@ -144,6 +158,7 @@ impl ClientCreatorFunction for Box<dyn ClientCreatorFunction> {
         packet_recv: Receiver<Packet>,
         packet_send: HashMap<NodeId, Sender<Packet>>,
         turn_handler: TurnHandlerArc,
+        host_keys: HostKeys,
     ) -> Box<dyn Runnable> {
         self.as_mut().create_client(
             id,
@@ -152,6 +167,7 @@ impl ClientCreatorFunction for Box<dyn ClientCreatorFunction> {
             packet_recv,
             packet_send,
             turn_handler,
+            host_keys,
         )
     }
 }
@@ -164,6 +180,7 @@ pub trait ServerCreatorFunction {
         controller_recv: Receiver<HostCommand>,
         packet_recv: Receiver<Packet>,
         packet_send: HashMap<NodeId, Sender<Packet>>,
+        host_keys: HostKeys,
     ) -> Box<dyn Runnable>;
 }
 
@@ -175,6 +192,7 @@ where
         Receiver<HostCommand>,
         Receiver<Packet>,
         HashMap<NodeId, Sender<Packet>>,
+        HostKeys,
     ) -> Box<dyn Runnable>,
 {
     fn create_server(
@@ -184,6 +202,7 @@ where
         controller_recv: Receiver<HostCommand>,
         packet_recv: Receiver<Packet>,
         packet_send: HashMap<NodeId, Sender<Packet>>,
+        host_keys: HostKeys,
     ) -> Box<dyn Runnable> {
         self(
             id,
@@ -191,6 +210,7 @@ where
             controller_recv,
             packet_recv,
             packet_send,
+            host_keys,
         )
     }
 }
@@ -203,6 +223,7 @@ impl ServerCreatorFunction for Box<dyn ServerCreatorFunction> {
         controller_recv: Receiver<HostCommand>,
         packet_recv: Receiver<Packet>,
         packet_send: HashMap<NodeId, Sender<Packet>>,
+        host_keys: HostKeys,
     ) -> Box<dyn Runnable> {
         self.as_mut().create_server(
             id,
@@ -210,6 +231,7 @@ impl ServerCreatorFunction for Box<dyn ServerCreatorFunction> {
             controller_recv,
             packet_recv,
             packet_send,
+            host_keys,
         )
     }
 }
@@ -226,26 +248,33 @@ pub trait DroneCreator {
         pdr: f32,
     ) -> Box<dyn Runnable>;
 
-    fn create_disconnected_drone(
-        &mut self,
-        id: NodeId,
-        controller_recv: Receiver<DroneCommand>,
-        packet_recv: Receiver<Packet>,
-        pdr: f32,
-    ) -> Box<dyn Runnable> {
-        self.create_drone(id, controller_recv, packet_recv, HashMap::new(), pdr)
+    /// Names of the drone implementations the next drone can be picked from.
+    fn implementations(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+
+    /// Makes the next drone be created by `implementation`, returning whether it exists.
+    fn select_implementation(&mut self, _implementation: &str) -> bool {
+        false
+    }
+
+    /// Name of the implementation the drone was created by, if known.
+    fn implementation_of(&self, _id: NodeId) -> Option<&'static str> {
+        None
     }
 }
 
 pub struct ActualDroneCreator {
-    factories: Vec<Box<dyn DroneCreatorFunction>>,
+    factories: Vec<(&'static str, Box<dyn DroneCreatorFunction>)>,
     index: usize,
     controller_send: Sender<DroneEvent>,
+    /// The implementation every drone was created by.
+    created: HashMap<NodeId, &'static str>,
 }
 
 impl ActualDroneCreator {
     pub fn current_factory_mut(&mut self) -> &mut Box<dyn DroneCreatorFunction> {
-        &mut self.factories[self.index]
+        &mut self.factories[self.index].1
     }
 }
 
@@ -266,6 +295,7 @@ impl DroneCreator for ActualDroneCreator {
             ),
             index: 0,
             controller_send,
+            created: HashMap::new(),
         }
     }
     fn create_drone(
@@ -285,9 +315,32 @@ impl DroneCreator for ActualDroneCreator {
             packet_send,
             pdr,
         );
+        self.created.insert(id, self.factories[self.index].0);
         self.index = (self.index + 1) % self.factories.len();
         drone
     }
+
+    fn implementations(&self) -> Vec<&'static str> {
+        self.factories.iter().map(|(name, _)| *name).collect()
+    }
+
+    fn select_implementation(&mut self, implementation: &str) -> bool {
+        match self
+            .factories
+            .iter()
+            .position(|(name, _)| *name == implementation)
+        {
+            Some(index) => {
+                self.index = index;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn implementation_of(&self, id: NodeId) -> Option<&'static str> {
+        self.created.get(&id).copied()
+    }
 }
 
 pub trait ClientCreator {
@@ -298,16 +351,8 @@ pub trait ClientCreator {
         controller_recv: Receiver<HostCommand>,
         packet_recv: Receiver<Packet>,
         packet_send: HashMap<NodeId, Sender<Packet>>,
+        host_keys: &HostKeys,
     ) -> Box<dyn Runnable>;
-
-    fn create_disconnected_client(
-        &mut self,
-        id: NodeId,
-        controller_recv: Receiver<HostCommand>,
-        packet_recv: Receiver<Packet>,
-    ) -> Box<dyn Runnable> {
-        self.create_client(id, controller_recv, packet_recv, HashMap::new())
-    }
 }
 
 pub struct ActualClientCreator {
@@ -321,12 +366,71 @@ impl ActualClientCreator {
     pub fn current_factory_mut(&mut self) -> &mut Box<dyn ClientCreatorFunction> {
         &mut self.factories[self.index]
     }
+
+    /// Creates clients that play the scenario instead of the card game.
+    pub fn scripted(controller_send: Sender<HostEvent>, scenario: Scenario) -> Self {
+        let chat_scenario = scenario.clone();
+        let factories: Vec<Box<dyn ClientCreatorFunction>> = vec![
+            Box::new(
+                move |id,
+                      csend,
+                      crecv,
+                      precv,
+                      psend,
+                      turn_handler,
+                      host_keys|
+                      -> Box<dyn Runnable> {
+                    Box::new(
+                        ScriptedClient::new(
+                            id,
+                            csend,
+                            crecv,
+                            precv,
+                            psend,
+                            turn_handler,
+                            ScriptedBehaviour::<ChatClientBehaviour>::new(chat_scenario.clone()),
+                        )
+                        .with_host_keys(host_keys),
+                    )
+                },
+            ),
+            Box::new(
+                move |id,
+                      csend,
+                      crecv,
+                      precv,
+                      psend,
+                      turn_handler,
+                      host_keys|
+                      -> Box<dyn Runnable> {
+                    Box::new(
+                        ScriptedClient::new(
+                            id,
+                            csend,
+                            crecv,
+                            precv,
+                            psend,
+                            turn_handler,
+                            ScriptedBehaviour::<WebBrowserBehaviour>::new(scenario.clone()),
+                        )
+                        .with_host_keys(host_keys),
+                    )
+                },
+            ),
+        ];
+        Self {
+            factories,
+            index: 0,
+            controller_send,
+            turn_handler: turn_handler::create_turn_handler(),
+        }
+    }
 }
 
 impl ClientCreator for ActualClientCreator {
     fn new(controller_send: Sender<HostEvent>) -> Self {
         Self {
-            factories: client_factories!(ChatClient, WebBrowser),
+            factories: client_factories!(ChatClient, WebBrowser, StressClient),
             index: 0,
             controller_send,
             turn_handler: turn_handler::create_turn_handler(),
@@ -338,6 +442,7 @@ impl ClientCreator for ActualClientCreator {
         controller_recv: Receiver<HostCommand>,
         packet_recv: Receiver<Packet>,
         packet_send: HashMap<NodeId, Sender<Packet>>,
+        host_keys: &HostKeys,
     ) -> Box<dyn Runnable> {
         let controller_send = self.controller_send.clone();
         let turn_handler = self.turn_handler.clone();
@@ -348,6 +453,7 @@ impl ClientCreator for ActualClientCreator {
             packet_recv,
             packet_send,
             turn_handler,
+            host_keys.clone(),
         );
         self.index = (self.index + 1) % self.factories.len();
         client
@@ -363,16 +469,8 @@ pub trait ServerCreator {
         controller_recv: Receiver<HostCommand>,
         packet_recv: Receiver<Packet>,
         packet_send: HashMap<NodeId, Sender<Packet>>,
+        host_keys: &HostKeys,
     ) -> Box<dyn Runnable>;
-
-    fn create_disconnected_server(
-        &mut self,
-        id: NodeId,
-        controller_recv: Receiver<HostCommand>,
-        packet_recv: Receiver<Packet>,
-    ) -> Box<dyn Runnable> {
-        self.create_server(id, controller_recv, packet_recv, HashMap::new())
-    }
 }
 
 pub struct ActualServerCreator {
@@ -390,7 +488,13 @@ impl ActualServerCreator {
 impl ServerCreator for ActualServerCreator {
     fn new(controller_send: Sender<HostEvent>) -> Self {
         Self {
-            factories: server_factories!(ChatServer, TextServer, MediaServer),
+            factories: server_factories!(
+                ChatServer,
+                TextServer,
+                MediaServer,
+                FileStorageServer,
+                EchoServer
+            ),
             index: 0,
             controller_send,
         }
@@ -401,6 +505,7 @@ impl ServerCreator for ActualServerCreator {
         controller_recv: Receiver<HostCommand>,
         packet_recv: Receiver<Packet>,
         packet_send: HashMap<NodeId, Sender<Packet>>,
+        host_keys: &HostKeys,
     ) -> Box<dyn Runnable> {
         let controller_send = self.controller_send.clone();
         let client = self.current_factory_mut().create_server(
@@ -409,6 +514,7 @@ impl ServerCreator for ActualServerCreator {
             controller_recv,
             packet_recv,
             packet_send,
+            host_keys.clone(),
         );
         self.index = (self.index + 1) % self.factories.len();
         client
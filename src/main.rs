@@ -1,5 +1,6 @@
 mod application;
 mod client;
+mod controller_core;
 mod initialization;
 mod message;
 mod server;
@@ -8,6 +9,7 @@ mod simulation_controller_pilli;
 mod tester;
 use std::{env, str::FromStr};
 
+use client::scripted_client::Scenario;
 use initialization::network_initializer::*;
 
 #[derive(Debug, Clone, Copy)]
@@ -81,8 +83,10 @@ fn main() {
         2 => match args[1].parse() {
             Ok(sim_type) => sim_type,
             Err(sim_type) => {
-                println!("Invalid simulation controller type, defaulting to None");
-                println!("Available simulation controllers: --pilli, --shrimp");
+                if args[1].starts_with("--") {
+                    println!("Invalid simulation controller type, defaulting to None");
+                    println!("Available simulation controllers: --pilli, --shrimp");
+                }
                 sim_type
             }
         },
@@ -107,14 +111,22 @@ fn main() {
                     top
                 }))
                 .unwrap_or(Topology::DoubleChain);
-            start_without_simulation_controller(topology);
+            start_without_simulation_controller(topology, args.get(2));
         }
     }
 }
 
-fn start_without_simulation_controller(topology: Topology) {
+/// Runs the topology headless, with the clients playing the scenario file if one is given.
+fn start_without_simulation_controller(topology: Topology, scenario: Option<&String>) {
     println!("{}", topology.to_path());
-    let info = start_actual_simulation(topology.to_path());
+    let info = match scenario.map(|path| Scenario::load(path)) {
+        Some(Ok(scenario)) => start_scripted_simulation(topology.to_path(), scenario),
+        Some(Err(err)) => {
+            println!("Invalid scenario, {err}");
+            return;
+        }
+        None => start_actual_simulation(topology.to_path()),
+    };
 
     for handle in info.handles.into_values() {
         handle.join().unwrap();
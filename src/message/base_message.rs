@@ -2,17 +2,56 @@ use std::fmt::Display;
 
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use serde_json::Error;
 use wg_2024::network::NodeId;
 
+use crate::application::topology::node::ApplicationType;
+use crate::message::error_response::ErrorResponse;
+use crate::message::server_stats::ServerStats;
+
+/// The version of the protocol the hosts speak, raised whenever a message changes so that the
+/// hosts built before tell it apart.
+pub const PROTOCOL_VERSION: u16 = 3;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Message<M: MessageContent> {
+    /// The [`PROTOCOL_VERSION`] of the sender, first so that a [`MessageHeader`] reads it
+    /// whatever the content.
+    pub version: u16,
     pub source_id: NodeId,
     pub destination_id: NodeId,
     pub session_id: u64,
+    pub priority: Priority,
+    /// The exchange the message is a step of, if it is one: the requests a client sends one
+    /// after the other to get something done, each in a session of its own, and their
+    /// responses.
+    pub exchange_id: Option<u64>,
     pub content: M,
 }
 
+/// How urgently a message should get through, so that small interactive messages are not
+/// held up behind bulk transfers sharing a host.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub enum Priority {
+    /// Transfers of whole media and files.
+    Low,
+    #[default]
+    Normal,
+    /// Chats, which the users wait on as they type.
+    High,
+}
+
+impl Display for Priority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Priority::Low => write!(f, "Low"),
+            Priority::Normal => write!(f, "Normal"),
+            Priority::High => write!(f, "High"),
+        }
+    }
+}
+
 impl<M: MessageContent + Display> Display for Message<M> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -23,24 +62,15 @@ impl<M: MessageContent + Display> Display for Message<M> {
     }
 }
 
-impl<M: MessageContent + Serialize> Message<M> {
-    pub fn serialize(&self) -> String {
-        serde_json::to_string(self).unwrap()
-    }
-}
-
-impl<M: MessageContent + DeserializeOwned> Message<M> {
-    pub fn deserialize(serialized: String) -> Result<Self, Error> {
-        serde_json::from_str(serialized.as_str())
-    }
-}
-
 impl<M: MessageContent> Message<M> {
     pub fn new(source_id: NodeId, destination_id: NodeId, session_id: u64, content: M) -> Self {
         Message {
+            version: PROTOCOL_VERSION,
             source_id,
             destination_id,
             session_id,
+            priority: Priority::default(),
+            exchange_id: None,
             content,
         }
     }
@@ -50,9 +80,12 @@ impl<M: MessageContent> Message<M> {
         M: Request,
     {
         Message {
+            version: PROTOCOL_VERSION,
             source_id: self.destination_id,
             destination_id: self.source_id,
             session_id: self.session_id,
+            priority: self.priority,
+            exchange_id: self.exchange_id,
             content,
         }
     }
@@ -72,7 +105,65 @@ impl<M: MessageContent> Message<M> {
 
 impl MessageContent for String {}
 
+/// The start of a [`Message`], read before the rest to check that the sender speaks the same
+/// version of the protocol.
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct MessageHeader {
+    /// 0 for the hosts built before the messages had a version.
+    #[serde(default)]
+    pub version: u16,
+}
+
+/// What a server provides, as it answers when asked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ServerType {
+    Chat,
+    Text,
+    Media,
+    Storage,
+    Echo,
+}
+
+impl ServerType {
+    pub fn application_type(self) -> ApplicationType {
+        match self {
+            ServerType::Chat => ApplicationType::Chat,
+            ServerType::Text | ServerType::Media | ServerType::Storage | ServerType::Echo => {
+                ApplicationType::Content
+            }
+        }
+    }
+}
+
+impl Display for ServerType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ServerType::Chat => write!(f, "Chat"),
+            ServerType::Text => write!(f, "Text"),
+            ServerType::Media => write!(f, "Media"),
+            ServerType::Storage => write!(f, "Storage"),
+            ServerType::Echo => write!(f, "Echo"),
+        }
+    }
+}
+
 pub trait MessageContent {}
 
-pub trait Request: Send + MessageContent + Serialize + DeserializeOwned {}
-pub trait Response: Send + MessageContent + Serialize + DeserializeOwned {}
+pub trait Request: Send + MessageContent + Serialize + DeserializeOwned {
+    /// What the request asks for, without its arguments, as the access logs count it.
+    fn kind(&self) -> &'static str;
+    /// The request asking a server for its [`ServerStats`], which every server answers itself
+    /// whatever its behaviour.
+    fn server_stats() -> Self;
+    fn is_server_stats(&self) -> bool;
+    /// The priority the request and its response are sent with.
+    fn priority(&self) -> Priority {
+        Priority::Normal
+    }
+}
+pub trait Response: Send + MessageContent + Serialize + DeserializeOwned {
+    fn server_stats(stats: ServerStats) -> Self;
+    fn as_server_stats(&self) -> Option<&ServerStats>;
+    /// The error the server answered with, if the request was turned down.
+    fn as_error(&self) -> Option<&ErrorResponse>;
+}
@@ -1,23 +1,62 @@
-use crate::message::base_message::{MessageContent, Request, Response};
+use crate::message::base_message::{MessageContent, Priority, Request, Response};
+use crate::message::error_response::ErrorResponse;
+use crate::message::page::{Page, PageRequest};
+use crate::message::server_stats::ServerStats;
 use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ChatRequest {
-    ClientList,
+    ClientList(PageRequest),
     Register(String),
+    /// Frees a username registered by the client.
+    Unregister(String),
+    /// Moves a registration of the client to a free username.
+    Rename {
+        from: String,
+        to: String,
+    },
+    /// Tells the server the client is still there, so its registrations do not expire.
+    KeepAlive(String),
     SendMessage {
         from: String,
         to: String,
         message: String,
     },
+    /// Asks the server to tell the client about the users registering from now on.
+    Subscribe,
+    /// Opens a group with `username` as its first member.
+    CreateGroup {
+        group: String,
+        username: String,
+    },
+    JoinGroup {
+        group: String,
+        username: String,
+    },
+    /// A message for every other member of the group.
+    SendToGroup {
+        from: String,
+        group: String,
+        message: String,
+    },
+    /// Asks the server for the messages it passed on to the client after the one with id
+    /// `since`, to catch up on the ones lost on the way.
+    History {
+        since: u64,
+    },
+    /// Asks the server for the requests it handled.
+    ServerStats,
 }
 
 impl Display for ChatRequest {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            ChatRequest::ClientList => write!(f, "ClientList"),
+            ChatRequest::ClientList(page) => write!(f, "ClientList({})", page),
             ChatRequest::Register(name) => write!(f, "Register({})", name),
+            ChatRequest::Unregister(name) => write!(f, "Unregister({})", name),
+            ChatRequest::Rename { from, to } => write!(f, "Rename(from: {}, to: {})", from, to),
+            ChatRequest::KeepAlive(name) => write!(f, "KeepAlive({})", name),
             ChatRequest::SendMessage { from, to, message } => {
                 write!(
                     f,
@@ -25,29 +64,200 @@ impl Display for ChatRequest {
                     from, to, message
                 )
             }
+            ChatRequest::Subscribe => write!(f, "Subscribe"),
+            ChatRequest::History { since } => write!(f, "History(since: {})", since),
+            ChatRequest::ServerStats => write!(f, "ServerStats"),
+            ChatRequest::CreateGroup { group, username } => {
+                write!(f, "CreateGroup(group: {}, username: {})", group, username)
+            }
+            ChatRequest::JoinGroup { group, username } => {
+                write!(f, "JoinGroup(group: {}, username: {})", group, username)
+            }
+            ChatRequest::SendToGroup {
+                from,
+                group,
+                message,
+            } => write!(
+                f,
+                "SendToGroup(from: {}, group: {}, message: {})",
+                from, group, message
+            ),
         }
     }
 }
 impl MessageContent for ChatRequest {}
-impl Request for ChatRequest {}
+impl Request for ChatRequest {
+    fn kind(&self) -> &'static str {
+        match self {
+            ChatRequest::ClientList(_) => "ClientList",
+            ChatRequest::Register(_) => "Register",
+            ChatRequest::Unregister(_) => "Unregister",
+            ChatRequest::Rename { .. } => "Rename",
+            ChatRequest::KeepAlive(_) => "KeepAlive",
+            ChatRequest::SendMessage { .. } => "SendMessage",
+            ChatRequest::Subscribe => "Subscribe",
+            ChatRequest::CreateGroup { .. } => "CreateGroup",
+            ChatRequest::JoinGroup { .. } => "JoinGroup",
+            ChatRequest::SendToGroup { .. } => "SendToGroup",
+            ChatRequest::History { .. } => "History",
+            ChatRequest::ServerStats => "ServerStats",
+        }
+    }
+
+    fn server_stats() -> Self {
+        ChatRequest::ServerStats
+    }
+
+    fn is_server_stats(&self) -> bool {
+        matches!(self, ChatRequest::ServerStats)
+    }
+
+    fn priority(&self) -> Priority {
+        match self {
+            ChatRequest::SendMessage { .. } | ChatRequest::SendToGroup { .. } => Priority::High,
+            _ => Priority::Normal,
+        }
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ChatResponse {
-    ClientList(Vec<String>),
-    MessageFrom { from: String, message: String },
-    DestinationNotFound,
+    ClientList(Page<String>),
+    /// The username is the client's, the others registered being `clients`.
+    Registered {
+        username: String,
+        clients: Vec<String>,
+    },
+    Unregistered(String),
+    Renamed {
+        from: String,
+        to: String,
+    },
+    /// Answers a keepalive, the username being still registered.
+    Alive,
+    /// A message for the client. Its id is one more than the one of the message the server
+    /// passed on to the client before, so that the client notices the ones it missed.
+    MessageFrom {
+        id: u64,
+        /// Seconds since the Unix epoch, when the server received the message.
+        timestamp: u64,
+        from: String,
+        message: String,
+    },
+    /// The server passed the message on to the client registered as `to`.
+    MessageForwarded {
+        to: String,
+    },
+    /// The server will tell the client about the users registering.
+    Subscribed,
+    /// Sent by the server without a request, to the clients that subscribed.
+    UserJoined(String),
+    /// The client is in the group, along with `members`.
+    GroupJoined {
+        group: String,
+        members: Vec<String>,
+    },
+    /// A message sent to a group the client is in, numbered like [`ChatResponse::MessageFrom`].
+    GroupMessage {
+        id: u64,
+        timestamp: u64,
+        group: String,
+        from: String,
+        message: String,
+    },
+    /// The server passed the message on to the other `recipients` members of the group.
+    GroupMessageSent {
+        group: String,
+        recipients: usize,
+    },
+    /// The [`ChatResponse::MessageFrom`] and [`ChatResponse::GroupMessage`] the server still
+    /// keeps among those the history asked for, oldest first.
+    History(Vec<ChatResponse>),
+    ServerStats(ServerStats),
+    Error(ErrorResponse),
+}
+
+impl ChatResponse {
+    /// The id the server gave the message it passed on, if this is one.
+    pub fn message_id(&self) -> Option<u64> {
+        match self {
+            ChatResponse::MessageFrom { id, .. } | ChatResponse::GroupMessage { id, .. } => {
+                Some(*id)
+            }
+            _ => None,
+        }
+    }
 }
 
 impl Display for ChatResponse {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            ChatResponse::ClientList(clients) => write!(f, "ClientList({:?})", clients),
-            ChatResponse::MessageFrom { from, message } => {
-                write!(f, "MessageFrom(from: {}, message: {})", from, message)
+            ChatResponse::ClientList(clients) => write!(f, "ClientList({})", clients),
+            ChatResponse::Registered { username, clients } => write!(
+                f,
+                "Registered(username: {}, clients: {:?})",
+                username, clients
+            ),
+            ChatResponse::Unregistered(name) => write!(f, "Unregistered({})", name),
+            ChatResponse::Renamed { from, to } => write!(f, "Renamed(from: {}, to: {})", from, to),
+            ChatResponse::Alive => write!(f, "Alive"),
+            ChatResponse::MessageFrom {
+                id,
+                timestamp,
+                from,
+                message,
+            } => write!(
+                f,
+                "MessageFrom(id: {}, at: {}, from: {}, message: {})",
+                id, timestamp, from, message
+            ),
+            ChatResponse::MessageForwarded { to } => write!(f, "MessageForwarded(to: {})", to),
+            ChatResponse::Subscribed => write!(f, "Subscribed"),
+            ChatResponse::UserJoined(username) => write!(f, "UserJoined({})", username),
+            ChatResponse::GroupJoined { group, members } => {
+                write!(f, "GroupJoined(group: {}, members: {:?})", group, members)
             }
-            ChatResponse::DestinationNotFound => write!(f, "DestinationNotFound"),
+            ChatResponse::GroupMessage {
+                id,
+                timestamp,
+                group,
+                from,
+                message,
+            } => write!(
+                f,
+                "GroupMessage(id: {}, at: {}, group: {}, from: {}, message: {})",
+                id, timestamp, group, from, message
+            ),
+            ChatResponse::GroupMessageSent { group, recipients } => write!(
+                f,
+                "GroupMessageSent(group: {}, recipients: {})",
+                group, recipients
+            ),
+            ChatResponse::History(messages) => write!(f, "History({} messages)", messages.len()),
+            ChatResponse::ServerStats(stats) => {
+                write!(f, "ServerStats({} requests)", stats.requests)
+            }
+            ChatResponse::Error(error) => write!(f, "Error({})", error),
         }
     }
 }
 impl MessageContent for ChatResponse {}
-impl Response for ChatResponse {}
+impl Response for ChatResponse {
+    fn server_stats(stats: ServerStats) -> Self {
+        ChatResponse::ServerStats(stats)
+    }
+
+    fn as_server_stats(&self) -> Option<&ServerStats> {
+        match self {
+            ChatResponse::ServerStats(stats) => Some(stats),
+            _ => None,
+        }
+    }
+
+    fn as_error(&self) -> Option<&ErrorResponse> {
+        match self {
+            ChatResponse::Error(error) => Some(error),
+            _ => None,
+        }
+    }
+}
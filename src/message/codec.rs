@@ -0,0 +1,87 @@
+use std::fmt::Display;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// A way of turning messages into bytes and back.
+pub trait Codec {
+    /// Written before every encoded message, so that the receiver knows how to decode it.
+    const TAG: u8;
+    fn encode<T: Serialize>(value: &T) -> Vec<u8>;
+    fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, CodecError>;
+}
+
+/// Human readable, kept as the default for debuggability.
+pub struct Json;
+
+impl Codec for Json {
+    const TAG: u8 = b'j';
+    fn encode<T: Serialize>(value: &T) -> Vec<u8> {
+        serde_json::to_vec(value).unwrap()
+    }
+    fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, CodecError> {
+        serde_json::from_slice(bytes).map_err(CodecError::Json)
+    }
+}
+
+/// Compact, stores bytes as they are instead of as lists of numbers.
+pub struct Bincode;
+
+impl Codec for Bincode {
+    const TAG: u8 = b'b';
+    fn encode<T: Serialize>(value: &T) -> Vec<u8> {
+        bincode::serialize(value).unwrap()
+    }
+    fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, CodecError> {
+        bincode::deserialize(bytes).map_err(CodecError::Bincode)
+    }
+}
+
+/// The codec a host encodes its messages with. Any host decodes messages of every codec.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MessageCodec {
+    #[default]
+    Json,
+    Bincode,
+}
+
+impl MessageCodec {
+    /// Encodes the value, prefixed by the tag of the codec.
+    pub fn encode<T: Serialize>(self, value: &T) -> Vec<u8> {
+        let (tag, mut encoded) = match self {
+            MessageCodec::Json => (Json::TAG, Json::encode(value)),
+            MessageCodec::Bincode => (Bincode::TAG, Bincode::encode(value)),
+        };
+        encoded.insert(0, tag);
+        encoded
+    }
+
+    /// Decodes a value with the codec named by its tag.
+    pub fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, CodecError> {
+        match bytes.split_first() {
+            Some((&Json::TAG, encoded)) => Json::decode(encoded),
+            Some((&Bincode::TAG, encoded)) => Bincode::decode(encoded),
+            Some((tag, _)) => Err(CodecError::UnknownCodec(*tag)),
+            None => Err(CodecError::Empty),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum CodecError {
+    Empty,
+    UnknownCodec(u8),
+    Json(serde_json::Error),
+    Bincode(bincode::Error),
+}
+
+impl Display for CodecError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CodecError::Empty => write!(f, "nothing to decode"),
+            CodecError::UnknownCodec(tag) => write!(f, "unknown codec {tag:#04x}"),
+            CodecError::Json(err) => write!(f, "{err}"),
+            CodecError::Bincode(err) => write!(f, "{err}"),
+        }
+    }
+}
@@ -0,0 +1,280 @@
+use crate::message::base_message::{Message, Request};
+use crate::message::chat_message::{ChatRequest, ChatResponse};
+use crate::message::codec::{Bincode, Codec, Json};
+use crate::message::content_message::{ContentRequest, ContentResponse};
+use crate::message::discovery::Discovery;
+use crate::message::file_message::FileResponse;
+use crate::message::handshake::Handshake;
+use crate::message::media_message::MediaResponse;
+use crate::message::text_message::TextResponse;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::BTreeSet;
+
+// The fixtures in `compat/` are every request and response as the hosts encode them, one per
+// line: JSON in the `.jsonl` files and bincode, as hex, on the same line of the `.hex` ones.
+// A change that keeps the hosts built before from reading them, such as renaming a field or
+// inserting a variant before others (bincode numbers the variants), fails here. A new variant
+// goes after the others of its enum, with a fixture of its own; a change meant to break the
+// wire format raises `PROTOCOL_VERSION` and replaces the fixtures.
+
+fn from_hex(hex: &str) -> Vec<u8> {
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+/// Reads the fixtures in both encodings, checking that each value encodes back to its
+/// fixtures, and returns them.
+fn read_fixtures<T: Serialize + DeserializeOwned>(json: &str, hex: &str) -> Vec<T> {
+    let (json, hex): (Vec<_>, Vec<_>) = (json.lines().collect(), hex.lines().collect());
+    assert_eq!(json.len(), hex.len(), "every fixture has both encodings");
+    json.into_iter()
+        .zip(hex)
+        .map(|(json, hex)| {
+            let value: T = Json::decode(json.as_bytes())
+                .unwrap_or_else(|err| panic!("cannot read {json}: {err}"));
+            let expected: serde_json::Value = serde_json::from_str(json).unwrap();
+            assert_eq!(serde_json::to_value(&value).unwrap(), expected);
+            let bytes = from_hex(hex);
+            let decoded: T = Bincode::decode(&bytes)
+                .unwrap_or_else(|err| panic!("cannot read the bincode of {json}: {err}"));
+            assert_eq!(Bincode::encode(&decoded), bytes, "{json} encodes otherwise");
+            assert_eq!(serde_json::to_value(&decoded).unwrap(), expected);
+            value
+        })
+        .collect()
+}
+
+/// Checks that the fixtures hold every variant named.
+fn assert_covered<T>(fixtures: &[T], variant: impl Fn(&T) -> &'static str, variants: &[&str]) {
+    let covered: BTreeSet<_> = fixtures.iter().map(variant).collect();
+    let variants: BTreeSet<_> = variants.iter().copied().collect();
+    assert_eq!(covered, variants, "every variant has a fixture");
+}
+
+// The variants are named by an exhaustive match, so that a new one does not compile until it
+// is named here, and in the list of its test.
+
+fn chat_response_variant(response: &ChatResponse) -> &'static str {
+    match response {
+        ChatResponse::ClientList(_) => "ClientList",
+        ChatResponse::Registered { .. } => "Registered",
+        ChatResponse::Unregistered(_) => "Unregistered",
+        ChatResponse::Renamed { .. } => "Renamed",
+        ChatResponse::Alive => "Alive",
+        ChatResponse::MessageFrom { .. } => "MessageFrom",
+        ChatResponse::MessageForwarded { .. } => "MessageForwarded",
+        ChatResponse::Subscribed => "Subscribed",
+        ChatResponse::UserJoined(_) => "UserJoined",
+        ChatResponse::GroupJoined { .. } => "GroupJoined",
+        ChatResponse::GroupMessage { .. } => "GroupMessage",
+        ChatResponse::GroupMessageSent { .. } => "GroupMessageSent",
+        ChatResponse::History(_) => "History",
+        ChatResponse::ServerStats(_) => "ServerStats",
+        ChatResponse::Error(_) => "Error",
+    }
+}
+
+fn content_response_variant(response: &ContentResponse) -> &'static str {
+    match response {
+        ContentResponse::TextResponse(response) => match response {
+            TextResponse::TextList(_) => "TextList",
+            TextResponse::Text(_) => "Text",
+            TextResponse::Uploaded(_) => "TextUploaded",
+            TextResponse::Metadata(_) => "TextMetadata",
+            TextResponse::SearchResults(_) => "SearchResults",
+        },
+        ContentResponse::MediaResponse(response) => match response {
+            MediaResponse::MediaList(_) => "MediaList",
+            MediaResponse::Media(_) => "Media",
+            MediaResponse::Uploaded(_) => "MediaUploaded",
+            MediaResponse::Metadata(_) => "MediaMetadata",
+            MediaResponse::Chunk { .. } => "Chunk",
+            MediaResponse::Thumbnail(_) => "Thumbnail",
+        },
+        ContentResponse::FileResponse(response) => match response {
+            FileResponse::FileList(_) => "FileList",
+            FileResponse::File(_) => "File",
+            FileResponse::Uploaded(_) => "FileUploaded",
+            FileResponse::Deleted(_) => "Deleted",
+        },
+        ContentResponse::Echo(_) => "Echo",
+        ContentResponse::Error(_) => "Error",
+        ContentResponse::ServerType(_) => "ServerType",
+        ContentResponse::Subscribed => "Subscribed",
+        ContentResponse::NewContent(_) => "NewContent",
+        ContentResponse::ServerStats(_) => "ServerStats",
+    }
+}
+
+fn discovery_variant(discovery: &Discovery) -> &'static str {
+    match discovery {
+        Discovery::ServerTypeRequest => "ServerTypeRequest",
+        Discovery::ServerTypeResponse(_) => "ServerTypeResponse",
+        Discovery::UnsupportedVersion => "UnsupportedVersion",
+    }
+}
+
+fn handshake_variant(handshake: &Handshake) -> &'static str {
+    match handshake {
+        Handshake::Offer(_) => "Offer",
+        Handshake::Accept(_) => "Accept",
+    }
+}
+
+#[test]
+fn chat_requests_read_as_before() {
+    let requests: Vec<ChatRequest> = read_fixtures(
+        include_str!("compat/chat_request.jsonl"),
+        include_str!("compat/chat_request.hex"),
+    );
+    assert_covered(
+        &requests,
+        |request| request.kind(),
+        &[
+            "ClientList",
+            "Register",
+            "Unregister",
+            "Rename",
+            "KeepAlive",
+            "SendMessage",
+            "Subscribe",
+            "CreateGroup",
+            "JoinGroup",
+            "SendToGroup",
+            "History",
+            "ServerStats",
+        ],
+    );
+}
+
+#[test]
+fn chat_responses_read_as_before() {
+    let responses: Vec<ChatResponse> = read_fixtures(
+        include_str!("compat/chat_response.jsonl"),
+        include_str!("compat/chat_response.hex"),
+    );
+    assert_covered(
+        &responses,
+        chat_response_variant,
+        &[
+            "ClientList",
+            "Registered",
+            "Unregistered",
+            "Renamed",
+            "Alive",
+            "MessageFrom",
+            "MessageForwarded",
+            "Subscribed",
+            "UserJoined",
+            "GroupJoined",
+            "GroupMessage",
+            "GroupMessageSent",
+            "History",
+            "ServerStats",
+            "Error",
+        ],
+    );
+}
+
+#[test]
+fn content_requests_read_as_before() {
+    let requests: Vec<ContentRequest> = read_fixtures(
+        include_str!("compat/content_request.jsonl"),
+        include_str!("compat/content_request.hex"),
+    );
+    assert_covered(
+        &requests,
+        |request| request.kind(),
+        &[
+            "TextList",
+            "Text",
+            "TextUpload",
+            "TextMetadata",
+            "Search",
+            "MediaList",
+            "Media",
+            "ExpandList",
+            "MediaUpload",
+            "MediaMetadata",
+            "Chunk",
+            "Thumbnail",
+            "FileList",
+            "File",
+            "FileUpload",
+            "Delete",
+            "Echo",
+            "ServerType",
+            "NotifyOnNew",
+            "ServerStats",
+        ],
+    );
+}
+
+#[test]
+fn content_responses_read_as_before() {
+    let responses: Vec<ContentResponse> = read_fixtures(
+        include_str!("compat/content_response.jsonl"),
+        include_str!("compat/content_response.hex"),
+    );
+    assert_covered(
+        &responses,
+        content_response_variant,
+        &[
+            "TextList",
+            "Text",
+            "TextUploaded",
+            "TextMetadata",
+            "SearchResults",
+            "MediaList",
+            "Media",
+            "MediaUploaded",
+            "MediaMetadata",
+            "Chunk",
+            "Thumbnail",
+            "FileList",
+            "File",
+            "FileUploaded",
+            "Deleted",
+            "Echo",
+            "Error",
+            "ServerType",
+            "Subscribed",
+            "NewContent",
+            "ServerStats",
+        ],
+    );
+}
+
+#[test]
+fn discovery_and_handshakes_read_as_before() {
+    let discovery: Vec<Discovery> = read_fixtures(
+        include_str!("compat/discovery.jsonl"),
+        include_str!("compat/discovery.hex"),
+    );
+    assert_covered(
+        &discovery,
+        discovery_variant,
+        &[
+            "ServerTypeRequest",
+            "ServerTypeResponse",
+            "UnsupportedVersion",
+        ],
+    );
+    let handshakes: Vec<Handshake> = read_fixtures(
+        include_str!("compat/handshake.jsonl"),
+        include_str!("compat/handshake.hex"),
+    );
+    assert_covered(&handshakes, handshake_variant, &["Offer", "Accept"]);
+}
+
+#[test]
+fn message_envelopes_read_as_before() {
+    let messages: Vec<Message<ChatRequest>> = read_fixtures(
+        include_str!("compat/message.jsonl"),
+        include_str!("compat/message.hex"),
+    );
+    assert!(messages.iter().all(|message| message.session_id == 7));
+}
@@ -1,7 +1,11 @@
 use std::fmt::Display;
 
-use crate::message::base_message::{MessageContent, Request, Response};
+use crate::message::base_message::{MessageContent, Priority, Request, Response, ServerType};
+use crate::message::echo_message::{EchoRequest, EchoResponse};
+use crate::message::error_response::ErrorResponse;
+use crate::message::file_message::{FileRequest, FileResponse};
 use crate::message::media_message::{MediaRequest, MediaResponse};
+use crate::message::server_stats::ServerStats;
 use crate::message::text_message::{TextRequest, TextResponse};
 use serde::{Deserialize, Serialize};
 
@@ -9,6 +13,14 @@ use serde::{Deserialize, Serialize};
 pub enum ContentRequest {
     TextRequest(TextRequest),
     MediaRequest(MediaRequest),
+    FileRequest(FileRequest),
+    Echo(EchoRequest),
+    /// Asks the server what it provides.
+    ServerType,
+    /// Asks the server to tell the client about the texts or media added from now on.
+    NotifyOnNew,
+    /// Asks the server for the requests it handled.
+    ServerStats,
 }
 impl Display for ContentRequest {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -17,16 +29,81 @@ impl Display for ContentRequest {
             ContentRequest::MediaRequest(media_request) => {
                 write!(f, "MediaRequest({})", media_request)
             }
+            ContentRequest::FileRequest(file_request) => write!(f, "FileRequest({})", file_request),
+            ContentRequest::Echo(echo) => write!(f, "{}", echo),
+            ContentRequest::ServerType => write!(f, "ServerType"),
+            ContentRequest::NotifyOnNew => write!(f, "NotifyOnNew"),
+            ContentRequest::ServerStats => write!(f, "ServerStats"),
         }
     }
 }
+impl From<TextRequest> for ContentRequest {
+    fn from(request: TextRequest) -> Self {
+        ContentRequest::TextRequest(request)
+    }
+}
+impl From<MediaRequest> for ContentRequest {
+    fn from(request: MediaRequest) -> Self {
+        ContentRequest::MediaRequest(request)
+    }
+}
+impl From<FileRequest> for ContentRequest {
+    fn from(request: FileRequest) -> Self {
+        ContentRequest::FileRequest(request)
+    }
+}
 impl MessageContent for ContentRequest {}
-impl Request for ContentRequest {}
+impl Request for ContentRequest {
+    fn kind(&self) -> &'static str {
+        match self {
+            ContentRequest::TextRequest(request) => request.kind(),
+            ContentRequest::MediaRequest(request) => request.kind(),
+            ContentRequest::FileRequest(request) => request.kind(),
+            ContentRequest::Echo(_) => "Echo",
+            ContentRequest::ServerType => "ServerType",
+            ContentRequest::NotifyOnNew => "NotifyOnNew",
+            ContentRequest::ServerStats => "ServerStats",
+        }
+    }
+
+    fn server_stats() -> Self {
+        ContentRequest::ServerStats
+    }
+
+    fn is_server_stats(&self) -> bool {
+        matches!(self, ContentRequest::ServerStats)
+    }
+
+    /// The media and files moved whole or in chunks are bulk, the rest are small.
+    fn priority(&self) -> Priority {
+        match self {
+            ContentRequest::MediaRequest(
+                MediaRequest::Media(_)
+                | MediaRequest::Upload(..)
+                | MediaRequest::Chunk { .. }
+                | MediaRequest::ExpandList,
+            )
+            | ContentRequest::FileRequest(FileRequest::File(_) | FileRequest::Upload(..)) => {
+                Priority::Low
+            }
+            _ => Priority::Normal,
+        }
+    }
+}
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ContentResponse {
     TextResponse(TextResponse),
     MediaResponse(MediaResponse),
-    ServiceNotProvided,
+    FileResponse(FileResponse),
+    Echo(EchoResponse),
+    Error(ErrorResponse),
+    ServerType(ServerType),
+    /// The server will tell the client about the content added.
+    Subscribed,
+    /// Sent by the server without a request, to the clients that subscribed, with the name of
+    /// the text or media added.
+    NewContent(String),
+    ServerStats(ServerStats),
 }
 impl Display for ContentResponse {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -37,9 +114,37 @@ impl Display for ContentResponse {
             ContentResponse::MediaResponse(media_response) => {
                 write!(f, "MediaResponse({})", media_response)
             }
-            ContentResponse::ServiceNotProvided => write!(f, "ServiceNotProvided"),
+            ContentResponse::FileResponse(file_response) => {
+                write!(f, "FileResponse({})", file_response)
+            }
+            ContentResponse::Echo(echo) => write!(f, "{}", echo),
+            ContentResponse::Error(error) => write!(f, "Error({})", error),
+            ContentResponse::ServerType(server_type) => write!(f, "ServerType({})", server_type),
+            ContentResponse::Subscribed => write!(f, "Subscribed"),
+            ContentResponse::NewContent(name) => write!(f, "NewContent({})", name),
+            ContentResponse::ServerStats(stats) => {
+                write!(f, "ServerStats({} requests)", stats.requests)
+            }
         }
     }
 }
 impl MessageContent for ContentResponse {}
-impl Response for ContentResponse {}
+impl Response for ContentResponse {
+    fn server_stats(stats: ServerStats) -> Self {
+        ContentResponse::ServerStats(stats)
+    }
+
+    fn as_server_stats(&self) -> Option<&ServerStats> {
+        match self {
+            ContentResponse::ServerStats(stats) => Some(stats),
+            _ => None,
+        }
+    }
+
+    fn as_error(&self) -> Option<&ErrorResponse> {
+        match self {
+            ContentResponse::Error(error) => Some(error),
+            _ => None,
+        }
+    }
+}
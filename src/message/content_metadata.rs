@@ -0,0 +1,58 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+/// What a client can know of a text or media before downloading it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContentMetadata {
+    pub name: String,
+    pub size: u64,
+    /// The [`content_hash`] of the bytes, for the client to check what it downloaded.
+    pub hash: u64,
+    pub mime_type: String,
+}
+
+impl ContentMetadata {
+    pub fn of(name: String, data: &[u8]) -> Self {
+        ContentMetadata {
+            name,
+            size: data.len() as u64,
+            hash: content_hash(data),
+            mime_type: mime_type(data).to_string(),
+        }
+    }
+
+    /// Whether the bytes are the ones described.
+    pub fn matches(&self, data: &[u8]) -> bool {
+        data.len() as u64 == self.size && content_hash(data) == self.hash
+    }
+}
+
+impl Display for ContentMetadata {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ({}, {} bytes, hash {:016x})",
+            self.name, self.mime_type, self.size, self.hash
+        )
+    }
+}
+
+/// The 64-bit FNV-1a hash of the bytes, which unlike the hashers of the standard library is the
+/// same whichever build computes it.
+pub fn content_hash(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// The type of the bytes, told by their first ones.
+fn mime_type(data: &[u8]) -> &'static str {
+    match data {
+        [0x89, b'P', b'N', b'G', ..] => "image/png",
+        [0xff, 0xd8, 0xff, ..] => "image/jpeg",
+        [b'G', b'I', b'F', b'8', ..] => "image/gif",
+        _ if std::str::from_utf8(data).is_ok() => "text/plain",
+        _ => "application/octet-stream",
+    }
+}
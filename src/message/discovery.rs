@@ -0,0 +1,35 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::message::base_message::{MessageContent, ServerType};
+use crate::message::codec::MessageCodec;
+
+/// Discovery messages are always JSON whatever the codec of the host, so that their variants
+/// are told apart by name from those of the applications.
+pub const DISCOVERY_CODEC: MessageCodec = MessageCodec::Json;
+
+/// The messages every server reads whatever its application, so that a client can ask a node
+/// it knows nothing of what it provides before sending it requests it may not understand.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Discovery {
+    ServerTypeRequest,
+    ServerTypeResponse(ServerType),
+    /// Answers a message sent with another version of the protocol than the server speaks, the
+    /// version of the answer telling which.
+    UnsupportedVersion,
+}
+
+impl MessageContent for Discovery {}
+
+impl Display for Discovery {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Discovery::ServerTypeRequest => write!(f, "ServerTypeRequest"),
+            Discovery::ServerTypeResponse(server_type) => {
+                write!(f, "ServerTypeResponse({})", server_type)
+            }
+            Discovery::UnsupportedVersion => write!(f, "UnsupportedVersion"),
+        }
+    }
+}
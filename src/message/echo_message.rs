@@ -0,0 +1,48 @@
+use std::fmt::Display;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+/// Milliseconds since the Unix epoch, the clock echoes are timed with.
+pub fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
+
+/// A payload for an echo server to send back.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EchoRequest {
+    pub payload: Vec<u8>,
+    /// Milliseconds since the Unix epoch when the client sent it.
+    pub sent_at: u64,
+}
+
+impl Display for EchoRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Echo({} bytes)", self.payload.len())
+    }
+}
+
+/// The payload sent back, with how the request got to the server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EchoResponse {
+    pub payload: Vec<u8>,
+    /// Drones crossed by the last fragment of the request.
+    pub hops: usize,
+    /// Milliseconds the request took to reach the server, fragments lost and sent again
+    /// included.
+    pub one_way_ms: u64,
+}
+
+impl Display for EchoResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Echo({} bytes, {} hops, {} ms)",
+            self.payload.len(),
+            self.hops,
+            self.one_way_ms
+        )
+    }
+}
@@ -0,0 +1,79 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+/// Why a server turned a request down, whatever its application.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ErrorCode {
+    /// There is no text, media, file, group or user by that name.
+    NotFound,
+    /// The server does not provide what the request asks for.
+    ServiceNotProvided,
+    /// The client registered no such username, or its registration expired.
+    NotRegistered,
+    /// Another client has the name already.
+    AlreadyTaken,
+    /// Another client owns what the request would change.
+    NotOwner,
+    /// Storing what the request sends would take the client past its quota.
+    QuotaExceeded,
+    /// The server has what the request names but cannot make of it what the request asks.
+    Unprocessable,
+}
+
+impl Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ErrorCode::NotFound => write!(f, "NotFound"),
+            ErrorCode::ServiceNotProvided => write!(f, "ServiceNotProvided"),
+            ErrorCode::NotRegistered => write!(f, "NotRegistered"),
+            ErrorCode::AlreadyTaken => write!(f, "AlreadyTaken"),
+            ErrorCode::NotOwner => write!(f, "NotOwner"),
+            ErrorCode::QuotaExceeded => write!(f, "QuotaExceeded"),
+            ErrorCode::Unprocessable => write!(f, "Unprocessable"),
+        }
+    }
+}
+
+/// The answer of a server refusing a request, embedded by every response so that clients
+/// handle the failures of any application alike.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    pub code: ErrorCode,
+    /// What went wrong, for the player to read.
+    pub message: String,
+    /// The same request may succeed later, the failure coming from the state of the server
+    /// rather than from the request.
+    pub retryable: bool,
+}
+
+impl ErrorResponse {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        ErrorResponse {
+            code,
+            message: message.into(),
+            retryable: false,
+        }
+    }
+
+    pub fn retryable(code: ErrorCode, message: impl Into<String>) -> Self {
+        ErrorResponse {
+            retryable: true,
+            ..ErrorResponse::new(code, message)
+        }
+    }
+
+    /// The server does not provide what the request asks for.
+    pub fn service_not_provided(service: &str) -> Self {
+        ErrorResponse::new(
+            ErrorCode::ServiceNotProvided,
+            format!("The server does not provide {service}"),
+        )
+    }
+}
+
+impl Display for ErrorResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.code, self.message)
+    }
+}
@@ -0,0 +1,58 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum FileRequest {
+    FileList,
+    File(String),
+    /// Stores a file under the name, replacing the one the client may already have there.
+    Upload(String, Vec<u8>),
+    /// Removes a file the client uploaded.
+    Delete(String),
+}
+
+impl FileRequest {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            FileRequest::FileList => "FileList",
+            FileRequest::File(_) => "File",
+            FileRequest::Upload(..) => "FileUpload",
+            FileRequest::Delete(_) => "Delete",
+        }
+    }
+}
+
+impl Display for FileRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileRequest::FileList => write!(f, "FileList"),
+            FileRequest::File(name) => write!(f, "File({})", name),
+            FileRequest::Upload(name, data) => {
+                write!(f, "Upload({}, {} bytes)", name, data.len())
+            }
+            FileRequest::Delete(name) => write!(f, "Delete({})", name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum FileResponse {
+    /// The names of the files stored, with their sizes in bytes.
+    FileList(Vec<(String, u64)>),
+    File(Vec<u8>),
+    /// The file was stored under the name.
+    Uploaded(String),
+    Deleted(String),
+}
+
+impl Display for FileResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileResponse::FileList(files) => write!(f, "FileList({:?})", files),
+            FileResponse::File(data) => write!(f, "File({} bytes)", data.len()),
+            FileResponse::Uploaded(name) => write!(f, "Uploaded({})", name),
+            FileResponse::Deleted(name) => write!(f, "Deleted({})", name),
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::message::base_message::MessageContent;
+use crate::message::codec::MessageCodec;
+
+/// Handshakes are always JSON and never sealed, since the hosts exchanging them have no key to
+/// open them with yet.
+pub const HANDSHAKE_CODEC: MessageCodec = MessageCodec::Json;
+
+/// The X25519 public key of a host.
+pub type PublicKeyBytes = [u8; 32];
+
+/// The messages two hosts agree a key with, before sealing the messages they exchange so that
+/// the drones in between only see ciphertext.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Handshake {
+    /// The public key of a host asking to seal the messages it exchanges with the destination.
+    Offer(PublicKeyBytes),
+    /// The public key of the host that took the offer, sealing its messages from now on.
+    Accept(PublicKeyBytes),
+}
+
+impl MessageContent for Handshake {}
+
+impl Display for Handshake {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (name, public_key) = match self {
+            Handshake::Offer(public_key) => ("Offer", public_key),
+            Handshake::Accept(public_key) => ("Accept", public_key),
+        };
+        let prefix = public_key[..4]
+            .iter()
+            .fold(String::new(), |acc, b| format!("{acc}{b:02x}"));
+        write!(f, "{name}({prefix}...)")
+    }
+}
@@ -2,34 +2,103 @@ use std::fmt::Display;
 
 use serde::{Deserialize, Serialize};
 
+use super::content_metadata::ContentMetadata;
+use super::page::{Page, PageRequest};
+
+/// The largest chunk of a media a server sends at once.
+pub const CHUNK_SIZE: u64 = 32 * 1024;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum MediaRequest {
-    MediaList,
+    MediaList(PageRequest),
     Media(String),
+    /// Has the server scrape more media, answering with the first page of its list.
     ExpandList,
+    /// Stores a media under the name, replacing the one it may already have.
+    Upload(String, Vec<u8>),
+    /// Asks how large the media is and what it hashes to, before downloading it in chunks.
+    Metadata(String),
+    /// Up to `len` bytes of the media from `offset`, at most [`CHUNK_SIZE`].
+    Chunk {
+        name: String,
+        offset: u64,
+        len: u64,
+    },
+    /// The media scaled down to fit a square of the side in pixels, as a PNG, to preview it
+    /// in far fewer fragments.
+    Thumbnail(String, u32),
+}
+
+impl MediaRequest {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            MediaRequest::MediaList(_) => "MediaList",
+            MediaRequest::Media(_) => "Media",
+            MediaRequest::ExpandList => "ExpandList",
+            MediaRequest::Upload(..) => "MediaUpload",
+            MediaRequest::Metadata(_) => "MediaMetadata",
+            MediaRequest::Chunk { .. } => "Chunk",
+            MediaRequest::Thumbnail(..) => "Thumbnail",
+        }
+    }
 }
 
 impl Display for MediaRequest {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            MediaRequest::MediaList => write!(f, "MediaList"),
+            MediaRequest::MediaList(page) => write!(f, "MediaList({})", page),
             MediaRequest::Media(name) => write!(f, "Media({})", name),
             MediaRequest::ExpandList => write!(f, "ExpandList"),
+            MediaRequest::Upload(name, media) => {
+                write!(f, "Upload({}, {} bytes)", name, media.len())
+            }
+            MediaRequest::Metadata(name) => write!(f, "Metadata({})", name),
+            MediaRequest::Chunk { name, offset, len } => {
+                write!(f, "Chunk({}, offset: {}, len: {})", name, offset, len)
+            }
+            MediaRequest::Thumbnail(name, max_px) => {
+                write!(f, "Thumbnail({}, {}px)", name, max_px)
+            }
         }
     }
 }
 
+/// A media a server has, as it lists it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MediaListing {
+    pub name: String,
+    /// The [`content_hash`](super::content_metadata::content_hash) of the media, the same for
+    /// the names of identical media.
+    pub hash: u64,
+}
+
+impl Display for MediaListing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (hash {:016x})", self.name, self.hash)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum MediaResponse {
-    MediaList(Vec<String>),
+    MediaList(Page<MediaListing>),
     Media(Vec<u8>),
-    NotFound,
+    /// The media was stored under the name.
+    Uploaded(String),
+    Metadata(ContentMetadata),
+    /// The bytes of the media from `offset`, fewer than asked at its end.
+    Chunk {
+        name: String,
+        offset: u64,
+        data: Vec<u8>,
+    },
+    /// A PNG of the media scaled down, or the media itself if it is small enough already.
+    Thumbnail(Vec<u8>),
 }
 
 impl Display for MediaResponse {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            MediaResponse::MediaList(media_list) => write!(f, "MediaList({:?})", media_list),
+            MediaResponse::MediaList(media_list) => write!(f, "MediaList({})", media_list),
             MediaResponse::Media(media) => write!(
                 f,
                 "Media(0x{}...)",
@@ -38,7 +107,18 @@ impl Display for MediaResponse {
                     .take(10)
                     .fold(String::new(), |acc, b| format!("{acc}{b:02x}"))
             ),
-            MediaResponse::NotFound => write!(f, "NotFound"),
+            MediaResponse::Thumbnail(thumbnail) => {
+                write!(f, "Thumbnail({} bytes)", thumbnail.len())
+            }
+            MediaResponse::Uploaded(name) => write!(f, "Uploaded({})", name),
+            MediaResponse::Metadata(metadata) => write!(f, "Metadata({})", metadata),
+            MediaResponse::Chunk { name, offset, data } => write!(
+                f,
+                "Chunk({}, offset: {}, {} bytes)",
+                name,
+                offset,
+                data.len()
+            ),
         }
     }
 }
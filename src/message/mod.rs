@@ -1,5 +1,16 @@
 pub mod base_message;
 pub mod chat_message;
+pub mod codec;
+#[cfg(test)]
+mod compat;
 pub mod content_message;
+pub mod content_metadata;
+pub mod discovery;
+pub mod echo_message;
+pub mod error_response;
+pub mod file_message;
+pub mod handshake;
 pub mod media_message;
+pub mod page;
+pub mod server_stats;
 pub mod text_message;
@@ -0,0 +1,105 @@
+use std::fmt::{Debug, Display};
+
+use serde::{Deserialize, Serialize};
+
+/// The most items a server lists in a page, however many the request asks for.
+pub const MAX_PAGE_SIZE: usize = 50;
+
+/// The part of a list a request asks for: up to `limit` items from the one at `offset`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageRequest {
+    pub offset: usize,
+    pub limit: usize,
+}
+
+impl Default for PageRequest {
+    /// The first page, as large as the servers make them.
+    fn default() -> Self {
+        PageRequest {
+            offset: 0,
+            limit: MAX_PAGE_SIZE,
+        }
+    }
+}
+
+impl PageRequest {
+    /// The page following the one answered with the continuation token, as large as this one.
+    pub fn after(self, next: usize) -> Self {
+        PageRequest {
+            offset: next,
+            ..self
+        }
+    }
+
+    /// The part of the items asked for, in the order the server keeps them so that the pages
+    /// asked one after the other cover the whole list.
+    pub fn page<T>(&self, items: Vec<T>) -> Page<T> {
+        let total = items.len();
+        let end = self
+            .offset
+            .saturating_add(self.limit.min(MAX_PAGE_SIZE))
+            .min(total);
+        let items: Vec<T> = items
+            .into_iter()
+            .skip(self.offset)
+            .take(end.saturating_sub(self.offset))
+            .collect();
+        Page {
+            items,
+            next: (end < total).then_some(end),
+            total,
+        }
+    }
+}
+
+impl Display for PageRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "offset: {}, limit: {}", self.offset, self.limit)
+    }
+}
+
+/// A part of a list, with where the next part starts if the list goes on.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// The continuation token: the offset to ask the next page from, none after the last one.
+    pub next: Option<usize>,
+    /// How many items the whole list has.
+    pub total: usize,
+}
+
+impl<T> Page<T> {
+    /// The offset of the first item of the page, for numbering the items across pages.
+    pub fn offset(&self) -> usize {
+        self.next.unwrap_or(self.total) - self.items.len()
+    }
+}
+
+impl<T: Debug> Display for Page<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} of {}", self.items, self.total)?;
+        if let Some(next) = self.next {
+            write!(f, ", next: {}", next)?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn pages_cover_the_list_one_after_the_other() {
+    let list: Vec<usize> = (0..7).collect();
+    let request = PageRequest {
+        offset: 0,
+        limit: 3,
+    };
+    let mut page = request.page(list.clone());
+    let mut listed = page.items.clone();
+    while let Some(next) = page.next {
+        page = request.after(next).page(list.clone());
+        assert_eq!(page.offset(), next);
+        listed.extend(page.items.clone());
+    }
+    assert_eq!(listed, list);
+    let past_the_end = request.after(10).page(list);
+    assert!(past_the_end.items.is_empty() && past_the_end.next.is_none());
+}
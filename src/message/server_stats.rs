@@ -0,0 +1,46 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use wg_2024::network::NodeId;
+
+/// The requests a server handled since it started, as it reports them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ServerStats {
+    pub requests: u64,
+    /// The fragments of all the requests.
+    pub fragments: u64,
+    /// The requests handled in the last minute, telling how busy the server is now.
+    pub recent_requests: u64,
+    /// How long the behaviour of the server took on average to answer a request.
+    pub mean_processing: Duration,
+    pub slowest_processing: Duration,
+    /// The requests handled, by kind.
+    pub by_kind: BTreeMap<String, u64>,
+    /// The requests handled, by client.
+    pub by_client: BTreeMap<NodeId, u64>,
+}
+
+impl Display for ServerStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} requests ({} fragments), {} in the last minute",
+            self.requests, self.fragments, self.recent_requests
+        )?;
+        writeln!(
+            f,
+            "{} µs to answer on average, {} µs at most",
+            self.mean_processing.as_micros(),
+            self.slowest_processing.as_micros()
+        )?;
+        for (kind, count) in &self.by_kind {
+            writeln!(f, "  {kind}: {count}")?;
+        }
+        for (client, count) in &self.by_client {
+            writeln!(f, "  from client {client}: {count}")?;
+        }
+        Ok(())
+    }
+}
@@ -2,38 +2,75 @@ use std::fmt::Display;
 
 use serde::{Deserialize, Serialize};
 
+use super::content_metadata::ContentMetadata;
+use super::page::{Page, PageRequest};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum TextRequest {
-    TextList,
+    TextList(PageRequest),
     Text(String),
+    /// Stores a text under the name, replacing the one it may already have.
+    Upload(String, String),
+    Metadata(String),
+    /// The texts holding every word of the query.
+    Search(String),
+}
+
+impl TextRequest {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            TextRequest::TextList(_) => "TextList",
+            TextRequest::Text(_) => "Text",
+            TextRequest::Upload(..) => "TextUpload",
+            TextRequest::Metadata(_) => "TextMetadata",
+            TextRequest::Search(_) => "Search",
+        }
+    }
 }
 
 impl Display for TextRequest {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            TextRequest::TextList => write!(f, "TextList"),
+            TextRequest::TextList(page) => write!(f, "TextList({})", page),
             TextRequest::Text(text) => write!(f, "Text({})", text),
+            TextRequest::Upload(name, text) => {
+                write!(f, "Upload({}, {} bytes)", name, text.len())
+            }
+            TextRequest::Metadata(name) => write!(f, "Metadata({})", name),
+            TextRequest::Search(query) => write!(f, "Search({})", query),
         }
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum TextResponse {
-    TextList(Vec<String>),
+    TextList(Page<String>),
     Text(String),
-    NotFound,
+    /// The text was stored under the name.
+    Uploaded(String),
+    Metadata(ContentMetadata),
+    SearchResults(Vec<SearchHit>),
 }
 
 impl Display for TextResponse {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            TextResponse::TextList(text_list) => write!(f, "TextList({:?})", text_list),
+            TextResponse::TextList(text_list) => write!(f, "TextList({})", text_list),
             TextResponse::Text(text) => write!(
                 f,
                 "Text({:?}...)",
                 text.chars().take(10).collect::<String>()
             ),
-            TextResponse::NotFound => write!(f, "NotFound"),
+            TextResponse::Uploaded(name) => write!(f, "Uploaded({})", name),
+            TextResponse::Metadata(metadata) => write!(f, "Metadata({})", metadata),
+            TextResponse::SearchResults(hits) => write!(f, "SearchResults({} texts)", hits.len()),
         }
     }
 }
+
+/// A text matching a search, with the part of it where the query matched.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub name: String,
+    pub snippet: String,
+}
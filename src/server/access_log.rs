@@ -0,0 +1,78 @@
+use crate::message::server_stats::ServerStats;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use wg_2024::network::NodeId;
+
+/// Requests kept in the log, the older ones only counted in the totals.
+const ACCESS_LOG_LENGTH: usize = 1000;
+/// How far back the requests count as recent.
+const RECENT_WINDOW: Duration = Duration::from_secs(60);
+
+/// A request a server handled.
+#[derive(Debug, Clone)]
+pub struct AccessEntry {
+    pub at: Instant,
+    pub source: NodeId,
+    pub kind: &'static str,
+    pub fragments: u64,
+    /// How long the behaviour took to answer.
+    pub processing: Duration,
+}
+
+/// The latest requests a server handled, and the totals of all of them.
+#[derive(Debug, Default)]
+pub struct AccessLog {
+    entries: VecDeque<AccessEntry>,
+    totals: ServerStats,
+    total_processing: Duration,
+}
+
+impl AccessLog {
+    pub fn record(&mut self, entry: AccessEntry) {
+        let totals = &mut self.totals;
+        totals.requests += 1;
+        totals.fragments += entry.fragments;
+        totals.slowest_processing = totals.slowest_processing.max(entry.processing);
+        *totals.by_kind.entry(entry.kind.to_string()).or_default() += 1;
+        *totals.by_client.entry(entry.source).or_default() += 1;
+        self.total_processing += entry.processing;
+        self.entries.push_back(entry);
+        if self.entries.len() > ACCESS_LOG_LENGTH {
+            self.entries.pop_front();
+        }
+    }
+
+    pub fn stats(&self) -> ServerStats {
+        let recent_requests = self
+            .entries
+            .iter()
+            .filter(|entry| entry.at.elapsed() <= RECENT_WINDOW)
+            .count();
+        ServerStats {
+            recent_requests: recent_requests as u64,
+            mean_processing: self.total_processing / self.totals.requests.max(1) as u32,
+            ..self.totals.clone()
+        }
+    }
+}
+
+#[test]
+fn stats_count_every_request() {
+    let mut log = AccessLog::default();
+    for (source, kind, millis) in [(3, "Text", 4), (3, "TextList", 2), (5, "Text", 6)] {
+        log.record(AccessEntry {
+            at: Instant::now(),
+            source,
+            kind,
+            fragments: 1,
+            processing: Duration::from_millis(millis),
+        });
+    }
+    let stats = log.stats();
+    assert_eq!(stats.requests, 3);
+    assert_eq!(stats.recent_requests, 3);
+    assert_eq!(stats.by_kind["Text"], 2);
+    assert_eq!(stats.by_client[&3], 2);
+    assert_eq!(stats.mean_processing, Duration::from_millis(4));
+    assert_eq!(stats.slowest_processing, Duration::from_millis(6));
+}
@@ -0,0 +1,170 @@
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use wg_2024::network::NodeId;
+
+/// The directory the servers find their assets in, `./assets` if unset. A server whose id is
+/// N serves those of its `server_N` subdirectory instead, if it has one, so that servers of
+/// the same kind can host different content.
+pub const ASSETS_VARIABLE: &str = "BAGEL_ASSETS";
+
+const DEFAULT_ASSETS: &str = "./assets";
+
+/// How often the servers look for files added, modified or removed in their assets.
+pub const RELOAD_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The items of a library that changed since it was last reloaded.
+#[derive(Debug, Default, Clone)]
+pub struct LibraryChange {
+    /// The items modified or added, these also listed in `added`.
+    pub updated: Vec<String>,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl LibraryChange {
+    pub fn is_empty(&self) -> bool {
+        self.updated.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// What the items of an assets directory are stored in.
+pub trait Library {
+    /// Stores the item, replacing the one the name may hold, returning whether the name is new.
+    fn insert_item(&mut self, name: String, data: Vec<u8>) -> bool;
+    /// Forgets the item, returning whether there was one.
+    fn remove_item(&mut self, name: &str) -> bool;
+}
+
+impl Library for HashMap<String, Vec<u8>> {
+    fn insert_item(&mut self, name: String, data: Vec<u8>) -> bool {
+        self.insert(name, data).is_none()
+    }
+
+    fn remove_item(&mut self, name: &str) -> bool {
+        self.remove(name).is_some()
+    }
+}
+
+/// The files of an assets directory, each holding an item of a library, read again whenever
+/// they change.
+#[derive(Debug)]
+pub struct AssetDirectory {
+    path: PathBuf,
+    /// The name of the item a file holds, from the name of the file.
+    item_name: fn(&str) -> String,
+    /// When the files read were last modified, by item.
+    modified: HashMap<String, SystemTime>,
+}
+
+impl AssetDirectory {
+    pub fn new(path: impl AsRef<Path>, item_name: fn(&str) -> String) -> Self {
+        AssetDirectory {
+            path: path.as_ref().to_path_buf(),
+            item_name,
+            modified: HashMap::new(),
+        }
+    }
+
+    /// The `kind` subdirectory of the assets shared by every server.
+    pub fn shared(kind: &str, item_name: fn(&str) -> String) -> Self {
+        Self::new(Self::root().join(kind), item_name)
+    }
+
+    /// The `kind` subdirectory of the assets of the server, those shared if it has none.
+    pub fn of_server(id: NodeId, kind: &str, item_name: fn(&str) -> String) -> Self {
+        let own = Self::root().join(format!("server_{id}")).join(kind);
+        if own.is_dir() {
+            Self::new(own, item_name)
+        } else {
+            Self::shared(kind, item_name)
+        }
+    }
+
+    fn root() -> PathBuf {
+        PathBuf::from(env::var(ASSETS_VARIABLE).unwrap_or_else(|_| DEFAULT_ASSETS.to_string()))
+    }
+
+    /// Brings the library up to date with the files, leaving alone the items in `kept` (the
+    /// ones uploaded, which take precedence over the assets).
+    pub fn reload(&mut self, library: &mut impl Library, kept: &HashSet<String>) -> LibraryChange {
+        let (updated, removed) = self.scan();
+        let mut change = LibraryChange::default();
+        for (name, data) in updated {
+            if kept.contains(&name) {
+                continue;
+            }
+            if library.insert_item(name.clone(), data) {
+                change.added.push(name.clone());
+            }
+            change.updated.push(name);
+        }
+        for name in removed {
+            if !kept.contains(&name) && library.remove_item(&name) {
+                change.removed.push(name);
+            }
+        }
+        change
+    }
+
+    /// The items whose files were added or modified since the last scan, with their bytes, and
+    /// those whose files were removed. The first scan reads every file.
+    fn scan(&mut self) -> (Vec<(String, Vec<u8>)>, Vec<String>) {
+        let entries = match fs::read_dir(&self.path) {
+            Ok(entries) => entries,
+            Err(err) => {
+                println!("Unable to read {}: {err}", self.path.display());
+                return (Vec::new(), Vec::new());
+            }
+        };
+        let mut present = HashMap::new();
+        let mut updated = Vec::new();
+        for entry in entries.filter_map(Result::ok) {
+            let Some(file_name) = entry.file_name().to_str().map(self.item_name) else {
+                continue;
+            };
+            let Ok(modified) = entry.metadata().and_then(|metadata| metadata.modified()) else {
+                continue;
+            };
+            if self.modified.get(&file_name) != Some(&modified) {
+                match fs::read(entry.path()) {
+                    Ok(data) => updated.push((file_name.clone(), data)),
+                    // half written, read on the next scan
+                    Err(_) => continue,
+                }
+            }
+            present.insert(file_name, modified);
+        }
+        let removed = self
+            .modified
+            .keys()
+            .filter(|name| !present.contains_key(*name))
+            .cloned()
+            .collect();
+        self.modified = present;
+        (updated, removed)
+    }
+}
+
+#[test]
+fn libraries_follow_their_files() {
+    let path = std::env::temp_dir().join(format!("bagel_assets_{}", std::process::id()));
+    fs::create_dir_all(&path).unwrap();
+    fs::write(path.join("kept"), "kept").unwrap();
+    fs::write(path.join("gone"), "gone").unwrap();
+    let mut directory = AssetDirectory::new(&path, str::to_string);
+    let mut library: HashMap<String, Vec<u8>> = HashMap::new();
+    let uploaded = HashSet::from(["kept".to_string()]);
+    directory.reload(&mut library, &HashSet::new());
+    assert_eq!(library.len(), 2);
+    fs::remove_file(path.join("gone")).unwrap();
+    fs::write(path.join("new"), "new").unwrap();
+    let change = directory.reload(&mut library, &uploaded);
+    assert_eq!(change.added, vec!["new".to_string()]);
+    assert_eq!(change.removed, vec!["gone".to_string()]);
+    assert_eq!(change.updated, change.added);
+    assert_eq!(library.len(), 2);
+    fs::remove_dir_all(path).ok();
+}
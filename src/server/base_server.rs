@@ -1,20 +1,44 @@
-use crate::application::assembler::{Assembler, Disassembler};
-use crate::application::routing::SourceRouter;
-use crate::application::simulation_controller_messages::{HostCommand, HostEvent};
+use crate::application::assembler::{
+    Assembler, AssemblyError, AssemblyLimits, Disassembler, REASSEMBLY_TIMEOUT, SEND_WINDOW,
+};
+use crate::application::authentication::HostKeys;
+use crate::application::encryption::Keyring;
+use crate::application::retransmission::{RetransmissionScheduler, RETRANSMISSION_TICK};
+use crate::application::routing::{RouterSettings, SourceRouter, ROUTING_TABLE_INTERVAL};
+use crate::application::simulation_controller_messages::{
+    HostCommand, HostEvent, SessionDirection,
+};
 use crate::application::topology::node::{ApplicationType, Node, NodeType};
 use crate::initialization::network_initializer::Runnable;
-use crate::message::base_message::{Message, Request, Response};
-use crossbeam_channel::{Receiver, Sender};
+use crate::message::base_message::{Message, MessageContent, Request, Response, ServerType};
+use crate::message::codec::MessageCodec;
+use crate::message::discovery::{Discovery, DISCOVERY_CODEC};
+use crate::message::handshake::{Handshake, HANDSHAKE_CODEC};
+use crate::server::access_log::{AccessEntry, AccessLog};
+use crate::server::asset_directory::{LibraryChange, RELOAD_INTERVAL};
+use crate::server::behaviour_worker::{BehaviourWorker, Handled, Outgoing, Reloaded};
+use crate::server::media_replication::SYNC_INTERVAL;
+use crate::server::persistence::PERSIST_INTERVAL;
+use crossbeam_channel::{select, Receiver, Sender};
 use rand::random;
+use serde::Serialize;
 use std::collections::HashMap;
 use std::fmt::Display;
+use std::fs;
+use std::io;
 use std::time::{Duration, Instant};
 use wg_2024::network::{NodeId, SourceRoutingHeader};
 use wg_2024::packet::{
-    FloodRequest, Nack, NackType, NodeType as SimpleNodeType, Packet, PacketType,
+    FloodRequest, Fragment, Nack, NackType, NodeType as SimpleNodeType, Packet, PacketType,
 };
 
-pub trait ServerBehaviour: Send {
+/// How long a server stopping waits for the responses it is sending to be acked.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long a server waits for the responses to a flood before flooding again for the
+/// fragments that have no route.
+const FLOOD_RESPONSE_WAIT: Duration = Duration::from_millis(1500);
+
+pub trait ServerBehaviour: Send + 'static {
     type RequestType: Request + Display;
     type ResponseType: Response + Display;
     fn handle_request(
@@ -22,21 +46,101 @@ pub trait ServerBehaviour: Send {
         req: Message<Self::RequestType>,
         source_id: NodeId,
     ) -> Message<Self::ResponseType>;
-    fn application_type() -> ApplicationType;
+    /// Handles a request knowing the route its last fragment took, from the client to this
+    /// server, for the behaviours that report on the network rather than serve content.
+    fn handle_routed_request(
+        &mut self,
+        req: Message<Self::RequestType>,
+        id: NodeId,
+        _route: &[NodeId],
+    ) -> Message<Self::ResponseType> {
+        self.handle_request(req, id)
+    }
+    /// Messages the behaviour sends besides its responses, taken after every request. They
+    /// are sent in sessions of their own.
+    fn notifications(&mut self) -> Vec<Message<Self::ResponseType>> {
+        Vec::new()
+    }
+    /// The behaviour of the server with this id, for the behaviours whose content depends on
+    /// the server rather than being the same for all.
+    fn for_server(_id: NodeId) -> Self
+    where
+        Self: Default,
+    {
+        Self::default()
+    }
+    /// What the server answers it provides when asked with a [`Discovery`] message.
+    fn server_type() -> ServerType;
+    fn application_type() -> ApplicationType {
+        Self::server_type().application_type()
+    }
+    fn router_settings() -> RouterSettings {
+        RouterSettings::default()
+    }
+    /// How the messages sent by the host are encoded.
+    fn codec() -> MessageCodec {
+        MessageCodec::default()
+    }
+    /// How much the host buffers for the messages it sends and receives.
+    fn assembly_limits() -> AssemblyLimits {
+        AssemblyLimits::default()
+    }
+    /// Picks up the state saved by a server with the same id, before it stopped.
+    fn restore(&mut self, _id: NodeId) {}
+    /// Saves what a server respawned with the same id must not forget, done periodically and
+    /// when the server stops.
+    fn persist(&self, _id: NodeId) -> io::Result<()> {
+        Ok(())
+    }
+    /// Picks up the files added to, modified in or removed from the assets the behaviour
+    /// serves since it last looked, done periodically.
+    fn reload_assets(&mut self, _id: NodeId) -> LibraryChange {
+        LibraryChange::default()
+    }
+    /// Requests to the other servers reached, for the behaviours that copy their content,
+    /// done periodically. They are sent in sessions of their own.
+    fn sync_requests(
+        &mut self,
+        _id: NodeId,
+        _servers: &[NodeId],
+    ) -> Vec<Message<Self::RequestType>> {
+        Vec::new()
+    }
+    /// Takes the reply of another server to one of the [`Self::sync_requests`], returning the
+    /// requests following it.
+    fn handle_reply(
+        &mut self,
+        _reply: Message<Self::ResponseType>,
+        _id: NodeId,
+    ) -> Vec<Message<Self::RequestType>> {
+        Vec::new()
+    }
 }
 
 pub struct Server<B: ServerBehaviour> {
     pub id: NodeId,
-    behaviour: B,
+    /// The behaviour, answering requests on a thread of its own.
+    worker: BehaviourWorker<B>,
     controller_send: Sender<HostEvent>,
     controller_recv: Receiver<HostCommand>,
     packet_recv: Receiver<Packet>,
     packet_send: HashMap<NodeId, Sender<Packet>>,
     assembler: Assembler<B::RequestType>,
+    /// The replies of other servers to the requests of this one.
+    reply_assembler: Assembler<B::ResponseType>,
     disassembler: Disassembler<B::ResponseType>,
     router: SourceRouter,
+    retransmissions: RetransmissionScheduler,
+    /// The keys agreed with the clients that sealed their requests, their responses being
+    /// sealed alike.
+    keyring: Keyring,
     last_flood: Instant,
     last_route_update: Instant,
+    last_routing_table: Instant,
+    last_persist: Instant,
+    last_reload: Instant,
+    last_sync: Instant,
+    access_log: AccessLog,
     active: bool,
 }
 impl<B: ServerBehaviour> Server<B> {
@@ -46,24 +150,57 @@ impl<B: ServerBehaviour> Server<B> {
         controller_recv: Receiver<HostCommand>,
         packet_recv: Receiver<Packet>,
         packet_send: HashMap<NodeId, Sender<Packet>>,
-        behaviour: B,
+        mut behaviour: B,
     ) -> Self {
-        let router = SourceRouter::new(Node::new(id, NodeType::Server(B::application_type())));
+        behaviour.restore(id);
+        let router = SourceRouter::new(Node::new(id, NodeType::Server(B::application_type())))
+            .with_settings(B::router_settings());
+        let progress_send = controller_send.clone();
+        let assembler = Assembler::new()
+            .with_progress_observer(Box::new(move |session_id, received, total| {
+                progress_send
+                    .send(HostEvent::SessionProgress {
+                        host: id,
+                        session_id,
+                        remaining: total - received,
+                    })
+                    .expect("unable to send events to host");
+            }))
+            .with_limits(B::assembly_limits());
         Server {
             id,
             controller_send,
             controller_recv,
-            behaviour,
+            worker: BehaviourWorker::spawn(id, behaviour),
             packet_send,
             packet_recv,
             router,
-            assembler: Assembler::new(),
-            disassembler: Disassembler::new(),
+            retransmissions: RetransmissionScheduler::default(),
+            keyring: Keyring::default(),
+            assembler,
+            reply_assembler: Assembler::new().with_limits(B::assembly_limits()),
+            disassembler: Disassembler::new()
+                .with_codec(B::codec())
+                .with_window(SEND_WINDOW)
+                .with_limits(B::assembly_limits()),
             active: false,
             last_flood: Instant::now() - Duration::from_secs(30),
             last_route_update: Instant::now() - Duration::from_secs(25),
+            last_routing_table: Instant::now(),
+            last_persist: Instant::now(),
+            last_reload: Instant::now(),
+            last_sync: Instant::now(),
+            access_log: AccessLog::default(),
         }
     }
+    /// Signs the messages sent with the key issued to the server, checking that the ones
+    /// received are signed by the host they come from.
+    pub fn with_host_keys(mut self, host_keys: HostKeys) -> Self {
+        self.assembler = self.assembler.with_host_keys(host_keys.clone());
+        self.reply_assembler = self.reply_assembler.with_host_keys(host_keys.clone());
+        self.disassembler = self.disassembler.with_host_keys(self.id, host_keys);
+        self
+    }
     pub fn with_default_behaviour(
         id: NodeId,
         controller_send: Sender<HostEvent>,
@@ -80,7 +217,7 @@ impl<B: ServerBehaviour> Server<B> {
             controller_recv,
             packet_recv,
             packet_send,
-            Default::default(),
+            B::for_server(id),
         )
     }
     fn initiate_flood(&self) {
@@ -102,6 +239,32 @@ impl<B: ServerBehaviour> Server<B> {
         self.active = true;
         println!("server {} is activated", self.id);
         while self.active {
+            select! {
+                recv(self.controller_recv) -> command => {
+                    if let Ok(command) = command {
+                        self.handle_command(command);
+                    }
+                }
+                recv(self.packet_recv) -> packet => {
+                    if let Ok(packet) = packet {
+                        self.handle_packet(packet);
+                    }
+                }
+                recv(self.worker.handled()) -> handled => match handled {
+                    Ok(handled) => self.send_handled(handled),
+                    Err(_) => self.worker_died(),
+                },
+                recv(self.worker.reloaded()) -> reloaded => match reloaded {
+                    Ok(reloaded) => self.send_reloaded(reloaded),
+                    Err(_) => self.worker_died(),
+                },
+                recv(self.worker.outgoing()) -> outgoing => match outgoing {
+                    Ok(outgoing) => self.send_outgoing(outgoing),
+                    Err(_) => self.worker_died(),
+                },
+                default(RETRANSMISSION_TICK) => {}
+            }
+
             let flood_elapsed_seconds = self.last_flood.elapsed().as_secs();
             if flood_elapsed_seconds > 30 {
                 self.last_flood = Instant::now();
@@ -110,29 +273,224 @@ impl<B: ServerBehaviour> Server<B> {
             let route_update_elapsed_seconds = self.last_route_update.elapsed().as_secs();
             if route_update_elapsed_seconds > 30 {
                 self.last_route_update = Instant::now();
-                self.router.calculate_routes();
+                self.router.update_routes();
             }
-
-            for command in self.gather_commands() {
-                self.handle_command(command);
+            if self.last_persist.elapsed() >= PERSIST_INTERVAL {
+                self.persist();
+            }
+            if self.last_reload.elapsed() >= RELOAD_INTERVAL {
+                self.last_reload = Instant::now();
+                self.worker.reload_assets();
+            }
+            if self.last_sync.elapsed() >= SYNC_INTERVAL {
+                self.last_sync = Instant::now();
+                self.worker.sync(self.router.reachable_peer_servers());
+            }
+            if self.last_routing_table.elapsed() >= ROUTING_TABLE_INTERVAL {
+                self.last_routing_table = Instant::now();
+                self.send_event(HostEvent::RoutingTable(
+                    self.id,
+                    self.router.describe_routes(),
+                ));
+                let stats = self.assembler.stats().combine(self.disassembler.stats());
+                self.send_event(HostEvent::FragmentStats(self.id, stats));
+                self.send_event(HostEvent::ServerStats(self.id, self.access_log.stats()));
             }
+            self.abandon_stale_sessions();
+            self.retransmit_due();
+        }
+    }
 
-            for packet in self.gather_packets() {
-                self.handle_packet(packet);
+    /// Stops the server once the requests queued are answered and every response is acked,
+    /// retransmitting the fragments lost meanwhile, or once [`SHUTDOWN_TIMEOUT`] passed. The
+    /// requests arriving meanwhile are ignored.
+    fn stop(&mut self) {
+        let deadline = Instant::now() + SHUTDOWN_TIMEOUT;
+        for handled in self.worker.stop(deadline) {
+            self.send_handled(handled);
+        }
+        // without drones nothing can be acked
+        while self.disassembler.pending_sessions() > 0
+            && !self.packet_send.is_empty()
+            && Instant::now() < deadline
+        {
+            select! {
+                recv(self.controller_recv) -> command => {
+                    match command {
+                        Ok(HostCommand::Crash) | Err(_) => {}
+                        Ok(command) => self.handle_command(command),
+                    }
+                }
+                recv(self.packet_recv) -> packet => {
+                    if let Ok(packet) = packet {
+                        if matches!(packet.pack_type, PacketType::Ack(_) | PacketType::Nack(_)) {
+                            self.handle_packet(packet);
+                        }
+                    }
+                }
+                default(RETRANSMISSION_TICK) => {}
             }
+            self.retransmit_due();
         }
+        self.send_event(HostEvent::ShutDown {
+            host: self.id,
+            abandoned: self.disassembler.pending_sessions(),
+        });
+        self.active = false;
     }
 
-    fn gather_packets(&self) -> Vec<Packet> {
-        self.packet_recv.try_iter().take(10).collect()
+    /// Stops the server once the thread running its behaviour died, a handler having panicked,
+    /// since no request would be answered any more.
+    fn worker_died(&mut self) {
+        println!("Server {} stops, its behaviour panicked", self.id);
+        self.send_event(HostEvent::BehaviourPanicked { host: self.id });
+        self.stop();
     }
 
-    fn gather_commands(&self) -> Vec<HostCommand> {
-        self.controller_recv.try_iter().take(10).collect()
+    fn persist(&mut self) {
+        self.last_persist = Instant::now();
+        self.worker.persist_state();
     }
 
-    fn stop(&mut self) {
-        self.active = false;
+    /// Sends the response and notifications of a request the behaviour answered.
+    fn send_handled(&mut self, handled: Handled<B>) {
+        self.access_log.record(handled.access);
+        let sent = HostEvent::MessageSent(handled.response.to_string_message());
+        self.send_event(sent);
+        self.send_message(handled.response);
+        for notification in handled.notifications {
+            self.send_notification(notification);
+        }
+    }
+
+    /// Answers a request for the [`AccessLog`] statistics, which the behaviour knows nothing of.
+    fn send_server_stats(&mut self, request: Message<B::RequestType>, fragments: u64) {
+        let response =
+            request.generate_response(B::ResponseType::server_stats(self.access_log.stats()));
+        self.access_log.record(AccessEntry {
+            at: Instant::now(),
+            source: request.source_id,
+            kind: request.content.kind(),
+            fragments,
+            processing: Duration::ZERO,
+        });
+        self.send_event(HostEvent::MessageSent(response.to_string_message()));
+        self.send_message(response);
+    }
+
+    /// Tells the client what the server provides, whatever the messages it otherwise reads.
+    fn answer_discovery(&mut self, request: Message<Discovery>, fragments: u64) {
+        self.send_event(HostEvent::MessageReceived(request.to_string_message()));
+        let Discovery::ServerTypeRequest = request.content else {
+            return;
+        };
+        self.access_log.record(AccessEntry {
+            at: Instant::now(),
+            source: request.source_id,
+            kind: "ServerTypeRequest",
+            fragments,
+            processing: Duration::ZERO,
+        });
+        let response = Message::new(
+            self.id,
+            request.source_id,
+            request.session_id,
+            Discovery::ServerTypeResponse(B::server_type()),
+        );
+        self.send_event(HostEvent::MessageSent(response.to_string_message()));
+        self.send_message_with(response, DISCOVERY_CODEC);
+    }
+
+    /// Tells the sender of a message of another version of the protocol which version the
+    /// server speaks, rather than leaving it to guess from a nack.
+    fn answer_unsupported_version(&mut self, source: NodeId, session_id: u64, version: u16) {
+        println!(
+            "Server {} cannot read session {session_id} of {source}: protocol version {version}",
+            self.id
+        );
+        let response = Message::new(self.id, source, session_id, Discovery::UnsupportedVersion);
+        self.send_event(HostEvent::MessageSent(response.to_string_message()));
+        self.send_message_with(response, DISCOVERY_CODEC);
+    }
+
+    /// Agrees a key with a client offering one, sealing the messages exchanged with it from
+    /// now on. The answer itself is not sealed, the client having no key to open it yet.
+    fn answer_handshake(&mut self, handshake: Message<Handshake>) {
+        self.send_event(HostEvent::MessageReceived(handshake.to_string_message()));
+        let Handshake::Offer(public_key) = handshake.content else {
+            return;
+        };
+        let source = handshake.source_id;
+        if !self.keyring.agree(source, public_key) {
+            println!("Server {} refused the key offered by {source}", self.id);
+            return;
+        }
+        let response = Message::new(
+            self.id,
+            source,
+            handshake.session_id,
+            Handshake::Accept(self.keyring.public_key()),
+        );
+        self.send_event(HostEvent::MessageSent(response.to_string_message()));
+        self.send_message_with(response, HANDSHAKE_CODEC);
+    }
+
+    /// Reports a change of the library of the behaviour, sending the notifications it caused.
+    fn send_reloaded(&mut self, reloaded: Reloaded<B>) {
+        self.send_event(HostEvent::LibraryChanged {
+            host: self.id,
+            updated: reloaded.change.updated,
+            removed: reloaded.change.removed,
+        });
+        for notification in reloaded.notifications {
+            self.send_notification(notification);
+        }
+    }
+
+    /// Sends the requests the behaviour has for other servers, each in a session of its own,
+    /// and the notifications pending.
+    fn send_outgoing(&mut self, outgoing: Outgoing<B>) {
+        for mut request in outgoing.requests {
+            let destination = request.destination_id;
+            if !self.router.can_reach(destination) {
+                println!("Server {} has no route to server {destination}", self.id);
+                continue;
+            }
+            request.session_id = self.new_session_id();
+            self.send_event(HostEvent::MessageSent(request.to_string_message()));
+            self.send_message(request);
+        }
+        for notification in outgoing.notifications {
+            self.send_notification(notification);
+        }
+    }
+
+    /// Whether the session is one this server started, its fragments being a reply rather
+    /// than a request.
+    fn is_own_session(&self, session_id: u64) -> bool {
+        session_id >> 56 == self.id as u64
+    }
+
+    /// Passes on to the behaviour the reply of another server, once all its fragments came.
+    fn receive_reply(&mut self, source: NodeId, session_id: u64, fragment: Fragment) {
+        let reply = self
+            .reply_assembler
+            .insert_fragment(source, session_id, fragment)
+            .map(|reply| self.keyring.open(source, reply));
+        match reply {
+            Some(Ok(reply)) => {
+                self.send_event(HostEvent::MessageReceived(reply.to_string_message()));
+                self.worker.reply(reply);
+            }
+            Some(Err(AssemblyError::Spoofed(source))) => {
+                self.report_spoofed(source, session_id);
+            }
+            Some(Err(err)) => println!(
+                "Server {} cannot read the reply of session {}: {err}",
+                self.id, session_id
+            ),
+            None => {}
+        }
     }
 
     fn handle_packet(&mut self, packet: Packet) {
@@ -142,23 +500,51 @@ impl<B: ServerBehaviour> Server<B> {
         match packet.pack_type {
             PacketType::MsgFragment(frag) => {
                 let fragment_index = frag.fragment_index;
+                let fragments = frag.total_n_fragments;
                 let ack =
                     Packet::new_ack(routing_header.get_reversed(), session_id, fragment_index);
                 self.forward_packet(ack);
-                if let Some(request_msg_frags) = self
-                    .assembler
-                    .insert_fragment(packet.session_id, frag.clone())
-                {
+                let Some(source) = routing_header.source() else {
+                    return;
+                };
+                if self.is_own_session(session_id) {
+                    self.receive_reply(source, session_id, frag);
+                    return;
+                }
+                if let Some(request_msg_frags) = self.receive_fragment(source, session_id, frag) {
                     match request_msg_frags {
                         Ok(message) => {
                             let received = HostEvent::MessageReceived(message.to_string_message());
                             self.send_event(received);
-                            let response = self.behaviour.handle_request(message, self.id);
-                            let sent = HostEvent::MessageSent(response.to_string_message());
-                            self.send_event(sent);
-                            self.send_response(response)
+                            if message.content.is_server_stats() {
+                                self.send_server_stats(message, fragments);
+                            } else {
+                                self.worker
+                                    .handle(message, routing_header.hops.clone(), fragments);
+                            }
+                        }
+                        // a probe timing the route, acked already
+                        Err(AssemblyError::Empty) => {}
+                        Err(AssemblyError::UnsupportedVersion(version)) => {
+                            self.answer_unsupported_version(source, session_id, version);
                         }
-                        Err(_) => {
+                        // answering would only tell the forger the server is there
+                        Err(AssemblyError::Spoofed(source)) => {
+                            self.report_spoofed(source, session_id);
+                        }
+                        Err(err) => {
+                            if let Some(handshake) = err.read_as::<Handshake>() {
+                                self.answer_handshake(handshake);
+                                return;
+                            }
+                            if let Some(request) = err.read_as::<Discovery>() {
+                                self.answer_discovery(request, fragments);
+                                return;
+                            }
+                            println!(
+                                "Server {} cannot read session {}: {err}",
+                                self.id, session_id
+                            );
                             let packet = Packet::new_nack(
                                 routing_header.get_reversed(),
                                 session_id,
@@ -173,15 +559,17 @@ impl<B: ServerBehaviour> Server<B> {
                 }
             }
             PacketType::Ack(ack) => {
-                self.disassembler
-                    .forget_fragment(session_id, ack.fragment_index);
+                self.acknowledge_fragment(session_id, ack.fragment_index);
             }
             PacketType::Nack(nack_pack) => match nack_pack.nack_type {
                 NackType::ErrorInRouting(_) => {
-                    self.retransmit(session_id, nack_pack.fragment_index)
+                    self.schedule_retransmission(session_id, nack_pack.fragment_index, None)
                 }
                 NackType::Dropped => {
-                    self.retransmit(session_id, nack_pack.fragment_index);
+                    let dropped_by = packet.routing_header.source();
+                    self.router
+                        .fragment_dropped(session_id, nack_pack.fragment_index);
+                    self.schedule_retransmission(session_id, nack_pack.fragment_index, dropped_by);
                 }
                 NackType::DestinationIsDrone => {}
                 NackType::UnexpectedRecipient(_) => {}
@@ -207,6 +595,118 @@ impl<B: ServerBehaviour> Server<B> {
             HostCommand::Crash => {
                 self.stop();
             }
+            HostCommand::InitiateFlood => {
+                self.last_flood = Instant::now();
+                self.initiate_flood();
+            }
+            HostCommand::ExportTopology(path) => {
+                if let Err(err) = fs::write(&path, self.router.export_dot()) {
+                    println!("Server {} cannot export its topology: {err}", self.id);
+                }
+            }
+            HostCommand::SendRequest(..) => {}
+        }
+    }
+    /// Gives up the incoming messages whose fragments stopped coming, reporting them.
+    fn abandon_stale_sessions(&mut self) {
+        let mut abandoned = self.assembler.gc(REASSEMBLY_TIMEOUT);
+        abandoned.extend(self.reply_assembler.gc(REASSEMBLY_TIMEOUT));
+        for (_source, session_id) in abandoned {
+            self.send_event(HostEvent::SessionAbandoned {
+                host: self.id,
+                session_id,
+            });
+        }
+    }
+    /// Reports a message that its source did not sign, dropping it.
+    fn report_spoofed(&mut self, source: NodeId, session_id: u64) {
+        println!(
+            "Server {} dropped session {session_id}, not signed by {source}",
+            self.id
+        );
+        self.send_event(HostEvent::MessageSpoofed {
+            host: self.id,
+            source,
+            session_id,
+        });
+    }
+    /// Reports the sessions dropped to stay within the memory budget.
+    fn report_evicted(&mut self, evicted: Vec<u64>, direction: SessionDirection) {
+        for session_id in evicted {
+            if direction == SessionDirection::Outgoing {
+                self.retransmissions.forget_session(session_id);
+                self.router.forget_session(session_id);
+            }
+            self.send_event(HostEvent::SessionEvicted {
+                host: self.id,
+                session_id,
+                direction,
+            });
+        }
+    }
+    /// Adds an incoming fragment to its message, reporting when the session starts and ends.
+    fn receive_fragment(
+        &mut self,
+        source: NodeId,
+        session_id: u64,
+        fragment: Fragment,
+    ) -> Option<Result<Message<B::RequestType>, AssemblyError>> {
+        let total_fragments = fragment.total_n_fragments;
+        if self.assembler.received_fragments(source, session_id) == 0 {
+            self.send_event(HostEvent::SessionStarted {
+                host: self.id,
+                session_id,
+                direction: SessionDirection::Incoming,
+                total_fragments,
+            });
+        }
+        let message = self
+            .assembler
+            .insert_fragment(source, session_id, fragment)
+            .map(|message| self.keyring.open(source, message));
+        let evicted = self.assembler.enforce_limits();
+        let evicted = evicted.into_iter().map(|(_source, session_id)| session_id);
+        self.report_evicted(evicted.collect(), SessionDirection::Incoming);
+        if message.is_some() {
+            self.send_event(HostEvent::SessionEnded {
+                host: self.id,
+                session_id,
+            });
+        }
+        message
+    }
+    /// Forgets an acked fragment, reporting the progress of the session.
+    fn acknowledge_fragment(&mut self, session_id: u64, fragment_index: u64) {
+        self.router
+            .fragment_acknowledged(session_id, fragment_index);
+        self.retransmissions
+            .fragment_acknowledged(session_id, fragment_index);
+        if self
+            .disassembler
+            .forget_fragment(session_id, fragment_index)
+            .is_none()
+        {
+            return;
+        }
+        let event = match self.disassembler.remaining_fragments(session_id) {
+            0 => {
+                self.router.forget_session(session_id);
+                HostEvent::SessionEnded {
+                    host: self.id,
+                    session_id,
+                }
+            }
+            remaining => HostEvent::SessionProgress {
+                host: self.id,
+                session_id,
+                remaining: remaining as u64,
+            },
+        };
+        self.send_event(event);
+        // the ack made room in the window
+        let released = self.disassembler.release_fragments(session_id);
+        if let Some(destination) = self.disassembler.get_destination(session_id) {
+            self.send_fragments(session_id, destination, released);
         }
     }
     fn send_event(&mut self, event: HostEvent) {
@@ -214,23 +714,93 @@ impl<B: ServerBehaviour> Server<B> {
             .send(event)
             .expect("unable to send events to host");
     }
-    fn send_response(&mut self, response: Message<B::ResponseType>) {
-        let destination = response.destination_id;
-        let session = response.session_id;
-        let fragments = self.disassembler.disassembly(response);
+    /// A session for a message that does not answer a request, distinct from those of the
+    /// clients since it carries the id of the server.
+    fn new_session_id(&mut self) -> u64 {
+        Disassembler::<B::ResponseType>::transform_session_id(
+            self.disassembler.new_session_id(),
+            self.id,
+        )
+    }
+    /// Sends a message that answers no request, in a session of its own. The subscribers may
+    /// have left the network since they asked, so the message is dropped if none of the
+    /// routes known reaches its destination.
+    fn send_notification(&mut self, mut notification: Message<B::ResponseType>) {
+        let destination = notification.destination_id;
+        if !self.router.can_reach(destination) {
+            self.router.update_routes();
+        }
+        if !self.router.can_reach(destination) {
+            println!("Server {} has no route to client {destination}", self.id);
+            return;
+        }
+        notification.session_id = self.new_session_id();
+        let sent = HostEvent::MessageSent(notification.to_string_message());
+        self.send_event(sent);
+        self.send_message(notification);
+    }
+    /// Sends a response, or a request to another server, sealed if a key was agreed with the
+    /// destination.
+    fn send_message<T: MessageContent + Serialize>(&mut self, message: Message<T>) {
+        let destination = message.destination_id;
+        let session = message.session_id;
+        let fragments = self
+            .disassembler
+            .disassembly_sealed(message, B::codec(), &self.keyring);
+        self.send_disassembled(session, destination, fragments);
+    }
+    /// Sends a message that is never sealed, encoded with `codec`.
+    fn send_message_with<T: MessageContent + Serialize>(
+        &mut self,
+        message: Message<T>,
+        codec: MessageCodec,
+    ) {
+        let destination = message.destination_id;
+        let session = message.session_id;
+        let fragments = self.disassembler.disassembly_with(message, codec);
+        self.send_disassembled(session, destination, fragments);
+    }
+    /// Sends the fragments the disassembler released for a new session, unless the session
+    /// was evicted at once to respect the limits.
+    fn send_disassembled(&mut self, session: u64, destination: NodeId, fragments: Vec<Fragment>) {
+        let evicted = self.disassembler.enforce_limits();
+        let sendable = !evicted.contains(&session);
+        self.report_evicted(evicted, SessionDirection::Outgoing);
+        if !sendable {
+            return;
+        }
+        self.send_event(HostEvent::SessionStarted {
+            host: self.id,
+            session_id: session,
+            direction: SessionDirection::Outgoing,
+            total_fragments: self.disassembler.remaining_fragments(session) as u64,
+        });
+        self.send_fragments(session, destination, fragments);
+    }
+    fn send_fragments(&mut self, session: u64, destination: NodeId, fragments: Vec<Fragment>) {
         for frag in fragments.into_iter() {
             if !self.router.can_reach(destination) {
-                self.router.calculate_routes();
+                self.router.update_routes();
+            }
+            match self.router.get_best_route(destination) {
+                Some(routing_header) => {
+                    self.forward_packet(Packet::new_fragment(routing_header, session, frag))
+                }
+                None => self.route_missing(session, frag.fragment_index, None),
             }
-            let packet = Packet::new_fragment(
-                self.router.get_best_route(destination).unwrap(),
-                session,
-                frag,
-            );
-            self.forward_packet(packet);
         }
     }
-    fn forward_packet(&self, mut packet: Packet) {
+    /// Floods the network for a fragment that has no route to its destination, unless a flood
+    /// is still waiting for its responses, and retransmits the fragment after its backoff.
+    fn route_missing(&mut self, session_id: u64, fragment_index: u64, avoided: Option<NodeId>) {
+        if self.last_flood.elapsed() >= FLOOD_RESPONSE_WAIT {
+            self.last_flood = Instant::now();
+            self.initiate_flood();
+        }
+        self.schedule_retransmission(session_id, fragment_index, avoided);
+    }
+    fn forward_packet(&mut self, mut packet: Packet) {
+        self.router.fragment_sent(&packet);
         let sender = self
             .packet_send
             .get(&packet.routing_header.next_hop().unwrap());
@@ -239,17 +809,55 @@ impl<B: ServerBehaviour> Server<B> {
             sender.send(packet).expect("unable to send");
         }
     }
-    fn retransmit(&mut self, session_id: u64, fragment_index: u64) {
-        let frag = self
-            .disassembler
-            .get_fragment(session_id, fragment_index)
-            .unwrap();
-        let destination = self.disassembler.get_destination(session_id).unwrap();
-        let to_retransmit = Packet::new_fragment(
-            self.router.get_best_route(destination).unwrap(),
+    /// Retransmits the fragment after its backoff, giving up its message if it ran out of
+    /// retries.
+    fn schedule_retransmission(
+        &mut self,
+        session_id: u64,
+        fragment_index: u64,
+        avoided: Option<NodeId>,
+    ) {
+        let priority = self.disassembler.get_priority(session_id);
+        if self
+            .retransmissions
+            .schedule(session_id, fragment_index, avoided, priority)
+        {
+            return;
+        }
+        self.retransmissions.forget_session(session_id);
+        self.router.forget_session(session_id);
+        self.disassembler.forget(session_id);
+        self.send_event(HostEvent::MessageFailed {
+            host: self.id,
             session_id,
-            frag,
-        );
+        });
+    }
+    fn retransmit_due(&mut self) {
+        for (session_id, fragment_index, avoided) in self.retransmissions.due() {
+            self.retransmit(session_id, fragment_index, avoided);
+        }
+    }
+    /// Sends a fragment again, on a route as disjoint as possible from the one it was dropped
+    /// on and avoiding the drone that dropped it if there is another route.
+    fn retransmit(&mut self, session_id: u64, fragment_index: u64, avoided: Option<NodeId>) {
+        let Some(frag) = self
+            .disassembler
+            .retransmit_fragment(session_id, fragment_index)
+        else {
+            // acked or given up while waiting for the backoff
+            return;
+        };
+        let Some(destination) = self.disassembler.get_destination(session_id) else {
+            return;
+        };
+        let Some(routing_header) =
+            self.router
+                .get_retransmission_route(destination, session_id, fragment_index, avoided)
+        else {
+            self.route_missing(session_id, fragment_index, avoided);
+            return;
+        };
+        let to_retransmit = Packet::new_fragment(routing_header, session_id, frag);
         self.forward_packet(to_retransmit);
     }
 }
@@ -258,4 +866,7 @@ impl<B: ServerBehaviour> Runnable for Server<B> {
     fn run(&mut self) {
         self.run();
     }
+    fn application_type(&self) -> ApplicationType {
+        B::application_type()
+    }
 }
@@ -0,0 +1,312 @@
+use crate::message::base_message::{Message, Priority, Request};
+use crate::server::access_log::AccessEntry;
+use crate::server::asset_directory::LibraryChange;
+use crate::server::base_server::ServerBehaviour;
+use crossbeam_channel::{unbounded, Receiver, RecvTimeoutError, Sender};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::thread::{self, JoinHandle};
+use std::time::Instant;
+use wg_2024::network::NodeId;
+
+enum Work<B: ServerBehaviour> {
+    Request {
+        message: Message<B::RequestType>,
+        route: Vec<NodeId>,
+        fragments: u64,
+    },
+    /// Starts comparing the content with the other servers reached.
+    Sync(Vec<NodeId>),
+    /// A reply of another server to a request of the behaviour.
+    Reply(Message<B::ResponseType>),
+    Persist,
+    ReloadAssets,
+    /// Saves the state a last time and ends the thread.
+    Stop,
+}
+
+impl<B: ServerBehaviour> Work<B> {
+    /// How urgent the work is: a request as its priority says, the stop once everything queued
+    /// before it is done.
+    fn priority(&self) -> Priority {
+        match self {
+            Work::Request { message, .. } => message.priority,
+            Work::Stop => Priority::Low,
+            _ => Priority::Normal,
+        }
+    }
+}
+
+/// The work waiting for the behaviour, taken the most urgent first and in the order it came
+/// among the work as urgent.
+struct WorkQueue<B: ServerBehaviour> {
+    queued: BTreeMap<(Reverse<Priority>, u64), Work<B>>,
+    received: u64,
+}
+
+impl<B: ServerBehaviour> WorkQueue<B> {
+    fn new() -> Self {
+        WorkQueue {
+            queued: BTreeMap::new(),
+            received: 0,
+        }
+    }
+
+    fn push(&mut self, work: Work<B>) {
+        self.queued
+            .insert((Reverse(work.priority()), self.received), work);
+        self.received += 1;
+    }
+
+    fn pop(&mut self) -> Option<Work<B>> {
+        self.queued.pop_first().map(|(_key, work)| work)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.queued.is_empty()
+    }
+}
+
+/// A request the behaviour answered, with the notifications answering it caused.
+pub struct Handled<B: ServerBehaviour> {
+    pub response: Message<B::ResponseType>,
+    pub notifications: Vec<Message<B::ResponseType>>,
+    pub access: AccessEntry,
+}
+
+/// A change of the library of the behaviour, with the notifications it caused.
+pub struct Reloaded<B: ServerBehaviour> {
+    pub change: LibraryChange,
+    pub notifications: Vec<Message<B::ResponseType>>,
+}
+
+/// Requests the behaviour sends to other servers, with the notifications sending them caused.
+pub struct Outgoing<B: ServerBehaviour> {
+    pub requests: Vec<Message<B::RequestType>>,
+    pub notifications: Vec<Message<B::ResponseType>>,
+}
+
+/// Runs the behaviour of a server on a thread of its own, so that slow requests (scraping the
+/// web, for one) do not keep the server from forwarding acks and fragments meanwhile. The
+/// requests queued are answered the most urgent first.
+pub struct BehaviourWorker<B: ServerBehaviour> {
+    work_send: Sender<Work<B>>,
+    handled_recv: Receiver<Handled<B>>,
+    reloaded_recv: Receiver<Reloaded<B>>,
+    outgoing_recv: Receiver<Outgoing<B>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl<B: ServerBehaviour> BehaviourWorker<B> {
+    pub fn spawn(id: NodeId, mut behaviour: B) -> Self {
+        let (work_send, work_recv) = unbounded::<Work<B>>();
+        let (handled_send, handled_recv) = unbounded();
+        let (reloaded_send, reloaded_recv) = unbounded();
+        let (outgoing_send, outgoing_recv) = unbounded();
+        let handle = thread::spawn(move || {
+            let mut queue = WorkQueue::new();
+            loop {
+                // what came meanwhile is queued too, so that the urgent requests overtake it
+                if queue.is_empty() {
+                    let Ok(work) = work_recv.recv() else {
+                        break;
+                    };
+                    queue.push(work);
+                }
+                for work in work_recv.try_iter() {
+                    queue.push(work);
+                }
+                let Some(work) = queue.pop() else {
+                    break;
+                };
+                match work {
+                    Work::Request {
+                        message,
+                        route,
+                        fragments,
+                    } => {
+                        let source = message.source_id;
+                        let kind = message.content.kind();
+                        let (priority, exchange_id) = (message.priority, message.exchange_id);
+                        let started = Instant::now();
+                        let mut response = behaviour.handle_routed_request(message, id, &route);
+                        response.priority = priority;
+                        response.exchange_id = exchange_id;
+                        let access = AccessEntry {
+                            at: Instant::now(),
+                            source,
+                            kind,
+                            fragments,
+                            processing: started.elapsed(),
+                        };
+                        let notifications = behaviour.notifications();
+                        let handled = Handled {
+                            response,
+                            notifications,
+                            access,
+                        };
+                        if handled_send.send(handled).is_err() {
+                            break;
+                        }
+                    }
+                    Work::Sync(servers) => {
+                        let requests = behaviour.sync_requests(id, &servers);
+                        if !Self::send_outgoing(&mut behaviour, requests, &outgoing_send) {
+                            break;
+                        }
+                    }
+                    Work::Reply(reply) => {
+                        let requests = behaviour.handle_reply(reply, id);
+                        if !Self::send_outgoing(&mut behaviour, requests, &outgoing_send) {
+                            break;
+                        }
+                    }
+                    Work::Persist => Self::persist(&behaviour, id),
+                    Work::ReloadAssets => {
+                        let change = behaviour.reload_assets(id);
+                        if change.is_empty() {
+                            continue;
+                        }
+                        let notifications = behaviour.notifications();
+                        let reloaded = Reloaded {
+                            change,
+                            notifications,
+                        };
+                        if reloaded_send.send(reloaded).is_err() {
+                            break;
+                        }
+                    }
+                    Work::Stop => {
+                        Self::persist(&behaviour, id);
+                        break;
+                    }
+                }
+            }
+        });
+        Self {
+            work_send,
+            handled_recv,
+            reloaded_recv,
+            outgoing_recv,
+            handle: Some(handle),
+        }
+    }
+
+    /// Passes on the requests with the notifications pending, if any, returning whether the
+    /// server still takes them.
+    fn send_outgoing(
+        behaviour: &mut B,
+        requests: Vec<Message<B::RequestType>>,
+        outgoing_send: &Sender<Outgoing<B>>,
+    ) -> bool {
+        let notifications = behaviour.notifications();
+        if requests.is_empty() && notifications.is_empty() {
+            return true;
+        }
+        let outgoing = Outgoing {
+            requests,
+            notifications,
+        };
+        outgoing_send.send(outgoing).is_ok()
+    }
+
+    fn persist(behaviour: &B, id: NodeId) {
+        if let Err(err) = behaviour.persist(id) {
+            println!("Server {id} cannot save its state: {err}");
+        }
+    }
+
+    /// Queues the request of `fragments` fragments, its response being taken later by
+    /// [`Self::handled`].
+    pub fn handle(&self, message: Message<B::RequestType>, route: Vec<NodeId>, fragments: u64) {
+        let work = Work::Request {
+            message,
+            route,
+            fragments,
+        };
+        self.work_send.send(work).ok();
+    }
+
+    /// Queues a sync with the servers, the requests it needs being taken later by
+    /// [`Self::outgoing`].
+    pub fn sync(&self, servers: Vec<NodeId>) {
+        self.work_send.send(Work::Sync(servers)).ok();
+    }
+
+    pub fn reply(&self, reply: Message<B::ResponseType>) {
+        self.work_send.send(Work::Reply(reply)).ok();
+    }
+
+    pub fn persist_state(&self) {
+        self.work_send.send(Work::Persist).ok();
+    }
+
+    pub fn reload_assets(&self) {
+        self.work_send.send(Work::ReloadAssets).ok();
+    }
+
+    /// Where the requests answered arrive, the most urgent of those queued first.
+    pub fn handled(&self) -> &Receiver<Handled<B>> {
+        &self.handled_recv
+    }
+
+    /// Where the changes of the library arrive, only when there are some.
+    pub fn reloaded(&self) -> &Receiver<Reloaded<B>> {
+        &self.reloaded_recv
+    }
+
+    /// Where the requests for other servers arrive, only when there are some.
+    pub fn outgoing(&self) -> &Receiver<Outgoing<B>> {
+        &self.outgoing_recv
+    }
+
+    /// Waits for the requests queued and the state to be saved, so that a server respawned
+    /// right after finds it.
+    /// Stops the thread once the work queued before is done, returning the requests it answered
+    /// meanwhile. Past `deadline` the thread is left to finish on its own, since a slow request
+    /// may keep it busy for long.
+    pub fn stop(&mut self, deadline: Instant) -> Vec<Handled<B>> {
+        self.work_send.send(Work::Stop).ok();
+        let mut handled = Vec::new();
+        loop {
+            match self.handled_recv.recv_deadline(deadline) {
+                Ok(answered) => handled.push(answered),
+                // the thread ended, dropping its end of the channel
+                Err(RecvTimeoutError::Disconnected) => {
+                    if let Some(handle) = self.handle.take() {
+                        handle.join().ok();
+                    }
+                    break;
+                }
+                Err(RecvTimeoutError::Timeout) => break,
+            }
+        }
+        handled
+    }
+}
+
+#[test]
+fn urgent_requests_overtake_the_queue() {
+    use crate::message::chat_message::ChatRequest;
+    use crate::server::chat_server::ChatServerBehaviour;
+
+    let mut queue = WorkQueue::<ChatServerBehaviour>::new();
+    let request = |session_id, priority| Work::Request {
+        message: Message {
+            priority,
+            ..Message::new(1, 2, session_id, ChatRequest::Subscribe)
+        },
+        route: Vec::new(),
+        fragments: 1,
+    };
+    queue.push(request(0, Priority::Low));
+    queue.push(Work::Stop);
+    queue.push(request(1, Priority::Normal));
+    queue.push(request(2, Priority::High));
+    queue.push(request(3, Priority::Normal));
+    let mut sessions = Vec::new();
+    while let Some(Work::Request { message, .. }) = queue.pop() {
+        sessions.push(message.session_id);
+    }
+    assert_eq!(sessions, vec![2, 1, 3, 0]);
+}
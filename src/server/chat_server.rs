@@ -1,14 +1,217 @@
-use crate::application::topology::node::ApplicationType;
-use crate::message::base_message::Message;
+use crate::client::utils::unix_timestamp;
+use crate::message::base_message::{Message, ServerType};
 use crate::message::chat_message::{ChatRequest, ChatResponse};
+use crate::message::error_response::{ErrorCode, ErrorResponse};
 use crate::server::base_server::{Server, ServerBehaviour};
-use std::collections::HashMap;
+use crate::server::persistence;
+use crate::server::subscribers::Subscribers;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::io;
+use std::time::{Duration, Instant};
 use wg_2024::network::NodeId;
 
+/// How long a client can go without requests, keepalives included, before its usernames are
+/// freed.
+pub const REGISTRATION_TIMEOUT: Duration = Duration::from_secs(60);
+/// Most messages kept for every client to catch up on, the oldest are dropped first.
+const MAILBOX_SIZE: usize = 100;
+
 pub type ChatServer = Server<ChatServerBehaviour>;
+/// What a chat server keeps across restarts.
+#[derive(Default, Serialize, Deserialize)]
+struct ChatState {
+    users: HashMap<String, NodeId>,
+    groups: HashMap<String, BTreeSet<String>>,
+    #[serde(default)]
+    mailboxes: HashMap<NodeId, Mailbox>,
+}
+/// The messages passed on to a client, numbered in the order they were.
+#[derive(Default, Clone, Serialize, Deserialize)]
+struct Mailbox {
+    /// The id of the last message, 0 before the first.
+    last_id: u64,
+    /// The last [`MAILBOX_SIZE`] messages, oldest first.
+    messages: VecDeque<ChatResponse>,
+}
 #[derive(Default)]
 pub struct ChatServerBehaviour {
     users: HashMap<String, NodeId>,
+    /// When every client last sent a request.
+    last_seen: HashMap<NodeId, Instant>,
+    /// Confirmations for the senders of the messages passed on, and the users joining for
+    /// the subscribers.
+    notifications: Vec<Message<ChatResponse>>,
+    subscribers: Subscribers,
+    /// The usernames of the members of every group.
+    groups: HashMap<String, BTreeSet<String>>,
+    mailboxes: HashMap<NodeId, Mailbox>,
+}
+impl ChatServerBehaviour {
+    fn client_list(&self) -> Vec<String> {
+        let mut clients: Vec<String> = self.users.keys().cloned().collect();
+        clients.sort();
+        clients
+    }
+    fn owns(&self, client: NodeId, username: &str) -> bool {
+        self.users.get(username) == Some(&client)
+    }
+    fn username_taken(username: &str) -> ChatResponse {
+        let message = format!("{username} is taken");
+        ChatResponse::Error(ErrorResponse::new(ErrorCode::AlreadyTaken, message))
+    }
+    fn not_registered(username: &str) -> ChatResponse {
+        let message = format!("{username} is not registered");
+        ChatResponse::Error(ErrorResponse::new(ErrorCode::NotRegistered, message))
+    }
+    /// Registers the username for the client the first time it uses it, failing with the
+    /// error to answer if another client holds it.
+    fn claim(&mut self, id: NodeId, client: NodeId, username: &str) -> Result<(), ChatResponse> {
+        if self.owns(client, username) {
+            return Ok(());
+        }
+        match self.register(id, client, username.to_string()) {
+            ChatResponse::Registered { .. } => Ok(()),
+            error => Err(error),
+        }
+    }
+    fn group_not_found(group: &str) -> ChatResponse {
+        let message = format!("There is no group named {group} with you in it");
+        ChatResponse::Error(ErrorResponse::new(ErrorCode::NotFound, message))
+    }
+    /// Numbers and timestamps the message `content` makes for the client, keeping it in the
+    /// mailbox of the client.
+    fn deliver(
+        &mut self,
+        client: NodeId,
+        content: impl FnOnce(u64, u64) -> ChatResponse,
+    ) -> ChatResponse {
+        let mailbox = self.mailboxes.entry(client).or_default();
+        mailbox.last_id += 1;
+        let message = content(mailbox.last_id, unix_timestamp());
+        mailbox.messages.push_back(message.clone());
+        if mailbox.messages.len() > MAILBOX_SIZE {
+            mailbox.messages.pop_front();
+        }
+        message
+    }
+    /// The messages of the mailbox of the client after the one with id `since`.
+    fn history(&self, client: NodeId, since: u64) -> ChatResponse {
+        let messages = self
+            .mailboxes
+            .get(&client)
+            .map(|mailbox| {
+                mailbox
+                    .messages
+                    .iter()
+                    .filter(|message| message.message_id().is_some_and(|id| id > since))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default();
+        ChatResponse::History(messages)
+    }
+    fn register(&mut self, id: NodeId, client: NodeId, username: String) -> ChatResponse {
+        match self.users.get(&username).copied() {
+            Some(owner) if owner != client => Self::username_taken(&username),
+            owner => {
+                if owner.is_none() {
+                    let joined = ChatResponse::UserJoined(username.clone());
+                    let notifications = self.subscribers.notify(id, client, joined);
+                    self.notifications.extend(notifications);
+                }
+                self.users.insert(username.clone(), client);
+                let clients = self.client_list();
+                ChatResponse::Registered { username, clients }
+            }
+        }
+    }
+    fn rename(&mut self, client: NodeId, from: String, to: String) -> ChatResponse {
+        if !self.owns(client, &from) {
+            return Self::not_registered(&from);
+        }
+        if self.users.contains_key(&to) {
+            return Self::username_taken(&to);
+        }
+        self.users.remove(&from);
+        self.users.insert(to.clone(), client);
+        for members in self.groups.values_mut() {
+            if members.remove(&from) {
+                members.insert(to.clone());
+            }
+        }
+        ChatResponse::Renamed { from, to }
+    }
+    /// Frees the username, taking it out of the groups too.
+    fn forget_user(&mut self, username: &str) {
+        self.users.remove(username);
+        for members in self.groups.values_mut() {
+            members.remove(username);
+        }
+    }
+    /// Frees the usernames of the clients silent for [`REGISTRATION_TIMEOUT`], most likely
+    /// crashed.
+    fn expire_registrations(&mut self, now: Instant) {
+        let silent: Vec<NodeId> = self
+            .last_seen
+            .iter()
+            .filter(|(_client, seen)| now.duration_since(**seen) >= REGISTRATION_TIMEOUT)
+            .map(|(client, _seen)| *client)
+            .collect();
+        for client in silent {
+            self.last_seen.remove(&client);
+            let usernames: Vec<String> = self
+                .users
+                .iter()
+                .filter(|(_username, owner)| **owner == client)
+                .map(|(username, _owner)| username.clone())
+                .collect();
+            for username in usernames {
+                self.forget_user(&username);
+            }
+        }
+    }
+    fn join_group(&mut self, group: String, username: String) -> ChatResponse {
+        let Some(members) = self.groups.get_mut(&group) else {
+            return Self::group_not_found(&group);
+        };
+        members.insert(username);
+        let members = members.iter().cloned().collect();
+        ChatResponse::GroupJoined { group, members }
+    }
+    /// Queues the message for every member of the group but its sender, returning how many
+    /// there are, or `None` if the sender is not a member.
+    fn send_to_group(
+        &mut self,
+        id: NodeId,
+        from: String,
+        group: &str,
+        message: String,
+    ) -> Option<usize> {
+        let members = self.groups.get(group)?;
+        if !members.contains(&from) {
+            return None;
+        }
+        let recipients: Vec<NodeId> = members
+            .iter()
+            .filter(|&member| *member != from)
+            .filter_map(|member| self.users.get(member).copied())
+            .collect();
+        for &recipient in &recipients {
+            let content = self.deliver(recipient, |message_id, timestamp| {
+                ChatResponse::GroupMessage {
+                    id: message_id,
+                    timestamp,
+                    group: group.to_string(),
+                    from: from.clone(),
+                    message: message.clone(),
+                }
+            });
+            self.notifications
+                .push(Message::new(id, recipient, 0, content));
+        }
+        Some(recipients.len())
+    }
 }
 impl ServerBehaviour for ChatServerBehaviour {
     type RequestType = ChatRequest;
@@ -19,34 +222,264 @@ impl ServerBehaviour for ChatServerBehaviour {
         req: Message<Self::RequestType>,
         id: NodeId,
     ) -> Message<Self::ResponseType> {
+        // a client coming back after expiring has to register again
+        let now = Instant::now();
+        self.expire_registrations(now);
+        self.last_seen.insert(req.source_id, now);
         match req.content {
-            ChatRequest::ClientList => {
-                let response =
-                    ChatResponse::ClientList(self.users.keys().cloned().collect::<Vec<_>>());
+            ChatRequest::ClientList(page) => {
+                let response = ChatResponse::ClientList(page.page(self.client_list()));
                 Message::new(id, req.source_id, req.session_id, response)
             }
             ChatRequest::Register(username) => {
-                self.users.entry(username).or_insert(req.source_id);
-                let response =
-                    ChatResponse::ClientList(self.users.keys().cloned().collect::<Vec<_>>());
+                let response = self.register(id, req.source_id, username);
                 Message::new(id, req.source_id, req.session_id, response)
             }
-            ChatRequest::SendMessage { from, to, message } => {
-                let response = Self::ResponseType::MessageFrom {
-                    from: from.clone(),
-                    message,
+            ChatRequest::Unregister(username) => {
+                let response = if self.owns(req.source_id, &username) {
+                    self.forget_user(&username);
+                    ChatResponse::Unregistered(username)
+                } else {
+                    Self::not_registered(&username)
                 };
-                self.users.entry(from).or_insert(req.source_id);
-                if let Some(destination) = self.users.get(&to) {
-                    Message::new(id, *destination, req.session_id, response)
+                Message::new(id, req.source_id, req.session_id, response)
+            }
+            ChatRequest::Rename { from, to } => {
+                let response = self.rename(req.source_id, from, to);
+                Message::new(id, req.source_id, req.session_id, response)
+            }
+            ChatRequest::KeepAlive(username) => {
+                let response = if self.owns(req.source_id, &username) {
+                    ChatResponse::Alive
                 } else {
-                    let response = ChatResponse::DestinationNotFound;
+                    Self::not_registered(&username)
+                };
+                Message::new(id, req.source_id, req.session_id, response)
+            }
+            ChatRequest::History { since } => {
+                let response = self.history(req.source_id, since);
+                Message::new(id, req.source_id, req.session_id, response)
+            }
+            ChatRequest::ServerStats => unreachable!("the server answers ServerStats itself"),
+            ChatRequest::Subscribe => {
+                self.subscribers.subscribe(req.source_id);
+                req.generate_response(ChatResponse::Subscribed)
+            }
+            ChatRequest::CreateGroup { group, username } => {
+                if let Err(response) = self.claim(id, req.source_id, &username) {
+                    return Message::new(id, req.source_id, req.session_id, response);
+                }
+                if self.groups.contains_key(&group) {
+                    let message = format!("{group} already exists");
+                    let response =
+                        ChatResponse::Error(ErrorResponse::new(ErrorCode::AlreadyTaken, message));
+                    return Message::new(id, req.source_id, req.session_id, response);
+                }
+                self.groups.insert(group.clone(), BTreeSet::new());
+                let response = self.join_group(group, username);
+                Message::new(id, req.source_id, req.session_id, response)
+            }
+            ChatRequest::JoinGroup { group, username } => {
+                if let Err(response) = self.claim(id, req.source_id, &username) {
+                    return Message::new(id, req.source_id, req.session_id, response);
+                }
+                let response = self.join_group(group, username);
+                Message::new(id, req.source_id, req.session_id, response)
+            }
+            ChatRequest::SendToGroup {
+                from,
+                group,
+                message,
+            } => {
+                if let Err(response) = self.claim(id, req.source_id, &from) {
+                    return Message::new(id, req.source_id, req.session_id, response);
+                }
+                let response = match self.send_to_group(id, from, &group, message) {
+                    Some(recipients) => ChatResponse::GroupMessageSent { group, recipients },
+                    None => Self::group_not_found(&group),
+                };
+                Message::new(id, req.source_id, req.session_id, response)
+            }
+            ChatRequest::SendMessage { from, to, message } => {
+                if let Err(response) = self.claim(id, req.source_id, &from) {
+                    return Message::new(id, req.source_id, req.session_id, response);
+                }
+                if let Some(destination) = self.users.get(&to).copied() {
+                    let forwarded = ChatResponse::MessageForwarded { to };
+                    self.notifications.push(Message::new(
+                        id,
+                        req.source_id,
+                        req.session_id,
+                        forwarded,
+                    ));
+                    let response = self.deliver(destination, |message_id, timestamp| {
+                        ChatResponse::MessageFrom {
+                            id: message_id,
+                            timestamp,
+                            from,
+                            message,
+                        }
+                    });
+                    Message::new(id, destination, req.session_id, response)
+                } else {
+                    // the recipient may register later on
+                    let message = format!("{to} is not registered on this server");
+                    let response =
+                        ChatResponse::Error(ErrorResponse::retryable(ErrorCode::NotFound, message));
                     Message::new(id, req.source_id, req.session_id, response)
                 }
             }
         }
     }
-    fn application_type() -> ApplicationType {
-        ApplicationType::Chat
+    fn notifications(&mut self) -> Vec<Message<ChatResponse>> {
+        self.notifications.drain(..).collect()
+    }
+    fn restore(&mut self, id: NodeId) {
+        let Some(state) = persistence::load::<ChatState>("chat", id) else {
+            return;
+        };
+        // the clients get a full timeout to show they are still there
+        let now = Instant::now();
+        self.last_seen = state.users.values().map(|&client| (client, now)).collect();
+        self.users = state.users;
+        self.groups = state.groups;
+        self.mailboxes = state.mailboxes;
+    }
+    fn persist(&self, id: NodeId) -> io::Result<()> {
+        let state = ChatState {
+            users: self.users.clone(),
+            groups: self.groups.clone(),
+            mailboxes: self.mailboxes.clone(),
+        };
+        persistence::save("chat", id, &state)
+    }
+    fn server_type() -> ServerType {
+        ServerType::Chat
+    }
+}
+#[test]
+fn group_messages_reach_the_other_members() {
+    let mut server = ChatServerBehaviour::default();
+    let create = ChatRequest::CreateGroup {
+        group: "ducks".to_string(),
+        username: "ada".to_string(),
+    };
+    server.handle_request(Message::new(3, 1, 0, create), 1);
+    for (client, username) in [(4, "bob"), (5, "eve")] {
+        let join = ChatRequest::JoinGroup {
+            group: "ducks".to_string(),
+            username: username.to_string(),
+        };
+        server.handle_request(Message::new(client, 1, 0, join), 1);
+    }
+    let send = ChatRequest::SendToGroup {
+        from: "ada".to_string(),
+        group: "ducks".to_string(),
+        message: "quack".to_string(),
+    };
+    let response = server.handle_request(Message::new(3, 1, 1, send), 1);
+    assert!(matches!(
+        response.content,
+        ChatResponse::GroupMessageSent { recipients: 2, .. }
+    ));
+    let mut destinations: Vec<NodeId> = server
+        .notifications()
+        .iter()
+        .map(|notification| notification.destination_id)
+        .collect();
+    destinations.sort();
+    assert_eq!(destinations, vec![4, 5]);
+}
+#[test]
+fn usernames_cannot_be_taken_twice() {
+    let mut server = ChatServerBehaviour::default();
+    let register = |client| Message::new(client, 1, 0, ChatRequest::Register("ada".to_string()));
+    let first = server.handle_request(register(3), 1);
+    assert!(matches!(first.content, ChatResponse::Registered { .. }));
+    let second = server.handle_request(register(4), 1);
+    assert!(matches!(
+        second.content,
+        ChatResponse::Error(ErrorResponse {
+            code: ErrorCode::AlreadyTaken,
+            ..
+        })
+    ));
+    let again = server.handle_request(register(3), 1);
+    assert!(matches!(again.content, ChatResponse::Registered { .. }));
+}
+#[test]
+fn usernames_of_other_clients_cannot_be_used() {
+    let mut server = ChatServerBehaviour::default();
+    let create = ChatRequest::CreateGroup {
+        group: "ducks".to_string(),
+        username: "ada".to_string(),
+    };
+    server.handle_request(Message::new(3, 1, 0, create), 1);
+    let join = ChatRequest::JoinGroup {
+        group: "ducks".to_string(),
+        username: "ada".to_string(),
+    };
+    let joined = server.handle_request(Message::new(4, 1, 1, join), 1);
+    let send = ChatRequest::SendToGroup {
+        from: "ada".to_string(),
+        group: "ducks".to_string(),
+        message: "quack".to_string(),
+    };
+    let sent = server.handle_request(Message::new(4, 1, 2, send), 1);
+    for response in [joined, sent] {
+        assert!(matches!(
+            response.content,
+            ChatResponse::Error(ErrorResponse {
+                code: ErrorCode::AlreadyTaken,
+                ..
+            })
+        ));
+    }
+    assert!(server.notifications().is_empty());
+}
+#[test]
+fn silent_clients_lose_their_usernames() {
+    let mut server = ChatServerBehaviour::default();
+    let register = Message::new(3, 1, 0, ChatRequest::Register("ada".to_string()));
+    server.handle_request(register, 1);
+    server.expire_registrations(Instant::now() + REGISTRATION_TIMEOUT);
+    let keepalive = Message::new(3, 1, 1, ChatRequest::KeepAlive("ada".to_string()));
+    let response = server.handle_request(keepalive, 1);
+    assert!(matches!(
+        response.content,
+        ChatResponse::Error(ErrorResponse {
+            code: ErrorCode::NotRegistered,
+            ..
+        })
+    ));
+}
+#[test]
+fn history_holds_the_messages_after_the_one_asked() {
+    let mut server = ChatServerBehaviour::default();
+    for (client, username) in [(3, "ada"), (4, "bob")] {
+        let register = ChatRequest::Register(username.to_string());
+        server.handle_request(Message::new(client, 1, 0, register), 1);
+    }
+    for message in ["one", "two", "three"] {
+        let send = ChatRequest::SendMessage {
+            from: "ada".to_string(),
+            to: "bob".to_string(),
+            message: message.to_string(),
+        };
+        let delivered = server.handle_request(Message::new(3, 1, 1, send), 1);
+        assert_eq!(delivered.destination_id, 4);
     }
+    let history = Message::new(4, 1, 2, ChatRequest::History { since: 1 });
+    let ChatResponse::History(messages) = server.handle_request(history, 1).content else {
+        panic!("the history was not sent");
+    };
+    let ids: Vec<_> = messages
+        .iter()
+        .filter_map(ChatResponse::message_id)
+        .collect();
+    assert_eq!(ids, vec![2, 3]);
+    // the ids count the messages of every client apart
+    let history = Message::new(3, 1, 3, ChatRequest::History { since: 0 });
+    let response = server.handle_request(history, 1);
+    assert!(matches!(response.content, ChatResponse::History(messages) if messages.is_empty()));
 }
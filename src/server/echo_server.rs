@@ -0,0 +1,69 @@
+use crate::message::base_message::{Message, ServerType};
+use crate::message::content_message::{ContentRequest, ContentResponse};
+use crate::message::echo_message::{unix_millis, EchoResponse};
+use crate::message::error_response::ErrorResponse;
+use crate::server::base_server::{Server, ServerBehaviour};
+use wg_2024::network::NodeId;
+
+pub type EchoServer = Server<EchoServerBehaviour>;
+
+/// Sends every echo request back with how it travelled, a target for measuring the network
+/// that holds no content of its own.
+#[derive(Debug, Default)]
+pub struct EchoServerBehaviour;
+
+impl ServerBehaviour for EchoServerBehaviour {
+    type RequestType = ContentRequest;
+    type ResponseType = ContentResponse;
+
+    fn handle_request(
+        &mut self,
+        req: Message<Self::RequestType>,
+        id: NodeId,
+    ) -> Message<Self::ResponseType> {
+        self.handle_routed_request(req, id, &[])
+    }
+
+    fn handle_routed_request(
+        &mut self,
+        req: Message<Self::RequestType>,
+        id: NodeId,
+        route: &[NodeId],
+    ) -> Message<Self::ResponseType> {
+        let response = match req.content {
+            ContentRequest::Echo(echo) => ContentResponse::Echo(EchoResponse {
+                payload: echo.payload,
+                // the route starts at the client and ends here
+                hops: route.len().saturating_sub(2),
+                one_way_ms: unix_millis().saturating_sub(echo.sent_at),
+            }),
+            ContentRequest::ServerType => ContentResponse::ServerType(ServerType::Echo),
+            _ => ContentResponse::Error(ErrorResponse::service_not_provided("anything but echoes")),
+        };
+        Message::new(id, req.source_id, req.session_id, response)
+    }
+
+    fn server_type() -> ServerType {
+        ServerType::Echo
+    }
+}
+
+#[test]
+fn echoes_carry_the_payload_and_the_drones_crossed() {
+    use crate::message::echo_message::EchoRequest;
+
+    let mut server = EchoServerBehaviour;
+    let echo = EchoRequest {
+        payload: b"quack".to_vec(),
+        sent_at: unix_millis(),
+    };
+    let message = server.handle_routed_request(
+        Message::new(1, 9, 0, ContentRequest::Echo(echo)),
+        9,
+        &[1, 4, 5, 9],
+    );
+    assert!(matches!(
+        message.content,
+        ContentResponse::Echo(EchoResponse { payload, hops: 2, .. }) if payload == b"quack"
+    ));
+}
@@ -0,0 +1,201 @@
+use crate::message::base_message::{Message, ServerType};
+use crate::message::codec::MessageCodec;
+use crate::message::content_message::{ContentRequest, ContentResponse};
+use crate::message::error_response::{ErrorCode, ErrorResponse};
+use crate::message::file_message::{FileRequest, FileResponse};
+use crate::server::base_server::{Server, ServerBehaviour};
+use crate::server::persistence;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io;
+use wg_2024::network::NodeId;
+
+/// Bytes every client can store, all its files together.
+pub const CLIENT_QUOTA: u64 = 4 * 1024 * 1024;
+
+pub type FileStorageServer = Server<FileStorageServerBehaviour>;
+
+fn not_found(name: &str) -> ErrorResponse {
+    ErrorResponse::new(
+        ErrorCode::NotFound,
+        format!("There is no file named {name}"),
+    )
+}
+
+/// Another client uploaded the file, so this one cannot replace or delete it.
+fn not_owner(name: &str) -> ErrorResponse {
+    let message = format!("{name} was uploaded by another client");
+    ErrorResponse::new(ErrorCode::NotOwner, message)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredFile {
+    owner: NodeId,
+    data: Vec<u8>,
+}
+
+/// Stores any file the clients upload, up to [`CLIENT_QUOTA`] bytes each, and hands them to
+/// every client asking. Only the client that uploaded a file can replace or delete it.
+#[derive(Debug, Default)]
+pub struct FileStorageServerBehaviour {
+    files: HashMap<String, StoredFile>,
+}
+
+impl FileStorageServerBehaviour {
+    /// The bytes the client stores.
+    fn used_by(&self, client: NodeId) -> u64 {
+        self.files
+            .values()
+            .filter(|file| file.owner == client)
+            .map(|file| file.data.len() as u64)
+            .sum()
+    }
+
+    fn upload(
+        &mut self,
+        client: NodeId,
+        name: String,
+        data: Vec<u8>,
+    ) -> Result<FileResponse, ErrorResponse> {
+        let replaced = match self.files.get(&name) {
+            Some(file) if file.owner != client => return Err(not_owner(&name)),
+            Some(file) => file.data.len() as u64,
+            None => 0,
+        };
+        let used = self.used_by(client) - replaced;
+        if used + data.len() as u64 > CLIENT_QUOTA {
+            let message = format!(
+                "Storing {name} would go past the quota, {used} of {CLIENT_QUOTA} bytes being used"
+            );
+            return Err(ErrorResponse::new(ErrorCode::QuotaExceeded, message));
+        }
+        self.files.insert(
+            name.clone(),
+            StoredFile {
+                owner: client,
+                data,
+            },
+        );
+        Ok(FileResponse::Uploaded(name))
+    }
+
+    fn delete(&mut self, client: NodeId, name: String) -> Result<FileResponse, ErrorResponse> {
+        match self.files.get(&name) {
+            None => Err(not_found(&name)),
+            Some(file) if file.owner != client => Err(not_owner(&name)),
+            Some(_) => {
+                self.files.remove(&name);
+                Ok(FileResponse::Deleted(name))
+            }
+        }
+    }
+
+    fn handle_file_request(
+        &mut self,
+        client: NodeId,
+        request: FileRequest,
+    ) -> Result<FileResponse, ErrorResponse> {
+        match request {
+            FileRequest::FileList => {
+                let mut files: Vec<(String, u64)> = self
+                    .files
+                    .iter()
+                    .map(|(name, file)| (name.clone(), file.data.len() as u64))
+                    .collect();
+                files.sort();
+                Ok(FileResponse::FileList(files))
+            }
+            FileRequest::File(name) => match self.files.get(&name) {
+                Some(file) => Ok(FileResponse::File(file.data.clone())),
+                None => Err(not_found(&name)),
+            },
+            FileRequest::Upload(name, data) => self.upload(client, name, data),
+            FileRequest::Delete(name) => self.delete(client, name),
+        }
+    }
+}
+
+impl ServerBehaviour for FileStorageServerBehaviour {
+    type RequestType = ContentRequest;
+    type ResponseType = ContentResponse;
+
+    fn codec() -> MessageCodec {
+        MessageCodec::Bincode
+    }
+
+    fn handle_request(
+        &mut self,
+        req: Message<Self::RequestType>,
+        id: NodeId,
+    ) -> Message<Self::ResponseType> {
+        let response = match req.content {
+            ContentRequest::TextRequest(_)
+            | ContentRequest::MediaRequest(_)
+            | ContentRequest::Echo(_)
+            | ContentRequest::NotifyOnNew
+            | ContentRequest::ServerStats => {
+                ContentResponse::Error(ErrorResponse::service_not_provided("file storage"))
+            }
+            ContentRequest::ServerType => ContentResponse::ServerType(ServerType::Storage),
+            ContentRequest::FileRequest(request) => {
+                match self.handle_file_request(req.source_id, request) {
+                    Ok(response) => ContentResponse::FileResponse(response),
+                    Err(error) => ContentResponse::Error(error),
+                }
+            }
+        };
+        Message::new(id, req.source_id, req.session_id, response)
+    }
+
+    fn restore(&mut self, id: NodeId) {
+        if let Some(files) = persistence::load("storage", id) {
+            self.files = files;
+        }
+    }
+
+    fn persist(&self, id: NodeId) -> io::Result<()> {
+        persistence::save("storage", id, &self.files)
+    }
+
+    fn server_type() -> ServerType {
+        ServerType::Storage
+    }
+}
+
+#[test]
+fn clients_cannot_store_past_their_quota() {
+    let mut server = FileStorageServerBehaviour::default();
+    let half = vec![0; CLIENT_QUOTA as usize / 2];
+    let upload = |name: &str, data: &[u8]| FileRequest::Upload(name.to_string(), data.to_vec());
+    assert!(matches!(
+        server.handle_file_request(3, upload("a", &half)),
+        Ok(FileResponse::Uploaded(_))
+    ));
+    // replacing a file only counts its new size
+    assert!(matches!(
+        server.handle_file_request(3, upload("a", &half)),
+        Ok(FileResponse::Uploaded(_))
+    ));
+    assert!(matches!(
+        server.handle_file_request(3, upload("b", &half)),
+        Ok(FileResponse::Uploaded(_))
+    ));
+    assert!(matches!(
+        server.handle_file_request(3, upload("c", &[0])),
+        Err(ErrorResponse {
+            code: ErrorCode::QuotaExceeded,
+            ..
+        })
+    ));
+    assert!(matches!(
+        server.handle_file_request(4, FileRequest::Delete("a".to_string())),
+        Err(ErrorResponse {
+            code: ErrorCode::NotOwner,
+            ..
+        })
+    ));
+    assert!(matches!(
+        server.handle_file_request(4, upload("c", &[0])),
+        Ok(FileResponse::Uploaded(_))
+    ));
+}
@@ -0,0 +1,194 @@
+use crate::message::base_message::{Message, ServerType};
+use crate::message::content_message::{ContentRequest, ContentResponse};
+use crate::message::content_metadata::ContentMetadata;
+use crate::message::media_message::{MediaListing, MediaRequest, MediaResponse, CHUNK_SIZE};
+use crate::message::page::PageRequest;
+use crate::server::media_store::MediaStore;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use wg_2024::network::NodeId;
+
+/// How often the servers compare their content with the other servers they reach.
+pub const SYNC_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A media being copied from another server, one chunk at a time.
+#[derive(Debug)]
+struct Pull {
+    metadata: ContentMetadata,
+    data: Vec<u8>,
+    last_progress: Instant,
+}
+
+/// Copies to a media server the media the other media servers have and it lacks, so that the
+/// clients find them on either.
+///
+/// Every sync asks the servers not known yet what they are and the media servers for their
+/// list, page after page. The media missing are then pulled with the requests the clients use:
+/// their metadata first, then their chunks, checking the copy against the metadata once it is
+/// complete.
+#[derive(Debug, Default)]
+pub struct MediaReplication {
+    /// Whether each server answered that it is a media server.
+    peers: HashMap<NodeId, bool>,
+    pulls: HashMap<(NodeId, String), Pull>,
+}
+
+impl MediaReplication {
+    /// The requests starting a sync with the servers reached, giving up the pulls that stalled
+    /// since the last one.
+    pub fn sync_requests(
+        &mut self,
+        id: NodeId,
+        servers: &[NodeId],
+    ) -> Vec<Message<ContentRequest>> {
+        self.pulls
+            .retain(|_key, pull| pull.last_progress.elapsed() < SYNC_INTERVAL);
+        servers
+            .iter()
+            .filter_map(|&server| {
+                let request = match self.peers.get(&server) {
+                    None => ContentRequest::ServerType,
+                    Some(true) => MediaRequest::MediaList(PageRequest::default()).into(),
+                    Some(false) => return None,
+                };
+                Some(Message::new(id, server, 0, request))
+            })
+            .collect()
+    }
+
+    /// The requests following the reply of a server, and the media whose copy it completed.
+    /// The media whose bytes the library already has under another name are copied without
+    /// pulling them.
+    pub fn handle_reply(
+        &mut self,
+        reply: Message<ContentResponse>,
+        library: &MediaStore,
+        id: NodeId,
+    ) -> (Vec<Message<ContentRequest>>, Vec<(String, Vec<u8>)>) {
+        let server = reply.source_id;
+        let request = |request: MediaRequest| Message::new(id, server, 0, request.into());
+        let mut requests = Vec::new();
+        let mut copied = Vec::new();
+        match reply.content {
+            ContentResponse::ServerType(server_type) => {
+                let is_media = server_type == ServerType::Media;
+                self.peers.insert(server, is_media);
+                if is_media {
+                    requests.push(request(MediaRequest::MediaList(PageRequest::default())));
+                }
+            }
+            ContentResponse::MediaResponse(MediaResponse::MediaList(page)) => {
+                if let Some(next) = page.next {
+                    let rest = PageRequest::default().after(next);
+                    requests.push(request(MediaRequest::MediaList(rest)));
+                }
+                for MediaListing { name, hash } in page.items {
+                    if library.contains(&name) || self.pulls.contains_key(&(server, name.clone())) {
+                        continue;
+                    }
+                    match library.get_by_hash(hash) {
+                        Some(media) => copied.push((name, media.clone())),
+                        None => requests.push(request(MediaRequest::Metadata(name))),
+                    }
+                }
+            }
+            ContentResponse::MediaResponse(MediaResponse::Metadata(metadata)) => {
+                let key = (server, metadata.name.clone());
+                if !library.contains(&metadata.name) && !self.pulls.contains_key(&key) {
+                    requests.push(request(MediaRequest::Chunk {
+                        name: metadata.name.clone(),
+                        offset: 0,
+                        len: CHUNK_SIZE,
+                    }));
+                    let pull = Pull {
+                        data: Vec::with_capacity(metadata.size as usize),
+                        metadata,
+                        last_progress: Instant::now(),
+                    };
+                    self.pulls.insert(key, pull);
+                }
+            }
+            ContentResponse::MediaResponse(MediaResponse::Chunk { name, offset, data }) => {
+                let key = (server, name);
+                let Some(pull) = self.pulls.get_mut(&key) else {
+                    return (requests, copied);
+                };
+                if offset != pull.data.len() as u64 || data.is_empty() {
+                    // a duplicate, or the media shrank meanwhile
+                    self.pulls.remove(&key);
+                    return (requests, copied);
+                }
+                pull.data.extend(data);
+                pull.last_progress = Instant::now();
+                let received = pull.data.len() as u64;
+                if received < pull.metadata.size {
+                    requests.push(request(MediaRequest::Chunk {
+                        name: key.1.clone(),
+                        offset: received,
+                        len: CHUNK_SIZE,
+                    }));
+                } else if let Some(pull) = self.pulls.remove(&key) {
+                    if pull.metadata.matches(&pull.data) {
+                        copied.push((key.1, pull.data));
+                    }
+                }
+            }
+            _ => {}
+        }
+        (requests, copied)
+    }
+}
+
+#[test]
+fn missing_media_are_pulled_chunk_by_chunk() {
+    use crate::message::content_metadata::content_hash;
+    use crate::server::asset_directory::Library;
+
+    let mut replication = MediaReplication::default();
+    let mut library = MediaStore::default();
+    library.insert_item("#kept".to_string(), vec![1]);
+    let reply = |content: ContentResponse| Message::new(9, 1, 0, content);
+
+    let requests = replication.sync_requests(1, &[9]);
+    assert!(matches!(requests[0].content, ContentRequest::ServerType));
+    let (requests, _) = replication.handle_reply(
+        reply(ContentResponse::ServerType(ServerType::Media)),
+        &library,
+        1,
+    );
+    assert_eq!(requests[0].destination_id, 9);
+
+    let listing = [("#kept", 1), ("#alias", content_hash(&[1])), ("#new", 2)]
+        .map(|(name, hash)| MediaListing {
+            name: name.to_string(),
+            hash,
+        })
+        .to_vec();
+    let list = MediaResponse::MediaList(PageRequest::default().page(listing));
+    let (requests, copied) =
+        replication.handle_reply(reply(ContentResponse::MediaResponse(list)), &library, 1);
+    assert_eq!(requests.len(), 1);
+    assert_eq!(copied, vec![("#alias".to_string(), vec![1])]);
+
+    let media = vec![7; CHUNK_SIZE as usize + 1];
+    let metadata = ContentMetadata::of("#new".to_string(), &media);
+    let (requests, _) = replication.handle_reply(
+        reply(ContentResponse::MediaResponse(MediaResponse::Metadata(
+            metadata,
+        ))),
+        &library,
+        1,
+    );
+    assert_eq!(requests.len(), 1);
+    let mut copied = Vec::new();
+    for (i, chunk) in media.chunks(CHUNK_SIZE as usize).enumerate() {
+        let chunk = MediaResponse::Chunk {
+            name: "#new".to_string(),
+            offset: i as u64 * CHUNK_SIZE,
+            data: chunk.to_vec(),
+        };
+        let reply = reply(ContentResponse::MediaResponse(chunk));
+        copied = replication.handle_reply(reply, &library, 1).1;
+    }
+    assert_eq!(copied, vec![("#new".to_string(), media)]);
+}
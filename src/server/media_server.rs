@@ -1,52 +1,122 @@
-use crate::application::topology::node::ApplicationType;
-use crate::message::base_message::Message;
+use crate::message::base_message::{Message, ServerType};
+use crate::message::codec::MessageCodec;
 use crate::message::content_message::{ContentRequest, ContentResponse};
-use crate::message::media_message::{MediaRequest, MediaResponse};
+use crate::message::content_metadata::ContentMetadata;
+use crate::message::error_response::{ErrorCode, ErrorResponse};
+use crate::message::media_message::{MediaRequest, MediaResponse, CHUNK_SIZE};
+use crate::message::page::PageRequest;
+use crate::server::asset_directory::{AssetDirectory, Library, LibraryChange};
 use crate::server::base_server::{Server, ServerBehaviour};
-use std::collections::HashMap;
-use std::fs;
+use crate::server::media_replication::MediaReplication;
+use crate::server::media_store::MediaStore;
+use crate::server::persistence;
+use crate::server::subscribers::Subscribers;
+use image::{ImageOutputFormat, ImageResult};
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Cursor};
 use wg_2024::network::NodeId;
 
 pub type MediaServer = Server<crate::server::media_server::MediaServerBehaviour>;
 #[derive(Debug)]
 pub struct MediaServerBehaviour {
-    media_library: HashMap<String, Vec<u8>>,
+    media_library: MediaStore,
+    assets: AssetDirectory,
+    /// The media uploaded or scraped, kept across restarts unlike those of the assets.
+    added: HashSet<String>,
+    subscribers: Subscribers,
+    /// The new media, for the subscribers.
+    notifications: Vec<Message<ContentResponse>>,
+    /// The media being copied from the other media servers.
+    replication: MediaReplication,
+}
+/// The answer to a request naming a media the server does not have.
+fn not_found(name: &str) -> ContentResponse {
+    let message = format!("There is no media named {name}");
+    ContentResponse::Error(ErrorResponse::new(ErrorCode::NotFound, message))
+}
+/// The name a media is served under, from the name of its file.
+fn media_name(file_name: &str) -> String {
+    format!("#{}", file_name.replace(".png", ""))
+}
+/// The media as a PNG fitting a square of `max_px` pixels, keeping its proportions, or the
+/// media itself if it fits already.
+fn thumbnail(media: &[u8], max_px: u32) -> ImageResult<Vec<u8>> {
+    let image = image::load_from_memory(media)?;
+    let max_px = max_px.max(1);
+    if image.width() <= max_px && image.height() <= max_px {
+        return Ok(media.to_vec());
+    }
+    let mut png = Vec::new();
+    image
+        .thumbnail(max_px, max_px)
+        .write_to(&mut Cursor::new(&mut png), ImageOutputFormat::Png)?;
+    Ok(png)
 }
 impl Default for MediaServerBehaviour {
     fn default() -> Self {
-        let media_library = fs::read_dir("./assets/medias")
-            .unwrap()
-            .filter_map(|entry| {
-                let entry = entry.ok()?;
+        Self::with_assets(AssetDirectory::shared("medias", media_name))
+    }
+}
+impl MediaServerBehaviour {
+    fn with_assets(mut assets: AssetDirectory) -> Self {
+        let mut media_library = MediaStore::default();
+        assets.reload(&mut media_library, &HashSet::new());
+        MediaServerBehaviour {
+            media_library,
+            assets,
+            added: HashSet::new(),
+            subscribers: Subscribers::default(),
+            notifications: Vec::new(),
+            replication: MediaReplication::default(),
+        }
+    }
 
-                let hashtag_filename = format!(
-                    "#{}",
-                    entry.file_name().into_string().unwrap().replace(".png", "")
-                );
-                Some((hashtag_filename, fs::read(entry.path()).ok()?))
-            })
-            .collect();
-        MediaServerBehaviour { media_library }
+    /// Stores the media, telling the subscribers other than `client` if it is new.
+    fn add_media(&mut self, name: String, media: Vec<u8>, id: NodeId, client: NodeId) {
+        self.added.insert(name.clone());
+        if self.media_library.insert_item(name.clone(), media) {
+            let new = ContentResponse::NewContent(name);
+            let notifications = self.subscribers.notify(id, client, new);
+            self.notifications.extend(notifications);
+        }
     }
 }
 impl ServerBehaviour for MediaServerBehaviour {
     type RequestType = ContentRequest;
     type ResponseType = ContentResponse;
 
+    fn codec() -> MessageCodec {
+        MessageCodec::Bincode
+    }
+
+    fn for_server(id: NodeId) -> Self {
+        Self::with_assets(AssetDirectory::of_server(id, "medias", media_name))
+    }
+
     fn handle_request(
         &mut self,
         req: Message<Self::RequestType>,
         id: NodeId,
     ) -> Message<Self::ResponseType> {
         match req.content {
-            ContentRequest::TextRequest(_) => {
-                req.generate_response(ContentResponse::ServiceNotProvided)
+            ContentRequest::TextRequest(_)
+            | ContentRequest::FileRequest(_)
+            | ContentRequest::Echo(_)
+            | ContentRequest::ServerStats => {
+                let error = ErrorResponse::service_not_provided("media content");
+                req.generate_response(ContentResponse::Error(error))
+            }
+            ContentRequest::ServerType => {
+                req.generate_response(ContentResponse::ServerType(ServerType::Media))
+            }
+            ContentRequest::NotifyOnNew => {
+                self.subscribers.subscribe(req.source_id);
+                req.generate_response(ContentResponse::Subscribed)
             }
             ContentRequest::MediaRequest(active_request) => match active_request {
-                MediaRequest::MediaList => {
-                    let response = MediaResponse::MediaList(
-                        self.media_library.keys().cloned().collect::<Vec<String>>(),
-                    );
+                MediaRequest::MediaList(page) => {
+                    let response =
+                        MediaResponse::MediaList(page.page(self.media_library.listing()));
                     Message::new(
                         id,
                         req.source_id,
@@ -56,29 +126,81 @@ impl ServerBehaviour for MediaServerBehaviour {
                 }
                 MediaRequest::Media(requested_id) => {
                     let response = if let Some(media) = self.media_library.get(&requested_id) {
-                        MediaResponse::Media(media.clone())
+                        ContentResponse::MediaResponse(MediaResponse::Media(media.clone()))
                     } else {
-                        MediaResponse::NotFound
+                        not_found(&requested_id)
+                    };
+                    Message::new(id, req.source_id, req.session_id, response)
+                }
+                MediaRequest::Metadata(name) => {
+                    let response = match self.media_library.get(&name) {
+                        Some(media) => ContentResponse::MediaResponse(MediaResponse::Metadata(
+                            ContentMetadata::of(name, media),
+                        )),
+                        None => not_found(&name),
+                    };
+                    Message::new(id, req.source_id, req.session_id, response)
+                }
+                MediaRequest::Chunk { name, offset, len } => {
+                    let response = match self.media_library.get(&name) {
+                        Some(media) => {
+                            let start = (offset as usize).min(media.len());
+                            let end =
+                                start + (len.min(CHUNK_SIZE) as usize).min(media.len() - start);
+                            ContentResponse::MediaResponse(MediaResponse::Chunk {
+                                name,
+                                offset,
+                                data: media[start..end].to_vec(),
+                            })
+                        }
+                        None => not_found(&name),
                     };
+                    Message::new(id, req.source_id, req.session_id, response)
+                }
+                MediaRequest::Thumbnail(name, max_px) => {
+                    let response = match self
+                        .media_library
+                        .get(&name)
+                        .map(|media| thumbnail(media, max_px))
+                    {
+                        Some(Ok(thumbnail)) => {
+                            ContentResponse::MediaResponse(MediaResponse::Thumbnail(thumbnail))
+                        }
+                        Some(Err(err)) => {
+                            println!("Server {id} cannot scale {name} down: {err}");
+                            let message = format!("{name} is not an image it can scale down");
+                            let error = ErrorResponse::new(ErrorCode::Unprocessable, message);
+                            ContentResponse::Error(error)
+                        }
+                        None => not_found(&name),
+                    };
+                    Message::new(id, req.source_id, req.session_id, response)
+                }
+                MediaRequest::Upload(name, media) => {
+                    self.add_media(name.clone(), media, id, req.source_id);
                     Message::new(
                         id,
                         req.source_id,
                         req.session_id,
-                        ContentResponse::MediaResponse(response),
+                        ContentResponse::MediaResponse(MediaResponse::Uploaded(name)),
                     )
                 }
                 MediaRequest::ExpandList => {
-                    let mut scraper2 = crate::server::scraper::Scraper::new();
-                    let html = scraper2.get_html("https://scrapeme.live/shop/").unwrap();
-                    let urls = scraper2.get_urls(html);
-                    scraper2.insert_urls(urls);
+                    let mut scraper2 = crate::server::scraper::Scraper::from_env();
+                    if let Err(err) = scraper2.scrape() {
+                        println!("Server {id} cannot expand its list: {err}");
+                    }
                     for (name, png) in scraper2.data {
                         let newkey = format!("#{}", name.clone().to_ascii_lowercase());
-                        self.media_library.insert(newkey, png);
+                        self.add_media(newkey, png, id, req.source_id);
                     }
-                    let response = MediaResponse::MediaList(
-                        self.media_library.keys().cloned().collect::<Vec<String>>(),
+                    println!(
+                        "Server {id} has {} media, {} distinct",
+                        self.media_library.listing().len(),
+                        self.media_library.distinct()
                     );
+                    let listing = PageRequest::default().page(self.media_library.listing());
+                    let response = MediaResponse::MediaList(listing);
                     Message::new(
                         id,
                         req.source_id,
@@ -90,8 +212,61 @@ impl ServerBehaviour for MediaServerBehaviour {
         }
     }
 
-    fn application_type() -> ApplicationType {
-        ApplicationType::Content
+    fn reload_assets(&mut self, id: NodeId) -> LibraryChange {
+        let change = self.assets.reload(&mut self.media_library, &self.added);
+        for name in &change.added {
+            let new = ContentResponse::NewContent(name.clone());
+            let notifications = self.subscribers.notify(id, id, new);
+            self.notifications.extend(notifications);
+        }
+        change
+    }
+
+    fn sync_requests(&mut self, id: NodeId, servers: &[NodeId]) -> Vec<Message<ContentRequest>> {
+        self.replication.sync_requests(id, servers)
+    }
+
+    fn handle_reply(
+        &mut self,
+        reply: Message<ContentResponse>,
+        id: NodeId,
+    ) -> Vec<Message<ContentRequest>> {
+        let server = reply.source_id;
+        let (requests, copied) = self
+            .replication
+            .handle_reply(reply, &self.media_library, id);
+        for (name, media) in copied {
+            println!("Server {id} copied {name} from server {server}");
+            self.add_media(name, media, id, id);
+        }
+        requests
+    }
+
+    fn notifications(&mut self) -> Vec<Message<ContentResponse>> {
+        self.notifications.drain(..).collect()
+    }
+
+    fn restore(&mut self, id: NodeId) {
+        let Some(media) = persistence::load::<HashMap<String, Vec<u8>>>("media", id) else {
+            return;
+        };
+        self.added.extend(media.keys().cloned());
+        for (name, media) in media {
+            self.media_library.insert_item(name, media);
+        }
+    }
+
+    fn persist(&self, id: NodeId) -> io::Result<()> {
+        let media: HashMap<&String, &Vec<u8>> = self
+            .media_library
+            .iter()
+            .filter(|(name, _media)| self.added.contains(*name))
+            .collect();
+        persistence::save("media", id, &media)
+    }
+
+    fn server_type() -> ServerType {
+        ServerType::Media
     }
 }
 #[test]
@@ -102,9 +277,65 @@ fn test_medias() {
             0,
             0,
             0,
-            ContentRequest::MediaRequest(MediaRequest::MediaList),
+            ContentRequest::MediaRequest(MediaRequest::MediaList(PageRequest::default())),
         ),
         0,
     );
     println!("{:?}", message);
 }
+#[test]
+fn media_can_be_read_in_chunks() {
+    let mut server = MediaServerBehaviour::default();
+    let media = vec![7; CHUNK_SIZE as usize + 10];
+    let upload = MediaRequest::Upload("#big".to_string(), media);
+    server.handle_request(Message::new(0, 0, 0, upload.into()), 0);
+    let chunk = MediaRequest::Chunk {
+        name: "#big".to_string(),
+        offset: CHUNK_SIZE,
+        len: CHUNK_SIZE,
+    };
+    let message = server.handle_request(Message::new(0, 0, 1, chunk.into()), 0);
+    assert!(matches!(
+        message.content,
+        ContentResponse::MediaResponse(MediaResponse::Chunk { data, .. }) if data.len() == 10
+    ));
+}
+#[test]
+fn media_metadata_describes_the_bytes() {
+    let mut server = MediaServerBehaviour::default();
+    let media = b"\x89PNG not quite an image".to_vec();
+    let upload = MediaRequest::Upload("#tiny".to_string(), media.clone());
+    server.handle_request(Message::new(0, 0, 0, upload.into()), 0);
+    let request = MediaRequest::Metadata("#tiny".to_string());
+    let message = server.handle_request(Message::new(0, 0, 1, request.into()), 0);
+    let ContentResponse::MediaResponse(MediaResponse::Metadata(metadata)) = message.content else {
+        panic!("expected the metadata of the media");
+    };
+    assert_eq!(metadata.mime_type, "image/png");
+    assert!(metadata.matches(&media));
+    assert!(!metadata.matches(&media[1..]));
+}
+#[test]
+fn thumbnails_fit_the_size_asked() {
+    let mut png = Vec::new();
+    image::RgbImage::from_pixel(64, 32, image::Rgb([200, 120, 40]))
+        .write_to(&mut Cursor::new(&mut png), ImageOutputFormat::Png)
+        .unwrap();
+    let mut server = MediaServerBehaviour::default();
+    let upload = MediaRequest::Upload("#wide".to_string(), png.clone());
+    server.handle_request(Message::new(0, 0, 0, upload.into()), 0);
+    let request = MediaRequest::Thumbnail("#wide".to_string(), 16);
+    let message = server.handle_request(Message::new(0, 0, 1, request.into()), 0);
+    let ContentResponse::MediaResponse(MediaResponse::Thumbnail(thumbnail)) = message.content
+    else {
+        panic!("expected a thumbnail");
+    };
+    let thumbnail = image::load_from_memory(&thumbnail).unwrap();
+    assert_eq!((thumbnail.width(), thumbnail.height()), (16, 8));
+    let request = MediaRequest::Thumbnail("#wide".to_string(), 100);
+    let message = server.handle_request(Message::new(0, 0, 2, request.into()), 0);
+    assert!(matches!(
+        message.content,
+        ContentResponse::MediaResponse(MediaResponse::Thumbnail(same)) if same == png
+    ));
+}
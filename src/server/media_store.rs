@@ -0,0 +1,101 @@
+use crate::message::content_metadata::content_hash;
+use crate::message::media_message::MediaListing;
+use crate::server::asset_directory::Library;
+use std::collections::HashMap;
+
+/// The media of a server, each stored once however many names it has: scraping the same page
+/// twice, or copying a media another server has under another name, adds names but no bytes.
+#[derive(Debug, Default)]
+pub struct MediaStore {
+    /// The bytes of every media, by [`content_hash`].
+    blobs: HashMap<u64, Vec<u8>>,
+    /// The hash of the media each name refers to.
+    names: HashMap<String, u64>,
+}
+
+impl MediaStore {
+    pub fn get(&self, name: &str) -> Option<&Vec<u8>> {
+        self.names.get(name).map(|hash| &self.blobs[hash])
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.names.contains_key(name)
+    }
+
+    /// The media with the hash, under whichever name it is stored.
+    pub fn get_by_hash(&self, hash: u64) -> Option<&Vec<u8>> {
+        self.blobs.get(&hash)
+    }
+
+    /// The names with the hash of the media each refers to, by name.
+    pub fn listing(&self) -> Vec<MediaListing> {
+        let mut listing: Vec<_> = self
+            .names
+            .iter()
+            .map(|(name, &hash)| MediaListing {
+                name: name.clone(),
+                hash,
+            })
+            .collect();
+        listing.sort_by(|a, b| a.name.cmp(&b.name));
+        listing
+    }
+
+    /// The names with the bytes they refer to, those of identical media shared.
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Vec<u8>)> {
+        self.names
+            .iter()
+            .map(|(name, hash)| (name, &self.blobs[hash]))
+    }
+
+    /// How many distinct media the names refer to.
+    pub fn distinct(&self) -> usize {
+        self.blobs.len()
+    }
+
+    /// Drops the bytes no name refers to anymore.
+    fn release(&mut self, hash: u64) {
+        if !self.names.values().any(|&other| other == hash) {
+            self.blobs.remove(&hash);
+        }
+    }
+}
+
+impl Library for MediaStore {
+    /// Stores the media under the name, sharing the bytes of an identical one.
+    fn insert_item(&mut self, name: String, data: Vec<u8>) -> bool {
+        let hash = content_hash(&data);
+        self.blobs.entry(hash).or_insert(data);
+        match self.names.insert(name, hash) {
+            Some(previous) if previous != hash => {
+                self.release(previous);
+                false
+            }
+            previous => previous.is_none(),
+        }
+    }
+
+    fn remove_item(&mut self, name: &str) -> bool {
+        let Some(hash) = self.names.remove(name) else {
+            return false;
+        };
+        self.release(hash);
+        true
+    }
+}
+
+#[test]
+fn identical_media_are_stored_once() {
+    let mut store = MediaStore::default();
+    assert!(store.insert_item("#a".to_string(), vec![1, 2]));
+    assert!(store.insert_item("#b".to_string(), vec![1, 2]));
+    assert!(!store.insert_item("#a".to_string(), vec![1, 2]));
+    assert_eq!(store.distinct(), 1);
+    assert_eq!(store.listing()[0].hash, store.listing()[1].hash);
+    assert!(!store.insert_item("#b".to_string(), vec![3]));
+    assert_eq!(store.distinct(), 2);
+    store.remove_item("#a");
+    assert_eq!(store.distinct(), 1);
+    assert_eq!(store.get("#b"), Some(&vec![3]));
+    assert!(store.get_by_hash(content_hash(&[1, 2])).is_none());
+}
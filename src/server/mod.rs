@@ -1,5 +1,15 @@
+mod access_log;
+mod asset_directory;
 pub mod base_server;
+mod behaviour_worker;
 pub mod chat_server;
+pub mod echo_server;
+pub mod file_storage_server;
+mod media_replication;
 pub mod media_server;
+mod media_store;
+mod persistence;
 mod scraper;
+pub mod subscribers;
+mod text_index;
 pub mod text_server;
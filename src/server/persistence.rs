@@ -0,0 +1,48 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+use wg_2024::network::NodeId;
+
+/// The directory the servers keep what they learned in, `./server_state` if unset: one file
+/// per server, so that a server respawned with the same id picks up where it left off.
+pub const STATE_VARIABLE: &str = "BAGEL_STATE";
+
+const DEFAULT_STATE_DIRECTORY: &str = "./server_state";
+/// How often the servers save their state while running, besides when they stop.
+pub const PERSIST_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The directory the state is kept in, as [`STATE_VARIABLE`] tells.
+pub fn directory() -> PathBuf {
+    if let Ok(directory) = env::var(STATE_VARIABLE) {
+        return PathBuf::from(directory);
+    }
+    // the tests of a run keep their state apart from the working tree and from other runs
+    if cfg!(test) {
+        return env::temp_dir().join(format!("bagel_state_{}", std::process::id()));
+    }
+    PathBuf::from(DEFAULT_STATE_DIRECTORY)
+}
+
+fn path(kind: &str, id: NodeId) -> PathBuf {
+    directory().join(format!("{kind}_{id}.bin"))
+}
+
+/// The state saved by the server of this kind and id, if there is one that can be read.
+pub fn load<T: DeserializeOwned>(kind: &str, id: NodeId) -> Option<T> {
+    let data = fs::read(path(kind, id)).ok()?;
+    bincode::deserialize(&data).ok()
+}
+
+/// Saves the state of the server, replacing the previous one only once it is fully written.
+pub fn save<T: Serialize>(kind: &str, id: NodeId, state: &T) -> io::Result<()> {
+    fs::create_dir_all(directory())?;
+    let data = bincode::serialize(state).map_err(io::Error::other)?;
+    let path = path(kind, id);
+    let partial = path.with_extension("partial");
+    fs::write(&partial, data)?;
+    fs::rename(partial, path)
+}
@@ -1,74 +1,164 @@
-use rand::Rng;
+use rand::seq::SliceRandom;
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
+use std::env;
+use std::fmt::Display;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::PathBuf;
+
+/// The shop the media servers scrape their new media from, scrapeme.live if unset.
+pub const SCRAPE_URL_VARIABLE: &str = "BAGEL_SCRAPE_URL";
+/// Set to anything to have the scraper use only the pages and images it downloaded before.
+pub const SCRAPER_OFFLINE_VARIABLE: &str = "BAGEL_SCRAPER_OFFLINE";
+
+const DEFAULT_SCRAPE_URL: &str = "https://scrapeme.live/shop/";
+/// Where every page and image downloaded is kept, one file per URL.
+const CACHE_DIRECTORY: &str = "scraper_cache";
+/// Products picked from the shop on every scrape.
+const PRODUCTS_PER_SCRAPE: usize = 4;
+
+#[derive(Debug)]
+pub enum ScrapeError {
+    /// The scraper is offline and never downloaded the URL.
+    NotCached(String),
+    Http(attohttpc::Error),
+    /// The page is not UTF-8.
+    NotText(String),
+    /// The page lists no product with a name and an image.
+    NoProducts(String),
+}
+
+impl Display for ScrapeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScrapeError::NotCached(url) => write!(f, "{url} was never downloaded"),
+            ScrapeError::Http(err) => write!(f, "{err}"),
+            ScrapeError::NotText(url) => write!(f, "{url} is not a page"),
+            ScrapeError::NoProducts(url) => write!(f, "{url} lists no product"),
+        }
+    }
+}
+
+impl From<attohttpc::Error> for ScrapeError {
+    fn from(err: attohttpc::Error) -> Self {
+        ScrapeError::Http(err)
+    }
+}
 
 #[derive(Debug)]
 pub struct Scraper {
     pub data: HashMap<String, Vec<u8>>,
+    source: String,
+    offline: bool,
 }
 impl Scraper {
-    pub fn new() -> Scraper {
+    pub fn new(source: String, offline: bool) -> Scraper {
         Scraper {
             data: HashMap::new(),
+            source,
+            offline,
         }
     }
-    pub fn get_image(&mut self, url: &str) -> Option<Vec<u8>> {
-        let resp = attohttpc::get(url).send();
-        let bytes = resp.unwrap().bytes().unwrap();
-        // let path = Path::new(path);
-        // Scraper::write_to_file(
-        //     path.display().to_string(),
-        //     bytes.clone()).expect("write error");
-        Some(bytes)
+    /// A scraper set up by [`SCRAPE_URL_VARIABLE`] and [`SCRAPER_OFFLINE_VARIABLE`].
+    pub fn from_env() -> Scraper {
+        let source =
+            env::var(SCRAPE_URL_VARIABLE).unwrap_or_else(|_| DEFAULT_SCRAPE_URL.to_string());
+        Scraper::new(source, env::var_os(SCRAPER_OFFLINE_VARIABLE).is_some())
+    }
+    fn cache_path(url: &str) -> PathBuf {
+        let mut hasher = DefaultHasher::new();
+        url.hash(&mut hasher);
+        PathBuf::from(CACHE_DIRECTORY).join(format!("{:016x}", hasher.finish()))
     }
-    pub fn get_html(&mut self, url: &str) -> Option<String> {
-        let resp = attohttpc::get(url).send();
-        let bytes = resp.unwrap().text().unwrap();
-        Self::get_urls(self, bytes.clone());
-        Some(bytes)
+    fn is_cached(url: &str) -> bool {
+        Self::cache_path(url).exists()
+    }
+    fn cache(url: &str, bytes: &[u8]) -> io::Result<()> {
+        fs::create_dir_all(CACHE_DIRECTORY)?;
+        fs::write(Self::cache_path(url), bytes)
+    }
+    /// The bytes at the URL, downloaded only if they are not in the cache already.
+    fn fetch(&self, url: &str) -> Result<Vec<u8>, ScrapeError> {
+        if let Ok(bytes) = fs::read(Self::cache_path(url)) {
+            return Ok(bytes);
+        }
+        if self.offline {
+            return Err(ScrapeError::NotCached(url.to_string()));
+        }
+        let bytes = attohttpc::get(url).send()?.error_for_status()?.bytes()?;
+        if let Err(err) = Self::cache(url, &bytes) {
+            println!("Unable to cache {url}: {err}");
+        }
+        Ok(bytes)
+    }
+    pub fn get_image(&self, url: &str) -> Result<Vec<u8>, ScrapeError> {
+        self.fetch(url)
+    }
+    pub fn get_html(&self, url: &str) -> Result<String, ScrapeError> {
+        String::from_utf8(self.fetch(url)?).map_err(|_| ScrapeError::NotText(url.to_string()))
     }
 
-    pub fn get_urls(&mut self, html: String) -> Vec<(String, String)> {
+    /// The name and image URL of every product of the page.
+    pub fn get_urls(&self, html: String) -> Vec<(String, String)> {
         let document = scraper::Html::parse_document(&html);
 
         let html_product_selector = scraper::Selector::parse("li.product").unwrap();
-        let html_products = document.select(&html_product_selector);
-        let mut urls = Vec::<(String, String)>::new();
-        for product in html_products {
-            let product_name = product
-                .select(&scraper::Selector::parse("h2").unwrap())
-                .next()
-                .map(|a| a.text().collect::<String>());
-            let image_url = product
-                .select(&scraper::Selector::parse("img").unwrap())
-                .next()
-                .and_then(|a| a.value().attr("src"))
-                .map(str::to_owned);
-            urls.push((product_name.unwrap(), image_url.unwrap()));
-            // println!("{:?}", image_url);
-        }
-        urls
+        let name_selector = scraper::Selector::parse("h2").unwrap();
+        let image_selector = scraper::Selector::parse("img").unwrap();
+        document
+            .select(&html_product_selector)
+            .filter_map(|product| {
+                let product_name = product
+                    .select(&name_selector)
+                    .next()
+                    .map(|a| a.text().collect::<String>())?;
+                let image_url = product
+                    .select(&image_selector)
+                    .next()
+                    .and_then(|a| a.value().attr("src"))
+                    .map(str::to_owned)?;
+                Some((product_name, image_url))
+            })
+            .collect()
     }
+    /// Downloads the images of a few products picked at random, only among those cached when
+    /// offline, skipping the ones that fail.
     pub fn insert_urls(&mut self, urls: Vec<(String, String)>) -> Vec<(String, String)> {
-        let mut res = Vec::<(String, String)>::new();
-        let mut rng = rand::thread_rng();
-        let mut taken: Vec<usize> = Vec::new();
-        while taken.len() != 4 {
-            let n = rng.gen_range(0..urls.len());
-            if !taken.contains(&n) {
-                res.push((urls[n].0.clone(), urls[n].1.clone()));
-                taken.push(n);
+        let candidates: Vec<&(String, String)> = urls
+            .iter()
+            .filter(|(_name, address)| !self.offline || Self::is_cached(address))
+            .collect();
+        let picked: Vec<(String, String)> = candidates
+            .choose_multiple(&mut rand::thread_rng(), PRODUCTS_PER_SCRAPE)
+            .map(|&product| product.clone())
+            .collect();
+        for (name, address) in &picked {
+            match self.get_image(address) {
+                Ok(bytes) => {
+                    self.data.entry(name.clone()).or_insert(bytes);
+                }
+                Err(err) => println!("Unable to get the image of {name}: {err}"),
             }
         }
-        for (name, address) in res.clone().iter() {
-            let bytes = self.get_image(address);
-            self.data.entry(name.clone()).or_insert(bytes.unwrap());
+        picked
+    }
+    /// Adds a few products of the source page to the data.
+    pub fn scrape(&mut self) -> Result<(), ScrapeError> {
+        let html = self.get_html(&self.source)?;
+        let urls = self.get_urls(html);
+        if urls.is_empty() {
+            return Err(ScrapeError::NoProducts(self.source.clone()));
         }
-        res
+        self.insert_urls(urls);
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod scraper_test {
+    use super::Scraper;
     use crate::message::base_message::Message;
     use crate::message::content_message::ContentRequest;
     use crate::message::media_message::MediaRequest;
@@ -89,4 +179,15 @@ mod scraper_test {
         );
         println!("{:?}", message);
     }
+
+    #[test]
+    fn offline_scrapers_serve_only_the_cache() {
+        let cached = "https://example.invalid/cached.png";
+        Scraper::cache(cached, b"png").unwrap();
+        let scraper = Scraper::new(cached.to_string(), true);
+        assert_eq!(scraper.get_image(cached).unwrap(), b"png");
+        assert!(scraper
+            .get_image("https://example.invalid/missing.png")
+            .is_err());
+    }
 }
@@ -0,0 +1,46 @@
+use crate::message::base_message::{Message, Response};
+use std::collections::BTreeSet;
+use wg_2024::network::NodeId;
+
+/// The clients a server tells about what changes on it, without them asking again.
+#[derive(Debug, Default)]
+pub struct Subscribers {
+    clients: BTreeSet<NodeId>,
+}
+
+impl Subscribers {
+    pub fn subscribe(&mut self, client: NodeId) {
+        self.clients.insert(client);
+    }
+
+    /// A message for every subscriber but `except`, the client that caused the change. Their
+    /// sessions are chosen by the server as it sends them.
+    pub fn notify<R: Response + Clone>(
+        &self,
+        server: NodeId,
+        except: NodeId,
+        content: R,
+    ) -> Vec<Message<R>> {
+        self.clients
+            .iter()
+            .filter(|&&client| client != except)
+            .map(|&client| Message::new(server, client, 0, content.clone()))
+            .collect()
+    }
+}
+
+#[test]
+fn subscribers_are_not_told_about_their_own_changes() {
+    use crate::message::chat_message::ChatResponse;
+
+    let mut subscribers = Subscribers::default();
+    subscribers.subscribe(3);
+    subscribers.subscribe(4);
+    subscribers.subscribe(3);
+    let notifications = subscribers.notify(1, 4, ChatResponse::UserJoined("ada".to_string()));
+    let destinations: Vec<NodeId> = notifications
+        .iter()
+        .map(|notification| notification.destination_id)
+        .collect();
+    assert_eq!(destinations, vec![3]);
+}
@@ -0,0 +1,96 @@
+use std::collections::{BTreeSet, HashMap};
+
+/// Characters a snippet shows before the word it is centered on, twice as many from the word.
+const SNIPPET_CONTEXT: usize = 40;
+
+/// The lowercase words of the text, with the byte each starts at.
+fn words(text: &str) -> impl Iterator<Item = (usize, String)> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(move |word| {
+            (
+                word.as_ptr() as usize - text.as_ptr() as usize,
+                word.to_lowercase(),
+            )
+        })
+}
+
+/// The texts each word appears in, to find the texts matching a query without reading them all.
+#[derive(Debug, Default)]
+pub struct TextIndex {
+    postings: HashMap<String, BTreeSet<String>>,
+}
+
+impl TextIndex {
+    /// Indexes the text under the name, forgetting what the name held before.
+    pub fn insert(&mut self, name: &str, text: &str) {
+        self.remove(name);
+        for (_at, word) in words(text) {
+            self.postings
+                .entry(word)
+                .or_default()
+                .insert(name.to_string());
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.postings.retain(|_word, names| {
+            names.remove(name);
+            !names.is_empty()
+        });
+    }
+
+    /// The texts holding every word of the query, by name.
+    pub fn search(&self, query: &str) -> Vec<String> {
+        let mut matching: Option<BTreeSet<String>> = None;
+        for (_at, word) in words(query) {
+            let names = self.postings.get(&word).cloned().unwrap_or_default();
+            matching = Some(match matching {
+                Some(matching) => matching.intersection(&names).cloned().collect(),
+                None => names,
+            });
+        }
+        matching.unwrap_or_default().into_iter().collect()
+    }
+}
+
+/// The part of the text around the first word of the query it holds, on a single line.
+pub fn snippet(text: &str, query: &str) -> String {
+    let first = words(query).next().map(|(_at, word)| word);
+    let at = words(text)
+        .find(|(_at, word)| Some(word) == first.as_ref())
+        .map_or(0, |(at, _word)| at);
+    let start = text[..at]
+        .char_indices()
+        .rev()
+        .nth(SNIPPET_CONTEXT - 1)
+        .map_or(0, |(i, _c)| i);
+    let end = text[at..]
+        .char_indices()
+        .nth(2 * SNIPPET_CONTEXT)
+        .map_or(text.len(), |(i, _c)| at + i);
+    let mut snippet = text[start..end]
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    if start > 0 {
+        snippet.insert_str(0, "...");
+    }
+    if end < text.len() {
+        snippet.push_str("...");
+    }
+    snippet
+}
+
+#[test]
+fn texts_match_only_if_they_hold_every_word() {
+    let mut index = TextIndex::default();
+    index.insert("bagels", "Bagels are boiled, then baked.");
+    index.insert("bread", "Bread is baked.");
+    assert_eq!(index.search("BAKED"), vec!["bagels", "bread"]);
+    assert_eq!(index.search("baked bagels"), vec!["bagels"]);
+    index.insert("bagels", "Bagels are fried.");
+    assert_eq!(index.search("baked"), vec!["bread"]);
+    assert!(index.search("").is_empty());
+    assert_eq!(snippet("Bread is baked.", "baked"), "Bread is baked.");
+}
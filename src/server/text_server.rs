@@ -1,49 +1,110 @@
-use crate::application::topology::node::ApplicationType;
-use crate::message::base_message::Message;
+use crate::message::base_message::{Message, ServerType};
 use crate::message::content_message::{ContentRequest, ContentResponse};
-use crate::message::text_message::{TextRequest, TextResponse};
+use crate::message::content_metadata::ContentMetadata;
+use crate::message::error_response::{ErrorCode, ErrorResponse};
+use crate::message::text_message::{SearchHit, TextRequest, TextResponse};
+use crate::server::asset_directory::{AssetDirectory, LibraryChange};
 use crate::server::base_server::{Server, ServerBehaviour};
-use std::collections::HashMap;
-use std::fs;
+use crate::server::persistence;
+use crate::server::subscribers::Subscribers;
+use crate::server::text_index::{self, TextIndex};
+use std::collections::{HashMap, HashSet};
+use std::io;
 use wg_2024::network::NodeId;
 
 pub type TextServer = Server<TextServerBehaviour>;
+/// The answer to a request naming a text the server does not have.
+fn not_found(name: &str) -> ContentResponse {
+    let message = format!("There is no text named {name}");
+    ContentResponse::Error(ErrorResponse::new(ErrorCode::NotFound, message))
+}
 pub struct TextServerBehaviour {
     text_library: HashMap<String, Vec<u8>>,
+    assets: AssetDirectory,
+    index: TextIndex,
+    /// The texts uploaded, kept across restarts unlike those of the assets.
+    uploaded: HashSet<String>,
+    subscribers: Subscribers,
+    /// The new texts, for the subscribers.
+    notifications: Vec<Message<ContentResponse>>,
 }
 impl Default for TextServerBehaviour {
     fn default() -> Self {
-        let text_library = fs::read_dir("./assets/texts")
-            .unwrap()
-            .filter_map(|entry| {
-                let entry = entry.ok()?;
-                Some((
-                    entry.file_name().to_str()?.to_string(),
-                    fs::read(entry.path()).ok()?,
-                ))
+        Self::with_assets(AssetDirectory::shared("texts", str::to_string))
+    }
+}
+impl TextServerBehaviour {
+    fn with_assets(mut assets: AssetDirectory) -> Self {
+        let mut text_library = HashMap::new();
+        assets.reload(&mut text_library, &HashSet::new());
+        let mut behaviour = TextServerBehaviour {
+            text_library,
+            assets,
+            index: TextIndex::default(),
+            uploaded: HashSet::new(),
+            subscribers: Subscribers::default(),
+            notifications: Vec::new(),
+        };
+        let names: Vec<String> = behaviour.text_library.keys().cloned().collect();
+        for name in names {
+            behaviour.reindex(&name);
+        }
+        behaviour
+    }
+
+    /// Indexes the text of the library under the name, or forgets it if there is none anymore.
+    fn reindex(&mut self, name: &str) {
+        match self.text_library.get(name) {
+            Some(text) => self.index.insert(name, &String::from_utf8_lossy(text)),
+            None => self.index.remove(name),
+        }
+    }
+
+    fn search(&self, query: &str) -> Vec<SearchHit> {
+        self.index
+            .search(query)
+            .into_iter()
+            .map(|name| {
+                let text = String::from_utf8_lossy(&self.text_library[&name]);
+                let snippet = text_index::snippet(&text, query);
+                SearchHit { name, snippet }
             })
-            .collect();
-        TextServerBehaviour { text_library }
+            .collect()
     }
 }
 impl ServerBehaviour for TextServerBehaviour {
     type RequestType = ContentRequest;
     type ResponseType = ContentResponse;
 
+    fn for_server(id: NodeId) -> Self {
+        Self::with_assets(AssetDirectory::of_server(id, "texts", str::to_string))
+    }
+
     fn handle_request(
         &mut self,
         req: Message<Self::RequestType>,
         id: NodeId,
     ) -> Message<Self::ResponseType> {
         match req.content {
-            ContentRequest::MediaRequest(_) => {
-                req.generate_response(ContentResponse::ServiceNotProvided)
+            ContentRequest::MediaRequest(_)
+            | ContentRequest::FileRequest(_)
+            | ContentRequest::Echo(_)
+            | ContentRequest::ServerStats => {
+                let error = ErrorResponse::service_not_provided("text content");
+                req.generate_response(ContentResponse::Error(error))
+            }
+            ContentRequest::ServerType => {
+                req.generate_response(ContentResponse::ServerType(ServerType::Text))
+            }
+            ContentRequest::NotifyOnNew => {
+                self.subscribers.subscribe(req.source_id);
+                req.generate_response(ContentResponse::Subscribed)
             }
             ContentRequest::TextRequest(active_request) => match active_request {
-                TextRequest::TextList => {
-                    let response = TextResponse::TextList(
-                        self.text_library.keys().cloned().collect::<Vec<String>>(),
-                    );
+                TextRequest::TextList(page) => {
+                    let mut names: Vec<String> = self.text_library.keys().cloned().collect();
+                    names.sort();
+                    let response = TextResponse::TextList(page.page(names));
                     Message::new(
                         id,
                         req.source_id,
@@ -52,15 +113,30 @@ impl ServerBehaviour for TextServerBehaviour {
                     )
                 }
                 TextRequest::Text(requested_id) => {
-                    let response;
-                    if let Some(text) = self.text_library.get(&requested_id) {
-                        let txt = text.clone();
-                        let txt_string: String =
-                            String::from_utf8(txt).expect("couldn't convert text to string");
-                        response = TextResponse::Text(txt_string)
-                    } else {
-                        response = TextResponse::NotFound
-                    }
+                    let response = match self.text_library.get(&requested_id) {
+                        Some(text) => match String::from_utf8(text.clone()) {
+                            Ok(text) => ContentResponse::TextResponse(TextResponse::Text(text)),
+                            Err(_err) => {
+                                let message = format!("{requested_id} is not UTF-8 text");
+                                let error = ErrorResponse::new(ErrorCode::Unprocessable, message);
+                                ContentResponse::Error(error)
+                            }
+                        },
+                        None => not_found(&requested_id),
+                    };
+                    Message::new(id, req.source_id, req.session_id, response)
+                }
+                TextRequest::Metadata(name) => {
+                    let response = match self.text_library.get(&name) {
+                        Some(text) => ContentResponse::TextResponse(TextResponse::Metadata(
+                            ContentMetadata::of(name, text),
+                        )),
+                        None => not_found(&name),
+                    };
+                    Message::new(id, req.source_id, req.session_id, response)
+                }
+                TextRequest::Search(query) => {
+                    let response = TextResponse::SearchResults(self.search(&query));
                     Message::new(
                         id,
                         req.source_id,
@@ -68,20 +144,156 @@ impl ServerBehaviour for TextServerBehaviour {
                         ContentResponse::TextResponse(response),
                     )
                 }
+                TextRequest::Upload(name, text) => {
+                    self.uploaded.insert(name.clone());
+                    if self
+                        .text_library
+                        .insert(name.clone(), text.into_bytes())
+                        .is_none()
+                    {
+                        let new = ContentResponse::NewContent(name.clone());
+                        let notifications = self.subscribers.notify(id, req.source_id, new);
+                        self.notifications.extend(notifications);
+                    }
+                    self.reindex(&name);
+                    Message::new(
+                        id,
+                        req.source_id,
+                        req.session_id,
+                        ContentResponse::TextResponse(TextResponse::Uploaded(name)),
+                    )
+                }
             },
         }
     }
 
-    fn application_type() -> ApplicationType {
-        ApplicationType::Content
+    fn reload_assets(&mut self, id: NodeId) -> LibraryChange {
+        let change = self.assets.reload(&mut self.text_library, &self.uploaded);
+        for name in change.updated.iter().chain(&change.removed) {
+            self.reindex(name);
+        }
+        for name in &change.added {
+            let new = ContentResponse::NewContent(name.clone());
+            let notifications = self.subscribers.notify(id, id, new);
+            self.notifications.extend(notifications);
+        }
+        change
+    }
+
+    fn notifications(&mut self) -> Vec<Message<ContentResponse>> {
+        self.notifications.drain(..).collect()
+    }
+
+    fn restore(&mut self, id: NodeId) {
+        let Some(texts) = persistence::load::<HashMap<String, Vec<u8>>>("text", id) else {
+            return;
+        };
+        self.uploaded.extend(texts.keys().cloned());
+        let names: Vec<String> = texts.keys().cloned().collect();
+        self.text_library.extend(texts);
+        for name in names {
+            self.reindex(&name);
+        }
+    }
+
+    fn persist(&self, id: NodeId) -> io::Result<()> {
+        let texts: HashMap<&String, &Vec<u8>> = self
+            .text_library
+            .iter()
+            .filter(|(name, _text)| self.uploaded.contains(*name))
+            .collect();
+        persistence::save("text", id, &texts)
+    }
+
+    fn server_type() -> ServerType {
+        ServerType::Text
     }
 }
 #[test]
 fn test_texts() {
+    use crate::message::page::PageRequest;
+
     let mut server = TextServerBehaviour::default();
     let message = server.handle_request(
-        Message::new(0, 0, 0, ContentRequest::TextRequest(TextRequest::TextList)),
+        Message::new(
+            0,
+            0,
+            0,
+            ContentRequest::TextRequest(TextRequest::TextList(PageRequest::default())),
+        ),
         0,
     );
     println!("{:?}", message);
 }
+#[test]
+fn uploaded_texts_can_be_downloaded() {
+    let mut server = TextServerBehaviour::default();
+    let upload = TextRequest::Upload("notes.txt".to_string(), "hello #whale".to_string());
+    server.handle_request(Message::new(0, 0, 0, upload.into()), 0);
+    let message = server.handle_request(
+        Message::new(0, 0, 1, TextRequest::Text("notes.txt".to_string()).into()),
+        0,
+    );
+    assert!(matches!(
+        message.content,
+        ContentResponse::TextResponse(TextResponse::Text(text)) if text == "hello #whale"
+    ));
+}
+#[test]
+fn text_servers_tell_their_type() {
+    let mut server = TextServerBehaviour::default();
+    let message = server.handle_request(Message::new(0, 0, 0, ContentRequest::ServerType), 0);
+    assert!(matches!(
+        message.content,
+        ContentResponse::ServerType(ServerType::Text)
+    ));
+}
+#[test]
+fn subscribers_hear_of_new_texts() {
+    let mut server = TextServerBehaviour::default();
+    server.handle_request(Message::new(7, 0, 0, ContentRequest::NotifyOnNew), 0);
+    let upload = TextRequest::Upload("fresh.txt".to_string(), "news".to_string());
+    server.handle_request(Message::new(8, 0, 1, upload.into()), 0);
+    let notifications = server.notifications();
+    assert_eq!(notifications.len(), 1);
+    assert_eq!(notifications[0].destination_id, 7);
+    assert!(matches!(
+        &notifications[0].content,
+        ContentResponse::NewContent(name) if name == "fresh.txt"
+    ));
+}
+#[test]
+fn uploaded_texts_survive_a_restart() {
+    let mut server = TextServerBehaviour::default();
+    let upload = TextRequest::Upload("kept.txt".to_string(), "still here".to_string());
+    server.handle_request(Message::new(0, 0, 0, upload.into()), 0);
+    server.persist(251).unwrap();
+    let mut restarted = TextServerBehaviour::default();
+    restarted.restore(251);
+    let message = restarted.handle_request(
+        Message::new(0, 0, 1, TextRequest::Text("kept.txt".to_string()).into()),
+        0,
+    );
+    std::fs::remove_file(persistence::directory().join("text_251.bin")).ok();
+    assert!(matches!(
+        message.content,
+        ContentResponse::TextResponse(TextResponse::Text(text)) if text == "still here"
+    ));
+}
+#[test]
+fn uploaded_texts_can_be_searched() {
+    let mut server = TextServerBehaviour::default();
+    let upload = TextRequest::Upload(
+        "ode.txt".to_string(),
+        "An ode to the sesame bagel".to_string(),
+    );
+    server.handle_request(Message::new(0, 0, 0, upload.into()), 0);
+    let search = TextRequest::Search("Sesame".to_string());
+    let message = server.handle_request(Message::new(0, 0, 1, search.into()), 0);
+    let ContentResponse::TextResponse(TextResponse::SearchResults(hits)) = message.content else {
+        panic!("expected search results");
+    };
+    assert!(hits
+        .iter()
+        .any(|hit| hit.name == "ode.txt" && hit.snippet.contains("sesame")));
+}
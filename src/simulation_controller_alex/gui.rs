@@ -1,54 +1,87 @@
-use crate::application::simulation_controller_messages::{HostCommand, HostEvent};
+use crate::application::simulation_controller_messages::HostEvent;
+use crate::application::topology::node::ApplicationType;
+use crate::controller_core::backlog::{draw_backlog_badge, packet_backlogs};
+use crate::controller_core::confirmation::{confirmation_modal, crash_warning};
+use crate::controller_core::controller::SimulationController;
+use crate::controller_core::edges::{draw_selected_edge, edge_at, is_delete_key};
+use crate::controller_core::events::{ControllerEvent, POLL_INTERVAL};
+use crate::controller_core::operations::AsNetworkNode;
+use crate::controller_core::replay::Replay;
+use crate::controller_core::send_form::{SendForm, SendFormMessage};
+use crate::controller_core::settings::{Settings, ThemeChoice};
+use crate::controller_core::toast::Toasts;
 use crate::initialization::network_initializer::{start_actual_simulation, NetworkNode};
-use crate::initialization::node_creators::{
-    ActualClientCreator, ActualDroneCreator, ActualServerCreator, ClientCreator, DroneCreator,
-    ServerCreator,
-};
 use crate::simulation_controller_alex::gui::DroneCommandsMessage::{
     AddSenderPressed, CrashPressed, RmvSenderPressed,
 };
 use crate::simulation_controller_alex::gui::NodesPaneMessage::{ButtonPressed, TypeSelected};
 use crate::Topology as TopologyType;
-use crossbeam_channel::{unbounded, Receiver, Sender};
+use crossbeam_channel::unbounded;
 use graph::{AdjacencyVecGraph, ReferenceGraph};
 use iced::alignment::{Horizontal, Vertical};
+use iced::event::Status;
 use iced::font::Weight;
-use iced::mouse::Cursor;
-use iced::widget::canvas::{Frame, Geometry, Path, Program, Stroke, Text};
+use iced::keyboard;
+use iced::mouse::{self, Cursor};
+use iced::widget::canvas::{Event, Frame, Geometry, Path, Program, Stroke, Text};
 use iced::widget::pane_grid::{Axis, Content, Direction, Pane, ResizeEvent, State, TitleBar};
 use iced::widget::{
-    button, canvas, column, container, pane_grid, pick_list, row, scrollable, slider, text,
-    text_input,
+    button, canvas, checkbox, column, container, pane_grid, pick_list, row, scrollable, slider,
+    text, text_input,
 };
-use iced::{Color, Font, Pixels};
+use iced::{Color, Font, Pixels, Vector};
 use iced::{Element, Fill, Point, Rectangle, Renderer, Theme};
 use rand::{random, Rng};
 use std::cell::RefCell;
-use std::collections::HashMap;
-use std::ops::Deref;
+use std::collections::VecDeque;
+use std::env;
 use std::rc::Rc;
-use std::{env, thread};
-use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::controller::DroneEvent;
 use wg_2024::network::NodeId;
-use wg_2024::packet::Packet;
 
-#[derive(Debug, Clone)]
 struct Topology {
-    graph: AdjacencyVecGraph<NodeId, (NetworkNode, Point)>,
+    controller: SimulationController<(NetworkNode, Point)>,
     selected_node: Option<NodeId>,
+    selected_edge: Option<(NodeId, NodeId)>,
+    replay: Option<Replay>,
 }
 
 impl Topology {
-    fn new(graph: AdjacencyVecGraph<NodeId, (NetworkNode, Point)>) -> Self {
+    fn new(controller: SimulationController<(NetworkNode, Point)>) -> Self {
         Self {
-            graph,
+            controller,
             selected_node: None,
+            selected_edge: None,
+            replay: None,
+        }
+    }
+
+    fn graph(&self) -> &AdjacencyVecGraph<NodeId, (NetworkNode, Point)> {
+        self.controller.graph()
+    }
+
+    fn advance_replay(&mut self) {
+        let finished = self
+            .replay
+            .as_mut()
+            .is_some_and(|replay| !replay.advance(self.controller.packet_traces()));
+        if finished {
+            self.replay = None;
         }
     }
 }
 
 type TopologyRef = Rc<RefCell<Topology>>;
 
+impl AsNetworkNode for (NetworkNode, Point) {
+    fn network_node(&self) -> &NetworkNode {
+        &self.0
+    }
+    fn network_node_mut(&mut self) -> &mut NetworkNode {
+        &mut self.0
+    }
+}
+
 #[derive(Debug)]
 #[allow(clippy::enum_variant_names)]
 enum Message {
@@ -56,6 +89,8 @@ enum Message {
     DroneCommandsMessage(DroneCommandsMessage),
     TopologyMessage(TopologyMessage),
     DroneEventsMessage(DroneEventsMessage),
+    SendRequestMessage(SendFormMessage),
+    ThemeSelected(ThemeChoice),
     Clicked(Pane),
     Resized(ResizeEvent),
     Tick,
@@ -67,11 +102,15 @@ enum PaneType {
     DroneCommandsPane(DroneCommandsPane),
     TopologyPane(TopologyPane),
     DroneEventsPane(DroneEventsPane),
+    SendRequestPane(SendRequestPane),
+    ThroughputPane(ThroughputPane),
 }
 
 struct Application {
     pane_state: State<PaneType>,
     active_pane: Option<Pane>,
+    toasts: Rc<RefCell<Toasts>>,
+    settings: Settings,
 }
 
 impl Default for Application {
@@ -87,49 +126,49 @@ impl Default for Application {
                 .unwrap_or(TopologyType::DoubleChain);
         let controller_info = start_actual_simulation(topology.to_path());
 
-        let graph = controller_info
-            .network_graph
-            .map_values(|node| (node, random_point()));
+        let controller = SimulationController::new(controller_info, |node| (node, random_point()));
 
-        let topology = Rc::new(RefCell::new(Topology::new(graph)));
+        let topology = Rc::new(RefCell::new(Topology::new(controller)));
 
-        let packet_senders = Rc::new(RefCell::new(controller_info.packet_senders));
-        let drone_event_rcv = RefCell::new(controller_info.drone_event_controller_recv);
-        let host_event_rcv = RefCell::new(controller_info.host_event_controller_recv);
+        let toasts = Rc::new(RefCell::new(Toasts::default()));
 
         let (mut pane_state, pane) = State::new(PaneType::NodesPane(NodesPane::new(
             topology.clone(),
-            controller_info.drone_creator,
-            controller_info.client_creator,
-            controller_info.server_creator,
-            packet_senders.clone(),
+            toasts.clone(),
         )));
+        let (topology_pane, _) = pane_state
+            .split(
+                Axis::Vertical,
+                pane,
+                PaneType::TopologyPane(TopologyPane::new(topology.clone(), toasts.clone())),
+            )
+            .unwrap();
         pane_state.split(
             Axis::Vertical,
             pane,
-            PaneType::TopologyPane(TopologyPane::new(topology.clone())),
+            PaneType::DroneCommandsPane(DroneCommandsPane::new(topology.clone(), toasts.clone())),
         );
         pane_state.split(
-            Axis::Vertical,
+            Axis::Horizontal,
+            pane_state.adjacent(pane, Direction::Right).unwrap(),
+            PaneType::DroneEventsPane(DroneEventsPane::new(topology.clone())),
+        );
+        pane_state.split(
+            Axis::Horizontal,
             pane,
-            PaneType::DroneCommandsPane(DroneCommandsPane::new(
-                topology.clone(),
-                packet_senders.clone(),
-            )),
+            PaneType::SendRequestPane(SendRequestPane::new(topology.clone(), toasts.clone())),
         );
         pane_state.split(
             Axis::Horizontal,
-            pane_state.adjacent(pane, Direction::Right).unwrap(),
-            PaneType::DroneEventsPane(DroneEventsPane::new(
-                drone_event_rcv.clone(),
-                host_event_rcv.clone(),
-                packet_senders.clone(),
-            )),
+            topology_pane,
+            PaneType::ThroughputPane(ThroughputPane::new(topology.clone())),
         );
 
         Self {
             pane_state,
             active_pane: None,
+            toasts,
+            settings: Settings::load(),
         }
     }
 }
@@ -159,6 +198,50 @@ impl Application {
                     drone_commands_pane.update(drone_commands_message);
                 }
             }
+            Message::DroneEventsMessage(drone_events_message) => {
+                if let Some(PaneType::DroneEventsPane(drone_events_pane)) = self
+                    .active_pane
+                    .and_then(|pane| self.pane_state.get_mut(pane))
+                {
+                    drone_events_pane.update(drone_events_message);
+                }
+            }
+            Message::SendRequestMessage(send_form_message) => {
+                if let Some(PaneType::SendRequestPane(send_request_pane)) = self
+                    .active_pane
+                    .and_then(|pane| self.pane_state.get_mut(pane))
+                {
+                    send_request_pane.update(send_form_message);
+                }
+            }
+            Message::TopologyMessage(topology_message) => {
+                if let Some(topology_pane) =
+                    self.pane_state
+                        .iter_mut()
+                        .find_map(|(_pane, state)| match state {
+                            PaneType::TopologyPane(topology_pane) => Some(topology_pane),
+                            _ => None,
+                        })
+                {
+                    topology_pane.update(topology_message);
+                }
+            }
+            Message::ThemeSelected(theme) => {
+                self.settings.theme = theme;
+                if let Err(err) = self.settings.save() {
+                    self.toasts
+                        .borrow_mut()
+                        .push(format!("Unable to save the settings: {err}"));
+                }
+            }
+            Message::Tick => {
+                self.toasts.borrow_mut().expire();
+                for (_pane, state) in self.pane_state.iter_mut() {
+                    if let PaneType::DroneEventsPane(drone_events_pane) = state {
+                        drone_events_pane.poll_events();
+                    }
+                }
+            }
             _ => {}
         }
     }
@@ -190,6 +273,15 @@ impl Application {
                     Content::new(drone_events_pane.view().map(Message::DroneEventsMessage))
                         .title_bar(title_bar)
                 }
+                PaneType::SendRequestPane(send_request_pane) => {
+                    let title_bar = TitleBar::new(text("Send message")).padding(5);
+                    Content::new(send_request_pane.view().map(Message::SendRequestMessage))
+                        .title_bar(title_bar)
+                }
+                PaneType::ThroughputPane(throughput_pane) => {
+                    let title_bar = TitleBar::new(text("Throughput")).padding(5);
+                    Content::new(throughput_pane.view()).title_bar(title_bar)
+                }
             },
         )
         .width(Fill)
@@ -198,7 +290,23 @@ impl Application {
         .on_click(Message::Clicked)
         .on_resize(10, Message::Resized);
 
-        container(grid).width(Fill).height(Fill).padding(10).into()
+        let theme_picker = row![
+            text("Theme:"),
+            pick_list(
+                ThemeChoice::ALL,
+                Some(self.settings.theme),
+                Message::ThemeSelected
+            ),
+        ]
+        .spacing(10)
+        .align_y(Vertical::Center);
+
+        self.toasts.borrow().view(
+            container(column![theme_picker, grid].spacing(10))
+                .width(Fill)
+                .height(Fill)
+                .padding(10),
+        )
     }
 }
 
@@ -206,28 +314,20 @@ struct NodesPane {
     topology: TopologyRef,
     input_value: String,
     selected_type: Option<NetworkNode>,
-    drone_creator: ActualDroneCreator,
-    client_creator: ActualClientCreator,
-    server_creator: ActualServerCreator,
-    packet_senders: Rc<RefCell<HashMap<NodeId, Sender<Packet>>>>,
+    drone_implementation: Option<&'static str>,
+    initial_neighbors: Vec<NodeId>,
+    toasts: Rc<RefCell<Toasts>>,
 }
 
 impl NodesPane {
-    fn new(
-        topology: TopologyRef,
-        drone_creator: ActualDroneCreator,
-        client_creator: ActualClientCreator,
-        server_creator: ActualServerCreator,
-        packet_senders: Rc<RefCell<HashMap<NodeId, Sender<Packet>>>>,
-    ) -> Self {
+    fn new(topology: TopologyRef, toasts: Rc<RefCell<Toasts>>) -> Self {
         Self {
             topology,
             input_value: "".to_string(),
             selected_type: None,
-            drone_creator,
-            client_creator,
-            server_creator,
-            packet_senders,
+            drone_implementation: None,
+            initial_neighbors: Vec::new(),
+            toasts,
         }
     }
 }
@@ -237,6 +337,8 @@ enum NodesPaneMessage {
     ButtonPressed,
     InputChanged(String),
     TypeSelected(NetworkNode),
+    DroneImplementationSelected(&'static str),
+    InitialNeighborToggled(NodeId, bool),
     NodeSelected(NodeId),
 }
 
@@ -247,59 +349,55 @@ impl NodesPane {
                 let node_id = self.input_value.parse::<u8>();
                 if let Ok(id) = node_id {
                     if let Some(mut node_type) = self.selected_type.take() {
-                        if !self.topology.borrow().graph.contains_node(&id) {
-                            match &mut node_type {
-                                NetworkNode::Drone { pdr, command_send } => {
-                                    *pdr = random();
-                                    let (cmd_send, cmd_receive) = unbounded();
-                                    *command_send = cmd_send;
-                                    let (pckt_send, pckt_receive) = unbounded();
-                                    self.packet_senders.borrow_mut().insert(id, pckt_send);
-                                    let mut runnable =
-                                        self.drone_creator.create_disconnected_drone(
-                                            id,
-                                            cmd_receive,
-                                            pckt_receive,
-                                            *pdr,
-                                        );
-                                    thread::spawn(move || runnable.run());
-                                }
-                                NetworkNode::Client { command_send } => {
-                                    let (cmd_send, cmd_receive) = unbounded();
-                                    *command_send = cmd_send;
-                                    let (pckt_send, pckt_receive) = unbounded();
-                                    self.packet_senders.borrow_mut().insert(id, pckt_send);
-                                    let mut runnable = self
-                                        .client_creator
-                                        .create_disconnected_client(id, cmd_receive, pckt_receive);
-                                    thread::spawn(move || runnable.run());
-                                }
-                                NetworkNode::Server { command_send } => {
-                                    let (cmd_send, cmd_receive) = unbounded();
-                                    *command_send = cmd_send;
-                                    let (pckt_send, pckt_receive) = unbounded();
-                                    self.packet_senders.borrow_mut().insert(id, pckt_send);
-                                    let mut runnable = self
-                                        .server_creator
-                                        .create_disconnected_server(id, cmd_receive, pckt_receive);
-                                    thread::spawn(move || runnable.run());
+                        let mut topology = self.topology.borrow_mut();
+                        if let NetworkNode::Drone { pdr, .. } = &mut node_type {
+                            *pdr = random();
+                            if let Some(implementation) = self.drone_implementation {
+                                if let Err(reason) = topology
+                                    .controller
+                                    .select_drone_implementation(implementation)
+                                {
+                                    self.toasts.borrow_mut().push(reason);
+                                    return;
                                 }
                             }
-                            self.topology
-                                .borrow_mut()
-                                .graph
-                                .add_node(id, (node_type, random_point()));
                         }
+                        match topology.controller.spawn_node(
+                            id,
+                            (node_type, random_point()),
+                            &self.initial_neighbors,
+                        ) {
+                            Ok(()) => self.initial_neighbors.clear(),
+                            Err(reason) => self.toasts.borrow_mut().push(reason),
+                        }
+                    } else {
+                        self.toasts
+                            .borrow_mut()
+                            .push("Select the type of the new node");
                     }
+                } else {
+                    self.toasts
+                        .borrow_mut()
+                        .push("The node id must be a number between 0 and 255");
                 }
                 self.input_value = "".to_string();
             }
+            NodesPaneMessage::InitialNeighborToggled(id, checked) => {
+                if checked {
+                    self.initial_neighbors.push(id);
+                } else {
+                    self.initial_neighbors.retain(|ngh| *ngh != id);
+                }
+            }
             NodesPaneMessage::InputChanged(input) => {
                 self.input_value = input.clone();
             }
             TypeSelected(node_type) => {
                 self.selected_type = Some(node_type);
             }
+            NodesPaneMessage::DroneImplementationSelected(implementation) => {
+                self.drone_implementation = Some(implementation);
+            }
             NodesPaneMessage::NodeSelected(id) => {
                 self.topology.borrow_mut().selected_node = Some(id);
             }
@@ -322,21 +420,58 @@ impl NodesPane {
             },
             NetworkNode::Client {
                 command_send: unbounded().0,
+                application_type: ApplicationType::Unknown,
             },
             NetworkNode::Server {
                 command_send: unbounded().0,
+                application_type: ApplicationType::Unknown,
             },
         ];
 
+        let mut neighbor_ids = self
+            .topology
+            .borrow()
+            .graph()
+            .keys()
+            .copied()
+            .collect::<Vec<_>>();
+        neighbor_ids.sort();
+        let neighbors = scrollable(
+            row(neighbor_ids.into_iter().map(|id| {
+                checkbox(id.to_string(), self.initial_neighbors.contains(&id))
+                    .on_toggle(move |checked| NodesPaneMessage::InitialNeighborToggled(id, checked))
+                    .into()
+            }))
+            .spacing(10),
+        )
+        .direction(scrollable::Direction::Horizontal(
+            scrollable::Scrollbar::default(),
+        ));
+
         let spawn = container(
             column![
-                pick_list(node_types, self.selected_type.clone(), TypeSelected)
-                    .placeholder("NodeType"),
+                row![
+                    pick_list(node_types, self.selected_type.clone(), TypeSelected)
+                        .placeholder("NodeType")
+                ]
+                .push_maybe(
+                    matches!(self.selected_type, Some(NetworkNode::Drone { .. })).then(|| {
+                        pick_list(
+                            self.topology.borrow().controller.drone_implementations(),
+                            self.drone_implementation,
+                            NodesPaneMessage::DroneImplementationSelected,
+                        )
+                        .placeholder("Next in turn")
+                    })
+                )
+                .spacing(10),
+                text("Initial neighbors:"),
+                neighbors,
                 row![text_input, button,].spacing(10)
             ]
             .spacing(10),
         )
-        .height(80);
+        .height(150);
 
         container(
             column![spawn, scrollable(container(self.view_nodes())).height(Fill),].spacing(20),
@@ -351,7 +486,7 @@ impl NodesPane {
         column(
             self.topology
                 .borrow()
-                .graph
+                .graph()
                 .iter()
                 .map(|(id, (node_type, _position))| {
                     button(text(format!(
@@ -380,13 +515,51 @@ fn random_point() -> Point {
     Point::new(x, y)
 }
 
+struct SendRequestPane {
+    topology: TopologyRef,
+    form: SendForm,
+    toasts: Rc<RefCell<Toasts>>,
+}
+
+impl SendRequestPane {
+    fn new(topology: TopologyRef, toasts: Rc<RefCell<Toasts>>) -> Self {
+        Self {
+            topology,
+            form: SendForm::default(),
+            toasts,
+        }
+    }
+
+    fn update(&mut self, message: SendFormMessage) {
+        match message {
+            SendFormMessage::Submitted => {
+                let topology = self.topology.borrow();
+                if let Err(reason) = self.form.request().and_then(|(client, server, request)| {
+                    topology.controller.send_request(client, server, request)
+                }) {
+                    self.toasts.borrow_mut().push(reason);
+                }
+            }
+            message => self.form.update(message),
+        }
+    }
+
+    fn view(&self) -> Element<SendFormMessage> {
+        container(scrollable(self.form.view(self.topology.borrow().graph())))
+            .padding(10)
+            .into()
+    }
+}
+
 struct DroneCommandsPane {
     topology: TopologyRef,
-    slider_value: f32,
-    slider_input_content: String,
+    /// The PDR being typed and the drone it is meant for; the current PDR is always read from
+    /// the topology.
+    pdr_draft: Option<(NodeId, String)>,
     pick_list_add_selected: Option<NodeId>,
     pick_list_rmv_selected: Option<NodeId>,
-    packet_senders: Rc<RefCell<HashMap<NodeId, Sender<Packet>>>>,
+    pending_crash: Option<NodeId>,
+    toasts: Rc<RefCell<Toasts>>,
 }
 
 #[derive(Debug, Clone)]
@@ -395,192 +568,133 @@ enum DroneCommandsMessage {
     SliderInputChanged(String),
     SliderInputSubmitted(String),
     CrashPressed(Option<NodeId>),
+    CrashConfirmed,
+    CrashCancelled,
     AddNodeSelected(NodeId),
     RmvNodeSelected(NodeId),
     AddSenderPressed(Option<NodeId>),
     RmvSenderPressed(Option<NodeId>),
+    FloodPressed(NodeId),
+    ExportTopologyPressed(NodeId),
 }
 
 impl DroneCommandsPane {
-    fn new(
-        topology: TopologyRef,
-        packet_senders: Rc<RefCell<HashMap<NodeId, Sender<Packet>>>>,
-    ) -> Self {
+    fn new(topology: TopologyRef, toasts: Rc<RefCell<Toasts>>) -> Self {
         Self {
             topology,
-            slider_value: 0.0,
-            slider_input_content: "".to_string(),
+            pdr_draft: None,
             pick_list_add_selected: None,
             pick_list_rmv_selected: None,
-            packet_senders,
+            pending_crash: None,
+            toasts,
         }
     }
     fn update(&mut self, message: DroneCommandsMessage) {
         match message {
             DroneCommandsMessage::SliderChanged(slider_value) => {
-                let mut topology = self.topology.borrow_mut();
-                if let Some(id) = topology.selected_node {
-                    if let NetworkNode::Drone { pdr, command_send } = &mut topology.graph[&id].0 {
-                        self.slider_value = change_pdr(slider_value);
-                        *pdr = self.slider_value;
-                        command_send
-                            .send(DroneCommand::SetPacketDropRate(*pdr))
-                            .expect("error sending SetPacketDropRate");
-                    }
-                }
+                self.set_pdr(change_pdr(slider_value));
             }
             DroneCommandsMessage::SliderInputChanged(input_value) => {
-                self.slider_input_content = input_value.deref().to_owned();
+                self.pdr_draft = self
+                    .topology
+                    .borrow()
+                    .selected_node
+                    .map(|id| (id, input_value));
             }
             DroneCommandsMessage::SliderInputSubmitted(input_value) => {
-                let mut topology = self.topology.borrow_mut();
-                if let Some(id) = topology.selected_node {
-                    if let NetworkNode::Drone { pdr, command_send } = &mut topology.graph[&id].0 {
-                        let new_value = input_value.parse::<f32>();
-                        if let Ok(value) = new_value {
-                            if value >= 1f32 {
-                                self.slider_value = 1f32;
-                            } else if value <= 0f32 {
-                                self.slider_value = 0f32;
-                            } else {
-                                self.slider_value = change_pdr(value);
-                            }
-                            *pdr = self.slider_value;
-                            command_send
-                                .send(DroneCommand::SetPacketDropRate(*pdr))
-                                .expect("error sending SetPacketDropRate");
-                            self.slider_input_content = "".to_string();
-                        }
+                if let Ok(value) = input_value.parse::<f32>() {
+                    if self.set_pdr(change_pdr(value.clamp(0f32, 1f32))) {
+                        self.pdr_draft = None;
                     }
                 }
             }
             CrashPressed(option_id) => {
-                if let Some(id) = option_id {
+                self.pending_crash = option_id;
+            }
+            DroneCommandsMessage::CrashCancelled => {
+                self.pending_crash = None;
+            }
+            DroneCommandsMessage::CrashConfirmed => {
+                if let Some(id) = self.pending_crash.take() {
                     let mut topology = self.topology.borrow_mut();
-                    let mut new_graph = topology.graph.clone();
-                    new_graph.remove_node(&id);
-                    if new_graph.is_connected_undirected() && is_topology_valid(&new_graph) {
-                        if let NetworkNode::Drone { command_send, .. } = &topology.graph[&id].0 {
-                            for node in topology
-                                .graph
-                                .adjacents(&id)
-                                .map(|adj| &topology.graph[adj].0)
-                            {
-                                match node {
-                                    NetworkNode::Drone { command_send, .. } => {
-                                        command_send
-                                            .send(DroneCommand::RemoveSender(id))
-                                            .expect("error sending RemoveSender");
-                                    }
-                                    NetworkNode::Client { command_send }
-                                    | NetworkNode::Server { command_send } => {
-                                        command_send
-                                            .send(HostCommand::RemoveConnectedDrone(id))
-                                            .expect("error sending RemoveConnectedDrone");
-                                    }
-                                }
-                            }
-                            command_send
-                                .send(DroneCommand::Crash)
-                                .expect("error sending Crash");
-                            topology.graph.remove_node(&id);
-                        }
-                        topology.selected_node = None;
+                    match topology.controller.remove_node(id) {
+                        Ok(()) => topology.selected_node = None,
+                        Err(reason) => self.toasts.borrow_mut().push(reason),
                     }
                 }
             }
             DroneCommandsMessage::AddNodeSelected(node) => self.pick_list_add_selected = Some(node),
             AddSenderPressed(node_option) => {
                 let mut topology = self.topology.borrow_mut();
-                if let Some(selected_node) = topology.selected_node {
-                    if let Some(node) = node_option {
-                        let node_type = &topology.graph[&node].0;
-                        let selected_node_type = &topology.graph[&selected_node].0;
-                        let can_connect = !matches!(
-                            (node_type, selected_node_type),
-                            (NetworkNode::Client { .. }, NetworkNode::Client { .. })
-                                | (NetworkNode::Server { .. }, NetworkNode::Server { .. })
-                                | (NetworkNode::Server { .. }, NetworkNode::Client { .. })
-                                | (NetworkNode::Client { .. }, NetworkNode::Server { .. })
-                        );
-                        let mut new_graph = topology.graph.clone();
-                        new_graph.add_undirected_edge(selected_node, node);
-                        if can_connect && is_topology_valid(&new_graph) {
-                            match node_type {
-                                NetworkNode::Drone { command_send, .. } => {
-                                    command_send
-                                        .send(DroneCommand::AddSender(
-                                            selected_node,
-                                            self.packet_senders.borrow()[&selected_node].clone(),
-                                        ))
-                                        .expect("error sending AddSender");
-                                }
-                                NetworkNode::Client { command_send }
-                                | NetworkNode::Server { command_send } => {
-                                    command_send
-                                        .send(HostCommand::AddConnectedDrone(
-                                            selected_node,
-                                            self.packet_senders.borrow()[&selected_node].clone(),
-                                        ))
-                                        .expect("error sending AddConnectedDrone");
-                                }
-                            }
-                            match selected_node_type {
-                                NetworkNode::Drone { command_send, .. } => {
-                                    command_send
-                                        .send(DroneCommand::AddSender(
-                                            node,
-                                            self.packet_senders.borrow()[&node].clone(),
-                                        ))
-                                        .expect("error sending AddSender");
-                                }
-                                NetworkNode::Client { command_send }
-                                | NetworkNode::Server { command_send } => {
-                                    command_send
-                                        .send(HostCommand::AddConnectedDrone(
-                                            node,
-                                            self.packet_senders.borrow()[&node].clone(),
-                                        ))
-                                        .expect("error sending AddConnectedDrone");
-                                }
-                            }
-                            topology.graph.add_undirected_edge(selected_node, node);
-                        }
-                        self.pick_list_add_selected = None;
+                if let (Some(selected_node), Some(node)) = (topology.selected_node, node_option) {
+                    if let Err(reason) = topology.controller.add_edge(selected_node, node) {
+                        self.toasts.borrow_mut().push(reason);
                     }
+                    self.pick_list_add_selected = None;
                 }
             }
             DroneCommandsMessage::RmvNodeSelected(node) => self.pick_list_rmv_selected = Some(node),
             RmvSenderPressed(node_option) => {
                 let mut topology = self.topology.borrow_mut();
-                if let Some(id) = topology.selected_node {
-                    if let Some(node) = node_option {
-                        let mut new_graph = topology.graph.clone();
-                        new_graph.remove_undirected_edge(&id, &node);
-                        if new_graph.is_connected_undirected() && is_topology_valid(&new_graph) {
-                            match &topology.graph[&node].0 {
-                                NetworkNode::Drone { command_send, .. } => {
-                                    command_send
-                                        .send(DroneCommand::RemoveSender(id))
-                                        .expect("error sending RemoveSender");
-                                }
-                                NetworkNode::Client { command_send }
-                                | NetworkNode::Server { command_send } => {
-                                    command_send
-                                        .send(HostCommand::RemoveConnectedDrone(id))
-                                        .expect("error sending RemoveConnectedDrone");
-                                }
-                            }
-                            topology.graph.remove_undirected_edge(&id, &node);
-                        }
-                        self.pick_list_rmv_selected = None;
+                if let (Some(id), Some(node)) = (topology.selected_node, node_option) {
+                    if let Err(reason) = topology.controller.remove_edge(id, node) {
+                        self.toasts.borrow_mut().push(reason);
                     }
+                    self.pick_list_rmv_selected = None;
                 }
             }
+            DroneCommandsMessage::FloodPressed(id) => {
+                if let Err(reason) = self.topology.borrow().controller.initiate_flood(id) {
+                    self.toasts.borrow_mut().push(reason);
+                }
+            }
+            DroneCommandsMessage::ExportTopologyPressed(id) => {
+                let result = self
+                    .topology
+                    .borrow()
+                    .controller
+                    .export_topology_with_dialog(id);
+                if let Err(reason) = result {
+                    self.toasts.borrow_mut().push(reason);
+                }
+            }
+        }
+    }
+
+    /// Sets the PDR of the selected drone, returning whether it was changed.
+    fn set_pdr(&mut self, pdr: f32) -> bool {
+        let mut topology = self.topology.borrow_mut();
+        let Some(id) = topology.selected_node else {
+            return false;
+        };
+        match topology.controller.set_pdr(id, pdr) {
+            Ok(()) => true,
+            Err(reason) => {
+                self.toasts.borrow_mut().push(reason);
+                false
+            }
         }
     }
 
     fn view(&self) -> Element<DroneCommandsMessage> {
+        let content = self.view_commands();
+
+        match self.pending_crash {
+            Some(id) => {
+                let topology = self.topology.borrow();
+                confirmation_modal(
+                    content,
+                    format!("Crash drone {id}?"),
+                    crash_warning(id, topology.graph().adjacents(&id)),
+                    DroneCommandsMessage::CrashConfirmed,
+                    DroneCommandsMessage::CrashCancelled,
+                )
+            }
+            None => content,
+        }
+    }
+
+    fn view_commands(&self) -> Element<DroneCommandsMessage> {
         let topology = self.topology.borrow();
 
         let add_sender =
@@ -588,14 +702,14 @@ impl DroneCommandsPane {
 
         let pick_list_add = pick_list(
             topology
-                .graph
+                .graph()
                 .keys()
                 .copied()
                 .filter(|id| {
                     if topology.selected_node.is_some() {
                         topology.selected_node != Some(*id)
                             && !topology
-                                .graph
+                                .graph()
                                 .is_adjacent_to(&topology.selected_node.unwrap(), id)
                     } else {
                         false
@@ -607,19 +721,24 @@ impl DroneCommandsPane {
         )
         .placeholder("Select node");
 
-        let rmv_sender =
-            button("Remove sender").on_press(RmvSenderPressed(self.pick_list_rmv_selected));
+        let is_protected =
+            |id: Option<NodeId>| id.is_some_and(|id| topology.controller.is_protected(id));
+
+        let rmv_sender = button("Remove sender").on_press_maybe(
+            (!is_protected(topology.selected_node) && !is_protected(self.pick_list_rmv_selected))
+                .then_some(RmvSenderPressed(self.pick_list_rmv_selected)),
+        );
 
         let pick_list_rmv = pick_list(
             topology
-                .graph
+                .graph()
                 .keys()
                 .copied()
                 .filter(|id| {
                     if topology.selected_node.is_some() {
                         topology.selected_node != Some(*id)
                             && topology
-                                .graph
+                                .graph()
                                 .is_adjacent_to(&topology.selected_node.unwrap(), id)
                     } else {
                         false
@@ -632,15 +751,9 @@ impl DroneCommandsPane {
         .placeholder("Select node");
 
         if let Some(id) = topology.selected_node {
-            match topology.graph[&id].0 {
+            match topology.graph()[&id].0 {
                 NetworkNode::Drone { .. } => {
-                    let slider_value = match topology.selected_node {
-                        Some(id) => match topology.graph[&id].0 {
-                            NetworkNode::Drone { pdr, .. } => (pdr * 100f32).round() / 100f32,
-                            _ => 0f32,
-                        },
-                        _ => 0.0,
-                    };
+                    let slider_value = topology.controller.pdr(id).map_or(0.0, change_pdr);
 
                     let slider = container(
                         slider(
@@ -653,11 +766,15 @@ impl DroneCommandsPane {
                     )
                     .width(150);
 
-                    let text_input = text_input("...", &self.slider_input_content)
+                    let draft = match &self.pdr_draft {
+                        Some((draft_id, draft)) if *draft_id == id => draft.as_str(),
+                        _ => "",
+                    };
+                    let text_input = text_input(&slider_value.to_string(), draft)
                         .on_input(DroneCommandsMessage::SliderInputChanged)
                         .on_submit(DroneCommandsMessage::SliderInputSubmitted(
-                            self.slider_input_content.clone(),
-                        )); // ugly clones
+                            draft.to_string(),
+                        ));
 
                     let slider = container(
                         row![
@@ -670,7 +787,15 @@ impl DroneCommandsPane {
                     .width(Fill)
                     .padding(10);
 
-                    let crash = button("Crash").on_press(CrashPressed(topology.selected_node));
+                    let crash = button("Crash").on_press_maybe(
+                        (!is_protected(topology.selected_node))
+                            .then_some(CrashPressed(topology.selected_node)),
+                    );
+                    let crash = if is_protected(topology.selected_node) {
+                        row![crash, text("Protected node")].spacing(15)
+                    } else {
+                        row![crash]
+                    };
 
                     container(
                         column![
@@ -687,8 +812,18 @@ impl DroneCommandsPane {
                 }
                 _ => container(
                     column![
+                        row![
+                            button("Flood").on_press(DroneCommandsMessage::FloodPressed(id)),
+                            button("Export topology")
+                                .on_press(DroneCommandsMessage::ExportTopologyPressed(id)),
+                        ]
+                        .spacing(20),
                         container(row![add_sender, pick_list_add].spacing(20)),
-                        container(row![rmv_sender, pick_list_rmv].spacing(20))
+                        container(row![rmv_sender, pick_list_rmv].spacing(20)),
+                        topology.controller.host_sessions().view(id),
+                        topology.controller.routing_tables().view(id),
+                        topology.controller.fragment_stats().view(id),
+                        topology.controller.server_stats().view(id)
                     ]
                     .spacing(20),
                 )
@@ -696,42 +831,101 @@ impl DroneCommandsPane {
                 .padding(20)
                 .into(),
             }
+        } else if let Some((from, to)) = topology.selected_edge {
+            container(text(format!(
+                "Link {from} - {to} selected, press Delete on the topology to remove it"
+            )))
+            .padding(20)
+            .into()
         } else {
             container(text("Please select a node")).padding(20).into()
         }
     }
 }
 
-fn is_topology_valid(topology: &AdjacencyVecGraph<NodeId, (NetworkNode, Point)>) -> bool {
-    let client_drones = topology
-        .iter()
-        .filter(|(_id, (node_type, _position))| matches!(node_type, NetworkNode::Client { .. }))
-        .all(|(id, (_node_type, _position))| {
-            topology.adjacents(id).count() == 1 || topology.adjacents(id).count() == 2
-        });
-
-    let server_drones = topology
-        .iter()
-        .filter(|(_id, (node_type, _position))| matches!(node_type, NetworkNode::Server { .. }))
-        .all(|(id, (_node_type, _position))| topology.adjacents(id).count() >= 2);
-
-    client_drones && server_drones
-}
-
 fn change_pdr(val: f32) -> f32 {
     (val * 100f32).round() / 100f32
 }
 
 struct TopologyPane {
     topology: TopologyRef,
+    toasts: Rc<RefCell<Toasts>>,
+}
+
+const NODE_RADIUS: f32 = 20.0;
+
+/// Colors of the topology canvas, matching the light or dark theme.
+#[derive(Debug, Clone, Copy)]
+struct Palette {
+    edge: Color,
+    selected: Color,
+    drone: Color,
+    server: Color,
+    client: Color,
+    label: Color,
+}
+
+impl Palette {
+    fn of(theme: &Theme) -> Self {
+        if theme.extended_palette().is_dark {
+            Self {
+                edge: Color::from_rgb8(200, 200, 200),
+                selected: Color::from_rgb8(255, 90, 90),
+                drone: Color::from_rgb8(230, 230, 230),
+                server: Color::from_rgb8(190, 120, 230),
+                client: Color::from_rgb8(110, 230, 110),
+                label: Color::from_rgb8(170, 170, 170),
+            }
+        } else {
+            Self {
+                edge: Color::BLACK,
+                selected: Color::from_rgb8(255, 0, 0),
+                drone: Color::WHITE,
+                server: Color::from_rgb8(128, 0, 128),
+                client: Color::from_rgb8(0, 255, 0),
+                label: Color::from_rgb8(128, 128, 128),
+            }
+        }
+    }
 }
 
 #[derive(Debug)]
-enum TopologyMessage {}
+enum TopologyMessage {
+    NodeSelected(Option<NodeId>),
+    EdgeSelected((NodeId, NodeId)),
+    RemoveSelectedEdge,
+    NodeMoved(NodeId, Point),
+}
 
 impl TopologyPane {
-    fn new(topology: TopologyRef) -> Self {
-        Self { topology }
+    fn new(topology: TopologyRef, toasts: Rc<RefCell<Toasts>>) -> Self {
+        Self { topology, toasts }
+    }
+
+    fn update(&mut self, message: TopologyMessage) {
+        let mut topology = self.topology.borrow_mut();
+        match message {
+            TopologyMessage::NodeSelected(node) => {
+                topology.selected_node = node;
+                topology.selected_edge = None;
+            }
+            TopologyMessage::EdgeSelected(edge) => {
+                topology.selected_node = None;
+                topology.selected_edge = Some(edge);
+            }
+            TopologyMessage::RemoveSelectedEdge => {
+                if let Some((from, to)) = topology.selected_edge.take() {
+                    if let Err(reason) = topology.controller.remove_edge(from, to) {
+                        self.toasts.borrow_mut().push(reason);
+                    }
+                }
+            }
+            TopologyMessage::NodeMoved(id, position) => {
+                if let Some((_node_type, node_position)) = topology.controller.node_mut(id) {
+                    *node_position = position;
+                }
+            }
+        }
     }
 
     fn view(&self) -> Element<TopologyMessage> {
@@ -741,41 +935,121 @@ impl TopologyPane {
 }
 
 impl Program<TopologyMessage> for TopologyPane {
-    type State = ();
+    /// The node being dragged, if any.
+    type State = Option<NodeId>;
+
+    fn update(
+        &self,
+        dragging: &mut Self::State,
+        event: Event,
+        bounds: Rectangle,
+        cursor: Cursor,
+    ) -> (Status, Option<TopologyMessage>) {
+        let mouse_event = match event {
+            Event::Mouse(mouse_event) => mouse_event,
+            Event::Keyboard(keyboard::Event::KeyPressed { key, .. })
+                if self.topology.borrow().selected_edge.is_some()
+                    && cursor.is_over(bounds)
+                    && is_delete_key(&key) =>
+            {
+                return (Status::Captured, Some(TopologyMessage::RemoveSelectedEdge));
+            }
+            _ => return (Status::Ignored, None),
+        };
+        match mouse_event {
+            mouse::Event::ButtonPressed(mouse::Button::Left) => {
+                let Some(cursor_position) = cursor.position_in(bounds) else {
+                    return (Status::Ignored, None);
+                };
+                let topology = self.topology.borrow();
+                *dragging = topology
+                    .graph()
+                    .iter()
+                    .find(|(_id, (_node_type, position))| {
+                        position.distance(cursor_position) < NODE_RADIUS
+                    })
+                    .map(|(id, _)| *id);
+                let edge = edge_at(
+                    topology.graph(),
+                    |(_node_type, position)| *position,
+                    cursor_position,
+                );
+                match (*dragging, edge) {
+                    (None, Some(edge)) => {
+                        (Status::Captured, Some(TopologyMessage::EdgeSelected(edge)))
+                    }
+                    _ => (
+                        Status::Captured,
+                        Some(TopologyMessage::NodeSelected(*dragging)),
+                    ),
+                }
+            }
+            mouse::Event::CursorMoved { .. } => match (*dragging, cursor.position_in(bounds)) {
+                (Some(id), Some(position)) => (
+                    Status::Captured,
+                    Some(TopologyMessage::NodeMoved(id, position)),
+                ),
+                _ => (Status::Ignored, None),
+            },
+            mouse::Event::ButtonReleased(mouse::Button::Left) if dragging.is_some() => {
+                *dragging = None;
+                (Status::Captured, None)
+            }
+            _ => (Status::Ignored, None),
+        }
+    }
 
     fn draw(
         &self,
-        _state: &Self::State,
+        _dragging: &Self::State,
         renderer: &Renderer,
-        _theme: &Theme,
+        theme: &Theme,
         bounds: Rectangle,
         _cursor: Cursor,
     ) -> Vec<Geometry<Renderer>> {
         let mut frame = Frame::new(renderer, bounds.size());
+        let palette = Palette::of(theme);
         let topology = self.topology.borrow();
+        let backlogs = packet_backlogs(topology.controller.packet_senders());
         for (from, to) in topology
-            .graph
+            .graph()
             .edges()
-            .map(|(from, to)| (topology.graph[from].1, topology.graph[to].1))
+            .map(|(from, to)| (topology.graph()[from].1, topology.graph()[to].1))
         {
             frame.stroke(
                 &Path::line(from, to),
                 Stroke {
                     width: 5.0,
                     ..Default::default()
-                },
+                }
+                .with_color(palette.edge),
             );
         }
-        for (id, (node_type, position)) in topology.graph.iter() {
+        if let Some((from, to)) = topology.selected_edge {
+            if let (Some((_, from)), Some((_, to))) =
+                (topology.graph().get(&from), topology.graph().get(&to))
+            {
+                draw_selected_edge(&mut frame, *from, *to);
+            }
+        }
+        if let Some(replay) = &topology.replay {
+            replay.draw(&mut frame, topology.controller.packet_traces(), |id| {
+                topology
+                    .graph()
+                    .get(&id)
+                    .map(|(_node_type, position)| *position)
+            });
+        }
+        for (id, (node_type, position)) in topology.graph().iter() {
             frame.fill(
-                &Path::circle(*position, 20f32),
+                &Path::circle(*position, NODE_RADIUS),
                 if topology.selected_node == Some(*id) {
-                    Color::from_rgb8(255, 0, 0)
+                    palette.selected
                 } else {
                     match node_type {
-                        NetworkNode::Drone { .. } => Color::WHITE,
-                        NetworkNode::Server { .. } => Color::from_rgb8(128, 0, 128),
-                        NetworkNode::Client { .. } => Color::from_rgb8(0, 255, 0),
+                        NetworkNode::Drone { .. } => palette.drone,
+                        NetworkNode::Server { .. } => palette.server,
+                        NetworkNode::Client { .. } => palette.client,
                     }
                 },
             );
@@ -791,42 +1065,181 @@ impl Program<TopologyMessage> for TopologyPane {
                 vertical_alignment: Vertical::Center,
                 ..Default::default()
             });
+            if !matches!(node_type, NetworkNode::Drone { .. }) {
+                frame.fill_text(Text {
+                    content: node_type.to_string(),
+                    position: *position + Vector::new(0.0, 30.0),
+                    size: Pixels(14f32),
+                    color: palette.label,
+                    horizontal_alignment: Horizontal::Center,
+                    vertical_alignment: Vertical::Center,
+                    ..Default::default()
+                });
+            }
+            if let Some(&backlog) = backlogs.get(id) {
+                draw_backlog_badge(&mut frame, *position, backlog);
+            }
         }
 
         vec![frame.into_geometry()]
     }
 }
 
+const MAX_SHOWN_EVENTS: usize = 500;
+
+struct ThroughputPane {
+    topology: TopologyRef,
+}
+
+impl ThroughputPane {
+    fn new(topology: TopologyRef) -> Self {
+        Self { topology }
+    }
+
+    fn view<'a, M: 'a>(&'a self) -> Element<'a, M> {
+        container(
+            column![
+                canvas(self).width(Fill).height(Fill),
+                self.topology.borrow().controller.session_latencies().view(),
+            ]
+            .spacing(10),
+        )
+        .padding(20)
+        .into()
+    }
+}
+
+impl<M> Program<M> for ThroughputPane {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: Cursor,
+    ) -> Vec<Geometry<Renderer>> {
+        let mut frame = Frame::new(renderer, bounds.size());
+        self.topology
+            .borrow()
+            .controller
+            .throughput()
+            .draw_chart(&mut frame);
+        vec![frame.into_geometry()]
+    }
+}
+
 struct DroneEventsPane {
-    drone_event_rcv: RefCell<Receiver<DroneEvent>>,
-    host_event_rcv: RefCell<Receiver<HostEvent>>,
-    packet_senders: Rc<RefCell<HashMap<NodeId, Sender<Packet>>>>,
+    topology: TopologyRef,
+    drone_events: VecDeque<DroneEvent>,
+    host_events: VecDeque<HostEvent>,
+    replay_session: Option<u64>,
 }
 
-#[derive(Debug)]
-enum DroneEventsMessage {}
+#[derive(Debug, Clone)]
+enum DroneEventsMessage {
+    ExportPressed,
+    ClearPressed,
+    ReplaySessionSelected(u64),
+    ReplayPressed(u64),
+    StopReplayPressed,
+}
 
 impl DroneEventsPane {
-    fn new(
-        drone_event_rcv: RefCell<Receiver<DroneEvent>>,
-        host_event_rcv: RefCell<Receiver<HostEvent>>,
-        packet_senders: Rc<RefCell<HashMap<NodeId, Sender<Packet>>>>,
-    ) -> Self {
+    fn new(topology: TopologyRef) -> Self {
         Self {
-            drone_event_rcv,
-            host_event_rcv,
-            packet_senders,
+            topology,
+            drone_events: VecDeque::new(),
+            host_events: VecDeque::new(),
+            replay_session: None,
+        }
+    }
+
+    fn update(&mut self, message: DroneEventsMessage) {
+        match message {
+            DroneEventsMessage::ExportPressed => {
+                if let Err(err) = self
+                    .topology
+                    .borrow()
+                    .controller
+                    .event_log()
+                    .export_with_dialog()
+                {
+                    println!("Unable to export the event log: {err}");
+                }
+            }
+            DroneEventsMessage::ClearPressed => {
+                self.drone_events.clear();
+                self.host_events.clear();
+            }
+            DroneEventsMessage::ReplaySessionSelected(session_id) => {
+                self.replay_session = Some(session_id);
+            }
+            DroneEventsMessage::ReplayPressed(session_id) => {
+                self.topology.borrow_mut().replay = Some(Replay::new(session_id));
+            }
+            DroneEventsMessage::StopReplayPressed => self.topology.borrow_mut().replay = None,
+        }
+    }
+
+    /// Moves the pending events into the scrollback, keeping the latest [`MAX_SHOWN_EVENTS`],
+    /// and steps the packet replay.
+    fn poll_events(&mut self) {
+        let events = self.topology.borrow_mut().controller.drain_events();
+        self.topology.borrow_mut().advance_replay();
+        for event in events {
+            match event {
+                ControllerEvent::Drone(event) => {
+                    self.drone_events.push_front(event);
+                    self.drone_events.truncate(MAX_SHOWN_EVENTS);
+                }
+                ControllerEvent::Host(
+                    HostEvent::RoutingTable(..)
+                    | HostEvent::FragmentStats(..)
+                    | HostEvent::ServerStats(..),
+                ) => {}
+                ControllerEvent::Host(event) => {
+                    self.host_events.push_front(event);
+                    self.host_events.truncate(MAX_SHOWN_EVENTS);
+                }
+            }
         }
     }
 
     fn view(&self) -> Element<DroneEventsMessage> {
         container(
             column![
+                row![
+                    button("Export log").on_press(DroneEventsMessage::ExportPressed),
+                    button("Clear").on_press(DroneEventsMessage::ClearPressed),
+                ]
+                .spacing(10),
+                row![
+                    pick_list(
+                        self.topology.borrow().controller.packet_traces().sessions(),
+                        self.replay_session,
+                        DroneEventsMessage::ReplaySessionSelected,
+                    )
+                    .placeholder("Session"),
+                    button("Replay")
+                        .on_press_maybe(self.replay_session.map(DroneEventsMessage::ReplayPressed)),
+                    button("Stop").on_press(DroneEventsMessage::StopReplayPressed),
+                ]
+                .spacing(10),
                 container(
-                    column!(text("Drone Events:"), scrollable(self.drone_listener()),).spacing(15)
+                    column!(
+                        text("Drone Events:"),
+                        scrollable(self.drone_listener()).height(Fill),
+                    )
+                    .spacing(15)
                 ),
                 container(
-                    column!(text("Host Events:"), scrollable(self.host_listener()),).spacing(15)
+                    column!(
+                        text("Host Events:"),
+                        scrollable(self.host_listener()).height(Fill),
+                    )
+                    .spacing(15)
                 ),
             ]
             .spacing(40),
@@ -839,36 +1252,21 @@ impl DroneEventsPane {
 
     fn drone_listener(&self) -> Element<DroneEventsMessage> {
         column(
-            self.drone_event_rcv
-                .borrow()
-                .try_iter()
-                .filter_map(|event| {
-                    if let DroneEvent::ControllerShortcut(packet) = event {
-                        let destination_sender = &self.packet_senders.borrow()
-                            [&packet.routing_header.destination().unwrap()];
-                        destination_sender
-                            .send(packet)
-                            .expect("error sending packet");
-                        None
-                    } else {
-                        Some(text(format!("{:?}", event)).into())
-                    }
-                }),
+            self.drone_events
+                .iter()
+                .map(|event| text(format!("{:?}", event)).into()),
         )
         .spacing(30)
-        .height(100)
         .width(Fill)
         .into()
     }
     fn host_listener(&self) -> Element<DroneEventsMessage> {
         column(
-            self.host_event_rcv
-                .borrow()
-                .try_iter()
+            self.host_events
+                .iter()
                 .map(|event| text(format!("{:?}", event)).into()),
         )
         .spacing(30)
-        .height(100)
         .width(Fill)
         .into()
     }
@@ -880,6 +1278,8 @@ pub fn main() -> iced::Result {
         Application::update,
         Application::view,
     )
+    .subscription(|_state| iced::time::every(POLL_INTERVAL).map(|_| Message::Tick))
+    .theme(|application| application.settings.theme.theme())
     .centered()
     .run()
 }
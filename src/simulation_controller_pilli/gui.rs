@@ -1,17 +1,27 @@
-use crate::application::simulation_controller_messages::{HostCommand, HostEvent};
+use crate::application::simulation_controller_messages::HostEvent;
+use crate::application::topology::node::ApplicationType;
+use crate::controller_core::backlog::{draw_backlog_badge, packet_backlogs};
+use crate::controller_core::confirmation::{confirmation_modal, crash_warning};
+use crate::controller_core::controller::SimulationController;
+use crate::controller_core::edges::{draw_selected_edge, edge_at, is_delete_key};
+use crate::controller_core::events::{ControllerEvent, POLL_INTERVAL};
+use crate::controller_core::operations::AsNetworkNode;
+use crate::controller_core::replay::Replay;
+use crate::controller_core::send_form::{SendForm, SendFormMessage};
+use crate::controller_core::settings::{Settings, ThemeChoice};
+use crate::controller_core::toast::Toasts;
 use crate::initialization::network_initializer::{start_actual_simulation, NetworkNode};
-use crate::initialization::node_creators::{
-    ActualClientCreator, ActualDroneCreator, ActualServerCreator, ClientCreator, DroneCreator,
-    ServerCreator,
+use crate::simulation_controller_pilli::gui::PaneType::{
+    ControlPane, MessagesPane, NetworkPane, ThroughputPane,
 };
-use crate::simulation_controller_pilli::gui::PaneType::{ControlPane, MessagesPane, NetworkPane};
 use crate::Topology;
 use canvas::Program;
-use crossbeam_channel::{unbounded, Receiver, Sender};
+use crossbeam_channel::unbounded;
 use graph::{AdjacencyVecGraph, ReferenceGraph};
 use iced::advanced::image::{Handle, Image};
 use iced::alignment::{Horizontal, Vertical};
 use iced::event::Status;
+use iced::keyboard;
 use iced::mouse::Event as MouseEvent;
 use iced::mouse::{Button, Cursor};
 use iced::widget::canvas;
@@ -19,7 +29,7 @@ use iced::widget::canvas::stroke::Style;
 use iced::widget::canvas::{Event, Frame, Geometry, Path, Stroke, Text};
 use iced::widget::pane_grid::{Axis, State};
 use iced::widget::{
-    button, column, container, pane_grid, pick_list, row, scrollable, text, text_input,
+    button, checkbox, column, container, pane_grid, pick_list, row, scrollable, text, text_input,
 };
 use iced::{
     alignment, color, Color, Element, Length, Point, Rectangle, Renderer, Size, Task, Theme, Vector,
@@ -27,10 +37,10 @@ use iced::{
 use rand::{random, thread_rng, Rng};
 use std::cell::RefCell;
 use std::collections::{HashMap, VecDeque};
+use std::env;
 use std::fmt::{Display, Formatter};
-use std::time::{Duration, Instant};
-use std::{env, thread};
-use wg_2024::controller::{DroneCommand, DroneEvent};
+use std::time::Instant;
+use wg_2024::controller::DroneEvent;
 use wg_2024::network::NodeId;
 use wg_2024::packet::{Packet, PacketType};
 
@@ -40,6 +50,15 @@ pub struct DisplayableNode {
     value: NetworkNode,
 }
 
+impl AsNetworkNode for DisplayableNode {
+    fn network_node(&self) -> &NetworkNode {
+        &self.value
+    }
+    fn network_node_mut(&mut self) -> &mut NetworkNode {
+        &mut self.value
+    }
+}
+
 impl DisplayableNode {
     fn with_random_fields(value: NetworkNode) -> Self {
         let mut rng = thread_rng();
@@ -58,12 +77,64 @@ impl DisplayableNode {
     }
 }
 
-#[derive(Default, Clone)]
+/// Colors of the GUI, matching the light or dark theme.
+#[derive(Debug, Clone, Copy)]
+struct Palette {
+    accent: Color,
+    background: Color,
+    edge: Color,
+    packet: Color,
+    label: Color,
+}
+
+impl Palette {
+    fn of(theme: &Theme) -> Self {
+        if theme.extended_palette().is_dark {
+            Self {
+                accent: color!(0xe05252),
+                background: color!(0x1f2a33),
+                edge: color!(0xd0d0d0),
+                packet: Color::from_rgba8(255, 220, 120, 0.3),
+                label: color!(0xff6b6b),
+            }
+        } else {
+            Self {
+                accent: color!(0x9c0b0b),
+                background: Color::from_rgb8(155, 177, 191),
+                edge: Color::BLACK,
+                packet: Color::from_rgba8(255, 255, 255, 0.1),
+                label: color!(0xb5040f),
+            }
+        }
+    }
+}
+
 pub struct Network {
-    nodes: AdjacencyVecGraph<NodeId, DisplayableNode>,
+    controller: SimulationController<DisplayableNode>,
     selected_node: Option<NodeId>,
+    selected_edge: Option<(NodeId, NodeId)>,
     dragging_node: Option<NodeId>,
     packets: RefCell<Vec<(Instant, Packet)>>,
+    backlogs: RefCell<HashMap<NodeId, usize>>,
+    replay: Option<Replay>,
+}
+
+impl Network {
+    fn new(controller: SimulationController<DisplayableNode>) -> Self {
+        Self {
+            controller,
+            selected_node: None,
+            selected_edge: None,
+            dragging_node: None,
+            packets: Default::default(),
+            backlogs: Default::default(),
+            replay: None,
+        }
+    }
+
+    fn nodes(&self) -> &AdjacencyVecGraph<NodeId, DisplayableNode> {
+        self.controller.graph()
+    }
 }
 
 impl Program<Messages> for Network {
@@ -91,14 +162,20 @@ impl Program<Messages> for Network {
                 }
                 MouseEvent::ButtonPressed(Button::Left) => {
                     if let Some(cursor_position) = cursor.position_in(bounds) {
-                        let selected = self.nodes.iter().find_map(|(key, value)| {
+                        let selected = self.nodes().iter().find_map(|(key, value)| {
                             if value.position.distance(cursor_position) < 25.0 {
                                 Some(*key)
                             } else {
                                 None
                             }
                         });
-                        (Status::Captured, Some(Messages::NodeSelected(selected)))
+                        let edge = edge_at(self.nodes(), |node| node.position, cursor_position);
+                        match (selected, edge) {
+                            (None, Some(edge)) => {
+                                (Status::Captured, Some(Messages::EdgeSelected(edge)))
+                            }
+                            _ => (Status::Captured, Some(Messages::NodeSelected(selected))),
+                        }
                     } else {
                         (Status::Ignored, None)
                     }
@@ -108,6 +185,13 @@ impl Program<Messages> for Network {
                 }
                 _ => (Status::Ignored, None),
             },
+            Event::Keyboard(keyboard::Event::KeyPressed { key, .. })
+                if self.selected_edge.is_some()
+                    && cursor.is_over(bounds)
+                    && is_delete_key(&key) =>
+            {
+                (Status::Captured, Some(Messages::RemoveSelectedEdge))
+            }
             _ => (Status::Ignored, None),
         }
     }
@@ -116,26 +200,28 @@ impl Program<Messages> for Network {
         &self,
         _state: &Self::State,
         renderer: &Renderer,
-        _theme: &Theme,
+        theme: &Theme,
         bounds: Rectangle,
         _cursor: Cursor,
     ) -> Vec<Geometry<Renderer>> {
         let mut frame = Frame::new(renderer, bounds.size());
+        let palette = Palette::of(theme);
 
         frame.fill(
             &Path::rectangle(Point::default(), bounds.size()),
-            Color::from_rgb8(155, 177, 191),
+            palette.background,
         );
 
-        for (from, to) in self.nodes.edges() {
+        for (from, to) in self.nodes().edges() {
             let line = Path::line(
-                self.nodes.get(from).unwrap().position,
-                self.nodes.get(to).unwrap().position,
+                self.nodes().get(from).unwrap().position,
+                self.nodes().get(to).unwrap().position,
             );
             frame.stroke(
                 &line,
                 Stroke {
                     width: 2.0,
+                    style: Style::Solid(palette.edge),
                     ..Stroke::default()
                 },
             );
@@ -151,23 +237,35 @@ impl Program<Messages> for Network {
                     pack.routing_header.current_hop().unwrap(),
                 )
             })
-            .filter(|(from, to)| self.nodes.contains_node(from) && self.nodes.contains_node(to))
+            .filter(|(from, to)| self.nodes().contains_node(from) && self.nodes().contains_node(to))
         {
             let line = Path::line(
-                self.nodes.get(&from).unwrap().position,
-                self.nodes.get(&to).unwrap().position,
+                self.nodes().get(&from).unwrap().position,
+                self.nodes().get(&to).unwrap().position,
             );
             frame.stroke(
                 &line,
                 Stroke {
                     width: 2.0,
-                    style: Style::Solid(iced::Color::from_rgba8(255, 255, 255, 0.1)),
+                    style: Style::Solid(palette.packet),
                     ..Stroke::default()
                 },
             );
         }
 
-        for (id, node) in self.nodes.iter() {
+        if let Some((from, to)) = self.selected_edge {
+            if let (Some(from), Some(to)) = (self.nodes().get(&from), self.nodes().get(&to)) {
+                draw_selected_edge(&mut frame, from.position, to.position);
+            }
+        }
+
+        if let Some(replay) = &self.replay {
+            replay.draw(&mut frame, self.controller.packet_traces(), |id| {
+                self.nodes().get(&id).map(|node| node.position)
+            });
+        }
+
+        for (id, node) in self.nodes().iter() {
             let path = match node.value {
                 NetworkNode::Drone { .. } => "assets/pilli/Titti.png",
                 NetworkNode::Client { .. } => "assets/pilli/Bugs Bunny.png",
@@ -183,14 +281,21 @@ impl Program<Messages> for Network {
             );
 
             let text = Text {
-                content: id.to_string(),
+                content: match node.value {
+                    NetworkNode::Drone { .. } => id.to_string(),
+                    _ => format!("{} {id}", node.value),
+                },
                 position: node.position + Vector::new(0.0, 25.0),
                 horizontal_alignment: Horizontal::Center,
                 vertical_alignment: Vertical::Center,
-                color: color!(0xb5040f),
+                color: palette.label,
                 ..Text::default()
             };
             frame.fill_text(text);
+
+            if let Some(&backlog) = self.backlogs.borrow().get(id) {
+                draw_backlog_badge(&mut frame, node.position, backlog);
+            }
         }
 
         vec![frame.into_geometry()]
@@ -202,6 +307,7 @@ enum PaneType {
     NetworkPane,
     ControlPane,
     MessagesPane,
+    ThroughputPane,
 }
 
 #[derive(Debug, Clone)]
@@ -209,37 +315,52 @@ enum PaneType {
 enum Messages {
     AddPressed,
     SelectedToAdd(NetworkNode),
+    DroneImplementationSelected(&'static str),
+    ToggleInitialNeighbor(NodeId, bool),
     InputValue(String),
     PaneDragged(pane_grid::DragEvent),
     PaneResized(pane_grid::ResizeEvent),
     NodeSelected(Option<NodeId>),
+    EdgeSelected((NodeId, NodeId)),
+    RemoveSelectedEdge,
     InputPDR(String),
     ChangePressed,
     NodeMoved(Point),
     StopDragging,
+    RequestDelete,
+    CancelDelete,
     DeleteNode,
     AddNeighbor(NodeId),
     RemoveNeighbor(NodeId),
     ConfirmAddNgh,
     ConfirmRemNgh,
+    Flood,
+    ExportTopology,
+    ExportLog,
+    ThemeSelected(ThemeChoice),
+    SendForm(SendFormMessage),
+    ReplaySessionSelected(u64),
+    ReplayPressed(u64),
+    StopReplay,
     Tick,
 }
 
 pub struct Info {
     network: Network,
     to_add: Option<NetworkNode>,
+    drone_implementation: Option<&'static str>,
     input_id: String,
+    initial_neighbors: Vec<NodeId>,
     panes: State<PaneType>,
     input_pdr: String,
     to_add_ngh: Option<NodeId>,
     to_rem_ngh: Option<NodeId>,
-    host_events: RefCell<VecDeque<HostEvent>>,
-    drone_event_recv: Receiver<DroneEvent>,
-    host_event_recv: Receiver<HostEvent>,
-    packet_senders: HashMap<NodeId, Sender<Packet>>,
-    drone_creator: ActualDroneCreator,
-    client_creator: ActualClientCreator,
-    server_creator: ActualServerCreator,
+    pending_delete: Option<NodeId>,
+    toasts: Toasts,
+    host_events: VecDeque<HostEvent>,
+    send_form: SendForm,
+    settings: Settings,
+    replay_session: Option<u64>,
 }
 
 impl Display for HostEvent {
@@ -254,6 +375,111 @@ impl Display for HostEvent {
             HostEvent::MessageSent(message) => {
                 write!(f, "{message}")
             }
+            HostEvent::SessionStarted {
+                host,
+                session_id,
+                direction,
+                total_fragments,
+            } => {
+                write!(
+                    f,
+                    "{host} started session {session_id} ({direction:?}, {total_fragments} fragments)"
+                )
+            }
+            HostEvent::SessionProgress {
+                host,
+                session_id,
+                remaining,
+            } => {
+                write!(f, "{host} session {session_id}: {remaining} fragments left")
+            }
+            HostEvent::SessionEnded { host, session_id } => {
+                write!(f, "{host} ended session {session_id}")
+            }
+            HostEvent::SessionAbandoned { host, session_id } => {
+                write!(f, "{host} abandoned session {session_id}")
+            }
+            HostEvent::SessionEvicted {
+                host,
+                session_id,
+                direction,
+            } => {
+                write!(
+                    f,
+                    "{host} evicted session {session_id} ({direction:?}) to save memory"
+                )
+            }
+            HostEvent::MessageFailed { host, session_id } => {
+                write!(f, "{host} failed to send session {session_id}")
+            }
+            HostEvent::SessionStats {
+                host,
+                session_id,
+                destination,
+                latency,
+                retransmissions,
+                ..
+            } => {
+                write!(
+                    f,
+                    "{host} delivered session {session_id} to {destination} in {} ms with {retransmissions} retransmissions",
+                    latency.as_millis()
+                )
+            }
+            HostEvent::GameOver { leaderboard } => match leaderboard.first() {
+                Some((winner, points)) => {
+                    write!(f, "The game is over, {winner} won with {points} points")
+                }
+                None => write!(f, "The game is over"),
+            },
+            HostEvent::RoutesRecalculated { host, routes } => {
+                write!(f, "{host} found {routes} routes after flooding")
+            }
+            HostEvent::RoutingTable(host, routes) => {
+                write!(f, "{host} knows {} routes", routes.len())
+            }
+            HostEvent::FragmentStats(host, stats) => {
+                write!(
+                    f,
+                    "{host} received {} fragments and sent {}",
+                    stats.received, stats.sent
+                )
+            }
+            HostEvent::ServerStats(host, stats) => {
+                write!(
+                    f,
+                    "{host} handled {} requests, {} in the last minute",
+                    stats.requests, stats.recent_requests
+                )
+            }
+            HostEvent::LibraryChanged {
+                host,
+                updated,
+                removed,
+            } => {
+                write!(
+                    f,
+                    "{host} reloaded its library: {} items updated, {} removed",
+                    updated.len(),
+                    removed.len()
+                )
+            }
+            HostEvent::MessageSpoofed {
+                host,
+                source,
+                session_id,
+            } => {
+                write!(
+                    f,
+                    "{host} dropped session {session_id}, not signed by its source {source}"
+                )
+            }
+            HostEvent::BehaviourPanicked { host } => {
+                write!(f, "{host} stopped, its behaviour panicked")
+            }
+            HostEvent::ShutDown { host, abandoned } => {
+                write!(f, "{host} shut down, abandoning {abandoned} responses")
+            }
         }
     }
 }
@@ -275,27 +501,29 @@ impl Default for Info {
         pane_state
             .split(Axis::Horizontal, new_pane, MessagesPane)
             .unwrap();
+        pane_state
+            .split(Axis::Horizontal, pane, ThroughputPane)
+            .unwrap();
 
         Self {
-            network: Network {
-                nodes: info
-                    .network_graph
-                    .map_values(DisplayableNode::with_random_fields),
-                ..Network::default()
-            },
+            network: Network::new(SimulationController::new(
+                info,
+                DisplayableNode::with_random_fields,
+            )),
             panes: pane_state,
-            host_event_recv: info.host_event_controller_recv,
-            drone_event_recv: info.drone_event_controller_recv,
             host_events: Default::default(),
             to_add: Default::default(),
+            drone_implementation: None,
             to_rem_ngh: Default::default(),
+            pending_delete: Default::default(),
+            toasts: Default::default(),
+            send_form: Default::default(),
+            settings: Settings::load(),
+            replay_session: None,
             input_id: Default::default(),
-            packet_senders: info.packet_senders,
+            initial_neighbors: Default::default(),
             input_pdr: Default::default(),
             to_add_ngh: Default::default(),
-            drone_creator: info.drone_creator,
-            client_creator: info.client_creator,
-            server_creator: info.server_creator,
         }
     }
 }
@@ -304,59 +532,47 @@ impl Info {
     fn update(&mut self, messages: Messages) -> Task<Messages> {
         match messages {
             Messages::SelectedToAdd(selected) => self.to_add = Some(selected),
+            Messages::DroneImplementationSelected(implementation) => {
+                self.drone_implementation = Some(implementation)
+            }
             Messages::InputValue(value) => {
                 self.input_id = value;
             }
             Messages::AddPressed => {
-                if let (Some(mut to_add), Ok(id)) = (self.to_add.clone(), self.input_id.parse()) {
-                    if !self.network.nodes.contains_node(&id) {
-                        match to_add {
-                            NetworkNode::Drone {
-                                pdr,
-                                ref mut command_send,
-                            } => {
-                                let (cmd_send, cmd_recv) = unbounded();
-                                let (pck_send, pck_recv) = unbounded();
-                                *command_send = cmd_send;
-                                self.packet_senders.insert(id, pck_send);
-                                let mut runnable = self
-                                    .drone_creator
-                                    .create_disconnected_drone(id, cmd_recv, pck_recv, pdr);
-                                thread::spawn(move || runnable.run());
-                            }
-                            NetworkNode::Client {
-                                ref mut command_send,
-                            } => {
-                                let (cmd_send, cmd_recv) = unbounded();
-                                let (pck_send, pck_recv) = unbounded();
-                                *command_send = cmd_send;
-                                self.packet_senders.insert(id, pck_send);
-                                let mut runnable = self
-                                    .client_creator
-                                    .create_disconnected_client(id, cmd_recv, pck_recv);
-                                thread::spawn(move || runnable.run());
-                            }
-                            NetworkNode::Server {
-                                ref mut command_send,
-                            } => {
-                                let (cmd_send, cmd_recv) = unbounded();
-                                let (pck_send, pck_recv) = unbounded();
-                                *command_send = cmd_send;
-                                self.packet_senders.insert(id, pck_send);
-                                let mut runnable = self
-                                    .server_creator
-                                    .create_disconnected_server(id, cmd_recv, pck_recv);
-                                thread::spawn(move || runnable.run());
-                            }
+                if let (Some(to_add), Ok(id)) = (self.to_add.clone(), self.input_id.parse()) {
+                    if let (NetworkNode::Drone { .. }, Some(implementation)) =
+                        (&to_add, self.drone_implementation)
+                    {
+                        if let Err(reason) = self
+                            .network
+                            .controller
+                            .select_drone_implementation(implementation)
+                        {
+                            self.toasts.push(reason);
+                            return Task::none();
+                        }
+                    }
+                    match self.network.controller.spawn_node(
+                        id,
+                        DisplayableNode::with_random_fields(to_add),
+                        &self.initial_neighbors,
+                    ) {
+                        Ok(()) => {
+                            self.initial_neighbors.clear();
+                            self.input_id.clear();
+                            self.to_add.take();
                         }
-                        self.network
-                            .nodes
-                            .add_node(id, DisplayableNode::with_random_fields(to_add));
-                        self.input_id.clear();
-                        self.to_add.take();
+                        Err(reason) => self.toasts.push(reason),
                     }
                 }
             }
+            Messages::ToggleInitialNeighbor(id, checked) => {
+                if checked {
+                    self.initial_neighbors.push(id);
+                } else {
+                    self.initial_neighbors.retain(|ngh| *ngh != id);
+                }
+            }
             Messages::PaneDragged(pane_grid::DragEvent::Dropped { pane, target }) => {
                 self.panes.drop(pane, target)
             }
@@ -373,87 +589,53 @@ impl Info {
                     self.network.dragging_node = node;
                 }
                 self.network.selected_node = node;
+                self.network.selected_edge = None;
+            }
+            Messages::EdgeSelected(edge) => {
+                self.network.selected_node = None;
+                self.network.selected_edge = Some(edge);
+            }
+            Messages::RemoveSelectedEdge => {
+                if let Some((from, to)) = self.network.selected_edge.take() {
+                    if let Err(reason) = self.network.controller.remove_edge(from, to) {
+                        self.toasts.push(reason);
+                    }
+                }
             }
             Messages::InputPDR(val) => self.input_pdr = val,
             Messages::ChangePressed => {
-                if let Ok(new_pdr) = self.input_pdr.parse() {
-                    if (0.0..=1.0).contains(&new_pdr) {
-                        let node = self
-                            .network
-                            .nodes
-                            .get_mut(&self.network.selected_node.unwrap())
-                            .unwrap();
-
-                        match node.value {
-                            NetworkNode::Drone {
-                                ref mut pdr,
-                                ref command_send,
-                            } => {
-                                *pdr = new_pdr;
-                                command_send
-                                    .send(DroneCommand::SetPacketDropRate(new_pdr))
-                                    .ok();
-                            }
-                            _ => unreachable!(),
-                        }
-
-                        self.input_pdr.clear();
+                let result = match (self.input_pdr.parse(), self.network.selected_node) {
+                    (Ok(new_pdr), Some(selected)) => {
+                        self.network.controller.set_pdr(selected, new_pdr)
                     }
+                    _ => Err("The PDR must be a number between 0 and 1".to_string()),
+                };
+                match result {
+                    Ok(()) => self.input_pdr.clear(),
+                    Err(reason) => self.toasts.push(reason),
                 }
             }
+            Messages::RequestDelete => {
+                self.pending_delete = self.network.selected_node;
+            }
+            Messages::CancelDelete => {
+                self.pending_delete = None;
+            }
             Messages::DeleteNode => {
-                let to_delete = self.network.selected_node.unwrap();
-                let mut new_graph = self.network.nodes.clone();
-                new_graph.remove_node(&to_delete);
-
-                if new_graph.is_connected_undirected()
-                    && !self
-                        .network
-                        .nodes
-                        .adjacents(&self.network.selected_node.unwrap())
-                        .any(|node| {
-                            if let NetworkNode::Server { .. } = self.network.nodes[node].value {
-                                self.network.nodes.adjacents(node).count() <= 2
-                            } else {
-                                false
-                            }
-                        })
-                {
-                    match self.network.nodes.get(&to_delete).unwrap().value {
-                        NetworkNode::Drone {
-                            ref command_send, ..
-                        } => {
-                            command_send.send(DroneCommand::Crash).ok();
-                        }
-                        NetworkNode::Server { ref command_send }
-                        | NetworkNode::Client { ref command_send } => {
-                            command_send.send(HostCommand::Crash).ok();
-                        }
-                    }
-                    for ngh in self.network.nodes.adjacents(&to_delete) {
-                        match self.network.nodes[ngh].value {
-                            NetworkNode::Drone {
-                                ref command_send, ..
-                            } => {
-                                command_send
-                                    .send(DroneCommand::RemoveSender(to_delete))
-                                    .ok();
-                            }
-                            NetworkNode::Server { ref command_send }
-                            | NetworkNode::Client { ref command_send } => {
-                                command_send
-                                    .send(HostCommand::RemoveConnectedDrone(to_delete))
-                                    .ok();
-                            }
-                        }
+                if let Some(to_delete) = self.pending_delete.take() {
+                    match self.network.controller.remove_node(to_delete) {
+                        Ok(()) => self.network.selected_node = None,
+                        Err(reason) => self.toasts.push(reason),
                     }
-                    self.network.nodes.remove_node(&to_delete);
-                    self.network.selected_node = None;
                 }
             }
             Messages::NodeMoved(position) => {
-                if let Some(dragging) = self.network.dragging_node {
-                    self.network.nodes.get_mut(&dragging).unwrap().position = position;
+                if let Some(node) = self
+                    .network
+                    .dragging_node
+                    .and_then(|dragging| self.network.controller.node_mut(dragging))
+                {
+                    node.position = position;
                 }
             }
             Messages::StopDragging => {
@@ -469,122 +651,122 @@ impl Info {
                 if let (Some(to_add_ngh), Some(selected)) =
                     (self.to_add_ngh.take(), self.network.selected_node)
                 {
-                    match self.network.nodes[&selected].value {
-                        NetworkNode::Drone {
-                            ref command_send, ..
-                        } => {
-                            command_send
-                                .send(DroneCommand::AddSender(
-                                    to_add_ngh,
-                                    self.packet_senders[&to_add_ngh].clone(),
-                                ))
-                                .ok();
-                        }
-                        NetworkNode::Client { ref command_send }
-                        | NetworkNode::Server { ref command_send } => {
-                            command_send
-                                .send(HostCommand::AddConnectedDrone(
-                                    to_add_ngh,
-                                    self.packet_senders[&to_add_ngh].clone(),
-                                ))
-                                .ok();
-                        }
-                    }
-
-                    match self.network.nodes[&to_add_ngh].value {
-                        NetworkNode::Drone {
-                            ref command_send, ..
-                        } => {
-                            command_send
-                                .send(DroneCommand::AddSender(
-                                    selected,
-                                    self.packet_senders[&selected].clone(),
-                                ))
-                                .ok();
-                        }
-                        NetworkNode::Client { ref command_send }
-                        | NetworkNode::Server { ref command_send } => {
-                            command_send
-                                .send(HostCommand::AddConnectedDrone(
-                                    selected,
-                                    self.packet_senders[&selected].clone(),
-                                ))
-                                .ok();
-                        }
+                    if let Err(reason) = self.network.controller.add_edge(selected, to_add_ngh) {
+                        self.toasts.push(reason);
                     }
-                    self.network
-                        .nodes
-                        .add_undirected_edge(self.network.selected_node.unwrap(), to_add_ngh);
                 }
             }
             Messages::ConfirmRemNgh => {
                 if let (Some(to_rem_ngh), Some(selected)) =
                     (self.to_rem_ngh.take(), self.network.selected_node)
                 {
-                    match self.network.nodes[&selected].value {
-                        NetworkNode::Drone {
-                            ref command_send, ..
-                        } => {
-                            command_send
-                                .send(DroneCommand::RemoveSender(to_rem_ngh))
-                                .ok();
-                        }
-                        NetworkNode::Server { ref command_send }
-                        | NetworkNode::Client { ref command_send } => {
-                            command_send
-                                .send(HostCommand::RemoveConnectedDrone(to_rem_ngh))
-                                .ok();
-                        }
+                    if let Err(reason) = self.network.controller.remove_edge(selected, to_rem_ngh) {
+                        self.toasts.push(reason);
                     }
-                    match self.network.nodes[&to_rem_ngh].value {
-                        NetworkNode::Drone {
-                            ref command_send, ..
-                        } => {
-                            command_send.send(DroneCommand::RemoveSender(selected)).ok();
-                        }
-                        NetworkNode::Server { ref command_send }
-                        | NetworkNode::Client { ref command_send } => {
-                            command_send
-                                .send(HostCommand::RemoveConnectedDrone(selected))
-                                .ok();
-                        }
+                }
+            }
+            Messages::Flood => {
+                if let Some(selected) = self.network.selected_node {
+                    if let Err(reason) = self.network.controller.initiate_flood(selected) {
+                        self.toasts.push(reason);
                     }
-                    self.network
-                        .nodes
-                        .remove_undirected_edge(&self.network.selected_node.unwrap(), &to_rem_ngh);
                 }
             }
-            Messages::Tick => {}
-        }
-
-        Task::none()
-    }
-    fn view(&self) -> Element<'_, Messages> {
-        let mut network_packets = self.network.packets.borrow_mut();
-        network_packets.retain(|(instant, _)| instant.elapsed().as_millis() < 500);
-        let now = Instant::now();
-        for event in self.drone_event_recv.try_iter() {
-            match event {
-                DroneEvent::PacketSent(packet) => {
-                    if let PacketType::MsgFragment(_) = packet.pack_type {
-                        network_packets.push((now, packet));
+            Messages::ExportTopology => {
+                if let Some(selected) = self.network.selected_node {
+                    if let Err(reason) = self
+                        .network
+                        .controller
+                        .export_topology_with_dialog(selected)
+                    {
+                        self.toasts.push(reason);
+                    }
+                }
+            }
+            Messages::ExportLog => {
+                if let Err(err) = self.network.controller.event_log().export_with_dialog() {
+                    self.toasts
+                        .push(format!("Unable to export the event log: {err}"));
+                }
+            }
+            Messages::ThemeSelected(theme) => {
+                self.settings.theme = theme;
+                if let Err(err) = self.settings.save() {
+                    self.toasts
+                        .push(format!("Unable to save the settings: {err}"));
+                }
+            }
+            Messages::SendForm(SendFormMessage::Submitted) => {
+                if let Err(reason) =
+                    self.send_form
+                        .request()
+                        .and_then(|(client, server, request)| {
+                            self.network
+                                .controller
+                                .send_request(client, server, request)
+                        })
+                {
+                    self.toasts.push(reason);
+                }
+            }
+            Messages::SendForm(message) => self.send_form.update(message),
+            Messages::ReplaySessionSelected(session_id) => self.replay_session = Some(session_id),
+            Messages::ReplayPressed(session_id) => {
+                self.network.replay = Some(Replay::new(session_id));
+            }
+            Messages::StopReplay => self.network.replay = None,
+            Messages::Tick => {
+                self.toasts.expire();
+                let now = Instant::now();
+                for event in self.network.controller.drain_events() {
+                    match event {
+                        ControllerEvent::Drone(DroneEvent::PacketSent(packet)) => {
+                            if let PacketType::MsgFragment(_) = packet.pack_type {
+                                self.network.packets.borrow_mut().push((now, packet));
+                            }
+                        }
+                        ControllerEvent::Host(
+                            HostEvent::RoutingTable(..)
+                            | HostEvent::FragmentStats(..)
+                            | HostEvent::ServerStats(..),
+                        ) => {}
+                        ControllerEvent::Host(event) => self.host_events.push_front(event),
+                        _ => {}
                     }
                 }
-                DroneEvent::ControllerShortcut(packet) => {
-                    self.packet_senders[&packet.routing_header.destination().unwrap()]
-                        .send(packet)
-                        .ok();
+                let replay_finished =
+                    self.network.replay.as_mut().is_some_and(|replay| {
+                        !replay.advance(self.network.controller.packet_traces())
+                    });
+                if replay_finished {
+                    self.network.replay = None;
                 }
-                _ => {}
             }
         }
 
-        let mut host_events = self.host_events.borrow_mut();
-        for event in self.host_event_recv.try_iter() {
-            host_events.push_front(event);
-        }
+        Task::none()
+    }
+    /// `message`, unless it would crash or unlink a node protected in the topology file.
+    fn unless_protected(&self, message: Messages) -> Option<Messages> {
+        let neighbor = match message {
+            Messages::ConfirmRemNgh => self.to_rem_ngh,
+            _ => None,
+        };
+        let protected = [self.network.selected_node, neighbor]
+            .into_iter()
+            .flatten()
+            .any(|id| self.network.controller.is_protected(id));
+        (!protected).then_some(message)
+    }
+
+    fn view(&self) -> Element<'_, Messages> {
+        self.network
+            .packets
+            .borrow_mut()
+            .retain(|(instant, _)| instant.elapsed().as_millis() < 500);
 
-        drop(host_events);
+        *self.network.backlogs.borrow_mut() =
+            packet_backlogs(self.network.controller.packet_senders());
 
         let all = [
             NetworkNode::Drone {
@@ -593,20 +775,32 @@ impl Info {
             },
             NetworkNode::Client {
                 command_send: unbounded().0,
+                application_type: ApplicationType::Unknown,
             },
             NetworkNode::Server {
                 command_send: unbounded().0,
+                application_type: ApplicationType::Unknown,
             },
         ];
 
-        pane_grid(&self.panes, |_pane, state, _is_maximized| {
+        let palette = Palette::of(&self.settings.theme.theme());
+
+        let grid = pane_grid(&self.panes, |_pane, state, _is_maximized| {
             pane_grid::Content::new(match state {
                 PaneType::NetworkPane => container(column![
-                    row![text("SELECT A NODE".to_string())
-                        .size(25)
-                        .color(color!(0x9c0b0b))
-                        .align_x(alignment::Horizontal::Left)
-                        .align_y(alignment::Vertical::Top)]
+                    row![
+                        text("SELECT A NODE".to_string())
+                            .size(25)
+                            .color(palette.accent)
+                            .align_x(alignment::Horizontal::Left)
+                            .align_y(alignment::Vertical::Top),
+                        pick_list(
+                            ThemeChoice::ALL,
+                            Some(self.settings.theme),
+                            Messages::ThemeSelected
+                        ),
+                    ]
+                    .spacing(20)
                     .padding(10),
                     canvas(&self.network)
                         .width(Length::Fill)
@@ -615,10 +809,18 @@ impl Info {
                 PaneType::ControlPane => match self.network.selected_node {
                     Some(id) => container(
                         column![
-                            text(format!("{} {id}", self.network.nodes[&id].value))
-                                .size(25)
-                                .color(color!(0x9c0b0b)),
-                            match self.network.nodes.get(&id).unwrap().value {
+                            text(format!(
+                                "{} {id}{}",
+                                self.network.nodes()[&id].value,
+                                if self.network.controller.is_protected(id) {
+                                    " (protected)"
+                                } else {
+                                    ""
+                                }
+                            ))
+                            .size(25)
+                            .color(palette.accent),
+                            match self.network.nodes().get(&id).unwrap().value {
                                 NetworkNode::Drone { pdr: value, .. } => {
                                     container(scrollable(
                                         column![
@@ -631,19 +833,21 @@ impl Info {
                                                     .on_press(Messages::ChangePressed),
                                             ]
                                             .spacing(10),
-                                            button("Crash Drone").on_press(Messages::DeleteNode),
+                                            button("Crash Drone").on_press_maybe(
+                                                self.unless_protected(Messages::RequestDelete)
+                                            ),
                                             text("Add Neighbor"),
                                             row![
                                                 pick_list(
                                                     self.network
-                                                        .nodes
+                                                        .nodes()
                                                         .keys()
                                                         .copied()
                                                         .filter(|id| {
                                                             self.network.selected_node != Some(*id)
                                                                 && !self
                                                                     .network
-                                                                    .nodes
+                                                                    .nodes()
                                                                     .is_adjacent_to(
                                                                         id,
                                                                         &self
@@ -654,10 +858,10 @@ impl Info {
                                                         })
                                                         .filter(|id| {
                                                             if let NetworkNode::Client { .. } =
-                                                                self.network.nodes[id].value
+                                                                self.network.nodes()[id].value
                                                             {
                                                                 self.network
-                                                                    .nodes
+                                                                    .nodes()
                                                                     .adjacents(id)
                                                                     .count()
                                                                     < 2
@@ -677,7 +881,7 @@ impl Info {
                                             row![
                                                 pick_list(
                                                     self.network
-                                                        .nodes
+                                                        .nodes()
                                                         .adjacents(
                                                             &self.network.selected_node.unwrap()
                                                         )
@@ -687,7 +891,9 @@ impl Info {
                                                     Messages::RemoveNeighbor
                                                 )
                                                 .placeholder("Select a Node"),
-                                                button("Confirm").on_press(Messages::ConfirmRemNgh),
+                                                button("Confirm").on_press_maybe(
+                                                    self.unless_protected(Messages::ConfirmRemNgh)
+                                                ),
                                             ]
                                             .spacing(10),
                                         ]
@@ -696,26 +902,42 @@ impl Info {
                                 }
                                 NetworkNode::Server { .. } => {
                                     let mut elements = Vec::new();
+                                    elements.push(
+                                        container(button("Delete Server").on_press_maybe(
+                                            self.unless_protected(Messages::RequestDelete),
+                                        ))
+                                        .into(),
+                                    );
+                                    elements.push(
+                                        container(button("Flood").on_press(Messages::Flood)).into(),
+                                    );
                                     elements.push(
                                         container(
-                                            button("Delete Server").on_press(Messages::DeleteNode),
+                                            button("Export Topology")
+                                                .on_press(Messages::ExportTopology),
                                         )
                                         .into(),
                                     );
+                                    elements.push(self.network.controller.host_sessions().view(id));
+                                    elements
+                                        .push(self.network.controller.routing_tables().view(id));
+                                    elements
+                                        .push(self.network.controller.fragment_stats().view(id));
+                                    elements.push(self.network.controller.server_stats().view(id));
                                     elements.push(container(text("Add Neighbor")).into());
                                     elements.push(
                                         container(
                                             row![
                                                 pick_list(
                                                     self.network
-                                                        .nodes
+                                                        .nodes()
                                                         .iter()
                                                         .filter_map(|(id, node)| {
                                                             if self.network.selected_node
                                                                 != Some(*id)
                                                                 && !self
                                                                     .network
-                                                                    .nodes
+                                                                    .nodes()
                                                                     .is_adjacent_to(
                                                                         &self
                                                                             .network
@@ -748,7 +970,7 @@ impl Info {
 
                                     if self
                                         .network
-                                        .nodes
+                                        .nodes()
                                         .adjacents(&self.network.selected_node.unwrap())
                                         .count()
                                         > 2
@@ -759,7 +981,7 @@ impl Info {
                                                 row![
                                                     pick_list(
                                                         self.network
-                                                            .nodes
+                                                            .nodes()
                                                             .adjacents(
                                                                 &self
                                                                     .network
@@ -772,8 +994,11 @@ impl Info {
                                                         Messages::RemoveNeighbor
                                                     )
                                                     .placeholder("Select a Node"),
-                                                    button("Confirm")
-                                                        .on_press(Messages::ConfirmRemNgh),
+                                                    button("Confirm").on_press_maybe(
+                                                        self.unless_protected(
+                                                            Messages::ConfirmRemNgh
+                                                        )
+                                                    ),
                                                 ]
                                                 .spacing(10),
                                             )
@@ -785,21 +1010,36 @@ impl Info {
                                 }
                                 NetworkNode::Client { .. } => {
                                     let mut elements = Vec::new();
+                                    elements.push(
+                                        container(button("Delete Client").on_press_maybe(
+                                            self.unless_protected(Messages::RequestDelete),
+                                        ))
+                                        .into(),
+                                    );
+                                    elements.push(
+                                        container(button("Flood").on_press(Messages::Flood)).into(),
+                                    );
                                     elements.push(
                                         container(
-                                            button("Delete Client").on_press(Messages::DeleteNode),
+                                            button("Export Topology")
+                                                .on_press(Messages::ExportTopology),
                                         )
                                         .into(),
                                     );
+                                    elements.push(self.network.controller.host_sessions().view(id));
+                                    elements
+                                        .push(self.network.controller.routing_tables().view(id));
+                                    elements
+                                        .push(self.network.controller.fragment_stats().view(id));
                                     if self
                                         .network
-                                        .nodes
+                                        .nodes()
                                         .adjacents(&self.network.selected_node.unwrap())
                                         .count()
                                         < 2
                                         && self
                                             .network
-                                            .nodes
+                                            .nodes()
                                             .adjacents(&self.network.selected_node.unwrap())
                                             .count()
                                             > 0
@@ -810,14 +1050,14 @@ impl Info {
                                                 row![
                                                     pick_list(
                                                         self.network
-                                                            .nodes
+                                                            .nodes()
                                                             .iter()
                                                             .filter_map(|(id, node)| {
                                                                 if self.network.selected_node
                                                                     != Some(*id)
                                                                     && !self
                                                                         .network
-                                                                        .nodes
+                                                                        .nodes()
                                                                         .is_adjacent_to(
                                                                             &self
                                                                                 .network
@@ -851,7 +1091,7 @@ impl Info {
                                     }
                                     if self
                                         .network
-                                        .nodes
+                                        .nodes()
                                         .adjacents(&self.network.selected_node.unwrap())
                                         .count()
                                         > 1
@@ -863,7 +1103,7 @@ impl Info {
                                                 row![
                                                     pick_list(
                                                         self.network
-                                                            .nodes
+                                                            .nodes()
                                                             .adjacents(
                                                                 &self
                                                                     .network
@@ -876,8 +1116,11 @@ impl Info {
                                                         Messages::RemoveNeighbor
                                                     )
                                                     .placeholder("Select a Node"),
-                                                    button("Confirm")
-                                                        .on_press(Messages::ConfirmRemNgh),
+                                                    button("Confirm").on_press_maybe(
+                                                        self.unless_protected(
+                                                            Messages::ConfirmRemNgh
+                                                        )
+                                                    ),
                                                 ]
                                                 .spacing(10),
                                             )
@@ -893,33 +1136,104 @@ impl Info {
                     .padding(10),
                     None => container(
                         column![
-                            container(text("ADD NODE").size(20).color(color!(0x9c0b0b))),
+                            container(text("ADD NODE").size(20).color(palette.accent)),
                             container(scrollable(
                                 column![
-                                    pick_list(
+                                    row![pick_list(
                                         all.clone(),
                                         self.to_add.as_ref(),
                                         Messages::SelectedToAdd
                                     )
-                                    .placeholder("What to add"),
+                                    .placeholder("What to add")]
+                                    .push_maybe(
+                                        matches!(self.to_add, Some(NetworkNode::Drone { .. }))
+                                            .then(|| {
+                                                pick_list(
+                                                    self.network.controller.drone_implementations(),
+                                                    self.drone_implementation,
+                                                    Messages::DroneImplementationSelected,
+                                                )
+                                                .placeholder("Next in turn")
+                                            })
+                                    )
+                                    .spacing(10),
                                     text_input("Insert ID: ", &self.input_id)
                                         .on_input(Messages::InputValue),
+                                    text("Initial neighbors"),
+                                    column({
+                                        let mut ids = self
+                                            .network
+                                            .nodes()
+                                            .keys()
+                                            .copied()
+                                            .collect::<Vec<_>>();
+                                        ids.sort();
+                                        ids.into_iter().map(|id| {
+                                            checkbox(
+                                                id.to_string(),
+                                                self.initial_neighbors.contains(&id),
+                                            )
+                                            .on_toggle(move |checked| {
+                                                Messages::ToggleInitialNeighbor(id, checked)
+                                            })
+                                            .into()
+                                        })
+                                    })
+                                    .spacing(5),
                                     button("ADD").on_press(Messages::AddPressed),
                                 ]
                                 .spacing(10)
                             ))
                         ]
+                        .push_maybe(self.network.selected_edge.map(|(from, to)| {
+                            row![
+                                text(format!("Link {from} - {to} (Delete to remove)")),
+                                button("Remove").on_press(Messages::RemoveSelectedEdge),
+                            ]
+                            .spacing(10)
+                        }))
                         .spacing(10),
                     )
                     .padding(10),
                 },
+                PaneType::ThroughputPane => container(
+                    column![
+                        container(text("THROUGHPUT").size(25).color(palette.accent)),
+                        canvas(self.network.controller.throughput())
+                            .width(Length::Fill)
+                            .height(Length::Fill),
+                        self.network.controller.session_latencies().view(),
+                    ]
+                    .spacing(10),
+                )
+                .padding(10),
                 MessagesPane => container(column![
-                    container(text("MESSAGES").size(25).color(color!(0x9c0b0b))),
+                    row![
+                        container(text("MESSAGES").size(25).color(palette.accent)),
+                        button("Export Log").on_press(Messages::ExportLog),
+                    ]
+                    .spacing(20),
+                    container(text("SEND MESSAGE").size(20).color(palette.accent)),
+                    self.send_form
+                        .view(self.network.nodes())
+                        .map(Messages::SendForm),
+                    container(text("REPLAY SESSION").size(20).color(palette.accent)),
+                    row![
+                        pick_list(
+                            self.network.controller.packet_traces().sessions(),
+                            self.replay_session,
+                            Messages::ReplaySessionSelected
+                        )
+                        .placeholder("Session"),
+                        button("Replay")
+                            .on_press_maybe(self.replay_session.map(Messages::ReplayPressed)),
+                        button("Stop").on_press(Messages::StopReplay),
+                    ]
+                    .spacing(10),
                     container(
                         scrollable(
                             column(
                                 self.host_events
-                                    .borrow()
                                     .iter()
                                     .map(|element| format!("{element}"))
                                     .map(text)
@@ -934,15 +1248,26 @@ impl Info {
             })
         })
         .on_drag(Messages::PaneDragged)
-        .on_resize(10, Messages::PaneResized)
-        .into()
+        .on_resize(10, Messages::PaneResized);
+
+        let content = match self.pending_delete {
+            Some(id) => confirmation_modal(
+                grid,
+                format!("Remove {} {id}?", self.network.nodes()[&id].value),
+                crash_warning(id, self.network.nodes().adjacents(&id)),
+                Messages::DeleteNode,
+                Messages::CancelDelete,
+            ),
+            None => grid.into(),
+        };
+
+        self.toasts.view(content)
     }
 }
 
 pub fn main() -> iced::Result {
     iced::application("Bagel Bomber", Info::update, Info::view)
-        .subscription(|_state| {
-            iced::time::every(Duration::from_millis(100)).map(|_| Messages::Tick)
-        })
+        .theme(|info| info.settings.theme.theme())
+        .subscription(|_state| iced::time::every(POLL_INTERVAL).map(|_| Messages::Tick))
         .run()
 }
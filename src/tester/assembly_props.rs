@@ -0,0 +1,96 @@
+use crate::application::assembler::{Assembler, AssemblyError, Disassembler};
+use crate::message::base_message::Message;
+use crate::message::chat_message::ChatRequest;
+use crate::message::codec::MessageCodec;
+use crate::message::page::PageRequest;
+use proptest::prelude::*;
+use wg_2024::packet::Fragment;
+
+fn chat_request() -> impl Strategy<Value = ChatRequest> {
+    prop_oneof![
+        Just(ChatRequest::ClientList(PageRequest::default())),
+        Just(ChatRequest::Subscribe),
+        any::<String>().prop_map(ChatRequest::Register),
+        (any::<String>(), any::<String>(), ".{0,2000}")
+            .prop_map(|(from, to, message)| ChatRequest::SendMessage { from, to, message }),
+        (any::<String>(), any::<String>(), ".{0,2000}").prop_map(|(from, group, message)| {
+            ChatRequest::SendToGroup {
+                from,
+                group,
+                message,
+            }
+        }),
+    ]
+}
+
+fn codec() -> impl Strategy<Value = MessageCodec> {
+    prop_oneof![Just(MessageCodec::Json), Just(MessageCodec::Bincode)]
+}
+
+/// A message together with its fragments, shuffled, and the indices of some of them to deliver
+/// twice.
+fn delivery() -> impl Strategy<Value = (Message<ChatRequest>, Vec<Fragment>, Vec<usize>)> {
+    (
+        any::<u8>(),
+        any::<u8>(),
+        any::<u64>(),
+        chat_request(),
+        codec(),
+    )
+        .prop_flat_map(|(source_id, destination_id, session_id, content, codec)| {
+            let message = Message::new(source_id, destination_id, session_id, content);
+            let fragments = Disassembler::new()
+                .with_codec(codec)
+                .disassembly(message.clone());
+            let count = fragments.len();
+            (
+                Just(message),
+                Just(fragments).prop_shuffle(),
+                prop::collection::vec(0..count, 0..8),
+            )
+        })
+}
+
+/// Inserts the fragments in order, returning the first outcome the assembler produced.
+fn replay(fragments: Vec<Fragment>) -> Option<Result<Message<ChatRequest>, AssemblyError>> {
+    let mut assembler = Assembler::new();
+    fragments
+        .into_iter()
+        .find_map(|fragment| assembler.insert_fragment(0, 0, fragment))
+}
+
+proptest! {
+    #[test]
+    fn reordered_and_duplicated_fragments_rebuild_the_message(
+        (message, mut fragments, duplicated) in delivery()
+    ) {
+        for index in duplicated {
+            let duplicate = fragments[index].clone();
+            fragments.insert(index, duplicate);
+        }
+        let rebuilt = replay(fragments).expect("every fragment was delivered").unwrap();
+        prop_assert_eq!(rebuilt.to_string(), message.to_string());
+    }
+
+    #[test]
+    fn missing_fragments_never_produce_a_message(
+        (_message, mut fragments, _duplicated) in delivery(),
+        dropped in any::<prop::sample::Index>(),
+    ) {
+        fragments.remove(dropped.index(fragments.len()));
+        prop_assert!(replay(fragments).is_none());
+    }
+
+    #[test]
+    fn corrupted_fragments_are_rejected(
+        (_message, mut fragments, _duplicated) in delivery(),
+        corrupted in any::<prop::sample::Index>(),
+        byte in any::<prop::sample::Index>(),
+        bit in 0..8u8,
+    ) {
+        let fragment = corrupted.get_mut(&mut fragments);
+        let byte = byte.index(fragment.length as usize);
+        fragment.data[byte] ^= 1 << bit;
+        prop_assert!(matches!(replay(fragments), Some(Err(_))));
+    }
+}
@@ -1,4 +1,8 @@
 #[cfg(test)]
+mod assembly_props;
+#[cfg(test)]
+mod perturbation;
+#[cfg(test)]
 mod sandbox;
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,133 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::fmt::{Display, Formatter};
+use wg_2024::config::{Config, Drone};
+use wg_2024::network::NodeId;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Perturbation {
+    RemoveEdge(NodeId, NodeId),
+    RaisePdr(NodeId, f32),
+    InsertDrone {
+        id: NodeId,
+        between: (NodeId, NodeId),
+    },
+}
+
+impl Display for Perturbation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Perturbation::RemoveEdge(from, to) => write!(f, "remove edge {from}-{to}"),
+            Perturbation::RaisePdr(id, pdr) => write!(f, "raise pdr of {id} to {pdr:.2}"),
+            Perturbation::InsertDrone {
+                id,
+                between: (from, to),
+            } => write!(f, "insert drone {id} between {from} and {to}"),
+        }
+    }
+}
+
+impl Perturbation {
+    pub fn apply(&self, config: &mut Config) {
+        match *self {
+            Perturbation::RemoveEdge(from, to) => remove_drone_edge(config, from, to),
+            Perturbation::RaisePdr(id, pdr) => {
+                for drone in config.drone.iter_mut().filter(|drone| drone.id == id) {
+                    drone.pdr = drone.pdr.max(pdr);
+                }
+            }
+            Perturbation::InsertDrone {
+                id,
+                between: (from, to),
+            } => {
+                remove_drone_edge(config, from, to);
+                for drone in config.drone.iter_mut() {
+                    if drone.id == from || drone.id == to {
+                        drone.connected_node_ids.push(id);
+                    }
+                }
+                let pdr = config
+                    .drone
+                    .iter()
+                    .filter(|drone| drone.id == from || drone.id == to)
+                    .map(|drone| drone.pdr)
+                    .sum::<f32>()
+                    / 2.0;
+                config.drone.push(Drone {
+                    id,
+                    connected_node_ids: vec![from, to],
+                    pdr,
+                });
+            }
+        }
+    }
+
+    fn random(config: &Config, rng: &mut impl Rng) -> Option<Self> {
+        let edges = drone_edges(config);
+        match rng.gen_range(0..3) {
+            0 => edges
+                .choose(rng)
+                .map(|&(from, to)| Perturbation::RemoveEdge(from, to)),
+            1 => config
+                .drone
+                .choose(rng)
+                .map(|drone| Perturbation::RaisePdr(drone.id, rng.gen_range(0.5..0.9))),
+            _ => {
+                let id = (0..=NodeId::MAX).find(|id| !is_id_taken(config, *id))?;
+                edges
+                    .choose(rng)
+                    .map(|&between| Perturbation::InsertDrone { id, between })
+            }
+        }
+    }
+}
+
+/// Generates `count` variants of `base`, each with a single random perturbation applied, the
+/// same ones for the same `rng` seed.
+pub fn perturbed_variants(
+    base: &Config,
+    count: usize,
+    rng: &mut impl Rng,
+) -> Vec<(Perturbation, Config)> {
+    std::iter::repeat_with(|| Perturbation::random(base, rng))
+        .flatten()
+        .take(count)
+        .map(|perturbation| {
+            let mut config = base.clone();
+            perturbation.apply(&mut config);
+            (perturbation, config)
+        })
+        .collect()
+}
+
+fn drone_edges(config: &Config) -> Vec<(NodeId, NodeId)> {
+    config
+        .drone
+        .iter()
+        .flat_map(|drone| {
+            drone
+                .connected_node_ids
+                .iter()
+                .filter(move |&&neighbor| {
+                    drone.id < neighbor && config.drone.iter().any(|other| other.id == neighbor)
+                })
+                .map(move |&neighbor| (drone.id, neighbor))
+        })
+        .collect()
+}
+
+fn remove_drone_edge(config: &mut Config, from: NodeId, to: NodeId) {
+    for drone in config.drone.iter_mut() {
+        if drone.id == from {
+            drone.connected_node_ids.retain(|&id| id != to);
+        } else if drone.id == to {
+            drone.connected_node_ids.retain(|&id| id != from);
+        }
+    }
+}
+
+fn is_id_taken(config: &Config, id: NodeId) -> bool {
+    config.drone.iter().any(|drone| drone.id == id)
+        || config.client.iter().any(|client| client.id == id)
+        || config.server.iter().any(|server| server.id == id)
+}
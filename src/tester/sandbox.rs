@@ -1,3 +1,4 @@
+use crate::application::authentication::HostKeys;
 use crate::application::simulation_controller_messages::{HostCommand, HostEvent};
 use crate::initialization::network_initializer::{
     create_simulation, parse_topology_file, spawn_threads, NetworkNode, Runnable,
@@ -8,7 +9,7 @@ use graph::ReferenceGraph;
 use rand::{random, thread_rng, Rng};
 use std::collections::{HashMap, HashSet};
 use std::mem;
-use wg_2024::config::Client;
+use wg_2024::config::{Client, Config};
 use wg_2024::controller::DroneCommand;
 use wg_2024::network::NodeId;
 use wg_2024::packet::Packet;
@@ -176,7 +177,9 @@ where
         controller_recv: Receiver<HostCommand>,
         packet_recv: Receiver<Packet>,
         packet_send: HashMap<NodeId, Sender<Packet>>,
+        host_keys: &HostKeys,
     ) -> Box<dyn Runnable> {
+        // the test nodes are given no key, like the drones
         if let Some(test_node) = self.test_nodes.remove(&id) {
             Box::new(TestNode {
                 id,
@@ -187,8 +190,13 @@ where
                 node_behaviour: test_node.node_behaviour,
             })
         } else {
-            self.base_client_creator
-                .create_client(id, controller_recv, packet_recv, packet_send)
+            self.base_client_creator.create_client(
+                id,
+                controller_recv,
+                packet_recv,
+                packet_send,
+                host_keys,
+            )
         }
     }
 }
@@ -202,7 +210,22 @@ pub fn create_test_environment<DC, CC, SC>(
     CC: ClientCreator,
     SC: ServerCreator,
 {
-    let mut config = parse_topology_file(topology_file_path);
+    create_test_environment_with_config::<DC, CC, SC>(
+        parse_topology_file(topology_file_path),
+        test_nodes,
+        pdr_policy,
+    )
+}
+
+pub fn create_test_environment_with_config<DC, CC, SC>(
+    mut config: Config,
+    test_nodes: Vec<TestNodeInstructions>,
+    pdr_policy: PDRPolicy,
+) where
+    DC: DroneCreator,
+    CC: ClientCreator,
+    SC: ServerCreator,
+{
     let mut test_nodes = test_nodes
         .into_iter()
         .map(|node| (node.id, node))
@@ -271,7 +294,8 @@ pub fn create_test_environment<DC, CC, SC>(
                 NetworkNode::Drone { command_send, .. } => {
                     command_send.send(DroneCommand::RemoveSender(*adj)).ok();
                 }
-                NetworkNode::Client { command_send } | NetworkNode::Server { command_send } => {
+                NetworkNode::Client { command_send, .. }
+                | NetworkNode::Server { command_send, .. } => {
                     command_send
                         .send(HostCommand::RemoveConnectedDrone(*adj))
                         .ok();
@@ -282,7 +306,7 @@ pub fn create_test_environment<DC, CC, SC>(
             NetworkNode::Drone { command_send, .. } => {
                 command_send.send(DroneCommand::Crash).ok();
             }
-            NetworkNode::Client { command_send } | NetworkNode::Server { command_send } => {
+            NetworkNode::Client { command_send, .. } | NetworkNode::Server { command_send, .. } => {
                 command_send.send(HostCommand::Crash).ok();
             }
         }
@@ -1,27 +1,41 @@
-use super::sandbox::{create_test_environment, PDRPolicy, TestNodeInstructions};
-use crate::application::assembler::{Assembler, Disassembler};
-use crate::application::routing::SourceRouter;
+use super::perturbation::perturbed_variants;
+use super::sandbox::{
+    create_test_environment, create_test_environment_with_config, PDRPolicy, TestNodeInstructions,
+};
+use crate::application::assembler::{Assembler, AssemblyError, AssemblyLimits, Disassembler};
+use crate::application::retransmission::{RetransmissionScheduler, MAX_RETRANSMISSIONS};
+use crate::application::routing::{RouterSettings, SourceRouter, MAX_CONSECUTIVE_DROPS};
+use crate::application::simulation_controller_messages::{HostCommand, HostEvent};
 use crate::application::topology::node::{ApplicationType, Node, NodeType};
 use crate::initialization::dummies::DummyHostCreator;
-use crate::initialization::network_initializer::Runnable;
+use crate::initialization::network_initializer::{parse_topology_file, Runnable};
 use crate::initialization::node_creators::{ActualDroneCreator, ActualServerCreator, DroneCreator};
-use crate::message::base_message::Message;
+use crate::message::base_message::{Message, Priority};
 use crate::message::chat_message::ChatRequest;
+use crate::message::codec::MessageCodec;
 use crate::message::content_message::{ContentRequest, ContentResponse};
+use crate::message::discovery::{Discovery, DISCOVERY_CODEC};
+use crate::message::echo_message::{unix_millis, EchoRequest};
 use crate::message::media_message::{MediaRequest, MediaResponse};
+use crate::server::echo_server::EchoServer;
 use crate::server::media_server::MediaServer;
 use bagel_bomber::BagelBomber;
-use crossbeam_channel::{Receiver, Sender};
+use crossbeam_channel::{unbounded, Receiver, Sender};
 use graph::AdjacencyVecGraph;
-use std::collections::HashMap;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::collections::{BTreeMap, HashMap};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use wg_2024::config::Config;
 use wg_2024::controller::DroneEvent;
 use wg_2024::drone::Drone;
 use wg_2024::network::{NodeId, SourceRoutingHeader};
 use wg_2024::packet::NodeType as SimpleNodeType;
 use wg_2024::packet::NodeType::Client;
-use wg_2024::packet::{FloodRequest, Fragment, NackType, Packet, PacketType, FRAGMENT_DSIZE};
+use wg_2024::packet::{
+    FloodRequest, FloodResponse, Fragment, Nack, NackType, Packet, PacketType, FRAGMENT_DSIZE,
+};
 
 struct BagelBomberCreator {
     controller_send: Sender<DroneEvent>,
@@ -297,6 +311,205 @@ fn string_message() {
     println!("{}", str_message);
 }
 
+#[test]
+fn incomplete_sessions_are_abandoned() {
+    let message = Message::new(
+        0,
+        1,
+        7,
+        ChatRequest::Register("a".repeat(3 * FRAGMENT_DSIZE)),
+    );
+    let mut fragments = Disassembler::new().disassembly(message);
+    let mut assembler = Assembler::<ChatRequest>::new();
+    assert!(assembler
+        .insert_fragment(0, 7, fragments.remove(0))
+        .is_none());
+
+    assert!(assembler.gc(Duration::from_secs(60)).is_empty());
+    assert_eq!(assembler.received_fragments(0, 7), 1);
+
+    assert_eq!(assembler.gc(Duration::ZERO), vec![(0, 7)]);
+    assert_eq!(assembler.received_fragments(0, 7), 0);
+}
+
+#[test]
+fn corrupted_fragments_fail_the_integrity_check() {
+    let message = Message::new(0, 1, 7, ChatRequest::Register("daw".to_string()));
+    let mut fragments = Disassembler::new().disassembly(message);
+    fragments[0].data[0] ^= 1;
+    let result = Assembler::<ChatRequest>::new().compose_message(0, fragments);
+    assert!(matches!(result, Err(AssemblyError::Corrupted { .. })));
+}
+
+#[test]
+fn probes_complete_their_session_without_a_message() {
+    let probe = Fragment {
+        fragment_index: 0,
+        total_n_fragments: 1,
+        length: 0,
+        data: [0; FRAGMENT_DSIZE],
+    };
+    let mut assembler = Assembler::<ChatRequest>::new();
+    let result = assembler.insert_fragment(0, 7, probe);
+    assert!(matches!(result, Some(Err(AssemblyError::Empty))));
+    assert_eq!(assembler.received_fragments(0, 7), 0);
+}
+
+#[test]
+fn messages_are_decoded_with_the_codec_they_were_encoded_with() {
+    let message = Message::new(0, 1, 7, ChatRequest::Register("daw".to_string()));
+    for codec in [MessageCodec::Json, MessageCodec::Bincode] {
+        let fragments = Disassembler::new()
+            .with_codec(codec)
+            .disassembly(message.clone());
+        let composed = Assembler::<ChatRequest>::new()
+            .compose_message(0, fragments)
+            .unwrap();
+        assert_eq!(composed.to_string(), message.to_string());
+    }
+}
+
+#[test]
+fn messages_of_another_protocol_version_are_not_read() {
+    let message = Message {
+        version: 0,
+        ..Message::new(0, 1, 7, ChatRequest::Register("daw".to_string()))
+    };
+    for codec in [MessageCodec::Json, MessageCodec::Bincode] {
+        let fragments = Disassembler::new()
+            .with_codec(codec)
+            .disassembly(message.clone());
+        let result = Assembler::<ChatRequest>::new().compose_message(0, fragments);
+        assert!(matches!(result, Err(AssemblyError::UnsupportedVersion(0))));
+    }
+}
+
+#[test]
+fn discovery_is_read_by_hosts_of_any_application() {
+    let message = Message::new(0, 1, 7, Discovery::ServerTypeRequest);
+    let fragments = Disassembler::<ContentRequest>::new()
+        .with_codec(MessageCodec::Bincode)
+        .disassembly_with(message, DISCOVERY_CODEC);
+    let chat = Assembler::<ChatRequest>::new()
+        .compose_message(0, fragments.clone())
+        .unwrap_err();
+    let content = Assembler::<ContentRequest>::new()
+        .compose_message(0, fragments)
+        .unwrap_err();
+    for err in [chat, content] {
+        assert!(matches!(
+            err.read_as::<Discovery>().map(|message| message.content),
+            Some(Discovery::ServerTypeRequest)
+        ));
+    }
+}
+
+#[test]
+fn fragments_are_released_as_the_window_slides() {
+    let message = Message::new(
+        0,
+        1,
+        7,
+        ChatRequest::Register("a".repeat(10 * FRAGMENT_DSIZE)),
+    );
+    let mut disassembler = Disassembler::new().with_window(4);
+    let first = disassembler.disassembly(message);
+    assert_eq!(first.len(), 4);
+    assert!(disassembler.release_fragments(7).is_empty());
+
+    disassembler.forget_fragment(7, 2);
+    let released = disassembler.release_fragments(7);
+    assert_eq!(released.len(), 1);
+    assert_eq!(released[0].fragment_index, 4);
+}
+
+#[test]
+fn retransmissions_give_up_after_their_budget() {
+    let mut scheduler = RetransmissionScheduler::default();
+    for _ in 0..MAX_RETRANSMISSIONS {
+        assert!(scheduler.schedule(7, 0, None, Priority::Normal));
+    }
+    assert!(!scheduler.schedule(7, 0, None, Priority::Normal));
+
+    scheduler.fragment_acknowledged(7, 0);
+    assert!(scheduler.schedule(7, 0, None, Priority::Normal));
+}
+
+#[test]
+fn urgent_retransmissions_go_first() {
+    let mut scheduler = RetransmissionScheduler::default();
+    scheduler.schedule(1, 0, None, Priority::Low);
+    scheduler.schedule(2, 0, None, Priority::High);
+    scheduler.schedule(3, 0, None, Priority::Normal);
+    thread::sleep(Duration::from_millis(20));
+    let sessions: Vec<_> = scheduler
+        .due()
+        .into_iter()
+        .map(|(session_id, _fragment_index, _avoided)| session_id)
+        .collect();
+    assert_eq!(sessions, vec![2, 3, 1]);
+}
+
+#[test]
+fn duplicate_and_out_of_order_fragments_are_counted() {
+    let message = Message::new(
+        0,
+        1,
+        7,
+        ChatRequest::Register("a".repeat(3 * FRAGMENT_DSIZE)),
+    );
+    let fragments = Disassembler::new().disassembly(message);
+    let mut assembler = Assembler::<ChatRequest>::new();
+    assembler.insert_fragment(0, 7, fragments[1].clone());
+    assembler.insert_fragment(0, 7, fragments[0].clone());
+    assembler.insert_fragment(0, 7, fragments[0].clone());
+
+    let stats = assembler.stats();
+    assert_eq!(stats.received, 3);
+    assert_eq!(stats.duplicates, 1);
+    assert_eq!(stats.out_of_order, 2);
+}
+
+#[test]
+fn oldest_sessions_are_evicted_over_the_limits() {
+    let mut disassembler = Disassembler::new();
+    let mut assembler = Assembler::<ChatRequest>::new().with_limits(AssemblyLimits {
+        max_sessions: 1,
+        ..AssemblyLimits::default()
+    });
+    for session_id in [1, 2] {
+        let message = Message::new(
+            0,
+            1,
+            session_id,
+            ChatRequest::Register("a".repeat(3 * FRAGMENT_DSIZE)),
+        );
+        let fragment = disassembler.disassembly(message).remove(0);
+        assembler.insert_fragment(0, session_id, fragment);
+    }
+
+    assert_eq!(assembler.enforce_limits(), vec![(0, 1)]);
+    assert_eq!(assembler.received_fragments(0, 2), 1);
+}
+
+#[test]
+fn same_session_ids_from_different_hosts_do_not_mix() {
+    let mut disassembler = Disassembler::new();
+    let mut assembler = Assembler::<ChatRequest>::new();
+    for source in [1, 2] {
+        let message = Message::new(
+            source,
+            0,
+            7,
+            ChatRequest::Register("a".repeat(3 * FRAGMENT_DSIZE)),
+        );
+        let fragment = disassembler.disassembly(message).remove(0);
+        assert!(assembler.insert_fragment(source, 7, fragment).is_none());
+    }
+    assert_eq!(assembler.received_fragments(1, 7), 1);
+    assert_eq!(assembler.received_fragments(2, 7), 1);
+}
+
 #[test]
 fn download_chad_face() {
     let client = TestNodeInstructions::with_node_id(
@@ -337,8 +550,9 @@ fn download_chad_face() {
                         if let NackType::Dropped = nack.nack_type {
                             retransmit_count += 1;
 
-                            let fragment =
-                                disassembler.get_fragment(0, nack.fragment_index).unwrap();
+                            let fragment = disassembler
+                                .retransmit_fragment(0, nack.fragment_index)
+                                .unwrap();
                             packet_send
                                 .get(&3)
                                 .unwrap()
@@ -368,9 +582,12 @@ fn download_chad_face() {
 
             for packet in packet_recv.iter() {
                 let session_id = packet.session_id;
+                let source = packet.routing_header.source().unwrap();
                 if let PacketType::MsgFragment(fragment) = packet.pack_type {
                     packet_count += 1;
-                    if let Some(message_res) = assembler.insert_fragment(session_id, fragment) {
+                    if let Some(message_res) =
+                        assembler.insert_fragment(source, session_id, fragment)
+                    {
                         match message_res {
                             Ok(message) => {
                                 assert_eq!(message.source_id, 250);
@@ -736,3 +953,575 @@ fn route_with_host() {
 
     println!("Route to 2: {:?}", route);
 }
+
+#[test]
+fn cheapest_route_avoids_hosts() {
+    let graph = routing_graph(vec![
+        (0, chat_client(), vec![3, 5]),
+        (1, chat_client(), vec![3, 4]),
+        (2, chat_server(), vec![4, 8]),
+        (3, drone(), vec![0, 1, 4]),
+        (4, drone(), vec![1, 2, 3]),
+        (5, drone(), vec![0, 6]),
+        (6, drone(), vec![5, 7]),
+        (7, drone(), vec![6, 8]),
+        (8, drone(), vec![2, 7]),
+    ]);
+
+    let mut router = SourceRouter::new(Node::new(0, NodeType::Client(ApplicationType::Chat)));
+    router.graph = graph;
+    router.calculate_routes();
+
+    assert_eq!(router.get_best_route(2).unwrap().hops, vec![0, 3, 4, 2]);
+    assert_eq!(
+        router.get_route_avoiding(2, 3).unwrap().hops,
+        vec![0, 5, 6, 7, 8, 2]
+    );
+    assert!(router.get_best_route(1).is_none());
+}
+
+/// A graph of the nodes, each given with its neighbors.
+fn routing_graph(nodes: Vec<(NodeId, NodeType, Vec<NodeId>)>) -> AdjacencyVecGraph<NodeId, Node> {
+    AdjacencyVecGraph::from_iter(
+        nodes
+            .into_iter()
+            .map(|(id, node_type, adjacents)| (id, (Node::new(id, node_type), adjacents))),
+    )
+}
+
+fn chat_client() -> NodeType {
+    NodeType::Client(ApplicationType::Chat)
+}
+
+fn chat_server() -> NodeType {
+    NodeType::Server(ApplicationType::Chat)
+}
+
+fn drone() -> NodeType {
+    NodeType::Drone(Default::default())
+}
+
+/// A path the flood from the first node took, as the flood response carrying it.
+fn flood_response(path_trace: Vec<(NodeId, SimpleNodeType)>) -> Packet {
+    Packet {
+        session_id: 0,
+        routing_header: SourceRoutingHeader::empty_route(),
+        pack_type: PacketType::FloodResponse(FloodResponse {
+            flood_id: 0,
+            path_trace,
+        }),
+    }
+}
+
+#[test]
+#[cfg(not(feature = "path-enumeration"))]
+fn k_shortest_routes_are_distinct_loopless_and_cheapest_first() {
+    use crate::application::topology::node::{Drone as RoutingDrone, FragmentDelivery};
+    use std::collections::HashSet;
+
+    // drone 3 drops half of the fragments, so the routes through 4 come first
+    let lossy = RoutingDrone::with_delivery(
+        [FragmentDelivery::Dropped, FragmentDelivery::Forwarded].repeat(15),
+    );
+    let mut router = SourceRouter::new(Node::new(0, chat_client()));
+    router.graph = routing_graph(vec![
+        (0, chat_client(), vec![3, 4]),
+        (2, chat_server(), vec![5, 6]),
+        (3, NodeType::Drone(lossy), vec![0, 5, 6]),
+        (4, drone(), vec![0, 5, 6]),
+        (5, drone(), vec![2, 3, 4]),
+        (6, drone(), vec![2, 3, 4]),
+    ]);
+    router.calculate_routes();
+
+    let routes: Vec<_> = router
+        .describe_routes()
+        .into_iter()
+        .filter(|route| route.destination == 2)
+        .collect();
+    // eight routes exist, the four kept are the ones crossing two drones
+    assert_eq!(routes.len(), 4);
+    for route in routes.iter() {
+        assert_eq!((route.hops[0], route.hops.last()), (0, Some(&2)));
+        let distinct: HashSet<_> = route.hops.iter().collect();
+        assert_eq!(
+            distinct.len(),
+            route.hops.len(),
+            "{:?} has a loop",
+            route.hops
+        );
+        assert_eq!(route.hops.len(), 4);
+    }
+    let distinct: HashSet<_> = routes.iter().map(|route| route.hops.clone()).collect();
+    assert_eq!(distinct.len(), routes.len());
+    assert!(routes.windows(2).all(|pair| pair[0].cost <= pair[1].cost));
+    assert!(routes[..2].iter().all(|route| route.hops[1] == 4));
+}
+
+#[test]
+fn link_pdr_follows_the_direction_of_the_fragments() {
+    let mut router = SourceRouter::new(Node::new(0, chat_client()));
+    router.graph = routing_graph(vec![
+        (0, chat_client(), vec![3]),
+        (2, chat_server(), vec![4]),
+        (3, drone(), vec![0, 4]),
+        (4, drone(), vec![2, 3]),
+    ]);
+    router.calculate_routes();
+
+    // drone 4 drops 3 fragments out of 10, which the acks of the others must not hide
+    let fragment = Fragment::from_string(0, 1, "measured".to_string());
+    for session_id in 0..500 {
+        let route = router.get_best_route(2).unwrap();
+        router.fragment_sent(&Packet::new_fragment(route, session_id, fragment.clone()));
+        if session_id % 10 < 3 {
+            let nack = Packet::new_nack(
+                SourceRoutingHeader {
+                    hop_index: 2,
+                    hops: vec![4, 3, 0],
+                },
+                session_id,
+                Nack {
+                    fragment_index: 0,
+                    nack_type: NackType::Dropped,
+                },
+            );
+            router.update_graph(&nack);
+            router.fragment_dropped(session_id, 0);
+        } else {
+            let routing_header = SourceRoutingHeader {
+                hop_index: 3,
+                hops: vec![2, 4, 3, 0],
+            };
+            let ack = Packet::new_ack(routing_header, session_id, 0);
+            router.update_graph(&ack);
+            router.fragment_acknowledged(session_id, 0);
+        }
+    }
+
+    let link_pdr = |id: NodeId, previous: NodeId| match &router.graph[&id].node_type {
+        NodeType::Drone(drone) => drone.link_pdr(previous),
+        _ => unreachable!(),
+    };
+    assert!((link_pdr(4, 3) - 0.3).abs() < 0.05, "{}", link_pdr(4, 3));
+    assert!(link_pdr(3, 0) < 0.05, "{}", link_pdr(3, 0));
+}
+
+#[test]
+fn only_dirty_destinations_are_searched_again() {
+    let mut router = SourceRouter::new(Node::new(0, chat_client()));
+    router.graph = routing_graph(vec![
+        (0, chat_client(), vec![3]),
+        (2, chat_server(), vec![4]),
+        (9, chat_server(), vec![5]),
+        (3, drone(), vec![0, 4, 5]),
+        (4, drone(), vec![2, 3]),
+        (5, drone(), vec![3, 9]),
+    ]);
+    router.calculate_routes();
+
+    // a shortcut to 2 the router is not told about, and one to 9 it is told about
+    router.graph.add_undirected_edge(3, 2);
+    router.add_edge(3, 9);
+    router.update_routes();
+
+    assert_eq!(router.get_best_route(9).unwrap().hops, vec![0, 3, 9]);
+    assert_eq!(router.get_best_route(2).unwrap().hops, vec![0, 3, 4, 2]);
+}
+
+#[test]
+fn routes_dropping_fragments_are_demoted_for_a_while() {
+    let mut router = SourceRouter::new(Node::new(0, chat_client()));
+    router.graph = routing_graph(vec![
+        (0, chat_client(), vec![3, 5]),
+        (2, chat_server(), vec![4, 8]),
+        (3, drone(), vec![0, 4]),
+        (4, drone(), vec![2, 3]),
+        (5, drone(), vec![0, 6]),
+        (6, drone(), vec![5, 7]),
+        (7, drone(), vec![6, 8]),
+        (8, drone(), vec![2, 7]),
+    ]);
+    router.calculate_routes();
+    let cheapest = vec![0, 3, 4, 2];
+    let cost_of_cheapest = |router: &SourceRouter| {
+        router
+            .describe_routes()
+            .into_iter()
+            .find(|route| route.hops == cheapest)
+            .unwrap()
+            .cost
+    };
+    let base_cost = cost_of_cheapest(&router);
+
+    let fragment = Fragment::from_string(0, 1, "dropped".to_string());
+    for session_id in 0..MAX_CONSECUTIVE_DROPS as u64 {
+        assert_eq!(router.get_best_route(2).unwrap().hops, cheapest);
+        let route = router.get_best_route(2).unwrap();
+        router.fragment_sent(&Packet::new_fragment(route, session_id, fragment.clone()));
+        router.fragment_dropped(session_id, 0);
+    }
+
+    assert_eq!(
+        router.get_best_route(2).unwrap().hops,
+        vec![0, 5, 6, 7, 8, 2]
+    );
+    let demoted_cost = cost_of_cheapest(&router);
+    assert!(demoted_cost > base_cost + 1.0);
+    thread::sleep(Duration::from_millis(200));
+    let decayed_cost = cost_of_cheapest(&router);
+    assert!(decayed_cost < demoted_cost && decayed_cost > base_cost);
+}
+
+#[test]
+fn stale_topology_is_forgotten() {
+    let settings = RouterSettings {
+        topology_lifetime: Duration::from_millis(50),
+        ..Default::default()
+    };
+    let mut router = SourceRouter::new(Node::new(0, chat_client())).with_settings(settings);
+    router.update_graph(&flood_response(vec![
+        (0, SimpleNodeType::Client),
+        (3, SimpleNodeType::Drone),
+        (4, SimpleNodeType::Drone),
+        (2, SimpleNodeType::Server),
+    ]));
+    router.calculate_routes();
+    assert_eq!(router.get_best_route(2).unwrap().hops, vec![0, 3, 4, 2]);
+
+    thread::sleep(Duration::from_millis(100));
+    router.update_routes();
+
+    assert!(router.get_best_route(2).is_none());
+    assert!(router.graph.contains_node(&0));
+    assert!(!router.graph.contains_node(&4) && !router.graph.contains_node(&2));
+}
+
+#[test]
+fn the_source_is_never_evicted() {
+    let settings = RouterSettings {
+        max_nodes: 3,
+        ..Default::default()
+    };
+    let mut router = SourceRouter::new(Node::new(0, chat_client())).with_settings(settings);
+    // a flood of another client, which never saw the source
+    router.update_graph(&flood_response(vec![
+        (100, SimpleNodeType::Client),
+        (3, SimpleNodeType::Drone),
+        (4, SimpleNodeType::Drone),
+        (5, SimpleNodeType::Drone),
+        (2, SimpleNodeType::Server),
+    ]));
+
+    assert!(router.graph.contains_node(&0));
+    assert_eq!(router.graph.iter().count(), 3);
+}
+
+/// Whether a message sent over `config` reaches the server within the few seconds the
+/// scenario lasts.
+fn delivery_scenario(config: Config, pdr_policy: PDRPolicy) -> bool {
+    const CLIENT_ID: NodeId = 150;
+    const SERVER_ID: NodeId = 250;
+
+    let (result_send, result_recv) = unbounded();
+
+    let client = TestNodeInstructions::with_node_id(
+        CLIENT_ID,
+        &[1],
+        |id,
+         _controller_send,
+         _controller_recv,
+         packet_recv: Receiver<Packet>,
+         packet_send: HashMap<u8, Sender<Packet>>| {
+            let mut router =
+                SourceRouter::new(Node::new(id, NodeType::Client(ApplicationType::Chat)));
+
+            for sender in packet_send.values() {
+                sender
+                    .send(Packet::new_flood_request(
+                        SourceRoutingHeader::empty_route(),
+                        0,
+                        FloodRequest::initialize(0, id, SimpleNodeType::Client),
+                    ))
+                    .ok();
+            }
+
+            let flood_deadline = Instant::now() + Duration::from_millis(1500);
+            while let Ok(packet) = packet_recv.recv_deadline(flood_deadline) {
+                router.update_graph(&packet);
+            }
+            router.calculate_routes();
+
+            let message = Message::new(
+                id,
+                SERVER_ID,
+                0,
+                ChatRequest::SendMessage {
+                    from: "perturbation".to_string(),
+                    to: "server".to_string(),
+                    message: "robustness ".repeat(100),
+                },
+            );
+            let mut disassembler = Disassembler::new();
+            let send_fragment = |router: &mut SourceRouter, fragment: Fragment| {
+                let Some(route) = router.get_best_route(SERVER_ID) else {
+                    return;
+                };
+                let mut packet = Packet::new_fragment(route, 0, fragment);
+                packet.routing_header.increase_hop_index();
+                if let Some(sender) = packet
+                    .routing_header
+                    .current_hop()
+                    .and_then(|hop| packet_send.get(&hop))
+                {
+                    sender.send(packet).ok();
+                }
+            };
+
+            for fragment in disassembler.disassembly(message) {
+                send_fragment(&mut router, fragment);
+            }
+
+            let deadline = Instant::now() + Duration::from_secs(3);
+            while let Ok(packet) = packet_recv.recv_deadline(deadline) {
+                router.update_graph(&packet);
+                if let PacketType::Nack(nack) = packet.pack_type {
+                    if !matches!(nack.nack_type, NackType::Dropped) {
+                        router.calculate_routes();
+                    }
+                    if let Some(fragment) = disassembler.retransmit_fragment(0, nack.fragment_index)
+                    {
+                        send_fragment(&mut router, fragment);
+                    }
+                }
+            }
+        },
+    );
+
+    let server = TestNodeInstructions::with_node_id(
+        SERVER_ID,
+        &[9, 10],
+        move |id,
+              _controller_send,
+              _controller_recv,
+              packet_recv: Receiver<Packet>,
+              packet_send: HashMap<u8, Sender<Packet>>| {
+            let mut assembler = Assembler::<ChatRequest>::new();
+            let deadline = Instant::now() + Duration::from_secs(5);
+
+            while let Ok(packet) = packet_recv.recv_deadline(deadline) {
+                match packet.pack_type {
+                    PacketType::FloodRequest(request) => {
+                        let mut response = request
+                            .get_incremented(id, SimpleNodeType::Server)
+                            .generate_response(packet.session_id);
+                        response.routing_header.increase_hop_index();
+                        if let Some(sender) = response
+                            .routing_header
+                            .current_hop()
+                            .and_then(|hop| packet_send.get(&hop))
+                        {
+                            sender.send(response).ok();
+                        }
+                    }
+                    PacketType::MsgFragment(fragment) => {
+                        let source = packet.routing_header.source().unwrap();
+                        if let Some(Ok(_)) =
+                            assembler.insert_fragment(source, packet.session_id, fragment)
+                        {
+                            result_send.send(true).ok();
+                            return;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        },
+    );
+
+    create_test_environment_with_config::<ActualDroneCreator, DummyHostCreator, DummyHostCreator>(
+        config,
+        vec![client, server],
+        pdr_policy,
+    );
+
+    result_recv.try_recv().unwrap_or(false)
+}
+
+#[test]
+fn delivery_does_not_rise_with_the_drop_rate() {
+    let base = parse_topology_file("topologies/examples/double-chain/topology.toml");
+
+    // the drones drop at random in between, so only the rates whose outcome is certain
+    let delivered: Vec<bool> = [0.0, 1.0]
+        .into_iter()
+        .map(|pdr| delivery_scenario(base.clone(), PDRPolicy::Constant(pdr)))
+        .collect();
+
+    assert_eq!(
+        delivered,
+        [true, false],
+        "the baseline delivers without drops and nothing crosses drones dropping everything"
+    );
+}
+
+/// Reports the random perturbations of the topology that keep a message from being delivered,
+/// a benchmark of how robust the routing is rather than a check: run it with `--ignored`.
+#[test]
+#[ignore]
+fn perturbation_sensitivity() {
+    let base = parse_topology_file("topologies/examples/double-chain/topology.toml");
+
+    println!(
+        "baseline: {}",
+        if delivery_scenario(base.clone(), PDRPolicy::Unchanged) {
+            "delivered"
+        } else {
+            "broken"
+        }
+    );
+
+    let mut rng = StdRng::seed_from_u64(4033);
+    let broken = perturbed_variants(&base, 5, &mut rng)
+        .into_iter()
+        .filter_map(|(perturbation, config)| {
+            (!delivery_scenario(config, PDRPolicy::Unchanged)).then_some(perturbation)
+        })
+        .collect::<Vec<_>>();
+
+    println!("----- PERTURBATIONS BREAKING DELIVERY -----");
+    for perturbation in broken {
+        println!("{perturbation}");
+    }
+}
+
+/// An echo server running behind drone 5, driven through the channels of the drone and of
+/// the controller as if client 1 sent it requests.
+struct EchoServerHarness {
+    packets: Sender<Packet>,
+    drone: Receiver<Packet>,
+    commands: Sender<HostCommand>,
+    events: Receiver<HostEvent>,
+    server: thread::JoinHandle<()>,
+}
+
+impl EchoServerHarness {
+    fn start() -> Self {
+        let (controller_send, events) = unbounded();
+        let (commands, controller_recv) = unbounded();
+        let (packets, packet_recv) = unbounded();
+        let (drone_send, drone) = unbounded();
+        let server = thread::spawn(move || {
+            EchoServer::with_default_behaviour(
+                2,
+                controller_send,
+                controller_recv,
+                packet_recv,
+                HashMap::from([(5, drone_send)]),
+            )
+            .run();
+        });
+        EchoServerHarness {
+            packets,
+            drone,
+            commands,
+            events,
+            server,
+        }
+    }
+
+    fn route() -> SourceRoutingHeader {
+        SourceRoutingHeader {
+            hop_index: 2,
+            hops: vec![1, 5, 2],
+        }
+    }
+
+    /// Sends the request to the server, returning the session of its response and the
+    /// fragments of the response, left unacked.
+    fn request(&self, request: Message<ContentRequest>) -> (u64, Vec<Fragment>) {
+        let session_id = request.session_id;
+        for fragment in Disassembler::new().disassembly(request) {
+            self.packets
+                .send(Packet::new_fragment(Self::route(), session_id, fragment))
+                .unwrap();
+        }
+        let mut response = None;
+        let mut fragments = BTreeMap::new();
+        for packet in self.drone.iter() {
+            let PacketType::MsgFragment(fragment) = packet.pack_type else {
+                continue;
+            };
+            if *response.get_or_insert(packet.session_id) != packet.session_id {
+                continue;
+            }
+            let total = fragment.total_n_fragments as usize;
+            fragments.insert(fragment.fragment_index, fragment);
+            if fragments.len() == total {
+                break;
+            }
+        }
+        (response.unwrap(), fragments.into_values().collect())
+    }
+
+    fn echo(payload: &[u8]) -> ContentRequest {
+        ContentRequest::Echo(EchoRequest {
+            payload: payload.to_vec(),
+            sent_at: unix_millis(),
+        })
+    }
+
+    /// Stops the server, acking the fragments of `response` once it is stopping, and
+    /// returns how many responses it reports abandoned as it shuts down.
+    fn stop(self, response: Option<(u64, Vec<Fragment>)>) -> usize {
+        self.commands.send(HostCommand::Crash).unwrap();
+        thread::sleep(Duration::from_millis(100));
+        if let Some((session_id, fragments)) = response {
+            for fragment in fragments {
+                let ack = Packet::new_ack(Self::route(), session_id, fragment.fragment_index);
+                self.packets.send(ack).unwrap();
+            }
+        }
+        let abandoned = self
+            .events
+            .iter()
+            .find_map(|event| match event {
+                HostEvent::ShutDown { abandoned, .. } => Some(abandoned),
+                _ => None,
+            })
+            .unwrap();
+        self.server.join().unwrap();
+        abandoned
+    }
+}
+
+#[test]
+fn stopping_servers_wait_for_their_responses_to_be_acked() {
+    let server = EchoServerHarness::start();
+    let request = Message::new(1, 2, 7, EchoServerHarness::echo(b"quack"));
+    let response = server.request(request);
+    assert_eq!(server.stop(Some(response)), 0);
+}
+
+#[test]
+fn stopping_servers_report_the_responses_never_acked() {
+    let server = EchoServerHarness::start();
+    let request = Message::new(1, 2, 7, EchoServerHarness::echo(b"quack"));
+    server.request(request);
+    assert_eq!(server.stop(None), 1);
+}
+
+#[test]
+fn servers_answer_in_the_exchange_of_the_request() {
+    let server = EchoServerHarness::start();
+    let request = Message {
+        exchange_id: Some(42),
+        ..Message::new(1, 2, 7, EchoServerHarness::echo(b"quack"))
+    };
+    let (session_id, fragments) = server.request(request);
+    let response = Assembler::<ContentResponse>::new()
+        .compose_message(2, fragments.clone())
+        .unwrap();
+    assert_eq!(response.exchange_id, Some(42));
+    server.stop(Some((session_id, fragments)));
+}